@@ -0,0 +1,61 @@
+//! Wallet addresses, the SHA256 hash of the public key of the wallet, written as 43 characters
+//! of base64url.
+
+use crate::crypto::{base64::fixed_base64, hash::sha256};
+
+/// Length of an address in base64url.
+pub const ADDRESS_LENGTH: usize = 43;
+
+fixed_base64!(Address, 32, ADDRESS_LENGTH, InvalidAddress);
+
+impl Address {
+    /// Address of the wallet with the public key `owner`, as in the `owner` field of a
+    /// transaction.
+    pub fn from_owner(owner: &[u8]) -> Self {
+        Self(sha256(owner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{crypto::base64::Base64, error::Error, signer::ArweaveSigner};
+
+    use super::Address;
+
+    #[test]
+    fn test_address() -> Result<(), Error> {
+        let signer = ArweaveSigner::from_keypair_path(PathBuf::from("res/test_wallet.json"))?;
+        let address = Address::from_owner(&signer.get_public_key().0);
+        assert_eq!(signer.wallet_address(), address);
+
+        let string = address.to_string();
+        assert_eq!(string.len(), 43);
+        assert_eq!(string.parse::<Address>()?, address);
+        assert_eq!(
+            serde_json::to_string(&address).unwrap(),
+            format!("\"{}\"", string)
+        );
+        assert_eq!(
+            serde_json::from_str::<Address>(&format!("\"{}\"", string)).unwrap(),
+            address
+        );
+        assert_eq!(Address::try_from(&address.to_base64())?, address);
+
+        for invalid in [
+            "",
+            "address",
+            &Base64(vec![1; 48]).to_string(),
+            &"!".repeat(43),
+            &format!("{}+", "a".repeat(42)),
+        ] {
+            assert!(matches!(
+                invalid.parse::<Address>(),
+                Err(Error::InvalidAddress(_))
+            ));
+        }
+        assert!(Address::try_from(&Base64(vec![1; 31])).is_err());
+        Ok(())
+    }
+}
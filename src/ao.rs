@@ -0,0 +1,382 @@
+//! Messages to AO processes. Messages are data items tagged with `Data-Protocol: ao`, targeting
+//! the process, that are posted to a messenger unit (MU) for delivery. Their results are read
+//! from a compute unit (CU), which can also evaluate messages without sending them.
+
+use std::sync::Arc;
+
+use reqwest::{
+    header::{HeaderValue, ACCEPT, CONTENT_TYPE},
+    StatusCode,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bundle::DataItem,
+    consts::VERSION,
+    crypto::base64::Base64,
+    error::Error,
+    transaction::tags::Tag,
+    transport::{default_transport, HttpRequest, HttpTransport},
+    tx_id::TxId,
+    ArweaveSigner,
+};
+
+pub const MU_URL: &str = "https://mu.ao-testnet.xyz/";
+pub const CU_URL: &str = "https://cu.ao-testnet.xyz/";
+
+/// Protocol variant tagged on messages.
+pub const VARIANT: &str = "ao.TN.1";
+
+pub const DATA_PROTOCOL: &str = "Data-Protocol";
+pub const ACTION: &str = "Action";
+
+/// Message to the process `process`, converted to a data item once signed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub process: Base64,
+    pub tags: Vec<Tag<Base64>>,
+    pub data: Vec<u8>,
+}
+
+impl Message {
+    pub fn new(process: Base64) -> Self {
+        Self {
+            process,
+            tags: vec![],
+            data: vec![],
+        }
+    }
+
+    /// Sets the `Action` tag the process handlers match on.
+    pub fn action(self, action: &str) -> Self {
+        self.tag(ACTION, action)
+    }
+
+    pub fn tag(mut self, name: &str, value: &str) -> Self {
+        self.tags.push(Tag::new(name, value));
+        self
+    }
+
+    pub fn data(mut self, data: Vec<u8>) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Protocol tags followed by the message tags.
+    fn all_tags(&self) -> Vec<Tag<Base64>> {
+        let mut tags = vec![
+            Tag::new(DATA_PROTOCOL, "ao"),
+            Tag::new("Variant", VARIANT),
+            Tag::new("Type", "Message"),
+            Tag::new("SDK", &format!("arweave-rs/{}", VERSION)),
+        ];
+        tags.extend(self.tags.iter().cloned());
+        tags
+    }
+
+    /// Unsigned data item with the protocol tags followed by the message tags.
+    pub fn to_data_item(&self) -> Result<DataItem, Error> {
+        DataItem::new(
+            self.process.clone(),
+            Base64::empty(),
+            self.all_tags(),
+            self.data.clone(),
+        )
+    }
+}
+
+/// Message sent by a process while handling another one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct OutboxMessage {
+    pub target: Option<String>,
+    pub anchor: Option<String>,
+    #[serde(default)]
+    pub data: String,
+    #[serde(default)]
+    pub tags: Vec<Tag<String>>,
+}
+
+impl OutboxMessage {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|tag| tag.name == name)
+            .map(|tag| tag.value.as_str())
+    }
+}
+
+/// What the process printed, either text or a value with print and prompt hints.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Output {
+    Text(String),
+    Value {
+        #[serde(default)]
+        data: serde_json::Value,
+        #[serde(default)]
+        print: bool,
+        prompt: Option<String>,
+    },
+}
+
+/// Result of evaluating a message: the messages and processes it created, its output and the
+/// error raised by the handler, if any.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct MessageResult {
+    #[serde(default)]
+    pub messages: Vec<OutboxMessage>,
+    #[serde(default)]
+    pub spawns: Vec<OutboxMessage>,
+    pub output: Option<Output>,
+    pub error: Option<String>,
+    pub gas_used: Option<u64>,
+}
+
+/// Message evaluated by a dry run, as the CU expects it.
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct DryRun {
+    id: String,
+    target: String,
+    owner: String,
+    anchor: String,
+    data: String,
+    tags: Vec<Tag<String>>,
+}
+
+#[derive(Deserialize)]
+struct PostResponse {
+    id: String,
+}
+
+pub struct AoClient {
+    transport: Arc<dyn HttpTransport>,
+    mu_url: url::Url,
+    cu_url: url::Url,
+}
+
+impl Default for AoClient {
+    fn default() -> Self {
+        Self::new(url::Url::parse(MU_URL).unwrap()) //Checked unwrap
+    }
+}
+
+impl AoClient {
+    pub fn new(mu_url: url::Url) -> Self {
+        Self {
+            transport: default_transport(),
+            mu_url,
+            cu_url: url::Url::parse(CU_URL).unwrap(), //Checked unwrap
+        }
+    }
+
+    pub fn with_cu_url(mut self, cu_url: url::Url) -> Self {
+        self.cu_url = cu_url;
+        self
+    }
+
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Signs `message` with `signer` and posts it, returning the message id.
+    pub async fn send(&self, signer: &ArweaveSigner, message: &Message) -> Result<String, Error> {
+        let data_item = signer.sign_data_item(message.to_data_item()?)?;
+        self.post_message(&data_item).await
+    }
+
+    /// Posts a signed message to the MU.
+    pub async fn post_message(&self, data_item: &DataItem) -> Result<String, Error> {
+        if data_item.signature.is_empty() {
+            return Err(Error::UnsignedTransaction);
+        }
+        let request = HttpRequest::post(self.mu_url.clone())
+            .header(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/octet-stream"),
+            )
+            .header(ACCEPT, HeaderValue::from_static("application/json"))
+            .body(data_item.to_bytes()?);
+        let res = self.transport.send(request).await?;
+        if !res.status.is_success() {
+            return Err(Error::from_response(&res));
+        }
+        let id = res.json::<PostResponse>()?.id;
+        if id != data_item.id.to_string() {
+            return Err(Error::InvalidReceipt(format!(
+                "MU accepted {} instead of {}",
+                id, data_item.id
+            )));
+        }
+        Ok(id)
+    }
+
+    /// Result of the message `message` sent to `process`.
+    pub async fn get_result(
+        &self,
+        process: &Base64,
+        message: &TxId,
+    ) -> Result<MessageResult, Error> {
+        let mut url = self
+            .cu_url
+            .join(&format!("result/{}", message))
+            .map_err(Error::UrlParseError)?;
+        url.query_pairs_mut()
+            .append_pair("process-id", &process.to_string());
+        let res = self.transport.send(HttpRequest::get(url)).await?;
+        if res.status != StatusCode::OK {
+            return Err(Error::from_response(&res));
+        }
+        res.json()
+    }
+
+    /// Evaluates `message` as if sent by `owner`, without sending it or changing the state of
+    /// the process. Used to read the state of processes.
+    pub async fn dry_run(&self, message: &Message, owner: &str) -> Result<MessageResult, Error> {
+        let tags = message
+            .all_tags()
+            .iter()
+            .map(Tag::to_utf8_strings)
+            .collect::<Result<_, _>>()?;
+        let body = DryRun {
+            id: "0".repeat(43),
+            target: message.process.to_string(),
+            owner: owner.to_string(),
+            anchor: String::new(),
+            data: String::from_utf8_lossy(&message.data).into_owned(),
+            tags,
+        };
+        let mut url = self.cu_url.join("dry-run").map_err(Error::UrlParseError)?;
+        url.query_pairs_mut()
+            .append_pair("process-id", &message.process.to_string());
+        let res = self
+            .transport
+            .send(HttpRequest::post(url).json(&body)?)
+            .await?;
+        if res.status != StatusCode::OK {
+            return Err(Error::from_response(&res));
+        }
+        res.json()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use tokio_test::block_on;
+
+    use crate::{
+        bundle::DataItem,
+        crypto::base64::Base64,
+        error::Error,
+        transaction::tags::{find_tag_value, Tag},
+        tx_id::TxId,
+        ArweaveSigner,
+    };
+
+    use super::{AoClient, Message, Output};
+
+    #[test]
+    fn test_send() -> Result<(), Error> {
+        let signer = ArweaveSigner::from_keypair_path(PathBuf::from("res/test_wallet_4096.json"))?;
+        let process = Base64(vec![5; 32]);
+        let message = Message::new(process.clone())
+            .action("Transfer")
+            .tag("Quantity", "100")
+            .data(b"memo".to_vec());
+        let item = signer.sign_data_item(message.to_data_item()?)?;
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/")
+                .header("content-type", "application/octet-stream");
+            then.status(202).body(format!(
+                r#"{{"message":"Processing DataItem","id":"{}"}}"#,
+                item.id
+            ));
+        });
+        let client = AoClient::new(url::Url::parse(&server.url("/")).unwrap());
+
+        assert_eq!(block_on(client.post_message(&item))?, item.id.to_string());
+        mock.assert();
+        // A new signature gives the message another id.
+        assert!(matches!(
+            block_on(client.send(&signer, &message)),
+            Err(Error::InvalidReceipt(_))
+        ));
+
+        DataItem::from_bytes(&item.to_bytes()?)?.verify()?;
+        assert_eq!(item.target, process);
+        assert_eq!(
+            find_tag_value(&item.tags, "Data-Protocol").as_deref(),
+            Some("ao")
+        );
+        assert_eq!(
+            find_tag_value(&item.tags, "Action").as_deref(),
+            Some("Transfer")
+        );
+        assert_eq!(item.tags.last(), Some(&Tag::new("Quantity", "100")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_results() -> Result<(), Error> {
+        let process = Base64(vec![5; 32]);
+        let message = TxId::new([6; 32]);
+        let result = format!(
+            r#"{{
+                "Messages": [{{
+                    "Target": "{}",
+                    "Anchor": "00000000000000000000000000000001",
+                    "Data": "",
+                    "Tags": [{{"name": "Action", "value": "Balance"}}, {{"name": "Balance", "value": "100"}}]
+                }}],
+                "Spawns": [],
+                "Output": {{"data": "100", "print": true, "prompt": "aos> "}},
+                "GasUsed": 0
+            }}"#,
+            "a".repeat(43)
+        );
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/result/{}", message))
+                .query_param("process-id", process.to_string());
+            then.status(200)
+                .body(r#"{"Messages":[],"Spawns":[],"Output":"","Error":"boom"}"#);
+        });
+        let dry_run = server.mock(|when, then| {
+            when.method(POST)
+                .path("/dry-run")
+                .query_param("process-id", process.to_string())
+                .json_body_partial(format!(
+                    r#"{{"Owner":"owner","Data":"","Target":"{}"}}"#,
+                    process
+                ))
+                .body_contains(r#"{"name":"Action","value":"Balance"}"#);
+            then.status(200).body(result);
+        });
+        let client = AoClient::default().with_cu_url(url::Url::parse(&server.url("/")).unwrap());
+
+        let failed = block_on(client.get_result(&process, &message))?;
+        assert_eq!(failed.error.as_deref(), Some("boom"));
+        assert_eq!(failed.output, Some(Output::Text(String::new())));
+
+        let result = block_on(client.dry_run(&Message::new(process).action("Balance"), "owner"))?;
+        dry_run.assert();
+        assert_eq!(result.messages[0].tag("Balance"), Some("100"));
+        assert!(matches!(
+            result.output,
+            Some(Output::Value { print: true, .. })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,429 @@
+//! ArFS, the file system of ArDrive. Drives, folders and files are entities whose metadata is
+//! stored as JSON in transactions or data items, tagged with the ids linking them together.
+//! Metadata and data of private drives are encrypted with AES-256-GCM under a drive key, or a
+//! file key derived from it for files.
+
+use std::{fmt, str::FromStr};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::{
+    crypto::base64::Base64,
+    error::Error,
+    transaction::tags::{find_tag_value, Tag, UNIX_TIME},
+    tx_id::TxId,
+};
+
+pub const ARFS_VERSION: &str = "0.13";
+pub const CIPHER: &str = "AES256-GCM";
+
+pub const ARFS: &str = "ArFS";
+pub const ENTITY_TYPE: &str = "Entity-Type";
+pub const DRIVE_ID: &str = "Drive-Id";
+pub const FOLDER_ID: &str = "Folder-Id";
+pub const FILE_ID: &str = "File-Id";
+pub const PARENT_FOLDER_ID: &str = "Parent-Folder-Id";
+pub const DRIVE_PRIVACY: &str = "Drive-Privacy";
+pub const DRIVE_AUTH_MODE: &str = "Drive-Auth-Mode";
+pub const CIPHER_TAG: &str = "Cipher";
+pub const CIPHER_IV: &str = "Cipher-IV";
+
+const IV_SIZE: usize = 12;
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidEntity(reason.to_string())
+}
+
+/// HKDF-SHA256 without salt, expanded to a single 32 bytes block.
+fn hkdf(ikm: &[u8], info: &[u8]) -> [u8; 32] {
+    //Checked unwraps: HMAC accepts keys of any size.
+    let mut extract = <Hmac<Sha256> as Mac>::new_from_slice(&[0; 32]).unwrap();
+    extract.update(ikm);
+    let prk = extract.finalize().into_bytes();
+    let mut expand = <Hmac<Sha256> as Mac>::new_from_slice(&prk).unwrap();
+    expand.update(info);
+    expand.update(&[1]);
+    expand.finalize().into_bytes().into()
+}
+
+fn encrypt(key: &[u8; 32], plaintext: &[u8]) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let mut iv = vec![0; IV_SIZE];
+    rand::thread_rng().fill_bytes(&mut iv);
+    let ciphertext = Aes256Gcm::new(key.into())
+        .encrypt(Nonce::from_slice(&iv), plaintext)
+        .map_err(|_| invalid("encryption failed"))?;
+    Ok((ciphertext, iv))
+}
+
+fn decrypt(key: &[u8; 32], iv: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+    if iv.len() != IV_SIZE {
+        return Err(invalid("invalid cipher IV"));
+    }
+    Aes256Gcm::new(key.into())
+        .decrypt(Nonce::from_slice(iv), ciphertext)
+        .map_err(|_| invalid("wrong key or altered data"))
+}
+
+/// Key of a private drive, encrypting the metadata of the drive and its folders.
+#[derive(Clone, PartialEq, Eq)]
+pub struct DriveKey(pub [u8; 32]);
+
+impl fmt::Debug for DriveKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DriveKey(..)")
+    }
+}
+
+impl DriveKey {
+    /// Message the wallet signs to derive the key of `drive_id`.
+    pub fn signing_message(drive_id: &Uuid) -> Vec<u8> {
+        [b"drive".as_slice(), drive_id.as_bytes()].concat()
+    }
+
+    /// Derives the key from the wallet signature of [`DriveKey::signing_message`] and the drive
+    /// password.
+    pub fn derive(wallet_signature: &[u8], password: &str) -> Self {
+        Self(hkdf(wallet_signature, password.as_bytes()))
+    }
+
+    /// Key of the metadata and data of the file `file_id`.
+    pub fn file_key(&self, file_id: &Uuid) -> FileKey {
+        FileKey(hkdf(&self.0, file_id.as_bytes()))
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct FileKey(pub [u8; 32]);
+
+impl fmt::Debug for FileKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FileKey(..)")
+    }
+}
+
+impl FileKey {
+    /// Encrypts file data, returning the data and the tags of its transaction.
+    pub fn encrypt_data(&self, data: &[u8]) -> Result<(Vec<u8>, Vec<Tag<Base64>>), Error> {
+        let (ciphertext, iv) = encrypt(&self.0, data)?;
+        Ok((ciphertext, cipher_tags(&iv)))
+    }
+
+    /// Decrypts the data of a transaction tagged with `tags`.
+    pub fn decrypt_data(&self, tags: &[Tag<Base64>], data: &[u8]) -> Result<Vec<u8>, Error> {
+        decrypt(&self.0, &cipher_iv(tags)?, data)
+    }
+}
+
+fn cipher_tags(iv: &[u8]) -> Vec<Tag<Base64>> {
+    vec![
+        Tag::content_type("application/octet-stream"),
+        Tag::new(CIPHER_TAG, CIPHER),
+        Tag::new(CIPHER_IV, &base64::encode(iv)),
+    ]
+}
+
+fn cipher_iv(tags: &[Tag<Base64>]) -> Result<Vec<u8>, Error> {
+    match find_tag_value(tags, CIPHER_TAG).as_deref() {
+        Some(CIPHER) => {}
+        Some(_) => return Err(invalid("unsupported cipher")),
+        None => return Err(invalid("data is not encrypted")),
+    }
+    let iv = find_tag_value(tags, CIPHER_IV).ok_or_else(|| invalid("missing cipher IV"))?;
+    base64::decode(iv).map_err(|_| invalid("invalid cipher IV"))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DriveMetadata {
+    pub name: String,
+    pub root_folder_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FolderMetadata {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMetadata {
+    pub name: String,
+    pub size: u64,
+    /// Milliseconds since the Unix epoch.
+    pub last_modified_date: u64,
+    /// Transaction or data item holding the file data.
+    pub data_tx_id: TxId,
+    pub data_content_type: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Metadata {
+    Drive(DriveMetadata),
+    Folder(FolderMetadata),
+    File(FileMetadata),
+}
+
+impl Metadata {
+    pub fn entity_type(&self) -> &'static str {
+        match self {
+            Metadata::Drive(_) => "drive",
+            Metadata::Folder(_) => "folder",
+            Metadata::File(_) => "file",
+        }
+    }
+}
+
+/// Drive, folder or file entity. `id` is the drive, folder or file id of the entity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entity {
+    pub drive_id: Uuid,
+    pub id: Uuid,
+    /// Folder holding the folder or file, `None` for drives and root folders.
+    pub parent_folder_id: Option<Uuid>,
+    /// Seconds since the Unix epoch.
+    pub unix_time: u64,
+    pub metadata: Metadata,
+}
+
+impl Entity {
+    /// New drive named `name` and its root folder.
+    pub fn new_drive(name: &str, unix_time: u64) -> (Entity, Entity) {
+        let drive_id = Uuid::new_v4();
+        let root_folder_id = Uuid::new_v4();
+        let drive = Entity {
+            drive_id,
+            id: drive_id,
+            parent_folder_id: None,
+            unix_time,
+            metadata: Metadata::Drive(DriveMetadata {
+                name: name.to_string(),
+                root_folder_id,
+            }),
+        };
+        let root_folder = Entity {
+            drive_id,
+            id: root_folder_id,
+            parent_folder_id: None,
+            unix_time,
+            metadata: Metadata::Folder(FolderMetadata {
+                name: name.to_string(),
+            }),
+        };
+        (drive, root_folder)
+    }
+
+    pub fn new_folder(drive_id: Uuid, parent_folder_id: Uuid, name: &str, unix_time: u64) -> Self {
+        Entity {
+            drive_id,
+            id: Uuid::new_v4(),
+            parent_folder_id: Some(parent_folder_id),
+            unix_time,
+            metadata: Metadata::Folder(FolderMetadata {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    /// New file entity, its id is needed to derive the file key before the data is uploaded.
+    pub fn new_file(
+        drive_id: Uuid,
+        parent_folder_id: Uuid,
+        metadata: FileMetadata,
+        unix_time: u64,
+    ) -> Self {
+        Entity {
+            drive_id,
+            id: Uuid::new_v4(),
+            parent_folder_id: Some(parent_folder_id),
+            unix_time,
+            metadata: Metadata::File(metadata),
+        }
+    }
+
+    fn id_tag(&self) -> &'static str {
+        match self.metadata {
+            Metadata::Drive(_) => DRIVE_ID,
+            Metadata::Folder(_) => FOLDER_ID,
+            Metadata::File(_) => FILE_ID,
+        }
+    }
+
+    /// Tags and data of the metadata transaction, encrypted with `key` for private drives.
+    pub fn encode(&self, key: Option<&DriveKey>) -> Result<(Vec<Tag<Base64>>, Vec<u8>), Error> {
+        let json = match &self.metadata {
+            Metadata::Drive(metadata) => serde_json::to_vec(metadata),
+            Metadata::Folder(metadata) => serde_json::to_vec(metadata),
+            Metadata::File(metadata) => serde_json::to_vec(metadata),
+        }
+        .map_err(Error::SerdeJsonError)?;
+
+        let mut tags = vec![Tag::new(ARFS, ARFS_VERSION)];
+        let data = match key {
+            Some(key) => {
+                let key = match self.metadata {
+                    Metadata::File(_) => key.file_key(&self.id).0,
+                    _ => key.0,
+                };
+                let (ciphertext, iv) = encrypt(&key, &json)?;
+                tags.extend(cipher_tags(&iv));
+                ciphertext
+            }
+            None => {
+                tags.push(Tag::content_type("application/json"));
+                json
+            }
+        };
+        tags.push(Tag::new(ENTITY_TYPE, self.metadata.entity_type()));
+        tags.push(Tag::new(DRIVE_ID, &self.drive_id.to_string()));
+        if let Metadata::Drive(_) = self.metadata {
+            match key {
+                Some(_) => {
+                    tags.push(Tag::new(DRIVE_PRIVACY, "private"));
+                    tags.push(Tag::new(DRIVE_AUTH_MODE, "password"));
+                }
+                None => tags.push(Tag::new(DRIVE_PRIVACY, "public")),
+            }
+        } else {
+            tags.push(Tag::new(self.id_tag(), &self.id.to_string()));
+        }
+        if let Some(parent_folder_id) = self.parent_folder_id {
+            tags.push(Tag::new(PARENT_FOLDER_ID, &parent_folder_id.to_string()));
+        }
+        tags.push(Tag::unix_time(self.unix_time));
+        Ok((tags, data))
+    }
+
+    /// Parses the metadata transaction tagged with `tags` holding `data`. `key` is needed for
+    /// entities of private drives.
+    pub fn decode(
+        tags: &[Tag<Base64>],
+        data: &[u8],
+        key: Option<&DriveKey>,
+    ) -> Result<Self, Error> {
+        let tag = |name: &str| find_tag_value(tags, name).ok_or_else(|| invalid(name));
+        let uuid = |name: &str| Uuid::from_str(&tag(name)?).map_err(|_| invalid(name));
+        if find_tag_value(tags, ARFS).is_none() {
+            return Err(invalid("not an ArFS entity"));
+        }
+        let entity_type = tag(ENTITY_TYPE)?;
+        let drive_id = uuid(DRIVE_ID)?;
+        let id = match entity_type.as_str() {
+            "drive" => drive_id,
+            "folder" => uuid(FOLDER_ID)?,
+            "file" => uuid(FILE_ID)?,
+            _ => return Err(invalid(ENTITY_TYPE)),
+        };
+
+        let json = if find_tag_value(tags, CIPHER_TAG).is_some() {
+            let key = key.ok_or_else(|| invalid("private entity needs the drive key"))?;
+            let key = match entity_type.as_str() {
+                "file" => key.file_key(&id).0,
+                _ => key.0,
+            };
+            decrypt(&key, &cipher_iv(tags)?, data)?
+        } else {
+            data.to_vec()
+        };
+        let metadata = match entity_type.as_str() {
+            "drive" => serde_json::from_slice(&json).map(Metadata::Drive),
+            "folder" => serde_json::from_slice(&json).map(Metadata::Folder),
+            _ => serde_json::from_slice(&json).map(Metadata::File),
+        }
+        .map_err(Error::SerdeJsonError)?;
+
+        Ok(Entity {
+            drive_id,
+            id,
+            parent_folder_id: find_tag_value(tags, PARENT_FOLDER_ID)
+                .map(|_| uuid(PARENT_FOLDER_ID))
+                .transpose()?,
+            unix_time: find_tag_value(tags, UNIX_TIME)
+                .and_then(|time| time.parse().ok())
+                .unwrap_or_default(),
+            metadata,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::{error::Error, transaction::tags::find_tag_value, tx_id::TxId};
+
+    use super::{DriveKey, Entity, FileMetadata, Metadata};
+
+    #[test]
+    fn test_public_drive() -> Result<(), Error> {
+        let (drive, root) = Entity::new_drive("Photos", 1_700_000_000);
+        let file = Entity::new_file(
+            drive.drive_id,
+            root.id,
+            FileMetadata {
+                name: "cat.png".to_string(),
+                size: 1024,
+                last_modified_date: 1_700_000_000_000,
+                data_tx_id: TxId::new([1; 32]),
+                data_content_type: "image/png".to_string(),
+            },
+            1_700_000_000,
+        );
+
+        let (tags, data) = drive.encode(None)?;
+        assert_eq!(
+            find_tag_value(&tags, "Drive-Privacy").as_deref(),
+            Some("public")
+        );
+        let json: serde_json::Value = serde_json::from_slice(&data).unwrap();
+        assert_eq!(json["rootFolderId"], root.id.to_string());
+        assert_eq!(Entity::decode(&tags, &data, None)?, drive);
+
+        let (tags, data) = file.encode(None)?;
+        assert_eq!(
+            find_tag_value(&tags, "Parent-Folder-Id"),
+            Some(root.id.to_string())
+        );
+        assert_eq!(Entity::decode(&tags, &data, None)?, file);
+        Ok(())
+    }
+
+    #[test]
+    fn test_private_drive() -> Result<(), Error> {
+        let (drive, _) = Entity::new_drive("Private", 1_700_000_000);
+        let key = DriveKey::derive(b"wallet signature", "password");
+        let file_id = Uuid::new_v4();
+
+        let (tags, data) = drive.encode(Some(&key))?;
+        assert_eq!(
+            find_tag_value(&tags, "Drive-Privacy").as_deref(),
+            Some("private")
+        );
+        assert_eq!(
+            find_tag_value(&tags, "Cipher").as_deref(),
+            Some("AES256-GCM")
+        );
+        assert!(serde_json::from_slice::<serde_json::Value>(&data).is_err());
+        let decoded = Entity::decode(&tags, &data, Some(&key))?;
+        assert!(matches!(decoded.metadata, Metadata::Drive(ref m) if m.name == "Private"));
+        assert!(Entity::decode(&tags, &data, None).is_err());
+        let other = DriveKey::derive(b"wallet signature", "other");
+        assert!(matches!(
+            Entity::decode(&tags, &data, Some(&other)),
+            Err(Error::InvalidEntity(_))
+        ));
+
+        let file_key = key.file_key(&file_id);
+        assert_ne!(file_key.0, key.0);
+        let (ciphertext, tags) = file_key.encrypt_data(b"secret")?;
+        assert_eq!(file_key.decrypt_data(&tags, &ciphertext)?, b"secret");
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+//! Discovery of AR.IO gateways from the network registry, so that clients can rotate between
+//! staked, healthy gateways instead of relying on arweave.net alone.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), arweave_rs::error::Error> {
+//! use arweave_rs::{
+//!     ario::{GatewayFilter, GatewayRegistry},
+//!     ArweaveBuilder,
+//! };
+//!
+//! let gateways = GatewayRegistry::default()
+//!     .discover(&GatewayFilter::new().min_stake(50_000_000_000).limit(5))
+//!     .await?;
+//! let arweave = ArweaveBuilder::new().gateways(gateways).build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::HashMap, sync::Arc};
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    arns::ARNS_REGISTRY,
+    error::Error,
+    gateway::GatewayPool,
+    transport::{default_transport, HttpRequest, HttpTransport},
+};
+
+/// Service serving the evaluated state of the AR.IO network contract.
+pub const AR_IO_API_URL: &str = "https://api.arns.app/";
+
+/// Status of gateways serving the network, as opposed to leaving ones.
+pub const JOINED: &str = "joined";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewaySettings {
+    pub fqdn: String,
+    pub port: u16,
+    pub protocol: String,
+    #[serde(default)]
+    pub label: String,
+}
+
+/// Gateway registered in the network contract.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Gateway {
+    /// Wallet of the operator, the key of the gateway in the registry.
+    #[serde(default)]
+    pub address: String,
+    /// Stake of the operator in mIO.
+    pub operator_stake: u64,
+    pub status: String,
+    pub settings: GatewaySettings,
+}
+
+impl Gateway {
+    pub fn url(&self) -> Result<url::Url, Error> {
+        let settings = &self.settings;
+        url::Url::parse(&format!(
+            "{}://{}:{}/",
+            settings.protocol, settings.fqdn, settings.port
+        ))
+        .map_err(Error::UrlParseError)
+    }
+}
+
+#[derive(Deserialize)]
+struct Gateways {
+    gateways: HashMap<String, Gateway>,
+}
+
+/// Selection of discovered gateways.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GatewayFilter {
+    pub min_stake: u64,
+    pub limit: Option<usize>,
+    /// Keeps only gateways answering their `info` endpoint in time, see
+    /// [`GatewayPool::health_check`].
+    pub health_check: bool,
+}
+
+impl Default for GatewayFilter {
+    fn default() -> Self {
+        Self {
+            min_stake: 0,
+            limit: None,
+            health_check: true,
+        }
+    }
+}
+
+impl GatewayFilter {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn min_stake(mut self, min_stake: u64) -> Self {
+        self.min_stake = min_stake;
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn health_check(mut self, health_check: bool) -> Self {
+        self.health_check = health_check;
+        self
+    }
+}
+
+pub struct GatewayRegistry {
+    transport: Arc<dyn HttpTransport>,
+    url: url::Url,
+}
+
+impl Default for GatewayRegistry {
+    fn default() -> Self {
+        Self::new(url::Url::parse(AR_IO_API_URL).unwrap()) //Checked unwrap
+    }
+}
+
+impl GatewayRegistry {
+    pub fn new(url: url::Url) -> Self {
+        Self {
+            transport: default_transport(),
+            url,
+        }
+    }
+
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Registered gateways, from the largest stake to the smallest.
+    pub async fn get_gateways(&self) -> Result<Vec<Gateway>, Error> {
+        let url = self
+            .url
+            .join(&format!("v1/contract/{}/gateways", ARNS_REGISTRY))
+            .map_err(Error::UrlParseError)?;
+        let res = self.transport.send(HttpRequest::get(url)).await?;
+        if res.status != StatusCode::OK {
+            return Err(Error::from_response(&res));
+        }
+        let mut gateways: Vec<Gateway> = res
+            .json::<Gateways>()?
+            .gateways
+            .into_iter()
+            .map(|(address, gateway)| Gateway { address, ..gateway })
+            .collect();
+        gateways.sort_by(|a, b| {
+            b.operator_stake
+                .cmp(&a.operator_stake)
+                .then_with(|| a.address.cmp(&b.address))
+        });
+        Ok(gateways)
+    }
+
+    /// Urls of the joined gateways selected by `filter`, from the largest stake to the
+    /// smallest, to be given to [`ArweaveBuilder::gateways`](crate::ArweaveBuilder::gateways).
+    pub async fn discover(&self, filter: &GatewayFilter) -> Result<Vec<url::Url>, Error> {
+        let urls = self
+            .get_gateways()
+            .await?
+            .iter()
+            .filter(|gateway| {
+                gateway.status == JOINED && gateway.operator_stake >= filter.min_stake
+            })
+            .filter_map(|gateway| gateway.url().ok())
+            .collect::<Vec<_>>();
+        let mut urls = if filter.health_check && !urls.is_empty() {
+            let pool = GatewayPool::new(urls)?;
+            pool.health_check(self.transport.as_ref()).await;
+            pool.available_urls()
+        } else {
+            urls
+        };
+        if let Some(limit) = filter.limit {
+            urls.truncate(limit);
+        }
+        Ok(urls)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::{Method::GET, MockServer};
+    use serde_json::json;
+    use tokio_test::block_on;
+
+    use crate::{arns::ARNS_REGISTRY, error::Error};
+
+    use super::{GatewayFilter, GatewayRegistry};
+
+    #[test]
+    fn test_discover() -> Result<(), Error> {
+        let gateway = MockServer::start();
+        gateway.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200);
+        });
+        let settings = |port: u16| json!({ "fqdn": "127.0.0.1", "port": port, "protocol": "http", "label": "test" });
+        let registry = MockServer::start();
+        registry.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/v1/contract/{}/gateways", ARNS_REGISTRY));
+            then.status(200).json_body(json!({
+                "contractTxId": ARNS_REGISTRY,
+                "gateways": {
+                    "healthy": { "operatorStake": 20, "status": "joined", "settings": settings(gateway.port()) },
+                    "unreachable": { "operatorStake": 30, "status": "joined", "settings": settings(1) },
+                    "leaving": { "operatorStake": 40, "status": "leaving", "settings": settings(gateway.port()) },
+                    "small": { "operatorStake": 5, "status": "joined", "settings": settings(gateway.port()) },
+                }
+            }));
+        });
+        let registry = GatewayRegistry::new(url::Url::parse(&registry.url("/")).unwrap());
+
+        let gateways = block_on(registry.get_gateways())?;
+        assert_eq!(gateways[0].address, "leaving");
+        let urls = block_on(registry.discover(&GatewayFilter::new().min_stake(10)))?;
+        assert_eq!(urls, vec![url::Url::parse(&gateway.url("/")).unwrap()]);
+        let urls = block_on(registry.discover(&GatewayFilter::new().health_check(false).limit(2)))?;
+        assert_eq!(urls.len(), 2);
+        assert_eq!(urls[0].port(), Some(1));
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+//! Resolution of ArNS names, such as `ardrive` or the undername `dapp_ardrive`, to the
+//! transaction they point to. Names are resolved by AR.IO gateways, or from the registry
+//! contract state when the `smartweave` feature is enabled and a registry is configured.
+
+use std::sync::Arc;
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+#[cfg(feature = "smartweave")]
+use crate::{
+    crypto::base64::Base64,
+    smartweave::{StateQuery, WarpClient},
+};
+use crate::{
+    error::Error,
+    transport::{default_transport, HttpRequest, HttpTransport},
+    tx_id::TxId,
+};
+
+/// Contract holding the ArNS records.
+pub const ARNS_REGISTRY: &str = "bLAgYxAdX2Ry-nt6aH2ixgvJXbpsEYm28NgJgyqfs-U";
+
+/// Undername records of the name itself are stored under.
+pub const ROOT_UNDERNAME: &str = "@";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Resolution {
+    tx_id: String,
+}
+
+/// Splits `name` into its undername and base name, the undername being [`ROOT_UNDERNAME`] for
+/// base names.
+pub fn split_name(name: &str) -> Result<(String, String), Error> {
+    let name = name.trim().to_lowercase();
+    let valid = |part: &str| {
+        !part.is_empty()
+            && !part.starts_with('-')
+            && part
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    };
+    let (undername, base) = match name.rsplit_once('_') {
+        Some((undername, base)) => (undername.to_string(), base.to_string()),
+        None => (ROOT_UNDERNAME.to_string(), name.clone()),
+    };
+    let undername_valid = undername == ROOT_UNDERNAME || undername.split('_').all(valid);
+    if !valid(&base) || base.len() > 51 || !undername_valid {
+        return Err(Error::InvalidData(format!("invalid ArNS name {}", name)));
+    }
+    Ok((undername, base))
+}
+
+pub struct ArnsClient {
+    transport: Arc<dyn HttpTransport>,
+    url: url::Url,
+    #[cfg(feature = "smartweave")]
+    registry: Option<(WarpClient, Base64)>,
+}
+
+impl ArnsClient {
+    /// Client resolving names with the AR.IO gateway `url`.
+    pub fn new(url: url::Url) -> Self {
+        Self {
+            transport: default_transport(),
+            url,
+            #[cfg(feature = "smartweave")]
+            registry: None,
+        }
+    }
+
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Reads the records of the `registry` contract from `warp` when the gateway cannot
+    /// resolve a name.
+    #[cfg(feature = "smartweave")]
+    pub fn with_registry(mut self, warp: WarpClient, registry: Base64) -> Self {
+        self.registry = Some((warp, registry));
+        self
+    }
+
+    /// Id of the transaction `name` points to.
+    pub async fn resolve(&self, name: &str) -> Result<TxId, Error> {
+        let (undername, base) = split_name(name)?;
+        let resolved = self.resolve_with_gateway(&undername, &base).await;
+        #[cfg(feature = "smartweave")]
+        if let (Err(_), Some((warp, registry))) = (&resolved, &self.registry) {
+            return resolve_with_registry(warp, registry, &undername, &base).await;
+        }
+        resolved
+    }
+
+    async fn resolve_with_gateway(&self, undername: &str, base: &str) -> Result<TxId, Error> {
+        let name = match undername {
+            ROOT_UNDERNAME => base.to_string(),
+            _ => format!("{}_{}", undername, base),
+        };
+        let url = self
+            .url
+            .join(&format!("ar-io/resolver/records/{}", name))
+            .map_err(Error::UrlParseError)?;
+        let res = self.transport.send(HttpRequest::get(url)).await?;
+        match res.status {
+            StatusCode::OK => {
+                let resolution: Resolution = res.json()?;
+                resolution.tx_id.parse()
+            }
+            StatusCode::NOT_FOUND => Err(Error::ArnsNameNotFound(name)),
+            _ => Err(Error::from_response(&res)),
+        }
+    }
+}
+
+/// Follows the registry record of `base` to its ANT contract, then the record of `undername`
+/// in the ANT.
+#[cfg(feature = "smartweave")]
+async fn resolve_with_registry(
+    warp: &WarpClient,
+    registry: &Base64,
+    undername: &str,
+    base: &str,
+) -> Result<TxId, Error> {
+    let not_found = || Error::ArnsNameNotFound(format!("{}_{}", undername, base));
+    let state = warp
+        .get_state::<serde_json::Value>(registry, &StateQuery::new())
+        .await?
+        .state;
+    let ant: Base64 = state["records"][base]["contractTxId"]
+        .as_str()
+        .ok_or_else(not_found)?
+        .parse()
+        .map_err(Error::Base64DecodeError)?;
+    let state = warp
+        .get_state::<serde_json::Value>(&ant, &StateQuery::new())
+        .await?
+        .state;
+    let record = &state["records"][undername];
+    // Records of older ANTs are plain transaction ids.
+    record["transactionId"]
+        .as_str()
+        .or_else(|| record.as_str())
+        .ok_or_else(not_found)?
+        .parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::{Method::GET, MockServer};
+    use tokio_test::block_on;
+
+    use crate::{error::Error, tx_id::TxId};
+
+    use super::{split_name, ArnsClient};
+
+    #[test]
+    fn test_split_name() -> Result<(), Error> {
+        assert_eq!(
+            split_name("ArDrive")?,
+            ("@".to_string(), "ardrive".to_string())
+        );
+        assert_eq!(
+            split_name("dapp_ardrive")?,
+            ("dapp".to_string(), "ardrive".to_string())
+        );
+        assert!(split_name("ar.drive").is_err());
+        assert!(split_name("dapp_").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve() -> Result<(), Error> {
+        let id = TxId::new([6; 32]);
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/ar-io/resolver/records/dapp_ardrive");
+            then.status(200).body(format!(
+                r#"{{"txId":"{}","ttlSeconds":3600,"processId":"process"}}"#,
+                id
+            ));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/ar-io/resolver/records/missing");
+            then.status(404);
+        });
+        let client = ArnsClient::new(url::Url::parse(&server.url("/")).unwrap());
+
+        assert_eq!(block_on(client.resolve("dapp_ardrive"))?, id);
+        assert!(matches!(
+            block_on(client.resolve("missing")),
+            Err(Error::ArnsNameNotFound(_))
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "smartweave")]
+    #[test]
+    fn test_resolve_with_registry() -> Result<(), Error> {
+        use crate::{crypto::base64::Base64, smartweave::WarpClient};
+
+        let registry = Base64(vec![1; 32]);
+        let ant = Base64(vec![2; 32]);
+        let id = TxId::new([6; 32]);
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/ar-io/resolver/records/ardrive");
+            then.status(502);
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/v1/contract")
+                .query_param("id", registry.to_string());
+            then.status(200).json_body(serde_json::json!({
+                "contractTxId": registry.to_string(),
+                "state": { "records": { "ardrive": { "contractTxId": ant.to_string() } } }
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/v1/contract")
+                .query_param("id", ant.to_string());
+            then.status(200).json_body(serde_json::json!({
+                "contractTxId": ant.to_string(),
+                "state": { "records": { "@": { "transactionId": id.to_string() } } }
+            }));
+        });
+        let url = url::Url::parse(&server.url("/")).unwrap();
+        let client = ArnsClient::new(url.clone()).with_registry(WarpClient::new(url), registry);
+
+        assert_eq!(block_on(client.resolve("ardrive"))?, id);
+        Ok(())
+    }
+}
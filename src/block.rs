@@ -0,0 +1,157 @@
+//! Heights and independent hashes of blocks, kept apart from plain integers and bytes so that
+//! heights, counts and hashes of other sizes cannot be mixed up.
+
+use std::{
+    fmt,
+    ops::{Add, AddAssign},
+    str::FromStr,
+};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::crypto::base64::fixed_base64;
+
+/// Length of an independent hash in base64url.
+pub const BLOCK_HASH_LENGTH: usize = 64;
+
+/// Height of a block, the number of blocks before it in the chain.
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default,
+)]
+#[serde(transparent)]
+pub struct BlockHeight(u64);
+
+impl BlockHeight {
+    pub const GENESIS: BlockHeight = BlockHeight(0);
+
+    pub fn new(height: u64) -> Self {
+        Self(height)
+    }
+
+    pub fn get(self) -> u64 {
+        self.0
+    }
+
+    /// Height `blocks` blocks earlier, `None` if it would be below the genesis block.
+    pub fn checked_sub(self, blocks: u64) -> Option<Self> {
+        self.0.checked_sub(blocks).map(Self)
+    }
+
+    /// Number of blocks from `earlier` to this height, `None` if `earlier` is higher.
+    pub fn blocks_since(self, earlier: BlockHeight) -> Option<u64> {
+        self.0.checked_sub(earlier.0)
+    }
+}
+
+impl From<u64> for BlockHeight {
+    fn from(height: u64) -> Self {
+        Self(height)
+    }
+}
+
+impl From<BlockHeight> for u64 {
+    fn from(height: BlockHeight) -> Self {
+        height.0
+    }
+}
+
+impl fmt::Display for BlockHeight {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for BlockHeight {
+    type Err = std::num::ParseIntError;
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        str.parse().map(Self)
+    }
+}
+
+impl Add<u64> for BlockHeight {
+    type Output = BlockHeight;
+    fn add(self, blocks: u64) -> Self::Output {
+        Self(self.0 + blocks)
+    }
+}
+
+impl AddAssign<u64> for BlockHeight {
+    fn add_assign(&mut self, blocks: u64) {
+        self.0 += blocks;
+    }
+}
+
+fixed_base64!(
+    /// Independent hash of a block, the id blocks are fetched and referenced by.
+    BlockHash,
+    48,
+    BLOCK_HASH_LENGTH,
+    InvalidBlockHash
+);
+
+/// Serializes the hash of the previous block as [`deserialize_previous`] reads it back, empty
+/// for the genesis block.
+pub(crate) fn serialize_previous<S: Serializer>(
+    previous: &Option<BlockHash>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match previous {
+        Some(hash) => hash.serialize(serializer),
+        None => serializer.serialize_str(""),
+    }
+}
+
+/// Deserializes the hash of the previous block, empty or null for the genesis block.
+pub(crate) fn deserialize_previous<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<BlockHash>, D::Error> {
+    match Option::<String>::deserialize(deserializer)? {
+        Some(string) if !string.is_empty() => string.parse().map(Some).map_err(de::Error::custom),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{crypto::base64::Base64, error::Error};
+
+    use super::{BlockHash, BlockHeight};
+
+    #[test]
+    fn test_block_height() {
+        let height = BlockHeight::new(1_300_000);
+        assert_eq!(height + 10, BlockHeight::new(1_300_010));
+        assert_eq!((height + 10).blocks_since(height), Some(10));
+        assert_eq!(height.checked_sub(1), Some(BlockHeight::new(1_299_999)));
+        assert!(height < height + 1);
+        assert_eq!(BlockHeight::GENESIS.checked_sub(1), None);
+        assert_eq!(BlockHeight::GENESIS.blocks_since(height), None);
+        assert_eq!(height.blocks_since(BlockHeight::GENESIS), Some(1_300_000));
+        assert_eq!(serde_json::to_string(&height).unwrap(), "1300000");
+        assert_eq!(
+            serde_json::from_str::<BlockHeight>("1300000").unwrap(),
+            height
+        );
+        assert_eq!("1300000".parse::<BlockHeight>().unwrap(), height);
+    }
+
+    #[test]
+    fn test_block_hash() -> Result<(), Error> {
+        let hash = BlockHash::new([7; 48]);
+        let string = hash.to_string();
+        assert_eq!(string.len(), 64);
+        assert_eq!(string.parse::<BlockHash>()?, hash);
+        assert_eq!(BlockHash::try_from(&hash.to_base64())?, hash);
+        assert_eq!(
+            serde_json::from_str::<BlockHash>(&serde_json::to_string(&hash).unwrap()).unwrap(),
+            hash
+        );
+        // Transaction ids are 32 bytes.
+        assert!(matches!(
+            Base64(vec![7; 32]).to_string().parse::<BlockHash>(),
+            Err(Error::InvalidBlockHash(_))
+        ));
+        assert!(BlockHash::try_from(&Base64(vec![7; 32])).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,401 @@
+//! Synchronous client for scripts and tools that do not run an async runtime, built on
+//! [`reqwest::blocking`]. It must not be used from within an async runtime.
+
+use std::{path::PathBuf, str::FromStr};
+
+use reqwest::{
+    blocking::Response,
+    header::{ACCEPT, CONTENT_TYPE},
+    StatusCode,
+};
+
+use crate::{
+    address::Address,
+    crypto::base64::Base64,
+    currency::Currency,
+    error::Error,
+    network::Network,
+    retry::RetryPolicy,
+    transaction::{tags::Tag, Tx},
+    tx_id::TxId,
+    types::TxStatus,
+    ArweaveSigner,
+};
+
+/// Anchors are either a block hash (48 bytes) or a transaction id (32 bytes).
+const MAX_ANCHOR_LENGTH: usize = 48;
+
+/// Blocking counterpart of [`crate::Arweave`], covering submission, balances, statuses and
+/// uploads.
+pub struct Client {
+    client: reqwest::blocking::Client,
+    base_url: url::Url,
+    network: Network,
+    signer: Option<ArweaveSigner>,
+    retry_policy: RetryPolicy,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new(Network::Mainnet.url()).with_network(Network::Mainnet)
+    }
+}
+
+impl Client {
+    /// Read-only client for `base_url`, with the protocol parameters of mainnet.
+    pub fn new(base_url: url::Url) -> Self {
+        Self {
+            client: reqwest::blocking::Client::new(),
+            network: Network::custom(base_url.clone()),
+            base_url,
+            signer: None,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    pub fn from_keypair_path(keypair_path: PathBuf, base_url: url::Url) -> Result<Self, Error> {
+        Ok(Self::new(base_url).with_signer(ArweaveSigner::from_keypair_path(keypair_path)?))
+    }
+
+    pub fn with_signer(mut self, signer: ArweaveSigner) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Network whose gateway and protocol parameters are used, replacing the base url.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.base_url = network.url();
+        self.network = network;
+        self
+    }
+
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    pub fn with_client(mut self, client: reqwest::blocking::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Policy applied to transaction submission, chunk posting and reads.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    fn signer(&self) -> Result<&ArweaveSigner, Error> {
+        self.signer.as_ref().ok_or(Error::MissingSigner)
+    }
+
+    /// Sends a GET request, retrying transport failures and server errors.
+    fn get(&self, path: &str) -> Result<Response, Error> {
+        let url = self.base_url.join(path).map_err(Error::UrlParseError)?;
+        self.retry_policy
+            .retry_blocking(|| match self.client.get(url.clone()).send() {
+                Ok(res)
+                    if res.status().is_server_error()
+                        || res.status() == StatusCode::TOO_MANY_REQUESTS =>
+                {
+                    Err(Error::from_blocking_response(res))
+                }
+                Ok(res) => Ok(res),
+                Err(err) => Err(Error::ReqwestError(err)),
+            })
+    }
+
+    /// Sends `body` as JSON to `path`, retrying until the node answers with 200.
+    fn post_json<T: serde::Serialize>(&self, path: &str, body: &T) -> Result<(), Error> {
+        let url = self.base_url.join(path).map_err(Error::UrlParseError)?;
+        self.retry_policy.retry_blocking(|| {
+            match self
+                .client
+                .post(url.clone())
+                .json(body)
+                .header(&ACCEPT, "application/json")
+                .header(&CONTENT_TYPE, "application/json")
+                .send()
+            {
+                Ok(res) if res.status() == StatusCode::OK => Ok(()),
+                Ok(res) => Err(Error::from_blocking_response(res)),
+                Err(err) => Err(Error::ReqwestError(err)),
+            }
+        })
+    }
+
+    /// Fetches a recent anchor to be used as the `last_tx` of a new transaction.
+    pub fn get_tx_anchor(&self) -> Result<Base64, Error> {
+        let res = self.get("tx_anchor")?;
+        if res.status() != StatusCode::OK {
+            return Err(Error::from_blocking_response(res));
+        }
+        let anchor_str = res.text().map_err(Error::ReqwestError)?;
+        let anchor = Base64::from_str(anchor_str.trim()).map_err(Error::Base64DecodeError)?;
+        if anchor.is_empty() || anchor.0.len() > MAX_ANCHOR_LENGTH {
+            return Err(Error::TransactionInfoError(format!(
+                "invalid tx anchor of {} bytes",
+                anchor.0.len()
+            )));
+        }
+        Ok(anchor)
+    }
+
+    /// Winstons required to store `data_size` bytes, optionally transferring to a new `target`.
+    pub fn get_price(&self, data_size: u64, target: Option<&Address>) -> Result<u64, Error> {
+        let path = match target {
+            Some(target) => format!("price/{}/{}", data_size, target),
+            None => format!("price/{}", data_size),
+        };
+        self.get(&path)?.json::<u64>().map_err(Error::ReqwestError)
+    }
+
+    /// Balance of `address` in winstons.
+    pub fn get_balance(&self, address: &Address) -> Result<Currency, Error> {
+        let res = self.get(&format!("wallet/{}/balance", address))?;
+        if res.status() != StatusCode::OK {
+            return Err(Error::WalletError(res.status().to_string()));
+        }
+        let balance = res.text().map_err(Error::ReqwestError)?;
+        let winstons = u128::from_str(balance.trim()).map_err(|_| {
+            Error::WalletError(format!("invalid balance for {}: {}", address, balance))
+        })?;
+        Ok(Currency::from(winstons))
+    }
+
+    pub fn get_tx_status(&self, id: &TxId) -> Result<(StatusCode, Option<TxStatus>), Error> {
+        let res = self.get(&format!("tx/{}/status", id))?;
+        if res.status() == StatusCode::OK {
+            let status = res
+                .json::<TxStatus>()
+                .map_err(|err| Error::TransactionInfoError(err.to_string()))?;
+            Ok((StatusCode::OK, Some(status)))
+        } else if res.status() == StatusCode::ACCEPTED {
+            Ok((StatusCode::ACCEPTED, None))
+        } else {
+            Err(Error::TransactionInfoError(res.status().to_string()))
+        }
+    }
+
+    pub fn create_transaction(
+        &self,
+        target: Option<Address>,
+        other_tags: Vec<Tag<Base64>>,
+        data: Vec<u8>,
+        quantity: u128,
+        fee: u64,
+        auto_content_tag: bool,
+    ) -> Result<Tx, Error> {
+        let last_tx = self.get_tx_anchor()?;
+        Tx::new(
+            self.signer()?.get_provider(),
+            target.map_or_else(Base64::empty, |target| target.to_base64()),
+            data,
+            quantity,
+            fee,
+            last_tx,
+            other_tags,
+            auto_content_tag,
+        )
+    }
+
+    /// Signs `transaction` once checked with [`Tx::validate_for`] the protocol parameters of
+    /// the network.
+    pub fn sign_transaction(&self, transaction: Tx) -> Result<Tx, Error> {
+        let signer = self.signer()?;
+        transaction.sign_for(signer.get_provider(), &self.network.params())
+    }
+
+    /// Posts a signed transaction. Data above the `max_tx_data` of the network is posted chunk
+    /// by chunk after the header.
+    pub fn post_transaction(&self, signed_transaction: &Tx) -> Result<(TxId, u64), Error> {
+        if signed_transaction.id.0.is_empty() {
+            return Err(Error::UnsignedTransaction);
+        }
+        let id = TxId::try_from(&signed_transaction.id)?;
+        if signed_transaction.data.0.len() > self.network.params().max_tx_data as usize {
+            self.post_json("tx", &signed_transaction.clone_with_no_data()?)?;
+            for idx in 0..signed_transaction.chunks.len() {
+                let chunk =
+                    signed_transaction.get_chunk_ref_from(idx, &signed_transaction.data.0)?;
+                self.post_json("chunk", &chunk)?;
+            }
+        } else {
+            self.post_json("tx", signed_transaction)?;
+        }
+        Ok((id, signed_transaction.reward))
+    }
+
+    /// Creates, signs and posts a transaction holding `buffer`, tagged with `mime_type` if
+    /// given.
+    pub fn upload_bytes<T: AsRef<[u8]>>(
+        &self,
+        buffer: T,
+        mime_type: Option<&str>,
+        additional_tags: Vec<Tag<Base64>>,
+        fee: u64,
+    ) -> Result<(TxId, u64), Error> {
+        let mut additional_tags = additional_tags;
+        if let Some(mime_type) = mime_type {
+            additional_tags.push(Tag::content_type(mime_type));
+        }
+        let transaction = self.create_transaction(
+            None,
+            additional_tags,
+            buffer.as_ref().into(),
+            0,
+            fee,
+            mime_type.is_none(),
+        )?;
+        let signed_transaction = self.sign_transaction(transaction)?;
+        self.post_transaction(&signed_transaction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, time::Duration};
+
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use reqwest::StatusCode;
+
+    use crate::{
+        address::Address,
+        consts::MAX_TX_DATA,
+        crypto::base64::Base64,
+        error::Error,
+        network::{Network, ProtocolParams},
+        retry::RetryPolicy,
+        tx_id::TxId,
+        verify::verify_transaction,
+    };
+
+    use super::Client;
+
+    fn client(server: &MockServer) -> Client {
+        Client::from_keypair_path(
+            PathBuf::from("res/test_wallet.json"),
+            url::Url::parse(&server.url("/")).unwrap(),
+        )
+        .unwrap()
+        .with_retry_policy(
+            RetryPolicy::default()
+                .max_attempts(2)
+                .base_delay(Duration::from_millis(1)),
+        )
+    }
+
+    fn mock_anchor(server: &MockServer) {
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(Base64(vec![1; 48]).to_string());
+        });
+    }
+
+    #[test]
+    fn test_read() -> Result<(), Error> {
+        let address = Address::new([2; 32]);
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/wallet/{}/balance", address));
+            then.status(200).body("1000000000000");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/price/1024");
+            then.status(200).body("5000");
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/tx/{}/status", Base64(vec![3; 32])));
+            then.status(202);
+        });
+        let client = client(&server);
+
+        assert_eq!(client.get_balance(&address)?.to_string(), "1000000000000");
+        assert_eq!(client.get_price(1024, None)?, 5000);
+        assert!(matches!(
+            client.get_tx_status(&TxId::new([3; 32]))?,
+            (StatusCode::ACCEPTED, None)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_upload_bytes() -> Result<(), Error> {
+        let server = MockServer::start();
+        mock_anchor(&server);
+        let post = server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200);
+        });
+        let client = client(&server);
+
+        let (id, reward) = client.upload_bytes(b"hello", Some("text/plain"), vec![], 1000)?;
+        post.assert();
+        assert_eq!(reward, 1000);
+        assert_eq!(id.to_string().len(), 43);
+        assert!(matches!(
+            Client::new(url::Url::parse(&server.url("/")).unwrap()).upload_bytes(
+                b"hello",
+                None,
+                vec![],
+                1000
+            ),
+            Err(Error::MissingSigner)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_post_transaction_chunks() -> Result<(), Error> {
+        let server = MockServer::start();
+        mock_anchor(&server);
+        let header = server.mock(|when, then| {
+            when.method(POST).path("/tx").body_contains(r#""data":"""#);
+            then.status(200);
+        });
+        let chunks = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200);
+        });
+        let client = client(&server);
+
+        let data = vec![7; MAX_TX_DATA as usize + 1];
+        let transaction = client.create_transaction(None, vec![], data, 0, 1000, false)?;
+        let signed_transaction = client.sign_transaction(transaction)?;
+        verify_transaction(&signed_transaction)?;
+        client.post_transaction(&signed_transaction)?;
+        header.assert();
+        chunks.assert_hits(signed_transaction.chunks.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_post_transaction_network_limit() -> Result<(), Error> {
+        let server = MockServer::start();
+        mock_anchor(&server);
+        let header = server.mock(|when, then| {
+            when.method(POST).path("/tx").body_contains(r#""data":"""#);
+            then.status(200);
+        });
+        let chunks = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200);
+        });
+        let client = client(&server).with_network(Network::Custom {
+            url: url::Url::parse(&server.url("/")).unwrap(),
+            price_source: None,
+            params: ProtocolParams { max_tx_data: 1024 },
+        });
+
+        let transaction = client.create_transaction(None, vec![], vec![7; 2048], 0, 1000, false)?;
+        let signed_transaction = client.sign_transaction(transaction)?;
+        client.post_transaction(&signed_transaction)?;
+        header.assert();
+        chunks.assert_hits(signed_transaction.chunks.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,452 @@
+use std::io::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    crypto::{
+        base64::Base64,
+        hash::{sha256, DeepHashItem, ToItems},
+        sign::SignatureType,
+    },
+    error::Error,
+    transaction::tags::Tag,
+};
+
+use super::tags::AvroEncode;
+
+/// Length of the optional target and anchor fields.
+pub const TARGET_LENGTH: usize = 32;
+pub const ANCHOR_LENGTH: usize = 32;
+
+/// ANS-104 data item. (De)serializes to JSON with base64url-encoded binary fields.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct DataItem {
+    pub signature_type: SignatureType,
+    pub id: Base64,
+    pub signature: Base64,
+    pub owner: Base64,
+    pub target: Base64,
+    pub anchor: Base64,
+    pub tags: Vec<Tag<Base64>>,
+    pub data: Base64,
+}
+
+impl<'a> ToItems<'a, DataItem> for DataItem {
+    fn to_deep_hash_item(&'a self) -> Result<DeepHashItem<'a>, Error> {
+        self.to_deep_hash_item_with_data(DeepHashItem::from_item(&self.data.0))
+    }
+}
+
+impl DataItem {
+    /// Deep hash item of the data item with `data` in place of the data it holds, e.g. the
+    /// [`DeepHashItem::Hash`] of data read with [`crate::crypto::hash::deep_hash_reader`].
+    pub fn to_deep_hash_item_with_data<'a>(
+        &'a self,
+        data: DeepHashItem<'a>,
+    ) -> Result<DeepHashItem<'a>, Error> {
+        let signature_type = (self.signature_type as u16).to_string();
+        let tags = self.tags.encode()?;
+        let children = vec![
+            DeepHashItem::from_item(b"dataitem"),
+            DeepHashItem::from_item(b"1"),
+            DeepHashItem::Blob(signature_type.into_bytes().into()),
+            DeepHashItem::from_item(&self.owner.0),
+            DeepHashItem::from_item(&self.target.0),
+            DeepHashItem::from_item(&self.anchor.0),
+            DeepHashItem::Blob(tags.into()),
+            data,
+        ];
+
+        Ok(DeepHashItem::from_children(children))
+    }
+}
+
+/// Takes `len` bytes for `field` from `bytes` at `*offset`, advancing it.
+fn take<'a>(
+    bytes: &'a [u8],
+    offset: &mut usize,
+    len: usize,
+    field: &str,
+) -> Result<&'a [u8], Error> {
+    let end = offset
+        .checked_add(len)
+        .filter(|end| *end <= bytes.len())
+        .ok_or_else(|| {
+            Error::InvalidDataItem(format!(
+                "expected {} bytes of {} at offset {}, got {}",
+                len,
+                field,
+                offset,
+                bytes.len().saturating_sub(*offset)
+            ))
+        })?;
+    let slice = &bytes[*offset..end];
+    *offset = end;
+    Ok(slice)
+}
+
+/// Reads an optional field: a presence byte followed by `len` bytes if set.
+fn take_optional(
+    bytes: &[u8],
+    offset: &mut usize,
+    len: usize,
+    field: &str,
+) -> Result<Base64, Error> {
+    let presence_offset = *offset;
+    match take(bytes, offset, 1, field)?[0] {
+        0 => Ok(Base64::empty()),
+        1 => Ok(Base64(take(bytes, offset, len, field)?.to_vec())),
+        b => Err(Error::InvalidDataItem(format!(
+            "invalid presence byte {} for {} at offset {}",
+            b, field, presence_offset
+        ))),
+    }
+}
+
+impl DataItem {
+    /// Parses a data item from its ANS-104 binary layout. The id is computed from the signature.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut offset = 0;
+        let signature_type = u16::from_le_bytes(
+            take(bytes, &mut offset, 2, "signature type")?
+                .try_into()
+                .unwrap(), //Checked unwrap
+        );
+        let signature_type = SignatureType::try_from(signature_type)?;
+        let signature =
+            Base64(take(bytes, &mut offset, signature_type.sig_length(), "signature")?.to_vec());
+        let owner =
+            Base64(take(bytes, &mut offset, signature_type.pub_length(), "owner")?.to_vec());
+        let target = take_optional(bytes, &mut offset, TARGET_LENGTH, "target")?;
+        let anchor = take_optional(bytes, &mut offset, ANCHOR_LENGTH, "anchor")?;
+
+        let tags_count = u64::from_le_bytes(
+            take(bytes, &mut offset, 8, "number of tags")?
+                .try_into()
+                .unwrap(), //Checked unwrap
+        );
+        let tags_len = u64::from_le_bytes(
+            take(bytes, &mut offset, 8, "number of tag bytes")?
+                .try_into()
+                .unwrap(), //Checked unwrap
+        );
+        let tags_len = usize::try_from(tags_len).map_err(|_| {
+            Error::InvalidDataItem(format!("{} bytes of tags overflow usize", tags_len))
+        })?;
+        let tags_bytes = take(bytes, &mut offset, tags_len, "tags")?;
+        let tags = Vec::<Tag<Base64>>::decode(tags_bytes)?;
+        if usize::try_from(tags_count) != Ok(tags.len()) {
+            return Err(Error::InvalidDataItem(format!(
+                "header announces {} tags, found {}",
+                tags_count,
+                tags.len()
+            )));
+        }
+
+        Ok(Self {
+            signature_type,
+            id: Base64(sha256(&signature.0).to_vec()),
+            signature,
+            owner,
+            target,
+            anchor,
+            tags,
+            data: Base64(bytes[offset..].to_vec()),
+        })
+    }
+
+    /// Creates an unsigned data item. `target` and `anchor` are either empty or 32 bytes long.
+    pub fn new(
+        target: Base64,
+        anchor: Base64,
+        tags: Vec<Tag<Base64>>,
+        data: Vec<u8>,
+    ) -> Result<Self, Error> {
+        if !target.is_empty() && target.0.len() != TARGET_LENGTH {
+            return Err(Error::InvalidDataItem(format!(
+                "target must be {} bytes, got {}",
+                TARGET_LENGTH,
+                target.0.len()
+            )));
+        }
+        if !anchor.is_empty() && anchor.0.len() != ANCHOR_LENGTH {
+            return Err(Error::InvalidDataItem(format!(
+                "anchor must be {} bytes, got {}",
+                ANCHOR_LENGTH,
+                anchor.0.len()
+            )));
+        }
+
+        Ok(Self {
+            target,
+            anchor,
+            tags,
+            data: Base64(data),
+            ..Default::default()
+        })
+    }
+
+    /// Id of the signed data item: the SHA-256 hash of its signature.
+    pub fn compute_id(&self) -> Result<Base64, Error> {
+        if self.signature.is_empty() {
+            return Err(Error::UnsignedTransaction);
+        }
+        Ok(Base64(sha256(&self.signature.0).to_vec()))
+    }
+
+    /// Whether the data of the item is itself a bundle, see [`super::Bundle::from_data_item`].
+    pub fn is_bundle(&self) -> bool {
+        super::is_bundle(&self.tags)
+    }
+
+    /// Checks the id and signature of the data item.
+    pub fn verify(&self) -> Result<(), Error> {
+        crate::verify::verify_data_item(self)
+    }
+
+    /// ANS-104 binary layout of the signed data item up to its data.
+    pub(crate) fn binary_header(&self) -> Result<Vec<u8>, Error> {
+        if self.signature.is_empty() {
+            return Err(Error::UnsignedTransaction);
+        }
+        if self.signature.0.len() != self.signature_type.sig_length()
+            || self.owner.0.len() != self.signature_type.pub_length()
+        {
+            return Err(Error::InvalidDataItem(format!(
+                "signature and owner lengths do not match signature type {:?}",
+                self.signature_type
+            )));
+        }
+
+        let tags = self.tags.encode()?;
+        let mut bytes = Vec::with_capacity(
+            2 + self.signature.0.len()
+                + self.owner.0.len()
+                + 2
+                + self.target.0.len()
+                + self.anchor.0.len()
+                + 16
+                + tags.len(),
+        );
+        bytes.extend((self.signature_type as u16).to_le_bytes());
+        bytes.extend(&self.signature.0);
+        bytes.extend(&self.owner.0);
+        for optional in [&self.target, &self.anchor] {
+            if optional.is_empty() {
+                bytes.push(0);
+            } else {
+                bytes.push(1);
+                bytes.extend(&optional.0);
+            }
+        }
+        bytes.extend((self.tags.len() as u64).to_le_bytes());
+        bytes.extend((tags.len() as u64).to_le_bytes());
+        bytes.extend(tags);
+        Ok(bytes)
+    }
+
+    /// Size of the binary layout of the signed data item.
+    pub fn binary_len(&self) -> Result<usize, Error> {
+        Ok(self.binary_header()?.len() + self.data.0.len())
+    }
+
+    /// Writes the binary layout of the signed data item to `writer` without copying its data.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        writer.write_all(&self.binary_header()?)?;
+        writer.write_all(&self.data.0)?;
+        Ok(())
+    }
+
+    /// Serializes a signed data item into its ANS-104 binary layout.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = self.binary_header()?;
+        bytes.extend(&self.data.0);
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        crypto::{
+            base64::Base64,
+            hash::{deep_hash, ToItems},
+        },
+        error::Error,
+        signer::ArweaveSigner,
+        transaction::tags::{FromUtf8Strs, Tag},
+        verify::verify,
+    };
+
+    use super::DataItem;
+
+    /// ANS-104 requires 4096 bit RSA keys for Arweave signatures.
+    const WALLET_4096_PATH: &str = "res/test_wallet_4096.json";
+
+    #[test]
+    fn test_sign_data_item() -> Result<(), Error> {
+        let signer = ArweaveSigner::from_keypair_path(PathBuf::from(WALLET_4096_PATH))?;
+        let tags = vec![Tag::from_utf8_strs("Content-Type", "text/plain")?];
+        let item = DataItem::new(
+            Base64(vec![1; 32]),
+            Base64::empty(),
+            tags,
+            b"hello".to_vec(),
+        )?;
+        let item = signer.sign_data_item(item)?;
+
+        assert_eq!(item.owner, signer.get_public_key());
+        let message = deep_hash(item.to_deep_hash_item()?);
+        assert!(verify(&item.owner.0, &message, &item.signature.0).is_ok());
+        item.verify()?;
+
+        assert_eq!(item.compute_id()?, item.id);
+
+        let bytes = item.to_bytes()?;
+        assert_eq!(&bytes[..2], &[1, 0]);
+        // target present, anchor absent
+        assert_eq!(bytes[2 + 512 + 512], 1);
+        assert_eq!(bytes[2 + 512 + 512 + 33], 0);
+        assert!(bytes.ends_with(b"hello"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_data_item_from_bytes() -> Result<(), Error> {
+        let signer = ArweaveSigner::from_keypair_path(PathBuf::from(WALLET_4096_PATH))?;
+        let tags = vec![Tag::from_utf8_strs("App-Name", "arweave-rs")?];
+        let item = DataItem::new(Base64::empty(), Base64(vec![7; 32]), tags, vec![1, 2, 3])?;
+        let item = signer.sign_data_item(item)?;
+
+        let bytes = item.to_bytes()?;
+        assert_eq!(DataItem::from_bytes(&bytes)?, item);
+
+        let json = serde_json::to_value(&item).unwrap();
+        assert_eq!(json["signature_type"], 1);
+        assert_eq!(json["anchor"], Base64(vec![7; 32]).to_string());
+        assert_eq!(serde_json::from_value::<DataItem>(json).unwrap(), item);
+
+        let res = DataItem::from_bytes(&bytes[..600]);
+        assert!(
+            matches!(res, Err(Error::InvalidDataItem(msg)) if msg == "expected 512 bytes of owner at offset 514, got 86")
+        );
+
+        let mut tampered = bytes;
+        tampered[2 + 512 + 512 + 1] = 2;
+        assert!(matches!(
+            DataItem::from_bytes(&tampered),
+            Err(Error::InvalidDataItem(_))
+        ));
+        Ok(())
+    }
+
+    /// Writes the fields of the item in the ANS-104 layout, independently of `to_bytes`.
+    fn ans104_bytes(item: &DataItem, tags: &[u8], tag_count: u64) -> Vec<u8> {
+        let mut bytes = (item.signature_type as u16).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&item.signature.0);
+        bytes.extend_from_slice(&item.owner.0);
+        for field in [&item.target, &item.anchor] {
+            bytes.push(!field.is_empty() as u8);
+            bytes.extend_from_slice(&field.0);
+        }
+        bytes.extend_from_slice(&tag_count.to_le_bytes());
+        bytes.extend_from_slice(&(tags.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(tags);
+        bytes.extend_from_slice(&item.data.0);
+        bytes
+    }
+
+    #[test]
+    fn test_data_item_layout() -> Result<(), Error> {
+        let signer = ArweaveSigner::from_keypair_path(PathBuf::from(WALLET_4096_PATH))?;
+        let tags = vec![
+            Tag::from_utf8_strs("Content-Type", "text/plain")?,
+            Tag::from_utf8_strs("App-Name", "arbundles")?,
+        ];
+        let tagged = signer.sign_data_item(DataItem::new(
+            Base64(vec![1; 32]),
+            Base64(vec![2; 32]),
+            tags,
+            b"hello".to_vec(),
+        )?)?;
+        let untagged = signer.sign_data_item(DataItem::new(
+            Base64::empty(),
+            Base64::empty(),
+            vec![],
+            b"hello".to_vec(),
+        )?)?;
+
+        for (item, tags, tag_count) in [
+            (
+                tagged,
+                &b"\x04\x18Content-Type\x14text/plain\x10App-Name\x12arbundles\x00"[..],
+                2,
+            ),
+            // Without tags, both counts are zero and no Avro bytes follow.
+            (untagged, &[][..], 0),
+        ] {
+            let bytes = item.to_bytes()?;
+            assert_eq!(bytes, ans104_bytes(&item, tags, tag_count));
+            let parsed = DataItem::from_bytes(&bytes)?;
+            assert_eq!(parsed, item);
+            assert_eq!(parsed.to_bytes()?, bytes);
+            parsed.verify()?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_target() {
+        let res = DataItem::new(Base64(vec![1; 31]), Base64::empty(), vec![], vec![]);
+        assert!(matches!(res, Err(Error::InvalidDataItem(_))));
+    }
+
+    #[test]
+    fn test_unsigned_to_bytes() {
+        let item = DataItem::new(Base64::empty(), Base64::empty(), vec![], vec![]).unwrap();
+        assert!(matches!(item.to_bytes(), Err(Error::UnsignedTransaction)));
+        assert!(matches!(item.compute_id(), Err(Error::UnsignedTransaction)));
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn test_sign_ed25519_data_item() -> Result<(), Error> {
+        use crate::crypto::{ed25519::Ed25519Signer, sign::SignatureType};
+
+        let signer = ArweaveSigner::new(Box::new(Ed25519Signer::generate()));
+        let item = DataItem::new(Base64::empty(), Base64::empty(), vec![], b"hello".to_vec())?;
+        let mut item = signer.sign_data_item(item)?;
+        assert_eq!(item.signature_type, SignatureType::Ed25519);
+        assert_eq!(item.owner.0.len(), 32);
+        item.verify()?;
+
+        let bytes = item.to_bytes()?;
+        assert_eq!(&bytes[..2], &[2, 0]);
+        let parsed = DataItem::from_bytes(&bytes)?;
+        assert_eq!(parsed, item);
+        parsed.verify()?;
+
+        item.data = Base64(b"tampered".to_vec());
+        assert!(matches!(item.verify(), Err(Error::InvalidSignature)));
+        Ok(())
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn test_sign_secp256k1_data_item() -> Result<(), Error> {
+        use crate::crypto::{secp256k1::Secp256k1Signer, sign::SignatureType};
+
+        let signer = ArweaveSigner::new(Box::new(Secp256k1Signer::generate()));
+        let item = DataItem::new(Base64::empty(), Base64::empty(), vec![], b"hello".to_vec())?;
+        let item = signer.sign_data_item(item)?;
+        assert_eq!(item.signature_type, SignatureType::Ethereum);
+        item.verify()?;
+
+        let bytes = item.to_bytes()?;
+        assert_eq!(&bytes[..2], &[3, 0]);
+        DataItem::from_bytes(&bytes)?.verify()?;
+        Ok(())
+    }
+}
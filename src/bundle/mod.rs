@@ -0,0 +1,618 @@
+//! Functionality for creating [ANS-104](https://github.com/ArweaveTeam/arweave-standards/blob/master/ans/ANS-104.md)
+//! data items and bundles.
+
+use std::io::{Read, Write};
+
+use crate::{
+    crypto::{base64::Base64, utils::ct_eq},
+    error::Error,
+    transaction::tags::{find_tag_value, FromUtf8Strs, Tag},
+    verify::{par_map, verify_data_item},
+};
+
+pub mod data_item;
+pub mod tags;
+
+pub use data_item::DataItem;
+
+/// Size of the item count and of each item size in the bundle header.
+const HEADER_INT_LENGTH: usize = 32;
+/// Size of each data item id in the bundle header.
+const HEADER_ID_LENGTH: usize = 32;
+
+const FORMAT_TAG: &str = "Bundle-Format";
+const VERSION_TAG: &str = "Bundle-Version";
+const FORMAT: &str = "binary";
+const VERSION: &str = "2.0.0";
+
+/// Whether `tags` identify the data they are attached to as a binary bundle.
+pub fn is_bundle(tags: &[Tag<Base64>]) -> bool {
+    find_tag_value(tags, FORMAT_TAG).as_deref() == Some(FORMAT)
+        && find_tag_value(tags, VERSION_TAG).as_deref() == Some(VERSION)
+}
+
+/// Set of signed [`DataItem`]s posted together as the data of a single layer-1 transaction.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Bundle {
+    pub items: Vec<DataItem>,
+}
+
+/// Outcome of the verification of the item `index` of a bundle.
+#[derive(Debug)]
+pub struct ItemReport {
+    pub index: usize,
+    pub id: Base64,
+    pub result: Result<(), Error>,
+}
+
+/// Data item found by [`Bundle::unbundle`], with its index in each enclosing bundle from the
+/// outermost one.
+#[derive(Debug)]
+pub struct NestedItem {
+    pub path: Vec<usize>,
+    pub item: DataItem,
+    /// Outcome of unbundling the item if it is tagged as a bundle, `Ok` for other items. On
+    /// error, the items nested in it are not listed.
+    pub unbundled: Result<(), Error>,
+}
+
+impl NestedItem {
+    /// Number of bundles the item is nested in below the outermost one.
+    pub fn depth(&self) -> usize {
+        self.path.len() - 1
+    }
+}
+
+impl ItemReport {
+    pub fn is_valid(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// Encodes a number as the 32 bytes little endian integer used in bundle headers.
+fn to_header_int(n: usize) -> [u8; HEADER_INT_LENGTH] {
+    let mut bytes = [0; HEADER_INT_LENGTH];
+    bytes[..8].copy_from_slice(&(n as u64).to_le_bytes());
+    bytes
+}
+
+/// Decodes a 32 bytes little endian integer from a bundle header.
+fn from_header_int(bytes: &[u8]) -> Result<usize, Error> {
+    if bytes[8..].iter().any(|b| *b != 0) {
+        return Err(Error::InvalidBundle(
+            "header integer does not fit in 64 bits".to_string(),
+        ));
+    }
+    let n = u64::from_le_bytes(bytes[..8].try_into().unwrap()); //Checked unwrap
+    usize::try_from(n).map_err(|_| Error::InvalidBundle(format!("header integer {} too large", n)))
+}
+
+/// Iterates over the [`DataItem`]s of a binary bundle read from `R`, holding a single item in
+/// memory at a time.
+pub struct BundleReader<R: Read> {
+    reader: R,
+    entries: Vec<(usize, Base64)>,
+    next: usize,
+}
+
+impl<R: Read> BundleReader<R> {
+    /// Reads the bundle header.
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let mut count = [0; HEADER_INT_LENGTH];
+        reader
+            .read_exact(&mut count)
+            .map_err(|err| Error::InvalidBundle(format!("reading item count: {}", err)))?;
+        let count = from_header_int(&count)?;
+
+        let mut entries = Vec::new();
+        for idx in 0..count {
+            let mut entry = [0; HEADER_INT_LENGTH + HEADER_ID_LENGTH];
+            reader.read_exact(&mut entry).map_err(|err| {
+                Error::InvalidBundle(format!(
+                    "reading header entry {} of {}: {}",
+                    idx, count, err
+                ))
+            })?;
+            let size = from_header_int(&entry[..HEADER_INT_LENGTH])?;
+            entries.push((size, Base64(entry[HEADER_INT_LENGTH..].to_vec())));
+        }
+
+        Ok(Self {
+            reader,
+            entries,
+            next: 0,
+        })
+    }
+
+    /// Number of items announced in the header.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn read_item(&mut self, idx: usize) -> Result<DataItem, Error> {
+        let (size, id) = &self.entries[idx];
+        let mut bytes = Vec::new();
+        (&mut self.reader)
+            .take(*size as u64)
+            .read_to_end(&mut bytes)?;
+        if bytes.len() != *size {
+            return Err(Error::InvalidBundle(format!(
+                "item {} truncated: expected {} bytes, got {}",
+                idx,
+                size,
+                bytes.len()
+            )));
+        }
+        let item = DataItem::from_bytes(&bytes)
+            .map_err(|err| Error::InvalidBundle(format!("item {}: {}", idx, err)))?;
+        if !ct_eq(&item.id.0, &id.0) {
+            return Err(Error::InvalidBundle(format!(
+                "item {}: id {} in header does not match signature id {}",
+                idx, id, item.id
+            )));
+        }
+        Ok(item)
+    }
+}
+
+impl<R: Read> Iterator for BundleReader<R> {
+    type Item = Result<DataItem, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.entries.len() {
+            return None;
+        }
+        let idx = self.next;
+        self.next += 1;
+        Some(self.read_item(idx))
+    }
+}
+
+/// Writes a binary bundle to `W` from the headers of its items, copying the data of each item
+/// from a reader so that neither the bundle nor its items are held in memory whole.
+pub struct BundleWriter<W: Write> {
+    writer: W,
+    /// Binary header and data length of each item.
+    entries: Vec<(Vec<u8>, u64)>,
+    next: usize,
+    written: usize,
+}
+
+impl<W: Write> BundleWriter<W> {
+    /// Writes the bundle header for `headers`, each signed item with the length of its data,
+    /// see [`crate::ArweaveSigner::sign_data_item_reader`]. The data held by the items is
+    /// ignored.
+    pub fn new(writer: W, headers: &[(DataItem, u64)]) -> Result<Self, Error> {
+        let entries = headers
+            .iter()
+            .map(|(item, data_len)| Ok((item.id.clone(), item.binary_header()?, *data_len)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Self::from_entries(writer, entries)
+    }
+
+    fn from_entries(mut writer: W, entries: Vec<(Base64, Vec<u8>, u64)>) -> Result<Self, Error> {
+        writer.write_all(&to_header_int(entries.len()))?;
+        for (id, header, data_len) in &entries {
+            let size = usize::try_from(*data_len)
+                .ok()
+                .and_then(|data_len| data_len.checked_add(header.len()))
+                .ok_or_else(|| Error::InvalidBundle(format!("item {} too large", id)))?;
+            writer.write_all(&to_header_int(size))?;
+            writer.write_all(&id.0)?;
+        }
+        Ok(Self {
+            writer,
+            written: HEADER_INT_LENGTH + entries.len() * (HEADER_INT_LENGTH + HEADER_ID_LENGTH),
+            entries: entries
+                .into_iter()
+                .map(|(_, header, data_len)| (header, data_len))
+                .collect(),
+            next: 0,
+        })
+    }
+
+    /// Writes the next item, copying its data from `data`, which must hold at least the data
+    /// length given for the item.
+    pub fn write_item<R: Read>(&mut self, data: R) -> Result<(), Error> {
+        let idx = self.next;
+        let (header, data_len) = self.entries.get(idx).ok_or_else(|| {
+            Error::InvalidBundle(format!("all {} items already written", self.entries.len()))
+        })?;
+        self.writer.write_all(header)?;
+        let copied = std::io::copy(&mut data.take(*data_len), &mut self.writer)?;
+        if copied != *data_len {
+            return Err(Error::InvalidBundle(format!(
+                "item {} truncated: expected {} bytes of data, got {}",
+                idx, data_len, copied
+            )));
+        }
+        self.written += header.len() + copied as usize;
+        self.next += 1;
+        Ok(())
+    }
+
+    /// Flushes the writer once every item is written, returning the number of bytes written.
+    pub fn finish(mut self) -> Result<usize, Error> {
+        if self.next < self.entries.len() {
+            return Err(Error::InvalidBundle(format!(
+                "{} of {} items written",
+                self.next,
+                self.entries.len()
+            )));
+        }
+        self.writer.flush()?;
+        Ok(self.written)
+    }
+}
+
+impl Bundle {
+    /// Parses a binary bundle.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_reader(bytes)
+    }
+
+    /// Parses a binary bundle from a reader. Use [`BundleReader`] to process items one by one.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, Error> {
+        let items = BundleReader::new(reader)?.collect::<Result<Vec<DataItem>, Error>>()?;
+        Ok(Self { items })
+    }
+
+    pub fn from_items(items: Vec<DataItem>) -> Result<Self, Error> {
+        if let Some(idx) = items.iter().position(|item| item.id.is_empty()) {
+            return Err(Error::InvalidBundle(format!("item {} is not signed", idx)));
+        }
+        Ok(Self { items })
+    }
+
+    /// Tags identifying the data of a transaction as a binary bundle.
+    pub fn tags() -> Vec<Tag<Base64>> {
+        vec![
+            Tag::from_utf8_strs(FORMAT_TAG, FORMAT).unwrap(), //Checked unwrap
+            Tag::from_utf8_strs(VERSION_TAG, VERSION).unwrap(), //Checked unwrap
+        ]
+    }
+
+    /// Unsigned data item carrying the bundle, to be signed and nested in another bundle.
+    pub fn to_data_item(&self) -> Result<DataItem, Error> {
+        DataItem::new(
+            Base64::empty(),
+            Base64::empty(),
+            Self::tags(),
+            self.to_bytes()?,
+        )
+    }
+
+    /// Parses the bundle carried by a data item tagged with [`Bundle::tags`].
+    pub fn from_data_item(item: &DataItem) -> Result<Self, Error> {
+        if !item.is_bundle() {
+            return Err(Error::InvalidBundle(format!(
+                "data item {} is not tagged as a bundle",
+                item.id
+            )));
+        }
+        Self::from_bytes(&item.data.0)
+    }
+
+    /// Lists the items of the bundle depth-first, each followed by the items nested in it if it
+    /// is itself a bundle. Bundles that do not parse or are nested more than `max_depth` levels
+    /// deep are reported on their item and not unbundled, the other items are still listed.
+    pub fn unbundle(self, max_depth: usize) -> Vec<NestedItem> {
+        let mut items = Vec::new();
+        self.unbundle_into(&[], max_depth, &mut items);
+        items
+    }
+
+    fn unbundle_into(self, path: &[usize], max_depth: usize, items: &mut Vec<NestedItem>) {
+        for (idx, item) in self.items.into_iter().enumerate() {
+            let mut item_path = path.to_vec();
+            item_path.push(idx);
+            let nested = match item.is_bundle() {
+                false => Ok(None),
+                true if path.len() >= max_depth => Err(Error::InvalidBundle(format!(
+                    "item {} nests bundles deeper than {} levels",
+                    item.id, max_depth
+                ))),
+                true => Self::from_data_item(&item).map(Some).map_err(|err| {
+                    Error::InvalidBundle(format!("nested bundle {}: {}", item.id, err))
+                }),
+            };
+            let (unbundled, nested) = match nested {
+                Ok(nested) => (Ok(()), nested),
+                Err(err) => (Err(err), None),
+            };
+            items.push(NestedItem {
+                path: item_path.clone(),
+                item,
+                unbundled,
+            });
+            if let Some(nested) = nested {
+                nested.unbundle_into(&item_path, max_depth, items);
+            }
+        }
+    }
+
+    /// Verifies the id and signature of every item, across the available cores with the
+    /// `parallel` feature, see [`verify_data_item`], returning the report of each item in order.
+    pub fn verify_all(&self) -> Vec<ItemReport> {
+        let items: Vec<_> = self.items.iter().enumerate().collect();
+        par_map(&items, |(index, item)| ItemReport {
+            index: *index,
+            id: item.id.clone(),
+            result: verify_data_item(item),
+        })
+    }
+
+    /// Serializes the bundle: item count, then size and id of each item, then the items.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Streams the serialized bundle to `writer` one item at a time, so that the bundle is never
+    /// copied in memory whole. Returns the number of bytes written.
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<usize, Error> {
+        let entries = self
+            .items
+            .iter()
+            .map(|item| {
+                Ok((
+                    item.id.clone(),
+                    item.binary_header()?,
+                    item.data.0.len() as u64,
+                ))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let mut writer = BundleWriter::from_entries(writer, entries)?;
+        for item in &self.items {
+            writer.write_item(&item.data.0[..])?;
+        }
+        writer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        crypto::base64::Base64, error::Error, signer::ArweaveSigner,
+        transaction::tags::FromUtf8Strs,
+    };
+
+    use super::{is_bundle, Bundle, BundleReader, BundleWriter, DataItem, Tag};
+
+    fn signed_items() -> Result<Vec<DataItem>, Error> {
+        let signer = ArweaveSigner::from_keypair_path(PathBuf::from("res/test_wallet_4096.json"))?;
+        ["first", "second"]
+            .iter()
+            .map(|data| {
+                let tags = vec![Tag::from_utf8_strs("Content-Type", "text/plain")?];
+                let item = DataItem::new(
+                    Base64::empty(),
+                    Base64::empty(),
+                    tags,
+                    data.as_bytes().to_vec(),
+                )?;
+                signer.sign_data_item(item)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bundle_to_bytes() -> Result<(), Error> {
+        let items = signed_items()?;
+        let bundle = Bundle::from_items(items.clone())?;
+        let bytes = bundle.to_bytes()?;
+
+        let mut expected_count = [0; 32];
+        expected_count[0] = 2;
+        assert_eq!(bytes[..32], expected_count);
+
+        let mut offset = 32 + 2 * 64;
+        for (idx, item) in items.iter().enumerate() {
+            let item_bytes = item.to_bytes()?;
+            let entry = &bytes[32 + idx * 64..32 + (idx + 1) * 64];
+            assert_eq!(
+                u64::from_le_bytes(entry[..8].try_into().unwrap()) as usize,
+                item_bytes.len()
+            );
+            assert_eq!(entry[32..], item.id.0);
+            assert_eq!(bytes[offset..offset + item_bytes.len()], item_bytes);
+            offset += item_bytes.len();
+        }
+        assert_eq!(offset, bytes.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundle_requires_signed_items() {
+        let item = DataItem::new(Base64::empty(), Base64::empty(), vec![], vec![]).unwrap();
+        assert!(matches!(
+            Bundle::from_items(vec![item]),
+            Err(Error::InvalidBundle(_))
+        ));
+    }
+
+    #[test]
+    fn test_bundle_from_bytes() -> Result<(), Error> {
+        let items = signed_items()?;
+        let bytes = Bundle::from_items(items.clone())?.to_bytes()?;
+
+        let bundle = Bundle::from_bytes(&bytes)?;
+        assert_eq!(bundle.items, items);
+        assert_eq!(bundle.items[1].data.0, b"second");
+
+        let mut reader = BundleReader::new(&bytes[..])?;
+        assert_eq!(reader.len(), 2);
+        assert_eq!(reader.next().unwrap()?, items[0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_all() -> Result<(), Error> {
+        let mut bundle = Bundle::from_items(signed_items()?)?;
+        assert!(bundle.verify_all().iter().all(|report| report.is_valid()));
+
+        bundle.items[1].data = Base64(b"forged".to_vec());
+        let reports = bundle.verify_all();
+        assert!(reports[0].is_valid());
+        assert_eq!(reports[1].index, 1);
+        assert_eq!(reports[1].id, bundle.items[1].id);
+        assert!(matches!(reports[1].result, Err(Error::InvalidSignature)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed_bundle() -> Result<(), Error> {
+        let items = signed_items()?;
+        let bytes = Bundle::from_items(items)?.to_bytes()?;
+
+        let res = Bundle::from_bytes(&bytes[..100]);
+        assert!(
+            matches!(res, Err(Error::InvalidBundle(msg)) if msg.starts_with("reading header entry 1 of 2"))
+        );
+
+        let res = Bundle::from_bytes(&bytes[..bytes.len() - 1]);
+        assert!(
+            matches!(res, Err(Error::InvalidBundle(msg)) if msg.starts_with("item 1 truncated"))
+        );
+
+        let mut wrong_id = bytes;
+        wrong_id[32 + 32] ^= 1;
+        let res = Bundle::from_bytes(&wrong_id);
+        assert!(matches!(res, Err(Error::InvalidBundle(msg)) if msg.contains("does not match")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_bundle() -> Result<(), Error> {
+        let signer = ArweaveSigner::from_keypair_path(PathBuf::from("res/test_wallet_4096.json"))?;
+        let inner = Bundle::from_items(signed_items()?)?;
+        let nested = signer.sign_data_item(inner.to_data_item()?)?;
+        assert!(nested.is_bundle());
+        assert!(is_bundle(&Bundle::tags()));
+        assert_eq!(Bundle::from_data_item(&nested)?, inner);
+
+        let plain = signed_items()?.remove(0);
+        assert!(!plain.is_bundle());
+        assert!(matches!(
+            Bundle::from_data_item(&plain),
+            Err(Error::InvalidBundle(_))
+        ));
+
+        let outer = Bundle::from_items(vec![nested.clone(), plain.clone()])?;
+        let outer = Bundle::from_bytes(&outer.to_bytes()?)?;
+        let items = outer.clone().unbundle(1);
+        let paths: Vec<_> = items.iter().map(|nested| nested.path.clone()).collect();
+        assert_eq!(paths, vec![vec![0], vec![0, 0], vec![0, 1], vec![1]]);
+        assert_eq!(items[0].item, nested);
+        assert_eq!(items[2].item, inner.items[1]);
+        assert_eq!(items[2].depth(), 1);
+        assert_eq!(items[3].item, plain);
+        assert!(items.iter().all(|nested| nested.unbundled.is_ok()));
+
+        // Past the limit, the nested bundle is reported and the other items still listed.
+        let items = outer.unbundle(0);
+        assert_eq!(items.len(), 2);
+        assert!(matches!(items[0].unbundled, Err(Error::InvalidBundle(_))));
+        assert!(items[1].unbundled.is_ok());
+        assert_eq!(inner.clone().unbundle(0).len(), 2);
+
+        // A hostile item tagged as a bundle without being one does not hide its siblings.
+        let fake = signer.sign_data_item(DataItem::new(
+            Base64::empty(),
+            Base64::empty(),
+            Bundle::tags(),
+            b"not a bundle".to_vec(),
+        )?)?;
+        let items = Bundle::from_items(vec![fake, plain.clone()])?.unbundle(1);
+        assert_eq!(items.len(), 2);
+        assert!(matches!(items[0].unbundled, Err(Error::InvalidBundle(_))));
+        assert_eq!(items[1].item, plain);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundle_write_to() -> Result<(), Error> {
+        let bundle = Bundle::from_items(signed_items()?)?;
+        let path = crate::temp_path("arweave-rs-bundle.bin");
+        let file = std::fs::File::create(&path)?;
+        let written = bundle.write_to(std::io::BufWriter::new(file))?;
+
+        let bytes = std::fs::read(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(written, bytes.len());
+        assert_eq!(bytes, bundle.to_bytes()?);
+        assert_eq!(Bundle::from_reader(&bytes[..])?, bundle);
+        assert_eq!(
+            bundle.items[0].binary_len()?,
+            bundle.items[0].to_bytes()?.len()
+        );
+
+        let unsigned = DataItem::new(Base64::empty(), Base64::empty(), vec![], vec![])?;
+        let mut bytes = Vec::new();
+        assert!(Bundle {
+            items: vec![unsigned]
+        }
+        .write_to(&mut bytes)
+        .is_err());
+        assert!(bytes.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundle_writer() -> Result<(), Error> {
+        let signer = ArweaveSigner::from_keypair_path(PathBuf::from("res/test_wallet_4096.json"))?;
+        let data: [&[u8]; 2] = [b"first streamed", b""];
+        let headers = data
+            .iter()
+            .map(|data| {
+                let tags = vec![Tag::from_utf8_strs("Content-Type", "text/plain")?];
+                let item = DataItem::new(Base64::empty(), Base64::empty(), tags, vec![])?;
+                Ok((
+                    signer.sign_data_item_reader(item, *data)?,
+                    data.len() as u64,
+                ))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut bytes = Vec::new();
+        let mut writer = BundleWriter::new(&mut bytes, &headers)?;
+        for data in data {
+            writer.write_item(data)?;
+        }
+        let written = writer.finish()?;
+        assert_eq!(written, bytes.len());
+
+        let bundle = Bundle::from_bytes(&bytes)?;
+        assert_eq!(bundle.to_bytes()?, bytes);
+        assert!(bundle.verify_all().iter().all(|r| r.result.is_ok()));
+        for ((item, (header, _)), data) in bundle.items.iter().zip(&headers).zip(data) {
+            assert_eq!(item.id, header.id);
+            assert_eq!(item.data.0, data);
+        }
+
+        let mut writer = BundleWriter::new(Vec::new(), &headers)?;
+        assert!(matches!(
+            writer.write_item(&b"first"[..]),
+            Err(Error::InvalidBundle(_))
+        ));
+
+        let mut writer = BundleWriter::new(Vec::new(), &headers)?;
+        writer.write_item(data[0])?;
+        assert!(matches!(writer.finish(), Err(Error::InvalidBundle(_))));
+
+        let mut writer = BundleWriter::new(Vec::new(), &headers[..1])?;
+        writer.write_item(data[0])?;
+        assert!(matches!(
+            writer.write_item(data[1]),
+            Err(Error::InvalidBundle(_))
+        ));
+        Ok(())
+    }
+}
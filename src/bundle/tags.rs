@@ -0,0 +1,376 @@
+//! Avro encoding of data item tags, as specified by ANS-104.
+//!
+//! The tags block is an Avro array of records with a `name` and a `value` field of type
+//! `bytes`. Counts and lengths are zig-zag encoded variable length longs, and the array is
+//! written in blocks ended by a block of zero items. Blocks with a negative count are followed
+//! by their size in bytes.
+//!
+//! ANS-104 allows at most [`MAX_TAGS`] tags per data item, with names of at most
+//! [`MAX_TAG_NAME_BYTES`] and values of at most [`MAX_TAG_VALUE_BYTES`] bytes. Both encoding
+//! and decoding enforce the limits.
+
+use crate::{crypto::base64::Base64, error::Error, transaction::tags::Tag};
+
+/// Most tags a data item may carry.
+pub const MAX_TAGS: usize = 128;
+/// Longest tag name, in bytes.
+pub const MAX_TAG_NAME_BYTES: usize = 1024;
+/// Longest tag value, in bytes.
+pub const MAX_TAG_VALUE_BYTES: usize = 3072;
+
+/// Longest encoding of a 64 bit long.
+const MAX_LONG_BYTES: usize = 10;
+
+fn avro_error(reason: &str) -> Error {
+    Error::AvroError(reason.to_string())
+}
+
+fn check_tag(tag: &Tag<Base64>) -> Result<(), Error> {
+    if tag.name.0.len() > MAX_TAG_NAME_BYTES {
+        return Err(Error::AvroError(format!(
+            "tag name of {} bytes, at most {} allowed",
+            tag.name.0.len(),
+            MAX_TAG_NAME_BYTES
+        )));
+    }
+    if tag.value.0.len() > MAX_TAG_VALUE_BYTES {
+        return Err(Error::AvroError(format!(
+            "tag value of {} bytes, at most {} allowed",
+            tag.value.0.len(),
+            MAX_TAG_VALUE_BYTES
+        )));
+    }
+    Ok(())
+}
+
+fn too_many_tags(count: usize) -> Error {
+    Error::AvroError(format!("{} tags, at most {} allowed", count, MAX_TAGS))
+}
+
+fn write_long(buf: &mut Vec<u8>, n: i64) {
+    let mut z = ((n << 1) ^ (n >> 63)) as u64;
+    while z >= 0x80 {
+        buf.push((z as u8 & 0x7f) | 0x80);
+        z >>= 7;
+    }
+    buf.push(z as u8);
+}
+
+fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    write_long(buf, bytes.len() as i64);
+    buf.extend_from_slice(bytes);
+}
+
+fn read_long(bytes: &[u8], offset: &mut usize) -> Result<i64, Error> {
+    let mut z = 0u64;
+    for i in 0..MAX_LONG_BYTES {
+        let byte = *bytes
+            .get(*offset)
+            .ok_or_else(|| avro_error("unexpected end of tags"))?;
+        *offset += 1;
+        if i == MAX_LONG_BYTES - 1 && byte > 1 {
+            return Err(avro_error("long overflows 64 bits"));
+        }
+        z |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok((z >> 1) as i64 ^ -((z & 1) as i64));
+        }
+    }
+    Err(avro_error("long overflows 64 bits"))
+}
+
+fn read_bytes(bytes: &[u8], offset: &mut usize) -> Result<Vec<u8>, Error> {
+    let len = read_long(bytes, offset)?;
+    let len = usize::try_from(len).map_err(|_| avro_error("negative length of bytes"))?;
+    let end = offset
+        .checked_add(len)
+        .filter(|end| *end <= bytes.len())
+        .ok_or_else(|| avro_error("length of bytes past the end of tags"))?;
+    let value = bytes[*offset..end].to_vec();
+    *offset = end;
+    Ok(value)
+}
+
+/// Implemented to serialize and deserialize the tags block of a data item.
+pub trait AvroEncode: Sized {
+    fn encode(&self) -> Result<Vec<u8>, Error>;
+    fn decode(bytes: &[u8]) -> Result<Self, Error>;
+}
+
+impl AvroEncode for Vec<Tag<Base64>> {
+    /// An empty list of tags is encoded as no bytes at all rather than as an empty Avro array.
+    /// Other lists are written in a single block, as arbundles does.
+    fn encode(&self) -> Result<Vec<u8>, Error> {
+        if self.is_empty() {
+            return Ok(vec![]);
+        }
+        if self.len() > MAX_TAGS {
+            return Err(too_many_tags(self.len()));
+        }
+        let mut buf = Vec::new();
+        write_long(&mut buf, self.len() as i64);
+        for tag in self {
+            check_tag(tag)?;
+            write_bytes(&mut buf, &tag.name.0);
+            write_bytes(&mut buf, &tag.value.0);
+        }
+        write_long(&mut buf, 0);
+        Ok(buf)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let mut tags = vec![];
+        if bytes.is_empty() {
+            return Ok(tags);
+        }
+        let mut offset = 0;
+        loop {
+            let count = read_long(bytes, &mut offset)?;
+            if count == 0 {
+                break;
+            }
+            let block_size = if count < 0 {
+                let size = read_long(bytes, &mut offset)?;
+                Some(usize::try_from(size).map_err(|_| avro_error("negative size of block"))?)
+            } else {
+                None
+            };
+            let start = offset;
+            // Each tag takes at least two bytes, so larger counts cannot be satisfied.
+            if count.unsigned_abs() > ((bytes.len() - offset) / 2) as u64 {
+                return Err(avro_error("count of tags past the end of tags"));
+            }
+            let total = tags.len() as u64 + count.unsigned_abs();
+            if total > MAX_TAGS as u64 {
+                return Err(too_many_tags(usize::try_from(total).unwrap_or(usize::MAX)));
+            }
+            for _ in 0..count.unsigned_abs() {
+                let tag = Tag {
+                    name: Base64(read_bytes(bytes, &mut offset)?),
+                    value: Base64(read_bytes(bytes, &mut offset)?),
+                };
+                check_tag(&tag)?;
+                tags.push(tag);
+            }
+            if block_size.is_some_and(|size| size != offset - start) {
+                return Err(avro_error("size of block does not match its tags"));
+            }
+        }
+        if offset != bytes.len() {
+            return Err(avro_error("trailing bytes after tags"));
+        }
+        Ok(tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use avro_rs::{from_avro_datum, to_avro_datum, types::Value, Schema};
+
+    use crate::{
+        crypto::base64::Base64,
+        error::Error,
+        transaction::tags::{FromUtf8Strs, Tag},
+    };
+
+    use super::{
+        read_long, write_long, AvroEncode, MAX_TAGS, MAX_TAG_NAME_BYTES, MAX_TAG_VALUE_BYTES,
+    };
+
+    /// Two tags encoded by hand following the Avro specification, in a single block as avsc,
+    /// the encoder used by arbundles, writes them.
+    const SINGLE_BLOCK_TAGS: &[u8] =
+        b"\x04\x18Content-Type\x14text/plain\x10App-Name\x12arbundles\x00";
+
+    const TAGS_SCHEMA: &str = r#"{
+        "type": "array",
+        "items": {
+            "type": "record",
+            "name": "Tag",
+            "fields": [
+                { "name": "name", "type": "bytes" },
+                { "name": "value", "type": "bytes" }
+            ]
+        }
+    }"#;
+
+    fn to_avro_value(tags: &[Tag<Base64>]) -> Value {
+        Value::Array(
+            tags.iter()
+                .map(|tag| {
+                    Value::Record(vec![
+                        ("name".to_string(), Value::Bytes(tag.name.0.clone())),
+                        ("value".to_string(), Value::Bytes(tag.value.0.clone())),
+                    ])
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_encode_decode_tags() {
+        let tags = vec![Tag::from_utf8_strs("a", "b").unwrap()];
+        let encoded = tags.encode().unwrap();
+        assert_eq!(encoded, vec![2, 2, 97, 2, 98, 0]);
+        assert_eq!(Vec::<Tag<_>>::decode(&encoded).unwrap(), tags);
+    }
+
+    #[test]
+    fn test_encode_empty_tags() {
+        let encoded = Vec::<Tag<_>>::new().encode().unwrap();
+        assert!(encoded.is_empty());
+        assert!(Vec::<Tag<_>>::decode(&encoded).unwrap().is_empty());
+        assert!(Vec::<Tag<_>>::decode(&[0]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_arbundles_tags() {
+        let tags = vec![
+            Tag::from_utf8_strs("Content-Type", "text/plain").unwrap(),
+            Tag::from_utf8_strs("App-Name", "arbundles").unwrap(),
+        ];
+        assert_eq!(tags.encode().unwrap(), SINGLE_BLOCK_TAGS);
+        assert_eq!(Vec::<Tag<_>>::decode(SINGLE_BLOCK_TAGS).unwrap(), tags);
+
+        // A 64 byte value takes two bytes for its length.
+        let long = vec![Tag::from_utf8_strs("k", &"v".repeat(64)).unwrap()];
+        let encoded = long.encode().unwrap();
+        assert_eq!(encoded[..5], [2, 2, b'k', 0x80, 0x01]);
+        assert_eq!(encoded.len(), 5 + 64 + 1);
+        assert_eq!(Vec::<Tag<_>>::decode(&encoded).unwrap(), long);
+    }
+
+    /// Checks the codec against avro-rs, an independent implementation of the specification.
+    #[test]
+    fn test_avro_rs_compatibility() {
+        let schema = Schema::parse_str(TAGS_SCHEMA).unwrap();
+        let binary = Tag {
+            name: Base64(vec![0, 0xff, 0x80]),
+            value: Base64((0..=255).collect()),
+        };
+        for tags in [
+            vec![Tag::from_utf8_strs("a", "b").unwrap()],
+            vec![
+                Tag::from_utf8_strs("Content-Type", "text/plain").unwrap(),
+                Tag::from_utf8_strs("App-Name", "arbundles").unwrap(),
+            ],
+            vec![Tag::from_utf8_strs("", "").unwrap(), binary],
+            (0..100)
+                .map(|i| Tag::from_utf8_strs(&format!("k{}", i), &"v".repeat(i)).unwrap())
+                .collect(),
+        ] {
+            let expected = to_avro_datum(&schema, to_avro_value(&tags)).unwrap();
+            assert_eq!(tags.encode().unwrap(), expected);
+            assert_eq!(Vec::<Tag<_>>::decode(&expected).unwrap(), tags);
+
+            let encoded = tags.encode().unwrap();
+            let decoded = from_avro_datum(&schema, &mut &encoded[..], None).unwrap();
+            assert_eq!(decoded, to_avro_value(&tags));
+        }
+
+        // avro-rs writes an empty array as a single end of blocks, which decodes to no tags,
+        // while data items carry no tag bytes at all.
+        let empty = to_avro_datum(&schema, to_avro_value(&[])).unwrap();
+        assert_eq!(empty, [0]);
+        assert!(Vec::<Tag<_>>::decode(&empty).unwrap().is_empty());
+        assert!(Vec::<Tag<Base64>>::new().encode().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_decode_blocks() {
+        let a: Tag<Base64> = Tag::from_utf8_strs("a", "b").unwrap();
+        let c = Tag::from_utf8_strs("c", "d").unwrap();
+        // Several blocks, and a block with a negative count followed by its size.
+        let blocks = [2, 2, 97, 2, 98, 2, 2, 99, 2, 100, 0];
+        assert_eq!(Vec::<Tag<_>>::decode(&blocks).unwrap(), vec![a.clone(), c]);
+        let sized = [1, 8, 2, 97, 2, 98, 0];
+        assert_eq!(Vec::<Tag<_>>::decode(&sized).unwrap(), vec![a]);
+    }
+
+    #[test]
+    fn test_decode_invalid_tags() {
+        for invalid in [
+            &[2, 2, 97][..],
+            &[2, 2, 97, 2, 98],
+            &[2, 1, 97, 2, 98, 0],
+            &[2, 2, 97, 2, 98, 0, 0],
+            &[1, 10, 2, 97, 2, 98, 0],
+            &[1, 1, 2, 97, 2, 98, 0],
+            &[0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01],
+            &[0xff; 11],
+        ] {
+            assert!(matches!(
+                Vec::<Tag<_>>::decode(invalid),
+                Err(Error::AvroError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_tag_limits() {
+        let tag = |name: usize, value: usize| Tag {
+            name: Base64(vec![b'n'; name]),
+            value: Base64(vec![b'v'; value]),
+        };
+        let largest = vec![tag(MAX_TAG_NAME_BYTES, MAX_TAG_VALUE_BYTES); MAX_TAGS];
+        let encoded = largest.encode().unwrap();
+        assert_eq!(Vec::<Tag<_>>::decode(&encoded).unwrap(), largest);
+
+        let schema = Schema::parse_str(TAGS_SCHEMA).unwrap();
+        for invalid in [
+            vec![tag(1, 1); MAX_TAGS + 1],
+            vec![tag(MAX_TAG_NAME_BYTES + 1, 1)],
+            vec![tag(1, MAX_TAG_VALUE_BYTES + 1)],
+        ] {
+            assert!(matches!(invalid.encode(), Err(Error::AvroError(_))));
+            let encoded = to_avro_datum(&schema, to_avro_value(&invalid)).unwrap();
+            assert!(matches!(
+                Vec::<Tag<_>>::decode(&encoded),
+                Err(Error::AvroError(_))
+            ));
+        }
+
+        // The limit on tags spans blocks.
+        let mut blocks = vec![];
+        for _ in 0..MAX_TAGS + 1 {
+            blocks.extend_from_slice(&[2, 2, 97, 2, 98]);
+        }
+        blocks.push(0);
+        assert!(matches!(
+            Vec::<Tag<_>>::decode(&blocks),
+            Err(Error::AvroError(msg)) if msg == "129 tags, at most 128 allowed"
+        ));
+    }
+
+    #[test]
+    fn test_zig_zag_longs() {
+        for (n, encoded) in [
+            (0, &[0x00][..]),
+            (-1, &[0x01]),
+            (1, &[0x02]),
+            (-64, &[0x7f]),
+            (64, &[0x80, 0x01]),
+            (
+                i64::MAX,
+                &[0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01],
+            ),
+            (
+                i64::MIN,
+                &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01],
+            ),
+        ] {
+            let mut buf = vec![];
+            write_long(&mut buf, n);
+            assert_eq!(buf, encoded);
+            let mut offset = 0;
+            assert_eq!(read_long(&buf, &mut offset).unwrap(), n);
+            assert_eq!(offset, buf.len());
+        }
+        let mut offset = 0;
+        assert!(read_long(
+            &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02],
+            &mut offset
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,178 @@
+//! Caching of GET responses, revalidated with their `ETag` so that repeated reads of the same
+//! transactions, blocks or data cost the gateway a `304 Not Modified` only.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use reqwest::{
+    header::{ETAG, IF_NONE_MATCH, RANGE},
+    Method, StatusCode,
+};
+
+use crate::{
+    error::Error,
+    transport::{HttpRequest, HttpResponse, HttpTransport, StreamingResponse},
+};
+
+/// Largest body kept by default, larger responses are passed through.
+pub const MAX_CACHED_BODY_SIZE: u64 = 1024 * 1024;
+
+#[derive(Default)]
+struct Entries {
+    responses: HashMap<String, HttpResponse>,
+    /// Keys from the oldest to the newest entry.
+    order: VecDeque<String>,
+}
+
+/// Transport keeping the last `capacity` GET responses that carry an `ETag`. Cached requests
+/// are sent with `If-None-Match` and answered from the cache when the gateway replies
+/// `304 Not Modified`.
+pub struct CachingTransport {
+    inner: Arc<dyn HttpTransport>,
+    capacity: usize,
+    max_body_size: u64,
+    entries: Mutex<Entries>,
+}
+
+impl CachingTransport {
+    pub fn new(inner: Arc<dyn HttpTransport>, capacity: usize) -> Self {
+        Self {
+            inner,
+            capacity: capacity.max(1),
+            max_body_size: MAX_CACHED_BODY_SIZE,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    pub fn with_max_body_size(mut self, max_body_size: u64) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().responses.len() //Checked unwrap
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn key(request: &HttpRequest) -> Option<String> {
+        if request.method != Method::GET {
+            return None;
+        }
+        let range = request
+            .headers
+            .get(RANGE)
+            .and_then(|range| range.to_str().ok())
+            .unwrap_or_default();
+        Some(format!("{} {}", request.url, range))
+    }
+
+    fn get(&self, key: &str) -> Option<HttpResponse> {
+        let entries = self.entries.lock().unwrap(); //Checked unwrap
+        entries.responses.get(key).cloned()
+    }
+
+    fn insert(&self, key: String, response: HttpResponse) {
+        let mut entries = self.entries.lock().unwrap(); //Checked unwrap
+        if entries.responses.insert(key.clone(), response).is_some() {
+            entries.order.retain(|k| k != &key);
+        }
+        entries.order.push_back(key);
+        while entries.order.len() > self.capacity {
+            if let Some(oldest) = entries.order.pop_front() {
+                entries.responses.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl HttpTransport for CachingTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        self.send_streaming(request).await?.into_response().await
+    }
+
+    async fn send_streaming(&self, mut request: HttpRequest) -> Result<StreamingResponse, Error> {
+        let key = match Self::key(&request) {
+            Some(key) => key,
+            None => return self.inner.send_streaming(request).await,
+        };
+        let cached = self.get(&key);
+        if let Some(etag) = cached.as_ref().and_then(|cached| cached.headers.get(ETAG)) {
+            request.headers.insert(IF_NONE_MATCH, etag.clone());
+        }
+
+        let response = self.inner.send_streaming(request).await?;
+        match (response.status, cached) {
+            (StatusCode::NOT_MODIFIED, Some(cached)) => Ok(cached.into()),
+            (StatusCode::OK, _)
+                if response.headers.contains_key(ETAG)
+                    && response
+                        .content_length()
+                        .is_some_and(|len| len <= self.max_body_size) =>
+            {
+                let response = response.into_response().await?;
+                self.insert(key, response.clone());
+                Ok(response.into())
+            }
+            _ => Ok(response),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use httpmock::{Method::GET, MockServer};
+    use reqwest::StatusCode;
+    use tokio_test::block_on;
+
+    use crate::{
+        error::Error,
+        transport::{HttpRequest, HttpTransport, ReqwestTransport},
+    };
+
+    use super::CachingTransport;
+
+    #[test]
+    fn test_revalidate() -> Result<(), Error> {
+        let server = MockServer::start();
+        let mut fresh = server.mock(|when, then| {
+            when.method(GET).path("/tx/id");
+            then.status(200).header("etag", "\"v1\"").body("header");
+        });
+        let transport = CachingTransport::new(Arc::new(ReqwestTransport::default()), 1);
+        let url = url::Url::parse(&server.url("/tx/id")).unwrap();
+
+        let first = block_on(transport.send(HttpRequest::get(url.clone())))?;
+        fresh.assert();
+        fresh.delete();
+        let not_modified = server.mock(|when, then| {
+            when.method(GET)
+                .path("/tx/id")
+                .header("if-none-match", "\"v1\"");
+            then.status(304);
+        });
+        let second = block_on(transport.send(HttpRequest::get(url)))?;
+        not_modified.assert();
+        assert_eq!(second.status, StatusCode::OK);
+        assert_eq!(second.body, first.body);
+
+        // Only the newest entry is kept.
+        server.mock(|when, then| {
+            when.method(GET).path("/block/height/1");
+            then.status(200).header("etag", "\"b1\"").body("block");
+        });
+        let block = url::Url::parse(&server.url("/block/height/1")).unwrap();
+        block_on(transport.send(HttpRequest::get(block)))?;
+        assert_eq!(transport.len(), 1);
+        Ok(())
+    }
+}
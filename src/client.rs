@@ -1,17 +1,26 @@
-// Mostly duplicated pretend_reqwest, because pretend_reqwest causes
-// request to pull defeault features that forces us to pull openssl
-// and we want to use rustls-tls instead of native-tls.
+// Adapts the pretend clients to the configured [`HttpTransport`]. Mostly duplicated from
+// pretend_reqwest, because pretend_reqwest causes request to pull default features that
+// forces us to pull openssl and we want to use rustls-tls instead of native-tls.
 
-use std::mem;
+use std::sync::Arc;
 
 use async_trait::async_trait;
 use pretend::{client::Bytes, Error, HeaderMap, Response, Result};
 use reqwest::Method;
 use url::Url;
 
-#[derive(Default)]
-pub struct Client(reqwest::Client);
+use crate::transport::{default_transport, HttpRequest, HttpTransport};
 
+pub struct Client(Arc<dyn HttpTransport>);
+
+impl Default for Client {
+    fn default() -> Self {
+        Self(default_transport())
+    }
+}
+
+// Browser futures are not `Send`, so requests are executed on the local task on wasm32.
+#[cfg(not(target_arch = "wasm32"))]
 #[async_trait]
 impl pretend::client::Client for Client {
     async fn execute(
@@ -21,19 +30,43 @@ impl pretend::client::Client for Client {
         headers: HeaderMap,
         body: Option<Bytes>,
     ) -> Result<Response<Bytes>> {
-        let mut builder = self.0.request(method, url).headers(headers);
-        if let Some(body) = body {
-            builder = builder.body(body);
-        }
-        let response = builder.send().await;
-        let mut response = response.map_err(Error::response)?;
+        self.send(method, url, headers, body).await
+    }
+}
 
-        let status = response.status();
-        let headers = mem::take(response.headers_mut());
+#[cfg(target_arch = "wasm32")]
+#[async_trait(?Send)]
+impl pretend::client::LocalClient for Client {
+    async fn execute(
+        &self,
+        method: Method,
+        url: Url,
+        headers: HeaderMap,
+        body: Option<Bytes>,
+    ) -> Result<Response<Bytes>> {
+        self.send(method, url, headers, body).await
+    }
+}
 
-        let bytes = response.bytes().await;
-        let bytes = bytes.map_err(Error::body)?;
+impl Client {
+    pub fn new(transport: Arc<dyn HttpTransport>) -> Self {
+        Self(transport)
+    }
 
-        Ok(Response::new(status, headers, bytes))
+    async fn send(
+        &self,
+        method: Method,
+        url: Url,
+        headers: HeaderMap,
+        body: Option<Bytes>,
+    ) -> Result<Response<Bytes>> {
+        let mut request = HttpRequest::new(method, url).headers(headers);
+        request.body = body;
+        let response = self.0.send(request).await.map_err(Error::response)?;
+        Ok(Response::new(
+            response.status,
+            response.headers,
+            response.body,
+        ))
     }
 }
@@ -2,6 +2,13 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub const ARWEAVE_BASE_URL: &str = "https://arweave.net/";
 
+/// Default url of the [ArLocal](https://github.com/textury/arlocal) test network, started with
+/// `npx arlocal`. Its tokens are minted for free and blocks are mined on request.
+pub const TESTNET_BASE_URL: &str = "http://localhost:1984/";
+
+/// Environment variable read by `from_env` signer constructors, holding the JWK wallet JSON.
+pub const WALLET_JWK_ENV: &str = "ARWEAVE_WALLET_JWK";
+
 /// Block size used for pricing calculations = 256 KB
 pub const BLOCK_SIZE: u64 = 1024 * 256;
 
@@ -12,6 +19,12 @@ pub const MAX_TX_DATA: u64 = 10_000_000;
 /// of simultaneous request to the `chunk/ endpoint`.
 pub const CHUNKS_BUFFER_FACTOR: usize = 20;
 
+/// Default maximum number of simultaneous requests to the `chunk/` endpoint.
+pub const CHUNKS_CONCURRENCY: usize = 5;
+
+/// Maximum number of files of a manifest downloaded simultaneously.
+pub const DOWNLOAD_CONCURRENCY: usize = 5;
+
 /// Number of times to retry posting chunks if not successful.
 pub const CHUNKS_RETRIES: u16 = 10;
 
@@ -0,0 +1,126 @@
+//! Detection of the `Content-Type` tag of uploads, so that gateways serve files with the right
+//! headers. With the `mime` feature, types are guessed from the file extension and else from
+//! the magic bytes of the data. Overrides by extension take precedence over detection.
+
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    crypto::base64::Base64,
+    transaction::tags::{Tag, CONTENT_TYPE},
+};
+
+/// Content type of data that could not be identified.
+pub const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Content type guessed from the extension of `path`.
+#[cfg(feature = "mime")]
+pub fn from_path(path: &Path) -> Option<&'static str> {
+    mime_guess::from_path(path).first_raw()
+}
+
+#[cfg(not(feature = "mime"))]
+pub fn from_path(_path: &Path) -> Option<&'static str> {
+    None
+}
+
+/// Content type identified from the [magic numbers](https://developer.mozilla.org/en-US/docs/Web/HTTP/Basics_of_HTTP/MIME_types)
+/// at the start of `data`.
+#[cfg(feature = "mime")]
+pub fn from_data(data: &[u8]) -> Option<&'static str> {
+    infer::get(data).map(|kind| kind.mime_type())
+}
+
+#[cfg(not(feature = "mime"))]
+pub fn from_data(_data: &[u8]) -> Option<&'static str> {
+    None
+}
+
+/// Content types of uploaded files, with overrides by extension.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentTypes {
+    overrides: HashMap<String, String>,
+}
+
+impl ContentTypes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tags files with the extension `extension`, matched case-insensitively, as
+    /// `content_type`.
+    pub fn extension(mut self, extension: &str, content_type: &str) -> Self {
+        self.overrides.insert(
+            extension.trim_start_matches('.').to_ascii_lowercase(),
+            content_type.to_string(),
+        );
+        self
+    }
+
+    /// Content type of the file at `path` holding `data`: its override, else the type
+    /// guessed from its extension, else from `data`, else [`DEFAULT_CONTENT_TYPE`].
+    pub fn detect(&self, path: &Path, data: &[u8]) -> &str {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| self.overrides.get(&extension.to_ascii_lowercase()))
+            .map(String::as_str)
+            .or_else(|| from_path(path))
+            .or_else(|| from_data(data))
+            .unwrap_or(DEFAULT_CONTENT_TYPE)
+    }
+
+    /// Adds the `Content-Type` tag detected for the file to `tags`, unless they have one.
+    pub fn tag(&self, path: &Path, data: &[u8], tags: &mut Vec<Tag<Base64>>) {
+        if !tags.iter().any(|tag| tag.has_name(CONTENT_TYPE)) {
+            tags.push(Tag::content_type(self.detect(path, data)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::transaction::tags::{find_tag_value, Tag};
+
+    use super::ContentTypes;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[cfg(feature = "mime")]
+    #[test]
+    fn test_detect() {
+        let content_types = ContentTypes::new();
+        assert_eq!(
+            content_types.detect(Path::new("site/index.html"), b""),
+            "text/html"
+        );
+        assert_eq!(content_types.detect(Path::new("logo"), PNG), "image/png");
+        assert_eq!(
+            content_types.detect(Path::new("notes"), b"notes"),
+            super::DEFAULT_CONTENT_TYPE
+        );
+    }
+
+    #[test]
+    fn test_overrides() {
+        let content_types = ContentTypes::new()
+            .extension(".md", "text/markdown")
+            .extension("PNG", "image/apng");
+        assert_eq!(
+            content_types.detect(Path::new("README.MD"), b""),
+            "text/markdown"
+        );
+        assert_eq!(content_types.detect(Path::new("a.png"), PNG), "image/apng");
+
+        let mut tags = vec![];
+        content_types.tag(Path::new("README.md"), b"", &mut tags);
+        assert_eq!(
+            find_tag_value(&tags, "Content-Type").as_deref(),
+            Some("text/markdown")
+        );
+        // Tags set by the caller are kept.
+        let mut tags = vec![Tag::content_type("text/plain")];
+        content_types.tag(Path::new("README.md"), b"", &mut tags);
+        assert_eq!(tags, vec![Tag::content_type("text/plain")]);
+    }
+}
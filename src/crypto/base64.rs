@@ -4,7 +4,8 @@ use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::error::Error;
 
-#[derive(Debug, Clone, Eq, PartialEq, Default)]
+/// Bytes encoded as base64url without padding, as in the JSON of the Arweave HTTP API.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
 pub struct Base64(pub Vec<u8>);
 
 impl std::fmt::Display for Base64 {
@@ -20,6 +21,38 @@ impl From<&[u8]> for Base64 {
     }
 }
 
+impl From<Vec<u8>> for Base64 {
+    fn from(bytes: Vec<u8>) -> Self {
+        Base64(bytes)
+    }
+}
+
+impl From<Base64> for Vec<u8> {
+    fn from(base64: Base64) -> Self {
+        base64.0
+    }
+}
+
+impl TryFrom<&str> for Base64 {
+    type Error = base64::DecodeError;
+    fn try_from(str: &str) -> Result<Self, Self::Error> {
+        str.parse()
+    }
+}
+
+impl TryFrom<String> for Base64 {
+    type Error = base64::DecodeError;
+    fn try_from(string: String) -> Result<Self, Self::Error> {
+        string.parse()
+    }
+}
+
+impl AsRef<[u8]> for Base64 {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 impl FromStr for Base64 {
     type Err = base64::DecodeError;
     fn from_str(str: &str) -> Result<Self, Self::Err> {
@@ -43,15 +76,73 @@ impl Base64 {
     pub fn empty() -> Self {
         Base64(vec![])
     }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+
+    pub fn as_base64_ref(&self) -> Base64Ref<'_> {
+        Base64Ref(&self.0)
+    }
+}
+
+/// Borrowed [`Base64`], encoding the same way without owning or copying the bytes. Used on
+/// hot paths such as posting chunks, where the bytes are a slice of a larger buffer. Signing
+/// borrows fields the same way through [`crate::crypto::hash::DeepHashItem`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub struct Base64Ref<'a>(pub &'a [u8]);
+
+impl std::fmt::Display for Base64Ref<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let string = &base64::display::Base64Display::with_config(self.0, base64::URL_SAFE_NO_PAD);
+        write!(f, "{}", string)
+    }
+}
+
+impl<'a> From<&'a [u8]> for Base64Ref<'a> {
+    fn from(bytes: &'a [u8]) -> Self {
+        Base64Ref(bytes)
+    }
+}
+
+impl<'a> From<&'a Base64> for Base64Ref<'a> {
+    fn from(base64: &'a Base64) -> Self {
+        Base64Ref(&base64.0)
+    }
+}
+
+impl AsRef<[u8]> for Base64Ref<'_> {
+    fn as_ref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+impl Base64Ref<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn to_base64(&self) -> Base64 {
+        Base64(self.0.to_vec())
+    }
+}
+
+impl Serialize for Base64Ref<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
 }
 
 impl Serialize for Base64 {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        serializer.collect_str(&format!("{}", &self))
+        self.as_base64_ref().serialize(serializer)
     }
 }
 
-//TODO: remove unwraps
 impl<'de> Deserialize<'de> for Base64 {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
         struct Vis;
@@ -65,18 +156,136 @@ impl<'de> Deserialize<'de> for Base64 {
             fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
                 base64::decode_config(v, base64::URL_SAFE_NO_PAD)
                     .map(Base64)
-                    .map_err(|_| de::Error::custom("failed to decode base64 string"))
+                    .map_err(|e| {
+                        de::Error::custom(format!("failed to decode base64 string: {}", e))
+                    })
             }
         }
         deserializer.deserialize_str(Vis)
     }
 }
 
+/// Defines a fixed-size hash written as base64url, such as [`crate::tx_id::TxId`]: parsing
+/// checks the length and charset before decoding, and serde goes through the string form.
+macro_rules! fixed_base64 {
+    ($(#[$attr:meta])* $name:ident, $size:literal, $length:ident, $error:ident) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name([u8; $size]);
+
+        impl $name {
+            pub fn new(bytes: [u8; $size]) -> Self {
+                Self(bytes)
+            }
+
+            pub fn as_bytes(&self) -> &[u8; $size] {
+                &self.0
+            }
+
+            pub fn to_base64(&self) -> $crate::crypto::base64::Base64 {
+                $crate::crypto::base64::Base64(self.0.to_vec())
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self([0; $size])
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                $crate::crypto::base64::Base64Ref(&self.0).fmt(f)
+            }
+        }
+
+        impl std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "{}({})", stringify!($name), self)
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = $crate::error::Error;
+            fn from_str(str: &str) -> Result<Self, Self::Err> {
+                let is_base64url = str
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+                if str.len() != $length || !is_base64url {
+                    return Err($crate::error::Error::$error(str.to_string()));
+                }
+                let base64 = $crate::crypto::base64::Base64::from_str(str)
+                    .map_err(|_| $crate::error::Error::$error(str.to_string()))?;
+                Self::try_from(&base64)
+            }
+        }
+
+        impl TryFrom<&$crate::crypto::base64::Base64> for $name {
+            type Error = $crate::error::Error;
+            fn try_from(base64: &$crate::crypto::base64::Base64) -> Result<Self, Self::Error> {
+                base64
+                    .0
+                    .as_slice()
+                    .try_into()
+                    .map(Self)
+                    .map_err(|_| $crate::error::Error::$error(base64.to_string()))
+            }
+        }
+
+        impl From<$name> for $crate::crypto::base64::Base64 {
+            fn from(value: $name) -> Self {
+                value.to_base64()
+            }
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Self, D::Error> {
+                let string = String::deserialize(deserializer)?;
+                string.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+pub(crate) use fixed_base64;
+
+/// Serde helpers for byte fields encoded as base64url strings, for use as
+/// `#[serde(with = "arweave_rs::crypto::base64::base64url")]` on types converting to and from
+/// `Vec<u8>`.
+pub mod base64url {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{Base64, Base64Ref};
+
+    pub fn serialize<T: AsRef<[u8]>, S: Serializer>(
+        bytes: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Base64Ref(bytes.as_ref()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: From<Vec<u8>>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        Base64::deserialize(deserializer).map(|base64| base64.0.into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
 
-    use crate::crypto::base64::Base64;
+    use serde::{Deserialize, Serialize};
+
+    use crate::crypto::base64::{Base64, Base64Ref};
 
     #[test]
     fn test_deserialize_base64() {
@@ -106,4 +315,46 @@ mod tests {
         let foo_b64 = Base64(vec![44; 7]);
         assert_eq!(foo_b64.to_string(), "LCwsLCwsLA".to_string());
     }
+
+    #[test]
+    fn test_base64_conversions() {
+        let foo_b64 = Base64::try_from("LCwsLCwsLA").unwrap();
+        assert_eq!(foo_b64, Base64::from(vec![44; 7]));
+        assert_eq!(Base64::try_from(foo_b64.to_string()).unwrap(), foo_b64);
+        assert_eq!(Vec::from(foo_b64), vec![44; 7]);
+        assert!(Base64::try_from("LCws+CwsLA==").is_err());
+    }
+
+    #[test]
+    fn test_serde_fields() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Record {
+            owner: Base64,
+            #[serde(with = "super::base64url")]
+            data: Vec<u8>,
+        }
+        let record = Record {
+            owner: Base64(vec![44; 7]),
+            data: b"foo".to_vec(),
+        };
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(json, r#"{"owner":"LCwsLCwsLA","data":"Zm9v"}"#);
+        assert_eq!(serde_json::from_str::<Record>(&json).unwrap(), record);
+        assert!(serde_json::from_str::<Record>(r#"{"owner":"!","data":""}"#).is_err());
+    }
+
+    #[test]
+    fn test_base64_ref() {
+        let bytes = [44; 9];
+        let borrowed = Base64Ref(&bytes[..7]);
+        let owned = Base64(vec![44; 7]);
+        assert_eq!(borrowed, owned.as_base64_ref());
+        assert_eq!(borrowed.to_string(), owned.to_string());
+        assert_eq!(
+            serde_json::to_string(&borrowed).unwrap(),
+            serde_json::to_string(&owned).unwrap()
+        );
+        assert_eq!(borrowed.to_base64(), owned);
+        assert_eq!(owned.as_ref(), borrowed.as_ref());
+    }
 }
@@ -0,0 +1,115 @@
+//! [`Signer`] for Ed25519 keys, used by data items of signature type 2.
+
+use std::{fs, path::Path};
+
+use ed25519_dalek::{Signature, Signer as _, SigningKey, Verifier, VerifyingKey};
+
+use crate::error::Error;
+
+use super::{
+    base64::Base64,
+    sign::{SignatureType, Signer},
+};
+
+pub struct Ed25519Signer {
+    signing_key: SigningKey,
+}
+
+impl Ed25519Signer {
+    pub fn from_secret_key(secret_key: &[u8; 32]) -> Self {
+        Self {
+            signing_key: SigningKey::from_bytes(secret_key),
+        }
+    }
+
+    /// Parses the 64 bytes of a Solana keypair, the secret key followed by the public key.
+    pub fn from_keypair_bytes(keypair: &[u8]) -> Result<Self, Error> {
+        let keypair: &[u8; 64] = keypair.try_into().map_err(|_| {
+            Error::InvalidKey(format!("expected 64 keypair bytes, got {}", keypair.len()))
+        })?;
+        let signing_key = SigningKey::from_keypair_bytes(keypair)
+            .map_err(|e| Error::InvalidKey(e.to_string()))?;
+        Ok(Self { signing_key })
+    }
+
+    /// Loads a keypair file of the Solana CLI, a JSON array of the 64 keypair bytes. Items
+    /// are signed with signature type 2, as done by Solana signers of other ANS-104 tools.
+    pub fn from_solana_keypair_file(path: &Path) -> Result<Self, Error> {
+        let keypair: Vec<u8> =
+            serde_json::from_slice(&fs::read(path)?).map_err(Error::SerdeJsonError)?;
+        Self::from_keypair_bytes(&keypair)
+    }
+
+    /// Creates a new random keypair.
+    pub fn generate() -> Self {
+        Self {
+            signing_key: SigningKey::generate(&mut rand::thread_rng()),
+        }
+    }
+
+    pub fn secret_key(&self) -> [u8; 32] {
+        self.signing_key.to_bytes()
+    }
+}
+
+impl Signer for Ed25519Signer {
+    fn sign(&self, message: &[u8]) -> Result<Base64, Error> {
+        Ok(Base64(self.signing_key.sign(message).to_bytes().to_vec()))
+    }
+
+    fn public_key(&self) -> Base64 {
+        Base64(self.signing_key.verifying_key().to_bytes().to_vec())
+    }
+
+    fn signature_type(&self) -> SignatureType {
+        SignatureType::Ed25519
+    }
+}
+
+pub fn verify(pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), Error> {
+    let pub_key: &[u8; 32] = pub_key.try_into().map_err(|_| Error::InvalidSignature)?;
+    let pub_key = VerifyingKey::from_bytes(pub_key).map_err(|_| Error::InvalidSignature)?;
+    let signature = Signature::from_slice(signature).map_err(|_| Error::InvalidSignature)?;
+    pub_key
+        .verify(message, &signature)
+        .map_err(|_| Error::InvalidSignature)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{crypto::sign::Signer, error::Error};
+
+    use super::{verify, Ed25519Signer};
+
+    #[test]
+    fn test_solana_keypair_file() -> Result<(), Error> {
+        let signer = Ed25519Signer::generate();
+        let mut keypair = signer.secret_key().to_vec();
+        keypair.extend(&signer.public_key().0);
+        let path = crate::temp_path("arweave-rs-solana-keypair.json");
+        std::fs::write(&path, serde_json::to_vec(&keypair).unwrap())?;
+
+        let restored = Ed25519Signer::from_solana_keypair_file(&path)?;
+        assert_eq!(restored.public_key(), signer.public_key());
+        std::fs::remove_file(&path)?;
+
+        // Public half not matching the secret key.
+        keypair[40] ^= 1;
+        assert!(Ed25519Signer::from_keypair_bytes(&keypair).is_err());
+        assert!(Ed25519Signer::from_keypair_bytes(&keypair[..32]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_verify() -> Result<(), Error> {
+        let signer = Ed25519Signer::generate();
+        let signature = signer.sign(b"message")?;
+        assert_eq!(signature.0.len(), 64);
+        verify(&signer.public_key().0, b"message", &signature.0)?;
+        assert!(verify(&signer.public_key().0, b"other", &signature.0).is_err());
+
+        let restored = Ed25519Signer::from_secret_key(&signer.secret_key());
+        assert_eq!(restored.wallet_address(), signer.wallet_address());
+        Ok(())
+    }
+}
@@ -1,23 +1,87 @@
+use std::{
+    borrow::Cow,
+    io::{Read, Write},
+};
+
 use sha2::Digest;
+use tokio::io::{AsyncRead, AsyncReadExt};
 
 use crate::error::Error;
 
 use super::utils::concat_u8_48;
 
+/// Size of the pieces readers are hashed in.
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Incremental SHA256 hashing of data fed in pieces. Implements [`std::io::Write`], so that
+/// files can be hashed with [`std::io::copy`].
+#[derive(Debug, Clone, Default)]
+pub struct Sha256Hasher(sha2::Sha256);
+
+impl Sha256Hasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    pub fn finalize(self) -> [u8; 32] {
+        self.0.finalize().into()
+    }
+}
+
+/// Same as [`Sha256Hasher`] for SHA384.
+#[derive(Debug, Clone, Default)]
+pub struct Sha384Hasher(sha2::Sha384);
+
+impl Sha384Hasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    pub fn finalize(self) -> [u8; 48] {
+        self.0.finalize().into()
+    }
+}
+
+impl Write for Sha256Hasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Write for Sha384Hasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 pub fn sha256(message: &[u8]) -> [u8; 32] {
-    let mut context = sha2::Sha256::new();
-    context.update(message);
-    let mut result: [u8; 32] = [0; 32];
-    result.copy_from_slice(context.finalize().as_ref());
-    result
+    let mut hasher = Sha256Hasher::new();
+    hasher.update(message);
+    hasher.finalize()
 }
 
 pub fn sha384(message: &[u8]) -> [u8; 48] {
-    let mut context = sha2::Sha384::new();
-    context.update(message);
-    let mut result: [u8; 48] = [0; 48];
-    result.copy_from_slice(context.finalize().as_ref());
-    result
+    let mut hasher = Sha384Hasher::new();
+    hasher.update(message);
+    hasher.finalize()
 }
 
 /// Returns a SHA256 hash of the the concatenated SHA256 hashes of a vector of messages.
@@ -32,23 +96,27 @@ pub fn hash_all_sha384(messages: Vec<&[u8]>) -> [u8; 48] {
     sha384(&hash)
 }
 
+/// Item to deep hash. Blobs borrow the bytes of the transaction or data item being hashed
+/// where possible, so signing does not copy their data.
 #[derive(Debug)]
-pub enum DeepHashItem {
-    Blob(Vec<u8>),
-    List(Vec<DeepHashItem>),
+pub enum DeepHashItem<'a> {
+    Blob(Cow<'a, [u8]>),
+    List(Vec<DeepHashItem<'a>>),
+    /// Deep hash of an item computed beforehand, e.g. of a blob with [`deep_hash_reader`].
+    Hash([u8; 48]),
 }
 
-impl DeepHashItem {
-    pub fn from_item(item: &[u8]) -> DeepHashItem {
-        Self::Blob(item.to_vec())
+impl<'a> DeepHashItem<'a> {
+    pub fn from_item(item: &'a [u8]) -> DeepHashItem<'a> {
+        Self::Blob(Cow::Borrowed(item))
     }
-    pub fn from_children(children: Vec<DeepHashItem>) -> DeepHashItem {
+    pub fn from_children(children: Vec<DeepHashItem<'a>>) -> DeepHashItem<'a> {
         Self::List(children)
     }
 }
 
 pub trait ToItems<'a, T> {
-    fn to_deep_hash_item(&'a self) -> Result<DeepHashItem, Error>;
+    fn to_deep_hash_item(&'a self) -> Result<DeepHashItem<'a>, Error>;
 }
 
 /// Calculates data root of transaction in accordance with implementation in [arweave-js](https://github.com/ArweaveTeam/arweave-js/blob/master/src/common/lib/deepHash.ts).
@@ -70,15 +138,61 @@ pub fn deep_hash(deep_hash_item: DeepHashItem) -> [u8; 48] {
             }
             hash
         }
+        DeepHashItem::Hash(hash) => hash,
     };
     hash
 }
+
+/// Deep hash of the blob read from `reader` to the end, hashed in pieces instead of being
+/// buffered. Combined with other items as a [`DeepHashItem::Hash`].
+pub fn deep_hash_reader<R: Read>(mut reader: R) -> Result<[u8; 48], Error> {
+    let mut hasher = Sha384Hasher::new();
+    let mut buf = vec![0; READ_BUFFER_SIZE];
+    let mut size = 0;
+    loop {
+        let read = reader.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+        size += read;
+    }
+    Ok(blob_hash(size, hasher))
+}
+
+/// Same as [`deep_hash_reader`] for async readers.
+pub async fn deep_hash_async_reader<R: AsyncRead + Unpin>(
+    mut reader: R,
+) -> Result<[u8; 48], Error> {
+    let mut hasher = Sha384Hasher::new();
+    let mut buf = vec![0; READ_BUFFER_SIZE];
+    let mut size = 0;
+    loop {
+        let read = reader.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+        size += read;
+    }
+    Ok(blob_hash(size, hasher))
+}
+
+/// Deep hash of a blob of `size` bytes from the hasher the blob was fed to.
+fn blob_hash(size: usize, hasher: Sha384Hasher) -> [u8; 48] {
+    let data_hash = hasher.finalize();
+    let tag_hash = sha384(format!("blob{}", size).as_bytes());
+    sha384(&concat_u8_48(tag_hash, data_hash))
+}
 #[cfg(test)]
 mod tests {
     use std::{fs::File, io::Read, str::FromStr};
 
     use crate::{
-        crypto::hash::{deep_hash, ToItems},
+        crypto::hash::{
+            deep_hash, deep_hash_async_reader, deep_hash_reader, sha256, sha384, DeepHashItem,
+            Sha256Hasher, Sha384Hasher, ToItems,
+        },
         error::Error,
         transaction::Tx,
     };
@@ -101,4 +215,46 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_hashers() -> Result<(), Error> {
+        let data = std::fs::read("res/rebar3")?;
+        let mut sha256_hasher = Sha256Hasher::new();
+        for piece in data.chunks(1000) {
+            sha256_hasher.update(piece);
+        }
+        assert_eq!(sha256_hasher.finalize(), sha256(&data));
+
+        let mut sha384_hasher = Sha384Hasher::new();
+        std::io::copy(&mut std::fs::File::open("res/rebar3")?, &mut sha384_hasher)?;
+        assert_eq!(sha384_hasher.finalize(), sha384(&data));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_deep_hash_reader() -> Result<(), Error> {
+        let data = std::fs::read("res/rebar3")?;
+        let expected = deep_hash(DeepHashItem::Blob(data.as_slice().into()));
+        assert_eq!(deep_hash_reader(&data[..])?, expected);
+        assert_eq!(
+            deep_hash_async_reader(tokio::fs::File::open("res/rebar3").await?).await?,
+            expected
+        );
+        assert_eq!(
+            deep_hash_reader(&[][..])?,
+            deep_hash(DeepHashItem::Blob(Vec::new().into()))
+        );
+
+        let list = |data: DeepHashItem| {
+            deep_hash(DeepHashItem::List(vec![
+                DeepHashItem::from_item(b"dataitem"),
+                data,
+            ]))
+        };
+        assert_eq!(
+            list(DeepHashItem::Hash(deep_hash_reader(&data[..])?)),
+            list(DeepHashItem::Blob(data.as_slice().into()))
+        );
+        Ok(())
+    }
 }
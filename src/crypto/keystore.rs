@@ -0,0 +1,236 @@
+//! Password protected storage of JWK wallets.
+//!
+//! The JWK is encrypted with AES-256-GCM under a key derived from the passphrase with
+//! scrypt, and stored as JSON together with the KDF parameters, salt and nonce.
+
+use std::{fs, io::Write, path::Path};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use jsonwebkey as jwk;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::{crypto::base64::Base64, error::Error};
+
+pub const KEYSTORE_VERSION: u8 = 1;
+/// scrypt cost parameters recommended for interactive logins.
+pub const SCRYPT_LOG_N: u8 = 17;
+pub const SCRYPT_R: u32 = 8;
+pub const SCRYPT_P: u32 = 1;
+/// Most memory, `128 * r * 2^log_n` bytes, and parallelism scrypt is run with, so that the
+/// parameters of a crafted keystore can't exhaust the host.
+pub const MAX_SCRYPT_MEMORY: u64 = 1 << 30;
+pub const MAX_SCRYPT_P: u32 = 16;
+
+const SALT_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ScryptParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+    pub salt: Base64,
+}
+
+impl ScryptParams {
+    /// Parameters with the default cost and a random salt.
+    pub fn new() -> Self {
+        Self::with_cost(SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P)
+    }
+
+    pub fn with_cost(log_n: u8, r: u32, p: u32) -> Self {
+        let mut salt = vec![0; SALT_SIZE];
+        rand::thread_rng().fill_bytes(&mut salt);
+        Self {
+            log_n,
+            r,
+            p,
+            salt: Base64(salt),
+        }
+    }
+
+    fn derive_key(&self, passphrase: &str) -> Result<[u8; 32], Error> {
+        let memory = 1u64
+            .checked_shl(self.log_n.into())
+            .and_then(|n| n.checked_mul(128 * u64::from(self.r)));
+        if !matches!(memory, Some(memory) if memory <= MAX_SCRYPT_MEMORY) || self.p > MAX_SCRYPT_P {
+            return Err(Error::KeystoreError(format!(
+                "scrypt parameters log_n={} r={} p={} above the limits",
+                self.log_n, self.r, self.p
+            )));
+        }
+        let params = scrypt::Params::new(self.log_n, self.r, self.p, 32)
+            .map_err(|e| Error::KeystoreError(e.to_string()))?;
+        let mut key = [0; 32];
+        scrypt::scrypt(passphrase.as_bytes(), &self.salt.0, &params, &mut key)
+            .map_err(|e| Error::KeystoreError(e.to_string()))?;
+        Ok(key)
+    }
+}
+
+impl Default for ScryptParams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Encrypted JWK, as saved in keystore files.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Keystore {
+    pub version: u8,
+    pub kdf: ScryptParams,
+    pub nonce: Base64,
+    pub ciphertext: Base64,
+}
+
+impl Keystore {
+    pub fn encrypt(jwk: &jwk::JsonWebKey, passphrase: &str) -> Result<Self, Error> {
+        Self::encrypt_with_params(jwk, passphrase, ScryptParams::new())
+    }
+
+    pub fn encrypt_with_params(
+        jwk: &jwk::JsonWebKey,
+        passphrase: &str,
+        kdf: ScryptParams,
+    ) -> Result<Self, Error> {
+        let key = kdf.derive_key(passphrase)?;
+        let mut nonce = vec![0; NONCE_SIZE];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = Aes256Gcm::new(&key.into())
+            .encrypt(Nonce::from_slice(&nonce), jwk.to_string().as_bytes())
+            .map_err(|e| Error::KeystoreError(e.to_string()))?;
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            kdf,
+            nonce: Base64(nonce),
+            ciphertext: Base64(ciphertext),
+        })
+    }
+
+    /// Decrypts the JWK, failing if `passphrase` is wrong or the keystore was altered.
+    pub fn decrypt(&self, passphrase: &str) -> Result<jwk::JsonWebKey, Error> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(Error::KeystoreError(format!(
+                "unsupported version {}",
+                self.version
+            )));
+        }
+        if self.nonce.0.len() != NONCE_SIZE {
+            return Err(Error::KeystoreError("invalid nonce".to_string()));
+        }
+        let key = self.kdf.derive_key(passphrase)?;
+        let plaintext = Aes256Gcm::new(&key.into())
+            .decrypt(
+                Nonce::from_slice(&self.nonce.0),
+                self.ciphertext.0.as_slice(),
+            )
+            .map_err(|_| Error::KeystoreError("invalid passphrase".to_string()))?;
+        jwk::JsonWebKey::from_slice(plaintext).map_err(Error::JsonWebKeyError)
+    }
+
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        serde_json::from_slice(&fs::read(path)?).map_err(Error::SerdeJsonError)
+    }
+
+    /// Writes the keystore to `path`, readable by its owner only on unix.
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let json = serde_json::to_vec(self).map_err(Error::SerdeJsonError)?;
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        // The mode only applies to new files.
+        #[cfg(unix)]
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        file.write_all(&json)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use crate::{
+        crypto::sign::{RsaSigner, Signer},
+        error::Error,
+        ArweaveSigner,
+    };
+
+    use super::{Keystore, ScryptParams};
+
+    #[test]
+    fn test_encrypt_decrypt() -> Result<(), Error> {
+        let signer = RsaSigner::from_keypair_path(PathBuf::from("res/test_wallet.json"))?;
+        let jwk = signer.to_jwk();
+        let keystore =
+            Keystore::encrypt_with_params(&jwk, "secret", ScryptParams::with_cost(4, 8, 1))?;
+        assert_eq!(keystore.decrypt("secret")?, jwk);
+        assert!(matches!(
+            keystore.decrypt("wrong"),
+            Err(Error::KeystoreError(msg)) if msg == "invalid passphrase"
+        ));
+
+        let mut tampered = keystore.clone();
+        tampered.ciphertext.0[0] ^= 1;
+        assert!(tampered.decrypt("secret").is_err());
+
+        // Costs of a crafted keystore are rejected before running scrypt.
+        for (log_n, r, p) in [(40, 8, 1), (21, 8, 1), (4, u32::MAX, 1), (4, 8, 17)] {
+            let mut crafted = keystore.clone();
+            crafted.kdf.log_n = log_n;
+            crafted.kdf.r = r;
+            crafted.kdf.p = p;
+            assert!(matches!(
+                crafted.decrypt("secret"),
+                Err(Error::KeystoreError(msg)) if msg.contains("above the limits")
+            ));
+        }
+
+        // A keystore holding a public key fails instead of aborting.
+        let public: jsonwebkey::JsonWebKey = serde_json::from_value(serde_json::json!({
+            "kty": "RSA",
+            "e": "AQAB",
+            "n": signer.public_key().to_string(),
+        }))
+        .unwrap();
+        let path = crate::temp_path("arweave-rs-keystore-public.json");
+        Keystore::encrypt_with_params(&public, "secret", ScryptParams::with_cost(4, 8, 1))?
+            .write(&path)?;
+        assert!(matches!(
+            RsaSigner::from_encrypted_file(&path, "secret"),
+            Err(Error::InvalidKey(_))
+        ));
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_file() -> Result<(), Error> {
+        let signer = RsaSigner::from_keypair_path(PathBuf::from("res/test_wallet.json"))?;
+        let path = crate::temp_path("arweave-rs-keystore.json");
+        signer.to_encrypted_file(&path, "secret")?;
+        assert!(!fs::read_to_string(&path)?.contains(&signer.public_key().to_string()));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path)?.permissions().mode() & 0o777, 0o600);
+        }
+
+        let restored = RsaSigner::from_encrypted_file(&path, "secret")?;
+        assert_eq!(restored.wallet_address(), signer.wallet_address());
+
+        let signer = ArweaveSigner::from_keypair_path(PathBuf::from("res/test_wallet.json"))?;
+        signer.to_encrypted_file(&path, "other")?;
+        let restored = ArweaveSigner::from_encrypted_file(&path, "other")?;
+        assert_eq!(restored.wallet_address(), signer.wallet_address());
+        fs::remove_file(path)?;
+        Ok(())
+    }
+}
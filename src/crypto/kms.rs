@@ -0,0 +1,231 @@
+//! [`Signer`] backed by an RSA-4096 asymmetric key of AWS KMS.
+//!
+//! Signatures are RSASSA-PSS with SHA-256, computed by KMS over the raw message, so the
+//! private key never leaves KMS.
+
+use aws_sdk_kms::{
+    error::DisplayErrorContext,
+    primitives::Blob,
+    types::{MessageType, SigningAlgorithmSpec},
+    Client,
+};
+use rsa::{pkcs8::DecodePublicKey, PublicKeyParts, RsaPublicKey};
+use std::sync::mpsc;
+
+use tokio::runtime::{Builder, Runtime};
+
+use crate::error::Error;
+
+use super::{
+    base64::Base64,
+    sign::{SignatureType, Signer},
+};
+
+pub struct KmsSigner {
+    client: Client,
+    key_id: String,
+    public_key: Base64,
+    /// Runs the KMS requests of [`Signer::sign`], so that blocking callers work both
+    /// outside and inside any tokio runtime.
+    runtime: Option<Runtime>,
+}
+
+impl KmsSigner {
+    /// Fetches the public key of `key_id`, which must be an `RSA_4096` key with the
+    /// `SIGN_VERIFY` usage.
+    pub async fn new(client: Client, key_id: &str) -> Result<Self, Error> {
+        let output = client
+            .get_public_key()
+            .key_id(key_id)
+            .send()
+            .await
+            .map_err(|e| Error::CryptoError(DisplayErrorContext(e).to_string()))?;
+        let der = output
+            .public_key()
+            .ok_or_else(|| Error::NoneError("public key".to_string()))?;
+        let public_key = RsaPublicKey::from_public_key_der(der.as_ref())
+            .map_err(|e| Error::InvalidKey(e.to_string()))?;
+        let modulus = Base64(public_key.n().to_bytes_be());
+        if modulus.0.len() != SignatureType::Arweave.pub_length() {
+            return Err(Error::InvalidKey(format!(
+                "expected an RSA-4096 key, got {} bits",
+                modulus.0.len() * 8
+            )));
+        }
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("arweave-kms")
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            client,
+            key_id: key_id.to_string(),
+            public_key: modulus,
+            runtime: Some(runtime),
+        })
+    }
+
+    /// Signs `message` on the caller's runtime. Prefer this over [`Signer::sign`] in
+    /// async code.
+    pub async fn sign_async(&self, message: &[u8]) -> Result<Base64, Error> {
+        sign_with(&self.client, &self.key_id, message).await
+    }
+}
+
+async fn sign_with(client: &Client, key_id: &str, message: &[u8]) -> Result<Base64, Error> {
+    let output = client
+        .sign()
+        .key_id(key_id)
+        .message(Blob::new(message))
+        .message_type(MessageType::Raw)
+        .signing_algorithm(SigningAlgorithmSpec::RsassaPssSha256)
+        .send()
+        .await
+        .map_err(|e| Error::SigningError(DisplayErrorContext(e).to_string()))?;
+    let signature = output
+        .signature()
+        .ok_or_else(|| Error::NoneError("signature".to_string()))?;
+    Ok(Base64(signature.as_ref().to_vec()))
+}
+
+impl Signer for KmsSigner {
+    /// Blocks until the signer's own runtime has signed `message`. This blocks the calling
+    /// thread, within async code use [`KmsSigner::sign_async`] instead.
+    fn sign(&self, message: &[u8]) -> Result<Base64, Error> {
+        let (client, key_id, message) =
+            (self.client.clone(), self.key_id.clone(), message.to_vec());
+        let (sender, receiver) = mpsc::channel();
+        //Checked unwrap, the runtime is only taken on drop
+        self.runtime.as_ref().unwrap().spawn(async move {
+            let _ = sender.send(sign_with(&client, &key_id, &message).await);
+        });
+        receiver
+            .recv()
+            .map_err(|_| Error::SigningError("KMS signing task was cancelled".to_string()))?
+    }
+
+    fn public_key(&self) -> Base64 {
+        self.public_key.clone()
+    }
+
+    fn signature_type(&self) -> SignatureType {
+        SignatureType::Arweave
+    }
+}
+
+impl Drop for KmsSigner {
+    // Dropping a runtime blocks, which panics when the signer is dropped in async code.
+    fn drop(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use aws_sdk_kms::{
+        config::{BehaviorVersion, Credentials, Region},
+        Client, Config,
+    };
+    use httpmock::{Method::POST, MockServer};
+    use rsa::{pkcs8::EncodePublicKey, BigUint, RsaPublicKey};
+    use tokio_test::block_on;
+
+    use crate::{
+        crypto::{
+            sign::{RsaSigner, Signer},
+            verify::verify,
+        },
+        error::Error,
+    };
+
+    use super::KmsSigner;
+
+    fn client(server: &MockServer) -> Client {
+        let config = Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(Credentials::new("key", "secret", None, None, "test"))
+            .endpoint_url(server.url(""))
+            .build();
+        Client::from_conf(config)
+    }
+
+    #[test]
+    fn test_kms_sign() -> Result<(), Error> {
+        let local = RsaSigner::from_keypair_path(PathBuf::from("res/test_wallet_4096.json"))?;
+        let public_key = RsaPublicKey::new(
+            BigUint::from_bytes_be(&local.public_key().0),
+            BigUint::from(65537u32),
+        )
+        .unwrap();
+        let der = public_key.to_public_key_der().unwrap();
+        let message = b"message";
+        let signature = local.sign(message)?;
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST)
+                .header("x-amz-target", "TrentService.GetPublicKey")
+                .body_contains("\"KeyId\":\"alias/arweave\"");
+            then.status(200).json_body(serde_json::json!({
+                "KeyId": "alias/arweave",
+                "KeySpec": "RSA_4096",
+                "KeyUsage": "SIGN_VERIFY",
+                "PublicKey": base64::encode(der.as_ref()),
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(POST)
+                .header("x-amz-target", "TrentService.Sign")
+                .body_contains("\"MessageType\":\"RAW\"")
+                .body_contains("\"SigningAlgorithm\":\"RSASSA_PSS_SHA_256\"")
+                .body_contains(base64::encode(message));
+            then.status(200).json_body(serde_json::json!({
+                "KeyId": "alias/arweave",
+                "Signature": base64::encode(&signature.0),
+                "SigningAlgorithm": "RSASSA_PSS_SHA_256",
+            }));
+        });
+
+        let signer = block_on(KmsSigner::new(client(&server), "alias/arweave"))?;
+        assert_eq!(signer.wallet_address(), local.wallet_address());
+        let signature = signer.sign(message)?;
+        verify(&signer.public_key().0, message, &signature.0)?;
+
+        // Blocking signing within a current thread runtime, and dropping the signer there.
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let signature = runtime.block_on(async move { signer.sign(message) })?;
+        verify(&local.public_key().0, message, &signature.0)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_kms_rejects_short_keys() {
+        let local = RsaSigner::from_keypair_path(PathBuf::from("res/test_wallet.json")).unwrap();
+        let public_key = RsaPublicKey::new(
+            BigUint::from_bytes_be(&local.public_key().0),
+            BigUint::from(65537u32),
+        )
+        .unwrap();
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST)
+                .header("x-amz-target", "TrentService.GetPublicKey");
+            then.status(200).json_body(serde_json::json!({
+                "KeyId": "alias/arweave",
+                "PublicKey": base64::encode(public_key.to_public_key_der().unwrap().as_ref()),
+            }));
+        });
+        assert!(matches!(
+            block_on(KmsSigner::new(client(&server), "alias/arweave")),
+            Err(Error::InvalidKey(_))
+        ));
+    }
+}
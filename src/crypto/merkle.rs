@@ -2,8 +2,15 @@
 
 use crate::error::Error;
 use borsh::BorshDeserialize;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::ops::Range;
+use tokio::io::{AsyncRead, AsyncReadExt};
 
-use super::hash::{hash_all_sha256, sha256};
+use super::{
+    hash::{hash_all_sha256, sha256},
+    utils::ct_eq,
+};
 
 /// Single struct used for original data chunks (Leaves) and branch nodes (hashes of pairs of child nodes).
 #[derive(Debug, PartialEq, Clone)]
@@ -53,7 +60,7 @@ impl ProofDeserialize<LeafProof> for LeafProof {
         Ok(proof)
     }
     fn offset(&self) -> usize {
-        usize::from_be_bytes(self.offset)
+        u64::from_be_bytes(self.offset) as usize
     }
 }
 
@@ -63,14 +70,81 @@ impl ProofDeserialize<BranchProof> for BranchProof {
         Ok(proof)
     }
     fn offset(&self) -> usize {
-        usize::from_be_bytes(self.offset)
+        u64::from_be_bytes(self.offset) as usize
     }
 }
 
+/// Largest chunk accepted by nodes.
 pub const MAX_CHUNK_SIZE: usize = 256 * 1024;
+/// Smallest last chunk before the last two chunks are rebalanced.
 pub const MIN_CHUNK_SIZE: usize = 32 * 1024;
 pub const HASH_SIZE: usize = 32;
-const NOTE_SIZE: usize = 32;
+/// Size of the offsets in data paths.
+pub const NOTE_SIZE: usize = 32;
+
+/// How data is split into chunks of [`MAX_CHUNK_SIZE`] bytes. The defaults are those of the
+/// node and arweave-js, other options give a different data root for the same data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkingOptions {
+    max_chunk_size: usize,
+    min_chunk_size: usize,
+    rebalance: bool,
+}
+
+impl Default for ChunkingOptions {
+    fn default() -> Self {
+        Self {
+            max_chunk_size: MAX_CHUNK_SIZE,
+            min_chunk_size: MIN_CHUNK_SIZE,
+            rebalance: true,
+        }
+    }
+}
+
+impl ChunkingOptions {
+    /// Rebalances the last two chunks when the last one would be smaller than
+    /// `min_chunk_size`, at most [`MAX_CHUNK_SIZE`].
+    pub fn new(min_chunk_size: usize) -> Result<Self, Error> {
+        if min_chunk_size > MAX_CHUNK_SIZE {
+            return Err(Error::InvalidChunkingOptions(format!(
+                "minimum chunk size exceeds {} bytes",
+                MAX_CHUNK_SIZE
+            )));
+        }
+        Ok(Self {
+            min_chunk_size,
+            ..Default::default()
+        })
+    }
+
+    /// Smaller chunks, which nodes reject, to test chunking with little data.
+    #[cfg(test)]
+    pub(crate) fn with_max_chunk_size(mut self, max_chunk_size: usize) -> Self {
+        assert!(self.min_chunk_size <= max_chunk_size);
+        self.max_chunk_size = max_chunk_size;
+        self
+    }
+
+    /// Whether the last two chunks are rebalanced, otherwise the last chunk holds the
+    /// remaining bytes whatever their number.
+    pub fn rebalance(mut self, rebalance: bool) -> Self {
+        self.rebalance = rebalance;
+        self
+    }
+
+    pub fn max_chunk_size(&self) -> usize {
+        self.max_chunk_size
+    }
+
+    pub fn min_chunk_size(&self) -> usize {
+        self.min_chunk_size
+    }
+
+    /// Whether a last chunk of `len` bytes following a full chunk is rebalanced with it.
+    fn rebalances(&self, len: usize) -> bool {
+        self.rebalance && len > 0 && len < self.min_chunk_size
+    }
+}
 
 /// Includes a function to convert a number to a Vec of 32 bytes per the Arweave spec.
 pub trait Helpers<T> {
@@ -86,40 +160,225 @@ impl Helpers<usize> for usize {
 }
 /// Generates data chunks from which the calculation of root id starts.
 pub fn generate_leaves(data: Vec<u8>) -> Result<Vec<Node>, Error> {
-    let mut data_chunks: Vec<&[u8]> = data.chunks(MAX_CHUNK_SIZE).collect();
+    generate_leaves_from_slice(&data)
+}
 
-    #[allow(unused_assignments)]
-    let mut last_two = Vec::new();
+/// Same as [`generate_leaves`], borrowing the data, e.g. from a memory mapped file. With the
+/// `parallel` feature, chunks are hashed across cores.
+pub fn generate_leaves_from_slice(data: &[u8]) -> Result<Vec<Node>, Error> {
+    generate_leaves_with_options(data, &ChunkingOptions::default())
+}
 
-    if data_chunks.len() > 1 && data_chunks.last().unwrap().len() < MIN_CHUNK_SIZE {
-        last_two = data_chunks.split_off(data_chunks.len() - 2).concat();
-        let chunk_size = last_two.len() / 2 + (last_two.len() % 2 != 0) as usize;
-        data_chunks.append(&mut last_two.chunks(chunk_size).collect::<Vec<&[u8]>>());
+/// Same as [`generate_leaves_from_slice`], splitting the data as set by `options`.
+pub fn generate_leaves_with_options(
+    data: &[u8],
+    options: &ChunkingOptions,
+) -> Result<Vec<Node>, Error> {
+    let ranges = chunk_ranges(data.len(), options);
+    #[cfg(feature = "parallel")]
+    let leaves = ranges
+        .into_par_iter()
+        .map(|range| hash_leaf(&data[range.clone()], range.start))
+        .collect();
+    #[cfg(not(feature = "parallel"))]
+    let leaves = ranges
+        .into_iter()
+        .map(|range| hash_leaf(&data[range.clone()], range.start))
+        .collect();
+    Ok(leaves)
+}
+
+/// Byte ranges of the chunks of `data_size` bytes: full chunks, the last two rebalanced as
+/// set by `options`, followed by an empty chunk if the data ends with a full chunk.
+fn chunk_ranges(data_size: usize, options: &ChunkingOptions) -> Vec<Range<usize>> {
+    let max_chunk_size = options.max_chunk_size;
+    let mut ranges = Vec::with_capacity(data_size / max_chunk_size + 2);
+    let mut start = 0;
+    while data_size - start > max_chunk_size {
+        if options.rebalances(data_size - start - max_chunk_size) {
+            let len = (data_size - start).div_ceil(2);
+            ranges.push(start..start + len);
+            start += len;
+            break;
+        }
+        ranges.push(start..start + max_chunk_size);
+        start += max_chunk_size;
+    }
+    ranges.push(start..data_size);
+    if data_size - start == max_chunk_size {
+        ranges.push(data_size..data_size);
     }
+    ranges
+}
+
+/// Hashes a single leaf node from a data chunk starting at `min_byte_range`.
+fn hash_leaf(chunk: &[u8], min_byte_range: usize) -> Node {
+    let data_hash = sha256(chunk);
+    let max_byte_range = min_byte_range + chunk.len();
+    let offset = max_byte_range.to_note_vec();
+    let id = hash_all_sha256(vec![&data_hash, &offset]);
 
-    if data_chunks.last().unwrap().len() == MAX_CHUNK_SIZE {
-        data_chunks.push(&[]);
+    Node {
+        id,
+        data_hash: Some(data_hash),
+        min_byte_range,
+        max_byte_range,
+        left_child: None,
+        right_child: None,
     }
+}
+
+/// Generates leaves incrementally from data fed in arbitrarily sized pieces, producing the
+/// same leaves as [`generate_leaves`] while buffering at most two chunks of data.
+#[derive(Debug, Default)]
+pub struct LeavesGenerator {
+    buffer: Vec<u8>,
+    leaves: Vec<Node>,
+    min_byte_range: usize,
+    options: ChunkingOptions,
+}
 
-    let mut leaves = Vec::<Node>::new();
-    let mut min_byte_range = 0;
-    for chunk in data_chunks.into_iter() {
-        let data_hash = sha256(chunk);
-        let max_byte_range = min_byte_range + chunk.len();
-        let offset = max_byte_range.to_note_vec();
-        let id = hash_all_sha256(vec![&data_hash, &offset]);
+impl LeavesGenerator {
+    pub fn new() -> Self {
+        Default::default()
+    }
 
-        leaves.push(Node {
-            id,
-            data_hash: Some(data_hash),
-            min_byte_range,
-            max_byte_range,
-            left_child: None,
-            right_child: None,
-        });
-        min_byte_range += chunk.len()
+    /// Generator splitting the data as set by `options`.
+    pub fn with_options(options: ChunkingOptions) -> Self {
+        Self {
+            options,
+            ..Default::default()
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+        // A full chunk can be emitted as soon as enough data follows it to rule out
+        // rebalancing of the last two chunks.
+        let max_chunk_size = self.options.max_chunk_size;
+        while self.buffer.len() > max_chunk_size
+            && !self.options.rebalances(self.buffer.len() - max_chunk_size)
+        {
+            self.push_chunk(max_chunk_size);
+        }
+    }
+
+    /// Hashes the remaining data and returns all leaves.
+    pub fn finalize(mut self) -> Vec<Node> {
+        let max_chunk_size = self.options.max_chunk_size;
+        while self.buffer.len() >= max_chunk_size {
+            if self.options.rebalances(self.buffer.len() - max_chunk_size) {
+                self.push_chunk(self.buffer.len().div_ceil(2));
+            } else {
+                self.push_chunk(max_chunk_size);
+            }
+        }
+        self.push_chunk(self.buffer.len());
+        self.leaves
+    }
+
+    /// Total number of bytes hashed or buffered so far.
+    pub fn data_size(&self) -> usize {
+        self.min_byte_range + self.buffer.len()
+    }
+
+    fn push_chunk(&mut self, len: usize) {
+        let leaf = hash_leaf(&self.buffer[..len], self.min_byte_range);
+        self.leaves.push(leaf);
+        self.min_byte_range += len;
+        self.buffer.drain(..len);
+    }
+}
+
+/// Builds the merkle tree of data appended as it arrives, e.g. from a network stream or a tar
+/// writer, without knowing its length upfront. Implements [`std::io::Write`].
+#[derive(Debug, Default)]
+pub struct MerkleBuilder {
+    leaves: LeavesGenerator,
+}
+
+impl MerkleBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Builder splitting the data as set by `options`.
+    pub fn with_options(options: ChunkingOptions) -> Self {
+        Self {
+            leaves: LeavesGenerator::with_options(options),
+        }
+    }
+
+    pub fn append(&mut self, data: &[u8]) {
+        self.leaves.update(data);
+    }
+
+    /// Number of bytes appended so far.
+    pub fn data_size(&self) -> usize {
+        self.leaves.data_size()
+    }
+
+    /// Root of the tree, whose id is the data root. Proofs are derived from it with
+    /// [`resolve_proofs`] or [`generate_proof`].
+    pub fn finalize(self) -> Result<Node, Error> {
+        generate_data_root(self.leaves.finalize())
+    }
+}
+
+impl std::io::Write for MerkleBuilder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.append(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
     }
-    Ok(leaves)
+}
+
+/// Generates leaves from an iterator of data pieces without concatenating them.
+pub fn generate_leaves_from_iter<'a, I>(pieces: I) -> Vec<Node>
+where
+    I: IntoIterator<Item = &'a [u8]>,
+{
+    let mut generator = LeavesGenerator::new();
+    for piece in pieces {
+        generator.update(piece);
+    }
+    generator.finalize()
+}
+
+/// Generates leaves reading `reader` to the end in chunk sized pieces. Returns the leaves
+/// and the number of bytes read.
+pub async fn generate_leaves_from_reader<R>(reader: R) -> Result<(Vec<Node>, usize), Error>
+where
+    R: AsyncRead + Unpin,
+{
+    generate_leaves_from_reader_with_progress(reader, |_| {}).await
+}
+
+/// Same as [`generate_leaves_from_reader`], calling `on_progress` with the number of bytes
+/// read so far after every read.
+pub async fn generate_leaves_from_reader_with_progress<R, F>(
+    mut reader: R,
+    mut on_progress: F,
+) -> Result<(Vec<Node>, usize), Error>
+where
+    R: AsyncRead + Unpin,
+    F: FnMut(usize),
+{
+    let mut generator = LeavesGenerator::new();
+    let mut buf = vec![0; MAX_CHUNK_SIZE];
+    loop {
+        let read = reader.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        generator.update(&buf[..read]);
+        on_progress(generator.data_size());
+    }
+    let data_size = generator.data_size();
+    Ok((generator.finalize(), data_size))
 }
 
 /// Hashes together a single branch node from a pair of child nodes.
@@ -138,7 +397,7 @@ pub fn hash_branch(left: Node, right: Node) -> Result<Node, Error> {
 
 /// Builds one layer of branch nodes from a layer of child nodes.
 pub fn build_layer(nodes: Vec<Node>) -> Result<Vec<Node>, Error> {
-    let mut layer = Vec::<Node>::with_capacity(nodes.len() / 2 + (nodes.len() % 2 != 0) as usize);
+    let mut layer = Vec::<Node>::with_capacity(nodes.len().div_ceil(2));
     let mut nodes_iter = nodes.into_iter();
     while let Some(left) = nodes_iter.next() {
         if let Some(right) = nodes_iter.next() {
@@ -204,64 +463,133 @@ pub fn resolve_proofs(node: Node, proof: Option<Proof>) -> Result<Vec<Proof>, Er
     }
 }
 
-/// Validates chunk of data against provided [`Proof`].
-pub fn validate_chunk(
-    mut root_id: [u8; HASH_SIZE],
-    chunk: Node,
-    proof: Proof,
-) -> Result<(), Error> {
-    match chunk {
+/// [`Proof`] of the chunk `chunk_index` of the tree with root `tree`, whose `proof` is the
+/// data path sent with the chunk to `POST /chunk`. Returns `None` if the tree has no such
+/// chunk.
+pub fn generate_proof(tree: &Node, chunk_index: usize) -> Option<Proof> {
+    let mut remaining = chunk_index;
+    find_proof(tree, &mut remaining, &mut Vec::new())
+}
+
+/// Walks the leaves of `node` in order, counting `remaining` down to the leaf to prove and
+/// extending `path` on the way.
+fn find_proof(node: &Node, remaining: &mut usize, path: &mut Vec<u8>) -> Option<Proof> {
+    match node {
         Node {
             data_hash: Some(data_hash),
             max_byte_range,
+            left_child: None,
+            right_child: None,
             ..
         } => {
-            // Split proof into branches and leaf. Leaf is at the end and branches are ordered
-            // from root to leaf.
-            let (branches, leaf) = proof
-                .proof
-                .split_at(proof.proof.len() - HASH_SIZE - NOTE_SIZE);
-
-            // Deserialize proof.
-            let branch_proofs: Vec<BranchProof> = branches
-                .chunks(HASH_SIZE * 2 + NOTE_SIZE)
-                .map(|b| BranchProof::try_from_proof_slice(b).unwrap())
-                .collect();
-            let leaf_proof = LeafProof::try_from_proof_slice(leaf).unwrap();
-
-            // Validate branches.
-            for branch_proof in branch_proofs.iter() {
-                // Calculate the id from the proof.
-                let id = hash_all_sha256(vec![
-                    &branch_proof.left_id,
-                    &branch_proof.right_id,
-                    &branch_proof.offset().to_note_vec(),
-                ]);
-
-                // Ensure calculated id correct.
-                if id != root_id {
-                    return Err(Error::InvalidProof);
-                }
-
-                // If the offset from the proof is greater than the offset in the data chunk,
-                // then the next id to validate against is from the left.
-                root_id = match max_byte_range > branch_proof.offset() {
-                    true => branch_proof.right_id,
-                    false => branch_proof.left_id,
-                }
+            if *remaining > 0 {
+                *remaining -= 1;
+                return None;
             }
+            let mut proof = path.clone();
+            proof.extend(data_hash);
+            proof.extend(max_byte_range.to_note_vec());
+            Some(Proof {
+                offset: max_byte_range - 1,
+                proof,
+            })
+        }
+        Node {
+            data_hash: None,
+            min_byte_range,
+            left_child: Some(left_child),
+            right_child: Some(right_child),
+            ..
+        } => {
+            let len = path.len();
+            path.extend(left_child.id);
+            path.extend(right_child.id);
+            path.extend(min_byte_range.to_note_vec());
+            let proof = find_proof(left_child, remaining, path)
+                .or_else(|| find_proof(right_child, remaining, path));
+            path.truncate(len);
+            proof
+        }
+        _ => None,
+    }
+}
 
-            // Validate leaf: both id and data_hash are correct.
-            let id = hash_all_sha256(vec![&data_hash, &max_byte_range.to_note_vec()]);
-            if id != root_id && data_hash != leaf_proof.data_hash {
-                return Err(Error::InvalidProof);
-            }
+/// Offset stored in a note, which must fit in the last 8 bytes.
+fn note_offset(note: &[u8]) -> Result<usize, Error> {
+    let (high, low) = note.split_at(NOTE_SIZE - 8);
+    if high.iter().any(|&byte| byte != 0) {
+        return Err(Error::InvalidProof);
+    }
+    Ok(u64::from_be_bytes(low.try_into().unwrap()) as usize) //Checked unwrap
+}
+
+/// Validates `data_path` as nodes do, walking from `data_root` to the leaf of the chunk
+/// containing the byte at `offset` in the data, whose SHA-256 hash is `chunk_hash`. Returns
+/// the byte range of the chunk in the data.
+pub fn validate_path(
+    data_root: [u8; HASH_SIZE],
+    offset: usize,
+    data_path: &[u8],
+    chunk_hash: &[u8; HASH_SIZE],
+) -> Result<Range<usize>, Error> {
+    const BRANCH_SIZE: usize = HASH_SIZE * 2 + NOTE_SIZE;
+    const LEAF_SIZE: usize = HASH_SIZE + NOTE_SIZE;
+    if data_path.len() < LEAF_SIZE || !(data_path.len() - LEAF_SIZE).is_multiple_of(BRANCH_SIZE) {
+        return Err(Error::InvalidProof);
+    }
+    // Branches are ordered from the root to the leaf, which is at the end.
+    let (branches, leaf) = data_path.split_at(data_path.len() - LEAF_SIZE);
+
+    let mut id = data_root;
+    let (mut left_bound, mut right_bound) = (0, usize::MAX);
+    for branch in branches.chunks(BRANCH_SIZE) {
+        let (left_id, rest) = branch.split_at(HASH_SIZE);
+        let (right_id, note) = rest.split_at(HASH_SIZE);
+        if !ct_eq(&hash_all_sha256(vec![left_id, right_id, note]), &id) {
+            return Err(Error::InvalidProof);
+        }
+        // Bytes before the offset of the branch are in its left subtree.
+        let split = note_offset(note)?;
+        if offset < split {
+            id = left_id.try_into().unwrap(); //Checked unwrap
+            right_bound = right_bound.min(split);
+        } else {
+            id = right_id.try_into().unwrap(); //Checked unwrap
+            left_bound = left_bound.max(split);
         }
+    }
+
+    let (data_hash, note) = leaf.split_at(HASH_SIZE);
+    let end = note_offset(note)?;
+    if !ct_eq(&hash_all_sha256(vec![data_hash, note]), &id)
+        || !ct_eq(data_hash, chunk_hash)
+        || end <= left_bound
+        || end > right_bound
+        || offset >= end
+    {
+        return Err(Error::InvalidProof);
+    }
+    Ok(left_bound..end)
+}
+
+/// Validates chunk of data against provided [`Proof`].
+pub fn validate_chunk(root_id: [u8; HASH_SIZE], chunk: Node, proof: Proof) -> Result<(), Error> {
+    match chunk {
+        Node {
+            data_hash: Some(data_hash),
+            max_byte_range,
+            ..
+        } => validate_path(
+            root_id,
+            max_byte_range.saturating_sub(1),
+            &proof.proof,
+            &data_hash,
+        )
+        .map(|_| ()),
         _ => {
             unreachable!()
         }
     }
-    Ok(())
 }
 
 #[cfg(test)]
@@ -374,6 +702,35 @@ mod tests {
         );
         Ok(())
     }
+    #[tokio::test]
+    async fn test_validate_path() -> Result<(), Error> {
+        let data = fs::read(REBAR3).await.unwrap();
+        let leaves = generate_leaves(data).unwrap();
+        let root = generate_data_root(leaves.clone()).unwrap();
+        let proofs = resolve_proofs(root.clone(), None).unwrap();
+
+        for (leaf, proof) in leaves.iter().zip(&proofs) {
+            let chunk_hash = leaf.data_hash.unwrap();
+            let range = leaf.min_byte_range..leaf.max_byte_range;
+            for offset in [range.start, range.end - 1] {
+                assert_eq!(
+                    validate_path(root.id, offset, &proof.proof, &chunk_hash)?,
+                    range
+                );
+            }
+            assert!(validate_path(root.id, range.end, &proof.proof, &chunk_hash).is_err());
+        }
+
+        let chunk_hash = leaves[0].data_hash.unwrap();
+        let mut tampered = proofs[0].proof.clone();
+        tampered[0] ^= 1;
+        assert!(validate_path(root.id, 0, &tampered, &chunk_hash).is_err());
+        assert!(validate_path(root.id, 0, &proofs[0].proof[1..], &chunk_hash).is_err());
+        // The leaf must commit to the chunk.
+        assert!(validate_path(root.id, 0, &proofs[0].proof, &[0; HASH_SIZE]).is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_validate_chunks() -> Result<(), Error> {
         let data = fs::read(ONE_MB_BIN).await.unwrap();
@@ -445,4 +802,114 @@ mod tests {
         assert_eq!(131072, leaves[1].max_byte_range - leaves[1].min_byte_range);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_leaves_from_reader() -> Result<(), Error> {
+        for path in [ONE_MB_BIN, REBAR3] {
+            let data = fs::read(path).await.unwrap();
+            let (leaves, data_size) =
+                generate_leaves_from_reader(fs::File::open(path).await?).await?;
+            assert_eq!(data_size, data.len());
+            assert_eq!(leaves, generate_leaves(data).unwrap());
+        }
+
+        let mut progress = vec![];
+        generate_leaves_from_reader_with_progress(&[0u8; MAX_CHUNK_SIZE + 1][..], |read| {
+            progress.push(read)
+        })
+        .await?;
+        assert_eq!(progress.last(), Some(&(MAX_CHUNK_SIZE + 1)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_generate_proof_for_chunk() -> Result<(), Error> {
+        for path in [ONE_MB_BIN, REBAR3] {
+            let root = generate_data_root(generate_leaves(fs::read(path).await.unwrap())?)?;
+            let proofs = resolve_proofs(root.clone(), None)?;
+            for (index, proof) in proofs.iter().enumerate() {
+                assert_eq!(generate_proof(&root, index).as_ref(), Some(proof));
+            }
+            assert_eq!(generate_proof(&root, proofs.len()), None);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_merkle_builder() -> Result<(), Error> {
+        let data = fs::read(REBAR3).await.unwrap();
+        let mut builder = MerkleBuilder::new();
+        for piece in data.chunks(100_000) {
+            builder.append(piece);
+        }
+        assert_eq!(builder.data_size(), data.len());
+        let root = builder.finalize()?;
+        assert_eq!(root, generate_data_root(generate_leaves(data.clone())?)?);
+
+        let mut writer = MerkleBuilder::new();
+        std::io::copy(&mut &data[..], &mut writer)?;
+        assert_eq!(writer.finalize()?.id, root.id);
+        Ok(())
+    }
+
+    #[test]
+    fn test_chunking_options() -> Result<(), Error> {
+        assert!(ChunkingOptions::new(MAX_CHUNK_SIZE + 1).is_err());
+        assert_eq!(
+            ChunkingOptions::new(MIN_CHUNK_SIZE)?,
+            ChunkingOptions::default()
+        );
+        assert_eq!(ChunkingOptions::new(0)?.max_chunk_size(), MAX_CHUNK_SIZE);
+
+        let options = ChunkingOptions::new(256)?.with_max_chunk_size(1024);
+        let data: Vec<u8> = (0..2 * 1024 + 100).map(|i| i as u8).collect();
+        let ranges = |leaves: &[Node]| {
+            leaves
+                .iter()
+                .map(|leaf| leaf.max_byte_range - leaf.min_byte_range)
+                .collect::<Vec<_>>()
+        };
+        let leaves = generate_leaves_with_options(&data, &options)?;
+        assert_eq!(ranges(&leaves), vec![1024, 562, 562]);
+        let unbalanced = options.rebalance(false);
+        let leaves = generate_leaves_with_options(&data, &unbalanced)?;
+        assert_eq!(ranges(&leaves), vec![1024, 1024, 100]);
+
+        // Leaves generated incrementally match for any split of the data.
+        for options in [options, unbalanced] {
+            for len in [100, 1024, 1124, 2048, data.len()] {
+                let mut generator = LeavesGenerator::with_options(options);
+                for piece in data[..len].chunks(300) {
+                    generator.update(piece);
+                }
+                assert_eq!(
+                    generator.finalize(),
+                    generate_leaves_with_options(&data[..len], &options)?,
+                    "len {}",
+                    len
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_leaves_from_iter() {
+        for len in [
+            1,
+            MAX_CHUNK_SIZE,
+            MAX_CHUNK_SIZE + 1,
+            MAX_CHUNK_SIZE + MIN_CHUNK_SIZE,
+            3 * MAX_CHUNK_SIZE + MIN_CHUNK_SIZE - 1,
+        ] {
+            let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let leaves = generate_leaves_from_iter(data.chunks(10_000));
+            assert_eq!(leaves, generate_leaves(data).unwrap(), "len {}", len);
+        }
+        assert_eq!(generate_leaves_from_iter(vec![]).len(), 1);
+        assert_eq!(
+            generate_leaves(vec![]).unwrap(),
+            generate_leaves_from_iter(vec![])
+        );
+    }
 }
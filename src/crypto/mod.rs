@@ -1,36 +1,74 @@
 use jsonwebkey as jwk;
 use std::path::PathBuf;
 
-use crate::error::Error;
+use crate::{address::Address, consts::WALLET_JWK_ENV, error::Error};
 
 use self::{
     base64::Base64,
     hash::{deep_hash, sha256, DeepHashItem},
-    sign::Signer,
+    sign::{RsaSigner, Signer},
+    verify::Verifier,
 };
 
 pub mod base64;
+#[cfg(feature = "ed25519")]
+pub mod ed25519;
 pub mod hash;
+#[cfg(feature = "keystore")]
+pub mod keystore;
+#[cfg(feature = "kms")]
+pub mod kms;
 pub mod merkle;
+#[cfg(feature = "pkcs11")]
+pub mod pkcs11;
+#[cfg(feature = "secp256k1")]
+pub mod secp256k1;
 pub mod sign;
 pub mod utils;
 pub mod verify;
 
 pub struct Provider {
-    pub signer: Box<Signer>,
+    pub signer: Box<dyn Signer>,
 }
 
 impl Provider {
     pub fn from_keypair_path(keypair_path: PathBuf) -> Result<Self, Error> {
-        let signer = Signer::from_keypair_path(keypair_path)?;
+        let signer = RsaSigner::from_keypair_path(keypair_path)?;
         Ok(Provider::new(Box::new(signer)))
     }
 
-    pub fn from_jwk(jwk: jwk::JsonWebKey) -> Self {
-        Self::new(Box::new(Signer::from_jwk(jwk)))
+    pub fn from_jwk(jwk: jwk::JsonWebKey) -> Result<Self, Error> {
+        Ok(Self::new(Box::new(RsaSigner::from_jwk(jwk)?)))
     }
 
-    pub fn new(signer: Box<Signer>) -> Self {
+    pub fn from_jwk_str(jwk: &str) -> Result<Self, Error> {
+        Ok(Self::new(Box::new(RsaSigner::from_jwk_str(jwk)?)))
+    }
+
+    pub fn from_jwk_value(jwk: serde_json::Value) -> Result<Self, Error> {
+        Ok(Self::new(Box::new(RsaSigner::from_jwk_value(jwk)?)))
+    }
+
+    /// Reads the JWK wallet JSON held by [`WALLET_JWK_ENV`].
+    pub fn from_env() -> Result<Self, Error> {
+        Ok(Self::new(Box::new(RsaSigner::from_env(WALLET_JWK_ENV)?)))
+    }
+
+    #[cfg(feature = "keystore")]
+    pub fn from_encrypted_file(path: &std::path::Path, passphrase: &str) -> Result<Self, Error> {
+        let signer = RsaSigner::from_encrypted_file(path, passphrase)?;
+        Ok(Provider::new(Box::new(signer)))
+    }
+
+    /// Creates a provider for a new random RSA-4096 keypair, returned as a JWK to be saved
+    /// as a wallet file.
+    pub fn generate() -> Result<(Self, jwk::JsonWebKey), Error> {
+        let signer = RsaSigner::generate()?;
+        let jwk = signer.to_jwk();
+        Ok((Provider::new(Box::new(signer)), jwk))
+    }
+
+    pub fn new(signer: Box<dyn Signer>) -> Self {
         Provider { signer }
     }
 }
@@ -49,28 +87,54 @@ impl Provider {
     }
 
     pub fn keypair_modulus(&self) -> Base64 {
-        self.signer.keypair_modulus()
+        self.signer.public_key()
     }
 
-    pub fn wallet_address(&self) -> Base64 {
+    pub fn wallet_address(&self) -> Address {
         self.signer.wallet_address()
     }
 
     pub fn public_key(&self) -> Base64 {
         self.signer.public_key()
     }
+
+    /// Verification-only counterpart of the provider.
+    pub fn verifier(&self) -> Verifier {
+        Verifier::with_signature_type(self.signer.signature_type(), self.signer.public_key())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{error::Error, verify::verify};
 
-    use super::{base64::Base64, Provider};
+    use super::{
+        base64::Base64,
+        sign::{RsaSigner, SignatureType, Signer},
+        Provider,
+    };
+
+    /// Stands in for a signer that lives outside the crate, e.g. a remote signing service.
+    struct DelegatingSigner(RsaSigner);
+
+    impl Signer for DelegatingSigner {
+        fn sign(&self, message: &[u8]) -> Result<Base64, Error> {
+            self.0.sign(message)
+        }
+
+        fn public_key(&self) -> Base64 {
+            self.0.public_key()
+        }
+
+        fn signature_type(&self) -> SignatureType {
+            SignatureType::Arweave
+        }
+    }
 
     impl Default for Provider {
         fn default() -> Self {
             Self {
-                signer: Default::default(),
+                signer: Box::<RsaSigner>::default(),
             }
         }
     }
@@ -91,4 +155,19 @@ mod tests {
         assert!(verify(&pubk.0, &message.0, &signature.0).is_ok());
         Ok(())
     }
+
+    #[test]
+    fn test_custom_signer() -> Result<(), Error> {
+        let message = b"custom signer";
+        let rsa_signer = RsaSigner::default();
+        let wallet_address = rsa_signer.wallet_address();
+        let provider = Provider::new(Box::new(DelegatingSigner(rsa_signer)));
+
+        let signature = provider.sign(message)?;
+        assert!(verify(&provider.public_key().0, message, &signature.0).is_ok());
+        assert_eq!(provider.wallet_address(), wallet_address);
+        assert_eq!(provider.keypair_modulus(), provider.public_key());
+        provider.verifier().verify(message, &signature.0)?;
+        Ok(())
+    }
 }
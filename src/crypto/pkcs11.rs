@@ -0,0 +1,169 @@
+//! [`Signer`] delegating RSA-PSS signing to a PKCS#11 token, such as SoftHSM, YubiHSM or
+//! Luna HSMs, so keys never leave the hardware.
+
+use std::{path::PathBuf, sync::Mutex};
+
+use cryptoki::{
+    context::{CInitializeArgs, Pkcs11},
+    mechanism::{
+        rsa::{PkcsMgfType, PkcsPssParams},
+        Mechanism, MechanismType,
+    },
+    object::{Attribute, AttributeType, ObjectClass, ObjectHandle},
+    session::{Session, UserType},
+    slot::Slot,
+    types::AuthPin,
+};
+
+use crate::error::Error;
+
+use super::{
+    base64::Base64,
+    sign::{SignatureType, Signer},
+};
+
+/// Where to find the key. The slot is `slot_id` if set, otherwise the one holding the token
+/// labelled `token_label`, otherwise the first slot with a token.
+#[derive(Debug, Clone)]
+pub struct Pkcs11Config {
+    /// Path of the PKCS#11 module, e.g. `/usr/lib/softhsm/libsofthsm2.so`.
+    pub module: PathBuf,
+    pub slot_id: Option<u64>,
+    pub token_label: Option<String>,
+    /// `CKA_LABEL` of the RSA-4096 private key.
+    pub key_label: String,
+    pub pin: String,
+}
+
+pub struct Pkcs11Signer {
+    session: Mutex<Session>,
+    key: ObjectHandle,
+    public_key: Base64,
+}
+
+fn pkcs11_error(err: cryptoki::error::Error) -> Error {
+    Error::CryptoError(err.to_string())
+}
+
+impl Pkcs11Signer {
+    /// Loads the module, logs in to the token with the user PIN and looks up the key.
+    pub fn new(config: Pkcs11Config) -> Result<Self, Error> {
+        let pkcs11 = Pkcs11::new(&config.module).map_err(pkcs11_error)?;
+        pkcs11
+            .initialize(CInitializeArgs::OsThreads)
+            .map_err(pkcs11_error)?;
+        let slot = Self::find_slot(&pkcs11, &config)?;
+        let session = pkcs11.open_ro_session(slot).map_err(pkcs11_error)?;
+        session
+            .login(UserType::User, Some(&AuthPin::new(config.pin.clone())))
+            .map_err(pkcs11_error)?;
+
+        let key = session
+            .find_objects(&[
+                Attribute::Class(ObjectClass::PRIVATE_KEY),
+                Attribute::Label(config.key_label.as_bytes().to_vec()),
+            ])
+            .map_err(pkcs11_error)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::NoneError(format!("key {}", config.key_label)))?;
+        let modulus = match session
+            .get_attributes(key, &[AttributeType::Modulus])
+            .map_err(pkcs11_error)?
+            .pop()
+        {
+            Some(Attribute::Modulus(modulus)) => modulus,
+            _ => return Err(Error::NoneError("modulus".to_string())),
+        };
+        if modulus.len() != SignatureType::Arweave.pub_length() {
+            return Err(Error::InvalidKey(format!(
+                "expected an RSA-4096 key, got {} bits",
+                modulus.len() * 8
+            )));
+        }
+        Ok(Self {
+            session: Mutex::new(session),
+            key,
+            public_key: Base64(modulus),
+        })
+    }
+
+    fn find_slot(pkcs11: &Pkcs11, config: &Pkcs11Config) -> Result<Slot, Error> {
+        if let Some(slot_id) = config.slot_id {
+            return Slot::try_from(slot_id).map_err(pkcs11_error);
+        }
+        for slot in pkcs11.get_slots_with_token().map_err(pkcs11_error)? {
+            match &config.token_label {
+                Some(label) => {
+                    let info = pkcs11.get_token_info(slot).map_err(pkcs11_error)?;
+                    if info.label().trim_end() == label {
+                        return Ok(slot);
+                    }
+                }
+                None => return Ok(slot),
+            }
+        }
+        Err(Error::NoneError("PKCS#11 token".to_string()))
+    }
+}
+
+impl Signer for Pkcs11Signer {
+    fn sign(&self, message: &[u8]) -> Result<Base64, Error> {
+        let mechanism = Mechanism::Sha256RsaPkcsPss(PkcsPssParams {
+            hash_alg: MechanismType::SHA256,
+            mgf: PkcsMgfType::MGF1_SHA256,
+            s_len: 32.into(),
+        });
+        let session = self.session.lock().unwrap(); //Checked unwrap
+        let signature = session
+            .sign(&mechanism, self.key, message)
+            .map_err(|e| Error::SigningError(e.to_string()))?;
+        Ok(Base64(signature))
+    }
+
+    fn public_key(&self) -> Base64 {
+        self.public_key.clone()
+    }
+
+    fn signature_type(&self) -> SignatureType {
+        SignatureType::Arweave
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, path::PathBuf};
+
+    use crate::{
+        crypto::{sign::Signer, verify::verify},
+        error::Error,
+    };
+
+    use super::{Pkcs11Config, Pkcs11Signer};
+
+    /// Needs a token with an RSA-4096 key pair, e.g. with SoftHSM:
+    ///
+    /// ```sh
+    /// softhsm2-util --init-token --free --label arweave --pin 1234 --so-pin 1234
+    /// pkcs11-tool --module /usr/lib/softhsm/libsofthsm2.so --token-label arweave \
+    ///     --login --pin 1234 --keypairgen --key-type rsa:4096 --label wallet
+    /// PKCS11_MODULE=/usr/lib/softhsm/libsofthsm2.so PKCS11_TOKEN_LABEL=arweave \
+    ///     PKCS11_KEY_LABEL=wallet PKCS11_PIN=1234 \
+    ///     cargo test --features pkcs11 test_pkcs11_sign -- --ignored
+    /// ```
+    #[test]
+    #[ignore = "needs a PKCS#11 token, see the doc comment"]
+    fn test_pkcs11_sign() -> Result<(), Error> {
+        let var = |name| env::var(name).unwrap_or_else(|_| panic!("{} is not set", name));
+        let signer = Pkcs11Signer::new(Pkcs11Config {
+            module: PathBuf::from(var("PKCS11_MODULE")),
+            slot_id: None,
+            token_label: env::var("PKCS11_TOKEN_LABEL").ok(),
+            key_label: var("PKCS11_KEY_LABEL"),
+            pin: var("PKCS11_PIN"),
+        })?;
+        let signature = signer.sign(b"message")?;
+        verify(&signer.public_key().0, b"message", &signature.0)?;
+        Ok(())
+    }
+}
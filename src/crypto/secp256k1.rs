@@ -0,0 +1,147 @@
+//! [`Signer`] for secp256k1 keys, used by data items of signature type 3.
+//!
+//! As in ANS-104, messages are signed like Ethereum `personal_sign` messages: the keccak256
+//! hash of the message prefixed with `"\x19Ethereum Signed Message:\n" + len`, giving a
+//! 65 byte `r || s || v` signature. Owners are uncompressed 65 byte public keys.
+
+use k256::ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey};
+use sha3::{Digest, Keccak256};
+
+use crate::error::Error;
+
+use super::{
+    base64::Base64,
+    sign::{SignatureType, Signer},
+};
+
+pub struct Secp256k1Signer {
+    signing_key: SigningKey,
+}
+
+/// Hash signed for `message` by Ethereum wallets.
+pub fn hash_message(message: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(format!("\x19Ethereum Signed Message:\n{}", message.len()));
+    hasher.update(message);
+    hasher.finalize().into()
+}
+
+impl Secp256k1Signer {
+    pub fn from_secret_key(secret_key: &[u8]) -> Result<Self, Error> {
+        let signing_key =
+            SigningKey::from_slice(secret_key).map_err(|e| Error::InvalidKey(e.to_string()))?;
+        Ok(Self { signing_key })
+    }
+
+    /// Parses a hex encoded secret key, with or without `0x` prefix.
+    pub fn from_hex(secret_key: &str) -> Result<Self, Error> {
+        let hex = secret_key.trim().trim_start_matches("0x");
+        let bytes = data_encoding::HEXLOWER_PERMISSIVE
+            .decode(hex.as_bytes())
+            .map_err(|e| Error::InvalidKey(e.to_string()))?;
+        Self::from_secret_key(&bytes)
+    }
+
+    pub fn generate() -> Self {
+        Self {
+            signing_key: SigningKey::random(&mut rand::thread_rng()),
+        }
+    }
+
+    /// `0x` prefixed, lowercase Ethereum address of the key.
+    pub fn ethereum_address(&self) -> String {
+        let public_key = self.public_key();
+        let hash = Keccak256::digest(&public_key.0[1..]);
+        format!("0x{}", data_encoding::HEXLOWER.encode(&hash[12..]))
+    }
+}
+
+impl Signer for Secp256k1Signer {
+    fn sign(&self, message: &[u8]) -> Result<Base64, Error> {
+        let (signature, recovery_id) = self
+            .signing_key
+            .sign_prehash_recoverable(&hash_message(message))
+            .map_err(|e| Error::SigningError(e.to_string()))?;
+        let mut bytes = signature.to_bytes().to_vec();
+        bytes.push(recovery_id.to_byte() + 27);
+        Ok(Base64(bytes))
+    }
+
+    fn public_key(&self) -> Base64 {
+        let point = self.signing_key.verifying_key().to_encoded_point(false);
+        Base64(point.as_bytes().to_vec())
+    }
+
+    fn signature_type(&self) -> SignatureType {
+        SignatureType::Ethereum
+    }
+}
+
+/// Verifies a 65 byte signature of `message` by the uncompressed public key `pub_key`.
+///
+/// The recovery byte `v` must be 27 or 28, as written by [`Secp256k1Signer`], and `s` must
+/// be low (checked by k256), so that a signature, and the id derived from it, has a single
+/// valid encoding.
+pub fn verify(pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), Error> {
+    if signature.len() != 65 {
+        return Err(Error::InvalidSignature);
+    }
+    let recovery_id = match signature[64] {
+        v @ (27 | 28) => RecoveryId::from_byte(v - 27).ok_or(Error::InvalidSignature)?,
+        _ => return Err(Error::InvalidSignature),
+    };
+    let signature = Signature::from_slice(&signature[..64]).map_err(|_| Error::InvalidSignature)?;
+    let pub_key = VerifyingKey::from_sec1_bytes(pub_key).map_err(|_| Error::InvalidSignature)?;
+    match VerifyingKey::recover_from_prehash(&hash_message(message), &signature, recovery_id) {
+        Ok(key) if key == pub_key => Ok(()),
+        _ => Err(Error::InvalidSignature),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use k256::ecdsa::Signature;
+
+    use crate::{crypto::sign::Signer, error::Error};
+
+    use super::{verify, Secp256k1Signer};
+
+    #[test]
+    fn test_sign_verify() -> Result<(), Error> {
+        let signer = Secp256k1Signer::generate();
+        let signature = signer.sign(b"message")?;
+        assert_eq!(signature.0.len(), 65);
+        assert!(signature.0[64] == 27 || signature.0[64] == 28);
+        assert_eq!(signer.public_key().0.len(), 65);
+        verify(&signer.public_key().0, b"message", &signature.0)?;
+        assert!(verify(&signer.public_key().0, b"other", &signature.0).is_err());
+
+        // Other encodings of the recovery byte are rejected.
+        let mut malleated = signature.0.clone();
+        for v in [signature.0[64] - 27, 55 - signature.0[64], 29, 0, 1] {
+            malleated[64] = v;
+            assert!(verify(&signer.public_key().0, b"message", &malleated).is_err());
+        }
+        // As is the high `s` form, with its flipped recovery byte.
+        let low = Signature::from_slice(&signature.0[..64]).unwrap();
+        let (r, s) = low.split_scalars();
+        let high = Signature::from_scalars(r.to_bytes(), (-*s).to_bytes()).unwrap();
+        let mut malleated = high.to_bytes().to_vec();
+        malleated.push(55 - signature.0[64]);
+        assert!(verify(&signer.public_key().0, b"message", &malleated).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ethereum_address() -> Result<(), Error> {
+        // Well known key from the Hardhat and Anvil default accounts.
+        let signer = Secp256k1Signer::from_hex(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        )?;
+        assert_eq!(
+            signer.ethereum_address(),
+            "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+        );
+        Ok(())
+    }
+}
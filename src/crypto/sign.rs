@@ -1,54 +1,206 @@
 //! Functionality for creating and verifying signatures and hashing.
 
-use crate::error::Error;
+use crate::{address::Address, error::Error};
 use jsonwebkey as jwk;
 use rand::thread_rng;
-use rsa::{pkcs8::DecodePrivateKey, PaddingScheme, PublicKeyParts, RsaPrivateKey};
+use rsa::{pkcs8::DecodePrivateKey, BigUint, PaddingScheme, PublicKeyParts, RsaPrivateKey};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use sha2::Digest;
 use std::{fs, path::PathBuf};
 
 use super::base64::Base64;
 
-/// Struct for for crypto methods.
-pub struct Signer {
+/// Signature schemes a [`Signer`] can produce, numbered as in ANS-104.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureType {
+    #[default]
+    Arweave = 1,
+    Ed25519 = 2,
+    Ethereum = 3,
+    Solana = 4,
+}
+
+impl SignatureType {
+    /// Length in bytes of a signature.
+    pub fn sig_length(&self) -> usize {
+        match self {
+            SignatureType::Arweave => 512,
+            SignatureType::Ed25519 | SignatureType::Solana => 64,
+            SignatureType::Ethereum => 65,
+        }
+    }
+
+    /// Length in bytes of a public key (owner).
+    pub fn pub_length(&self) -> usize {
+        match self {
+            SignatureType::Arweave => 512,
+            SignatureType::Ed25519 | SignatureType::Solana => 32,
+            SignatureType::Ethereum => 65,
+        }
+    }
+}
+
+/// Serializes to the ANS-104 number of the signature type.
+impl Serialize for SignatureType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(*self as u16)
+    }
+}
+
+impl<'de> Deserialize<'de> for SignatureType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        SignatureType::try_from(u16::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+impl TryFrom<u16> for SignatureType {
+    type Error = Error;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(SignatureType::Arweave),
+            2 => Ok(SignatureType::Ed25519),
+            3 => Ok(SignatureType::Ethereum),
+            4 => Ok(SignatureType::Solana),
+            _ => Err(Error::InvalidSignatureType(value)),
+        }
+    }
+}
+
+/// Implemented by anything able to sign on behalf of an Arweave wallet, so that keys can live
+/// in remote signing services or custom key stores as well as in local keyfiles.
+pub trait Signer: Send + Sync {
+    fn sign(&self, message: &[u8]) -> Result<Base64, Error>;
+
+    /// Public key as it appears in the `owner` field of a transaction.
+    fn public_key(&self) -> Base64;
+
+    fn signature_type(&self) -> SignatureType;
+
+    /// Private key as a JWK, `None` for signers whose keys can't leave them, such as remote
+    /// signing services.
+    fn export_jwk(&self) -> Option<jwk::JsonWebKey> {
+        None
+    }
+
+    /// Wallet address, i.e. the SHA256 hash of the public key.
+    fn wallet_address(&self) -> Address {
+        Address::from_owner(&self.public_key().0)
+    }
+}
+
+/// Size in bits of the RSA keys of Arweave wallets.
+pub const RSA_KEY_SIZE: usize = 4096;
+
+/// [`Signer`] backed by an RSA private key, as found in Arweave JWK keyfiles.
+pub struct RsaSigner {
     priv_key: RsaPrivateKey,
 }
 
-impl Signer {
-    fn new(priv_key: RsaPrivateKey) -> Self {
+impl RsaSigner {
+    pub(crate) fn new(priv_key: RsaPrivateKey) -> Self {
         Self { priv_key }
     }
 
-    pub fn from_jwk(jwk: jwk::JsonWebKey) -> Self {
-        let pem = jwk.key.to_pem();
-        let priv_key = RsaPrivateKey::from_pkcs8_pem(&pem).unwrap();
-
-        Self::new(priv_key)
+    /// Signer of the RSA private key `jwk`, failing with [`Error::InvalidKey`] for public
+    /// or other keys.
+    pub fn from_jwk(jwk: jwk::JsonWebKey) -> Result<Self, Error> {
+        match *jwk.key {
+            jwk::Key::RSA {
+                private: Some(_), ..
+            } => {
+                let priv_key = RsaPrivateKey::from_pkcs8_pem(&jwk.key.to_pem())
+                    .map_err(|e| Error::InvalidKey(e.to_string()))?;
+                Ok(Self::new(priv_key))
+            }
+            _ => Err(Error::InvalidKey("not an RSA private key".to_string())),
+        }
     }
 
     pub fn from_keypair_path(keypair_path: PathBuf) -> Result<Self, Error> {
         let data = fs::read_to_string(keypair_path)?;
-        let jwk_parsed: jwk::JsonWebKey = data.parse().map_err(Error::JsonWebKeyError)?;
+        Self::from_jwk_str(&data)
+    }
+
+    /// Parses the contents of a JWK wallet file.
+    pub fn from_jwk_str(jwk: &str) -> Result<Self, Error> {
+        Self::from_jwk(jwk.parse().map_err(Error::JsonWebKeyError)?)
+    }
+
+    pub fn from_jwk_value(jwk: serde_json::Value) -> Result<Self, Error> {
+        Self::from_jwk(serde_json::from_value(jwk).map_err(Error::SerdeJsonError)?)
+    }
+
+    /// Reads the JWK wallet JSON held by the environment variable `var`, so that keys can
+    /// be injected into containers without being written to disk.
+    pub fn from_env(var: &str) -> Result<Self, Error> {
+        let jwk = std::env::var(var).map_err(|_| Error::MissingEnvVar(var.to_string()))?;
+        Self::from_jwk_str(&jwk)
+    }
+
+    /// Creates a new random RSA-4096 keypair.
+    pub fn generate() -> Result<Self, Error> {
+        let priv_key = RsaPrivateKey::new(&mut thread_rng(), RSA_KEY_SIZE)
+            .map_err(|e| Error::KeyGenerationError(e.to_string()))?;
+        Ok(Self::new(priv_key))
+    }
+
+    /// Private key as the JWK found in Arweave wallet files.
+    pub fn to_jwk(&self) -> jwk::JsonWebKey {
+        let primes = self.priv_key.primes();
+        let (p, q) = (&primes[0], &primes[1]);
+        let d = self.priv_key.d();
+        let one = BigUint::from(1u8);
+        let qi = self
+            .priv_key
+            .crt_coefficient()
+            .unwrap_or_default()
+            .to_bytes_be();
+        jwk::JsonWebKey::new(jwk::Key::RSA {
+            public: jwk::RsaPublic {
+                e: jwk::PublicExponent,
+                n: self.priv_key.n().to_bytes_be().into(),
+            },
+            private: Some(jwk::RsaPrivate {
+                d: d.to_bytes_be().into(),
+                p: Some(p.to_bytes_be().into()),
+                q: Some(q.to_bytes_be().into()),
+                dp: Some((d % (p - &one)).to_bytes_be().into()),
+                dq: Some((d % (q - &one)).to_bytes_be().into()),
+                qi: Some(qi.into()),
+            }),
+        })
+    }
+
+    /// Saves the private key to `path` encrypted with `passphrase`, see [`super::keystore`].
+    #[cfg(feature = "keystore")]
+    pub fn to_encrypted_file(&self, path: &std::path::Path, passphrase: &str) -> Result<(), Error> {
+        super::keystore::Keystore::encrypt(&self.to_jwk(), passphrase)?.write(path)
+    }
 
-        Ok(Self::from_jwk(jwk_parsed))
+    #[cfg(feature = "keystore")]
+    pub fn from_encrypted_file(path: &std::path::Path, passphrase: &str) -> Result<Self, Error> {
+        let jwk = super::keystore::Keystore::read(path)?.decrypt(passphrase)?;
+        Self::from_jwk(jwk)
     }
 
-    pub fn public_key(&self) -> Base64 {
-        Base64(self.priv_key.to_public_key().n().to_bytes_be())
+    /// Decrypts `ciphertext` encrypted for the wallet with RSA-OAEP and SHA-256, see
+    /// [`crate::encrypt`].
+    #[cfg(feature = "encrypt")]
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        self.priv_key
+            .decrypt(PaddingScheme::new_oaep::<sha2::Sha256>(), ciphertext)
+            .map_err(|e| Error::CryptoError(e.to_string()))
     }
 
     pub fn keypair_modulus(&self) -> Base64 {
         let modulus = self.priv_key.to_public_key().n().to_bytes_be();
         Base64(modulus.to_vec())
     }
+}
 
-    pub fn wallet_address(&self) -> Base64 {
-        let mut context = sha2::Sha256::new();
-        context.update(&self.keypair_modulus().0[..]);
-        Base64(context.finalize().to_vec())
-    }
-
-    pub fn sign(&self, message: &[u8]) -> Result<Base64, Error> {
+impl Signer for RsaSigner {
+    fn sign(&self, message: &[u8]) -> Result<Base64, Error> {
         let mut hasher = sha2::Sha256::new();
         hasher.update(message);
         let hashed = hasher.finalize();
@@ -67,6 +219,18 @@ impl Signer {
 
         Ok(Base64(signature))
     }
+
+    fn public_key(&self) -> Base64 {
+        self.keypair_modulus()
+    }
+
+    fn signature_type(&self) -> SignatureType {
+        SignatureType::Arweave
+    }
+
+    fn export_jwk(&self) -> Option<jwk::JsonWebKey> {
+        Some(self.to_jwk())
+    }
 }
 
 #[cfg(test)]
@@ -74,13 +238,17 @@ mod tests {
     use std::{path::PathBuf, str::FromStr};
 
     use crate::{
-        crypto::{base64::Base64, sign::Signer},
+        crypto::{
+            base64::Base64,
+            sign::{RsaSigner, Signer},
+            verify::verify,
+        },
         error,
     };
 
     const DEFAULT_WALLET_PATH: &str = "res/test_wallet.json";
 
-    impl Default for Signer {
+    impl Default for RsaSigner {
         fn default() -> Self {
             let path = PathBuf::from_str(DEFAULT_WALLET_PATH).unwrap();
             Self::from_keypair_path(path).expect("Could not create signer")
@@ -90,13 +258,68 @@ mod tests {
     #[test]
     fn test_default_keypair() {
         let path = PathBuf::from_str(DEFAULT_WALLET_PATH).unwrap();
-        let provider = Signer::from_keypair_path(path).expect("Valid wallet file");
+        let provider = RsaSigner::from_keypair_path(path).expect("Valid wallet file");
         assert_eq!(
             provider.wallet_address().to_string(),
             "ggHWyKn0I_CTtsyyt2OR85sPYz9OvKLd9DYIvRQ2ET4"
         );
     }
 
+    #[test]
+    fn test_from_jwk_str() -> Result<(), error::Error> {
+        let jwk = std::fs::read_to_string(DEFAULT_WALLET_PATH)?;
+        let address = RsaSigner::default().wallet_address();
+        assert_eq!(RsaSigner::from_jwk_str(&jwk)?.wallet_address(), address);
+
+        let value: serde_json::Value = serde_json::from_str(&jwk).unwrap();
+        assert_eq!(RsaSigner::from_jwk_value(value)?.wallet_address(), address);
+
+        std::env::set_var("ARWEAVE_RS_TEST_JWK", &jwk);
+        assert_eq!(
+            RsaSigner::from_env("ARWEAVE_RS_TEST_JWK")?.wallet_address(),
+            address
+        );
+        assert!(matches!(
+            RsaSigner::from_env("ARWEAVE_RS_MISSING_JWK"),
+            Err(error::Error::MissingEnvVar(_))
+        ));
+
+        let public = serde_json::json!({
+            "kty": "RSA",
+            "e": "AQAB",
+            "n": RsaSigner::default().public_key().to_string(),
+        });
+        assert!(matches!(
+            RsaSigner::from_jwk_value(public.clone()),
+            Err(error::Error::InvalidKey(_))
+        ));
+        assert!(matches!(
+            RsaSigner::from_jwk(serde_json::from_value(public).unwrap()),
+            Err(error::Error::InvalidKey(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate() -> Result<(), error::Error> {
+        let signer = RsaSigner::generate()?;
+        assert_eq!(signer.public_key().0.len(), 512);
+
+        let jwk_json = signer.to_jwk().to_string();
+        let restored = RsaSigner::from_jwk(jwk_json.parse().unwrap())?;
+        assert_eq!(restored.wallet_address(), signer.wallet_address());
+
+        let message = b"message";
+        let signature = restored.sign(message)?;
+        verify(&signer.public_key().0, message, &signature.0)?;
+
+        // Round trip of an existing wallet file.
+        let signer = RsaSigner::default();
+        let restored = RsaSigner::from_jwk(signer.to_jwk())?;
+        assert_eq!(restored.wallet_address(), signer.wallet_address());
+        Ok(())
+    }
+
     #[test]
     fn test_sign_verify() -> Result<(), error::Error> {
         let message = Base64(
@@ -108,7 +331,7 @@ mod tests {
             .to_vec(),
         );
         let path = PathBuf::from_str("res/test_wallet.json").expect("Could not open .wallet.json");
-        let provider = Signer::from_keypair_path(path)?;
+        let provider = RsaSigner::from_keypair_path(path)?;
         let signature = provider.sign(&message.0).unwrap();
         let pubk = provider.public_key();
         println!("pubk: {}", &pubk.to_string());
@@ -1,3 +1,5 @@
+use subtle::ConstantTimeEq;
+
 pub fn copy_into_slice_32(m: &[u8]) -> [u8; 32] {
     let mut result: [u8; 32] = [0; 32];
     result.copy_from_slice(m);
@@ -15,3 +17,23 @@ pub fn concat_u8_48(left: [u8; 48], right: [u8; 48]) -> [u8; 96] {
     let mut iter = left.into_iter().chain(right);
     [(); 96].map(|_| iter.next().expect("Could not get concat two arrays"))
 }
+
+/// Compares `a` and `b` in time independent of their contents, so that comparing untrusted
+/// input against expected hashes does not leak how many leading bytes match. Only the lengths
+/// are compared in variable time.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ct_eq;
+
+    #[test]
+    fn test_ct_eq() {
+        assert!(ct_eq(&[1, 2, 3], &[1, 2, 3]));
+        assert!(ct_eq(&[], &[]));
+        assert!(!ct_eq(&[1, 2, 3], &[1, 2, 4]));
+        assert!(!ct_eq(&[1, 2, 3], &[1, 2]));
+    }
+}
@@ -1,4 +1,6 @@
-use crate::error::Error;
+use crate::{address::Address, error::Error};
+
+use super::{base64::Base64, sign::SignatureType};
 use data_encoding::BASE64URL;
 use jsonwebkey as jwk;
 use rand::thread_rng;
@@ -10,9 +12,10 @@ pub fn verify(pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), Er
         "{{\"kty\":\"RSA\",\"e\":\"AQAB\",\"n\":\"{}\"}}",
         BASE64URL.encode(pub_key)
     );
-    let jwk: jwk::JsonWebKey = jwt_str.parse().unwrap();
+    let jwk: jwk::JsonWebKey = jwt_str.parse().map_err(|_| Error::InvalidSignature)?;
 
-    let pub_key = RsaPublicKey::from_public_key_der(jwk.key.to_der().as_slice()).unwrap();
+    let pub_key = RsaPublicKey::from_public_key_der(jwk.key.to_der().as_slice())
+        .map_err(|_| Error::InvalidSignature)?;
     let mut hasher = sha2::Sha256::new();
     hasher.update(message);
     let hashed = &hasher.finalize();
@@ -28,3 +31,93 @@ pub fn verify(pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), Er
         .map(|_| ())
         .map_err(|_| Error::InvalidSignature)
 }
+
+/// Verifies `signature` of `message` with the scheme of `signature_type`.
+pub fn verify_signature(
+    signature_type: SignatureType,
+    pub_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), Error> {
+    match signature_type {
+        SignatureType::Arweave => verify(pub_key, message, signature),
+        #[cfg(feature = "ed25519")]
+        SignatureType::Ed25519 | SignatureType::Solana => {
+            super::ed25519::verify(pub_key, message, signature)
+        }
+        #[cfg(feature = "secp256k1")]
+        SignatureType::Ethereum => super::secp256k1::verify(pub_key, message, signature),
+        #[allow(unreachable_patterns)]
+        signature_type => Err(Error::InvalidSignatureType(signature_type as u16)),
+    }
+}
+
+/// Public half of a wallet, for services that verify signatures and derive addresses
+/// without holding private keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verifier {
+    signature_type: SignatureType,
+    public_key: Base64,
+}
+
+impl Verifier {
+    /// Verifier of an Arweave wallet, from its modulus as found in `owner` fields.
+    pub fn new(public_key: Base64) -> Self {
+        Self::with_signature_type(SignatureType::Arweave, public_key)
+    }
+
+    pub fn with_signature_type(signature_type: SignatureType, public_key: Base64) -> Self {
+        Self {
+            signature_type,
+            public_key,
+        }
+    }
+
+    pub fn public_key(&self) -> &Base64 {
+        &self.public_key
+    }
+
+    pub fn signature_type(&self) -> SignatureType {
+        self.signature_type
+    }
+
+    pub fn wallet_address(&self) -> Address {
+        Address::from_owner(&self.public_key.0)
+    }
+
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), Error> {
+        verify_signature(self.signature_type, &self.public_key.0, message, signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        crypto::{
+            base64::Base64,
+            sign::{RsaSigner, SignatureType, Signer},
+        },
+        error::Error,
+    };
+
+    use super::Verifier;
+
+    #[test]
+    fn test_verifier() -> Result<(), Error> {
+        let signer = RsaSigner::default();
+        let verifier = Verifier::new(signer.public_key());
+        assert_eq!(verifier.wallet_address(), signer.wallet_address());
+        assert_eq!(verifier.signature_type(), SignatureType::Arweave);
+
+        let signature = signer.sign(b"message")?;
+        verifier.verify(b"message", &signature.0)?;
+        assert!(matches!(
+            verifier.verify(b"other", &signature.0),
+            Err(Error::InvalidSignature)
+        ));
+
+        let verifier = Verifier::with_signature_type(SignatureType::Ethereum, Base64(vec![4; 65]));
+        assert!(verifier.verify(b"message", &[0; 65]).is_err());
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use std::str::FromStr;
 
-use serde::{de, Deserialize, Deserializer};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 
 use crate::error::Error;
@@ -50,19 +50,29 @@ impl FromStr for Currency {
     }
 }
 
-impl ToString for Currency {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let decimal = format!("{:#012}", self.winston);
         if self.arweave == 0 && self.winston == 0 {
-            '0'.to_string()
+            write!(f, "0")
         } else if self.arweave == 0 {
-            decimal.trim_start_matches('0').to_string()
+            write!(f, "{}", decimal.trim_start_matches('0'))
         } else {
-            self.arweave.to_string() + &decimal
+            write!(f, "{}{}", self.arweave, decimal)
         }
     }
 }
 
+/// Serializes to a string of winstons, as in transaction JSON.
+impl Serialize for Currency {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
 //TODO: remove unwraps
 impl<'de> Deserialize<'de> for Currency {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -79,11 +89,185 @@ impl<'de> Deserialize<'de> for Currency {
     }
 }
 
+/// Amount in winstons, with overflow checked arithmetic and conversions to and from AR
+/// decimal strings.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Winston(pub u128);
+
+impl Winston {
+    pub const ZERO: Winston = Winston(0);
+
+    pub fn new(winstons: u128) -> Self {
+        Self(winstons)
+    }
+
+    pub fn as_u128(&self) -> u128 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Winston) -> Option<Winston> {
+        self.0.checked_add(other.0).map(Winston)
+    }
+
+    pub fn checked_sub(self, other: Winston) -> Option<Winston> {
+        self.0.checked_sub(other.0).map(Winston)
+    }
+
+    pub fn checked_mul(self, factor: u128) -> Option<Winston> {
+        self.0.checked_mul(factor).map(Winston)
+    }
+
+    /// Parses an amount of AR with up to 12 decimals, e.g. `"1.5"`.
+    pub fn from_ar(ar: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidAmount(ar.to_string());
+        let (integer, decimals) = ar.trim().split_once('.').unwrap_or((ar.trim(), ""));
+        if decimals.len() > 12
+            || (integer.is_empty() && decimals.is_empty())
+            || !integer
+                .chars()
+                .chain(decimals.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        let integer = match integer {
+            "" => 0,
+            integer => integer.parse::<u128>().map_err(|_| invalid())?,
+        };
+        let decimals = match decimals {
+            "" => 0,
+            decimals => format!("{:0<12}", decimals)
+                .parse::<u128>()
+                .map_err(|_| invalid())?,
+        };
+        integer
+            .checked_mul(WINSTONS_PER_AR as u128)
+            .and_then(|winstons| winstons.checked_add(decimals))
+            .map(Winston)
+            .ok_or_else(invalid)
+    }
+
+    /// Amount of AR without trailing zeros, e.g. `"1.5"`.
+    pub fn to_ar_string(&self) -> String {
+        let integer = self.0 / WINSTONS_PER_AR as u128;
+        let decimals = self.0 % WINSTONS_PER_AR as u128;
+        if decimals == 0 {
+            integer.to_string()
+        } else {
+            let decimals = format!("{:012}", decimals);
+            format!("{}.{}", integer, decimals.trim_end_matches('0'))
+        }
+    }
+}
+
+impl From<u64> for Winston {
+    fn from(winstons: u64) -> Self {
+        Self(winstons as u128)
+    }
+}
+
+impl From<u128> for Winston {
+    fn from(winstons: u128) -> Self {
+        Self(winstons)
+    }
+}
+
+impl From<Currency> for Winston {
+    fn from(currency: Currency) -> Self {
+        Self(currency.arweave as u128 * WINSTONS_PER_AR as u128 + currency.winston as u128)
+    }
+}
+
+impl From<Winston> for Currency {
+    fn from(winston: Winston) -> Self {
+        Currency::from(winston.0)
+    }
+}
+
+/// Formats the amount in winstons.
+impl std::fmt::Display for Winston {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Parses an amount in winstons.
+impl FromStr for Winston {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        s.trim()
+            .parse::<u128>()
+            .map(Winston)
+            .map_err(|_| Error::InvalidAmount(s.to_string()))
+    }
+}
+
+impl Serialize for Winston {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Winston {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Value::deserialize(deserializer)? {
+            Value::String(s) => Winston::from_str(&s).map_err(de::Error::custom),
+            Value::Number(num) => num
+                .as_u64()
+                .map(Winston::from)
+                .ok_or_else(|| de::Error::custom("Invalid winston amount")),
+            _ => Err(de::Error::custom("Wrong type")),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
 
-    use super::Currency;
+    use super::{Currency, Winston};
+
+    #[test]
+    fn test_winston_ar_conversion() {
+        assert_eq!(Winston::from_ar("1").unwrap(), Winston(1_000_000_000_000));
+        assert_eq!(Winston::from_ar("1.5").unwrap(), Winston(1_500_000_000_000));
+        assert_eq!(Winston::from_ar(".000000000001").unwrap(), Winston(1));
+        assert_eq!(Winston(1_500_000_000_000).to_ar_string(), "1.5");
+        assert_eq!(Winston(1).to_ar_string(), "0.000000000001");
+        assert_eq!(Winston(2_000_000_000_000).to_ar_string(), "2");
+        for invalid in ["", ".", "1.0000000000001", "-1", "1e3", "1.2.3"] {
+            assert!(Winston::from_ar(invalid).is_err(), "{}", invalid);
+        }
+        assert!(Winston::from_ar(&u128::MAX.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_winston_arithmetic() {
+        let fee = Winston::from(1000u64);
+        assert_eq!(fee.checked_add(Winston(1)), Some(Winston(1001)));
+        assert_eq!(fee.checked_sub(Winston(1001)), None);
+        assert_eq!(Winston(u128::MAX).checked_add(Winston(1)), None);
+        assert_eq!(fee.checked_mul(3), Some(Winston(3000)));
+
+        let currency = Currency::from_str("999.123123123123").unwrap();
+        assert_eq!(Winston::from(currency), Winston(999_123_123_123_123));
+        assert_eq!(Currency::from(Winston(999_123_123_123_123)), currency);
+    }
+
+    #[test]
+    fn test_winston_serde() {
+        assert_eq!(Winston::from_str("42").unwrap().to_string(), "42");
+        assert_eq!(serde_json::to_string(&Winston(42)).unwrap(), "\"42\"");
+        let parsed: Vec<Winston> = serde_json::from_str("[\"42\", 43]").unwrap();
+        assert_eq!(parsed, vec![Winston(42), Winston(43)]);
+    }
 
     #[test]
     fn test_str_parse() {
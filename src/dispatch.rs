@@ -0,0 +1,33 @@
+//! Routing of uploads between a bundler and layer-1 transactions, as `dispatch` does in
+//! arweave-js: small uploads are posted as data items to the configured bundler, which
+//! confirms them within seconds, larger ones as transactions.
+
+use async_trait::async_trait;
+
+use crate::{bundle::DataItem, error::Error};
+
+/// Largest upload dispatched to the bundler by default.
+pub const DEFAULT_DISPATCH_THRESHOLD: usize = 100 * 1024;
+
+/// Service accepting signed data items, such as an Irys node or Turbo.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait Bundler: Send + Sync {
+    /// Posts `data_item`, returning its id once the bundler accepted it.
+    async fn post(&self, data_item: &DataItem) -> Result<String, Error>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DispatchKind {
+    /// Posted as a data item to the bundler.
+    Bundled,
+    /// Posted as a layer-1 transaction paying `reward`.
+    Transaction { reward: u64 },
+}
+
+/// Outcome of [`Arweave::dispatch`](crate::Arweave::dispatch).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DispatchResult {
+    pub id: String,
+    pub kind: DispatchKind,
+}
@@ -0,0 +1,187 @@
+//! Client-side encryption of private data.
+//!
+//! Payloads are encrypted with AES-256-GCM under a random [`ContentKey`] before upload. The
+//! cipher and IV are recorded in the tags of the transaction, optionally together with the key
+//! wrapped with RSA-OAEP for the public key of a wallet, so that only that wallet can decrypt
+//! the data after download.
+
+use std::fmt;
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use rand::RngCore;
+use rsa::{BigUint, PaddingScheme, PublicKey, RsaPublicKey};
+
+use crate::{
+    content_type::DEFAULT_CONTENT_TYPE,
+    crypto::{base64::Base64, sign::RsaSigner},
+    error::Error,
+    transaction::tags::{find_tag_value, Tag},
+};
+
+pub const CIPHER_TAG: &str = "Cipher";
+pub const CIPHER_IV: &str = "Cipher-IV";
+pub const KEY_WRAP: &str = "Key-Wrap";
+pub const WRAPPED_KEY: &str = "Wrapped-Key";
+
+pub const CIPHER: &str = "AES256-GCM";
+/// RSA-OAEP with SHA-256.
+pub const RSA_OAEP_256: &str = "RSA-OAEP-256";
+
+const IV_SIZE: usize = 12;
+const RSA_EXPONENT: u32 = 65537;
+
+fn crypto_error(reason: &str) -> Error {
+    Error::CryptoError(reason.to_string())
+}
+
+fn tag_value(tags: &[Tag<Base64>], name: &str) -> Result<String, Error> {
+    find_tag_value(tags, name).ok_or_else(|| crypto_error(&format!("missing {} tag", name)))
+}
+
+/// AES-256 key of an encrypted payload.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ContentKey(pub [u8; 32]);
+
+impl fmt::Debug for ContentKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ContentKey(..)")
+    }
+}
+
+impl ContentKey {
+    pub fn generate() -> Self {
+        let mut key = [0; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        Self(key)
+    }
+
+    /// Encrypts `data` under a random IV, returning the ciphertext and the tags recording the
+    /// cipher and IV.
+    pub fn encrypt(&self, data: &[u8]) -> Result<(Vec<u8>, Vec<Tag<Base64>>), Error> {
+        let mut iv = vec![0; IV_SIZE];
+        rand::thread_rng().fill_bytes(&mut iv);
+        let ciphertext = Aes256Gcm::new((&self.0).into())
+            .encrypt(Nonce::from_slice(&iv), data)
+            .map_err(|_| crypto_error("encryption failed"))?;
+        let tags = vec![
+            Tag::content_type(DEFAULT_CONTENT_TYPE),
+            Tag::new(CIPHER_TAG, CIPHER),
+            Tag::new(CIPHER_IV, &Base64(iv).to_string()),
+        ];
+        Ok((ciphertext, tags))
+    }
+
+    /// Decrypts the data of a transaction tagged with `tags`.
+    pub fn decrypt(&self, tags: &[Tag<Base64>], data: &[u8]) -> Result<Vec<u8>, Error> {
+        let cipher = tag_value(tags, CIPHER_TAG)?;
+        if cipher != CIPHER {
+            return Err(crypto_error(&format!("unsupported cipher {}", cipher)));
+        }
+        let iv: Base64 = tag_value(tags, CIPHER_IV)?
+            .parse()
+            .map_err(Error::Base64DecodeError)?;
+        if iv.0.len() != IV_SIZE {
+            return Err(crypto_error("invalid cipher IV"));
+        }
+        Aes256Gcm::new((&self.0).into())
+            .decrypt(Nonce::from_slice(&iv.0), data)
+            .map_err(|_| crypto_error("wrong key or altered data"))
+    }
+
+    /// Encrypts the key for the wallet with the public key `owner`.
+    pub fn wrap(&self, owner: &Base64) -> Result<Vec<u8>, Error> {
+        RsaPublicKey::new(
+            BigUint::from_bytes_be(&owner.0),
+            BigUint::from(RSA_EXPONENT),
+        )
+        .and_then(|public_key| {
+            public_key.encrypt(
+                &mut rand::thread_rng(),
+                PaddingScheme::new_oaep::<sha2::Sha256>(),
+                &self.0,
+            )
+        })
+        .map_err(|e| Error::InvalidKey(e.to_string()))
+    }
+
+    /// Decrypts a key wrapped for the wallet of `signer`.
+    pub fn unwrap(signer: &RsaSigner, wrapped: &[u8]) -> Result<Self, Error> {
+        let key = signer.decrypt(wrapped)?;
+        Ok(Self(key.try_into().map_err(|_| {
+            Error::InvalidKey("wrapped key is not 32 bytes".to_string())
+        })?))
+    }
+}
+
+/// Encrypts `data` under a new key wrapped for the wallet with the public key `owner`,
+/// returning the ciphertext and the tags of its transaction.
+pub fn encrypt_for(owner: &Base64, data: &[u8]) -> Result<(Vec<u8>, Vec<Tag<Base64>>), Error> {
+    let key = ContentKey::generate();
+    let (ciphertext, mut tags) = key.encrypt(data)?;
+    tags.push(Tag::new(KEY_WRAP, RSA_OAEP_256));
+    tags.push(Tag::new(WRAPPED_KEY, &Base64(key.wrap(owner)?).to_string()));
+    Ok((ciphertext, tags))
+}
+
+/// Decrypts data encrypted with [`encrypt_for`] the wallet of `signer`.
+pub fn decrypt_with(
+    signer: &RsaSigner,
+    tags: &[Tag<Base64>],
+    data: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let key_wrap = tag_value(tags, KEY_WRAP)?;
+    if key_wrap != RSA_OAEP_256 {
+        return Err(crypto_error(&format!("unsupported key wrap {}", key_wrap)));
+    }
+    let wrapped: Base64 = tag_value(tags, WRAPPED_KEY)?
+        .parse()
+        .map_err(Error::Base64DecodeError)?;
+    ContentKey::unwrap(signer, &wrapped.0)?.decrypt(tags, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        crypto::sign::RsaSigner,
+        error::Error,
+        transaction::tags::{find_tag_value, Tag},
+    };
+
+    use super::{decrypt_with, encrypt_for, ContentKey, CIPHER_IV};
+
+    #[test]
+    fn test_encrypt() -> Result<(), Error> {
+        let key = ContentKey::generate();
+        let (ciphertext, tags) = key.encrypt(b"private")?;
+        assert_ne!(ciphertext, b"private");
+        assert_eq!(key.decrypt(&tags, &ciphertext)?, b"private");
+
+        let mut altered = ciphertext.clone();
+        altered[0] ^= 1;
+        assert!(matches!(
+            key.decrypt(&tags, &altered),
+            Err(Error::CryptoError(_))
+        ));
+        assert!(ContentKey::generate().decrypt(&tags, &ciphertext).is_err());
+        let unencrypted = [Tag::content_type("text/plain")];
+        assert!(key.decrypt(&unencrypted, &ciphertext).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypt_for_wallet() -> Result<(), Error> {
+        let signer = RsaSigner::from_keypair_path(PathBuf::from("res/test_wallet.json"))?;
+        let other = RsaSigner::from_keypair_path(PathBuf::from("res/test_wallet_4096.json"))?;
+        let (ciphertext, tags) = encrypt_for(&signer.keypair_modulus(), b"archive")?;
+
+        assert!(find_tag_value(&tags, CIPHER_IV).is_some());
+        assert_eq!(decrypt_with(&signer, &tags, &ciphertext)?, b"archive");
+        assert!(decrypt_with(&other, &tags, &ciphertext).is_err());
+        Ok(())
+    }
+}
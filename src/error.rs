@@ -3,7 +3,12 @@ use std::string::FromUtf8Error;
 use thiserror::Error;
 use url::ParseError;
 
+use crate::{transaction::validate::Violation, transport::HttpResponse};
+
+/// Errors of all operations of the crate. Errors of underlying libraries are available
+/// through [`std::error::Error::source`].
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
     #[error("Error getting oracle price: {0}")]
     OracleGetPriceError(String),
@@ -14,6 +19,10 @@ pub enum Error {
     #[error("Status code not Ok")]
     StatusCodeNotOk,
 
+    /// Response of a node with an error status, with the body explaining it.
+    #[error("Unexpected status code {status}: {body}")]
+    UnexpectedStatus { status: u16, body: String },
+
     #[error("Unsigned transaction")]
     UnsignedTransaction,
 
@@ -29,6 +38,55 @@ pub enum Error {
     #[error("Invalid tag encoding.")]
     InvalidTagEncoding,
 
+    #[error("Invalid data item: {0}")]
+    InvalidDataItem(String),
+
+    #[error("Invalid chunk at offset {offset}: {reason}")]
+    InvalidChunk { offset: u64, reason: String },
+
+    #[error("Invalid chunking options: {0}")]
+    InvalidChunkingOptions(String),
+
+    #[error("Invalid rate limit: {0}")]
+    InvalidRateLimit(String),
+
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+
+    #[error("Invalid transaction id: {0}")]
+    InvalidTxId(String),
+
+    #[error("Invalid block hash: {0}")]
+    InvalidBlockHash(String),
+
+    #[error("Invalid signature type: {0}")]
+    InvalidSignatureType(u16),
+
+    #[error("Invalid bundle: {0}")]
+    InvalidBundle(String),
+
+    /// Transaction breaking limits of the protocol, with all of the violations.
+    #[error("Invalid transaction: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    InvalidTransaction(Vec<Violation>),
+
+    #[error("Keystore error: {0}")]
+    KeystoreError(String),
+
+    #[error("Key generation error: {0}")]
+    KeyGenerationError(String),
+
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+
+    #[error("Invalid data: {0}")]
+    InvalidData(String),
+
+    #[error("Invalid manifest: {0}")]
+    InvalidManifest(String),
+
+    #[error("Avro error: {0}")]
+    AvroError(String),
+
     #[error("Error getting network info: {0}")]
     NetworkInfoError(String),
 
@@ -38,9 +96,33 @@ pub enum Error {
     #[error("Crypto error: {0}")]
     CryptoError(String),
 
+    /// Key material that cannot be parsed or has the wrong type or size.
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+
+    #[error("No signer configured")]
+    MissingSigner,
+
+    #[error("Environment variable not set: {0}")]
+    MissingEnvVar(String),
+
     #[error("Error getting transaction info: {0}")]
     TransactionInfoError(String),
 
+    #[error("Timed out waiting for confirmation of transaction: {0}")]
+    ConfirmationTimeout(String),
+
+    /// A request got no complete response within the request or connect timeout.
+    #[error("Request timed out: {0}")]
+    RequestTimeout(String),
+
+    /// An operation did not complete before the deadline of the client.
+    #[error("Operation timed out after {0:?}")]
+    OperationTimeout(std::time::Duration),
+
+    #[error("GraphQL error: {0}")]
+    GraphqlError(String),
+
     #[error("Unknown Error.")]
     UnknownError,
 
@@ -53,39 +135,203 @@ pub enum Error {
     #[error("Error posting chunk: {0}")]
     PostChunkError(String),
 
+    #[error("Upload state was recorded for another transaction: {0}")]
+    UploadStateMismatch(String),
+
     #[error("Error signing: {0}")]
     SigningError(String),
 
     #[error("No field present: {0}")]
     NoneError(String), //TODO: add option::NoneError implementation when released
 
-    #[error("Io Error")]
-    IoError(std::io::Error),
+    #[error("Io error: {0}")]
+    IoError(#[from] std::io::Error),
 
-    #[error("ParseIntError")]
-    ParseIntError(std::num::ParseIntError),
+    #[error("Invalid integer: {0}")]
+    ParseIntError(#[source] std::num::ParseIntError),
 
-    #[error("UrlParseError")]
-    UrlParseError(ParseError),
+    #[error("Invalid url: {0}")]
+    UrlParseError(#[source] ParseError),
 
-    #[error("FromUtf8Error")]
-    FromUtf8Error(FromUtf8Error),
+    #[error("Invalid utf-8: {0}")]
+    FromUtf8Error(#[source] FromUtf8Error),
 
-    #[error("FromUtf8Error")]
-    JsonWebKeyError(jsonwebkey::Error),
+    #[error("Invalid JWK: {0}")]
+    JsonWebKeyError(#[source] jsonwebkey::Error),
 
-    #[error("ReqwestError")]
-    ReqwestError(reqwest::Error),
+    #[error("Request failed: {0}")]
+    ReqwestError(#[source] reqwest::Error),
 
-    #[error("DecodeError")]
-    Base64DecodeError(base64::DecodeError),
+    /// Failure of a custom [`HttpTransport`](crate::transport::HttpTransport) to get a
+    /// response.
+    #[error("Transport error: {0}")]
+    TransportError(String),
 
-    #[error("SerdeJsonError")]
-    SerdeJsonError(serde_json::Error),
+    #[error("Invalid proxy: {0}")]
+    InvalidProxy(String),
+
+    /// Receipt of a bundler not matching the posted data item or not signed by the bundler.
+    #[error("Invalid receipt: {0}")]
+    InvalidReceipt(String),
+
+    #[error("ArNS name not found: {0}")]
+    ArnsNameNotFound(String),
+
+    #[error("Invalid license: {0}")]
+    InvalidLicense(String),
+
+    #[error("Invalid ArFS entity: {0}")]
+    InvalidEntity(String),
+
+    #[error("Invalid base64: {0}")]
+    Base64DecodeError(#[source] base64::DecodeError),
+
+    #[error("Invalid JSON: {0}")]
+    SerdeJsonError(#[source] serde_json::Error),
+}
+
+/// Broad classification of [`Error`]s, for callers deciding how to react to a failure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The node could not be reached or the connection failed.
+    Network,
+    Timeout,
+    /// Status 429.
+    RateLimited,
+    /// Status 5xx.
+    Server,
+    /// Any other error status of the node.
+    Rejected,
+    /// The wallet cannot pay for the transaction. Nodes answer overspending transactions
+    /// with status 410.
+    InsufficientFunds,
+    InvalidSignature,
+    /// Invalid keys, data, transactions or arguments.
+    Invalid,
+    Other,
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::ReqwestError(err) if err.is_timeout() => ErrorKind::Timeout,
+            Error::RequestTimeout(_) | Error::OperationTimeout(_) => ErrorKind::Timeout,
+            Error::ReqwestError(err) => match err.status() {
+                Some(status) => status_kind(status.as_u16()),
+                None if err.is_decode() || err.is_builder() => ErrorKind::Other,
+                None => ErrorKind::Network,
+            },
+            Error::UnexpectedStatus { status, .. } => status_kind(*status),
+            Error::PostChunkError(_) | Error::GetPriceError(_) | Error::TransportError(_) => {
+                ErrorKind::Network
+            }
+            Error::InvalidSignature | Error::InvalidSignatureType(_) => ErrorKind::InvalidSignature,
+            Error::UnsignedTransaction
+            | Error::InvalidProof
+            | Error::InvalidValueForTx
+            | Error::InvalidTagEncoding
+            | Error::InvalidDataItem(_)
+            | Error::InvalidChunk { .. }
+            | Error::InvalidChunkingOptions(_)
+            | Error::InvalidRateLimit(_)
+            | Error::InvalidAddress(_)
+            | Error::InvalidTxId(_)
+            | Error::InvalidBlockHash(_)
+            | Error::InvalidBundle(_)
+            | Error::InvalidTransaction(_)
+            | Error::InvalidAmount(_)
+            | Error::InvalidData(_)
+            | Error::InvalidManifest(_)
+            | Error::InvalidKey(_)
+            | Error::MissingSigner
+            | Error::MissingEnvVar(_)
+            | Error::InvalidProxy(_)
+            | Error::InvalidReceipt(_)
+            | Error::InvalidLicense(_)
+            | Error::InvalidEntity(_)
+            | Error::UrlParseError(_)
+            | Error::JsonWebKeyError(_) => ErrorKind::Invalid,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// Whether the operation may succeed when retried: network failures, timeouts, rate
+    /// limiting and server errors. Used by the default [`RetryPolicy`](crate::retry::RetryPolicy).
+    /// An expired operation deadline is final.
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, Error::OperationTimeout(_))
+            && matches!(
+                self.kind(),
+                ErrorKind::Network
+                    | ErrorKind::Timeout
+                    | ErrorKind::RateLimited
+                    | ErrorKind::Server
+            )
+    }
+
+    /// Error for a response with an unexpected status, reading the body sent with it.
+    pub(crate) fn from_response(res: &HttpResponse) -> Self {
+        Error::UnexpectedStatus {
+            status: res.status.as_u16(),
+            body: String::from_utf8_lossy(&res.body).into_owned(),
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn from_blocking_response(res: reqwest::blocking::Response) -> Self {
+        let status = res.status().as_u16();
+        let body = res.text().unwrap_or_default();
+        Error::UnexpectedStatus { status, body }
+    }
 }
 
-impl From<std::io::Error> for Error {
-    fn from(value: std::io::Error) -> Self {
-        Self::IoError(value)
+fn status_kind(status: u16) -> ErrorKind {
+    match status {
+        429 => ErrorKind::RateLimited,
+        410 => ErrorKind::InsufficientFunds,
+        500..=599 => ErrorKind::Server,
+        _ => ErrorKind::Rejected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::{Error, ErrorKind};
+
+    #[test]
+    fn test_source() {
+        let err = Error::from(std::io::Error::new(std::io::ErrorKind::NotFound, "missing"));
+        assert_eq!(err.to_string(), "Io error: missing");
+        assert_eq!(err.source().unwrap().to_string(), "missing");
+
+        let err = Error::UnexpectedStatus {
+            status: 400,
+            body: "Invalid JSON.".to_string(),
+        };
+        assert_eq!(err.to_string(), "Unexpected status code 400: Invalid JSON.");
+        assert!(err.source().is_none());
+    }
+
+    #[test]
+    fn test_kind() {
+        let status = |status| Error::UnexpectedStatus {
+            status,
+            body: String::new(),
+        };
+        assert_eq!(status(429).kind(), ErrorKind::RateLimited);
+        assert_eq!(status(502).kind(), ErrorKind::Server);
+        assert_eq!(status(410).kind(), ErrorKind::InsufficientFunds);
+        assert_eq!(status(400).kind(), ErrorKind::Rejected);
+        assert!(status(503).is_retryable());
+        assert!(!status(410).is_retryable());
+        assert!(!Error::InvalidSignature.is_retryable());
+        assert_eq!(Error::InvalidKey(String::new()).kind(), ErrorKind::Invalid);
+        assert!(Error::RequestTimeout(String::new()).is_retryable());
+        let deadline = Error::OperationTimeout(std::time::Duration::from_secs(1));
+        assert_eq!(deadline.kind(), ErrorKind::Timeout);
+        assert!(!deadline.is_retryable());
     }
 }
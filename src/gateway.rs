@@ -0,0 +1,442 @@
+//! Failover between several gateways or nodes.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+use reqwest::StatusCode;
+
+use crate::{
+    error::Error,
+    metrics::MetricsSink,
+    retry::is_transient,
+    runtime::{sleep, timeout, Instant},
+    transport::{HttpRequest, HttpResponse, HttpTransport, StreamingResponse},
+};
+
+/// Consecutive failures after which a gateway is put in cooldown.
+pub const FAILURE_THRESHOLD: u32 = 3;
+pub const COOLDOWN: Duration = Duration::from_secs(60);
+/// Gateways queried at once by [`GatewayPool::health_check`].
+pub const HEALTH_CHECK_CONCURRENCY: usize = 8;
+/// Time a gateway has to answer a health check.
+pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+struct GatewayState {
+    url: url::Url,
+    failures: u32,
+    failed_at: Option<Instant>,
+}
+
+/// Set of gateways used in turn. Requests go to the active gateway until it fails
+/// `failure_threshold` times in a row, in which case it is skipped for `cooldown` and the
+/// next gateway becomes active.
+pub struct GatewayPool {
+    gateways: Mutex<Vec<GatewayState>>,
+    active: Mutex<usize>,
+    failure_threshold: u32,
+    cooldown: Duration,
+    health_check_timeout: Duration,
+    metrics: Option<Arc<dyn MetricsSink>>,
+}
+
+impl std::fmt::Debug for GatewayPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GatewayPool")
+            .field("gateways", &self.gateways)
+            .field("active", &self.active)
+            .field("failure_threshold", &self.failure_threshold)
+            .field("cooldown", &self.cooldown)
+            .field("health_check_timeout", &self.health_check_timeout)
+            .finish()
+    }
+}
+
+impl GatewayPool {
+    pub fn new(urls: Vec<url::Url>) -> Result<Self, Error> {
+        if urls.is_empty() {
+            return Err(Error::NoneError("gateways".to_owned()));
+        }
+        Ok(Self {
+            gateways: Mutex::new(
+                urls.into_iter()
+                    .map(|url| GatewayState {
+                        url,
+                        failures: 0,
+                        failed_at: None,
+                    })
+                    .collect(),
+            ),
+            active: Mutex::new(0),
+            failure_threshold: FAILURE_THRESHOLD,
+            cooldown: COOLDOWN,
+            health_check_timeout: HEALTH_CHECK_TIMEOUT,
+            metrics: None,
+        })
+    }
+
+    pub fn with_failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = failure_threshold.max(1);
+        self
+    }
+
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    pub fn with_health_check_timeout(mut self, health_check_timeout: Duration) -> Self {
+        self.health_check_timeout = health_check_timeout;
+        self
+    }
+
+    /// Reports failovers to `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<dyn MetricsSink>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub fn urls(&self) -> Vec<url::Url> {
+        self.gateways
+            .lock()
+            .unwrap() //Checked unwrap
+            .iter()
+            .map(|gateway| gateway.url.clone())
+            .collect()
+    }
+
+    /// Gateways not cooling down, in order.
+    pub fn available_urls(&self) -> Vec<url::Url> {
+        self.gateways
+            .lock()
+            .unwrap() //Checked unwrap
+            .iter()
+            .filter(|gateway| {
+                gateway
+                    .failed_at
+                    .is_none_or(|failed_at| failed_at.elapsed() >= self.cooldown)
+            })
+            .map(|gateway| gateway.url.clone())
+            .collect()
+    }
+
+    /// Gateway requests should be sent to. If every gateway is cooling down, the one that
+    /// failed first is returned.
+    pub fn current(&self) -> url::Url {
+        let gateways = self.gateways.lock().unwrap(); //Checked unwrap
+        let mut active = self.active.lock().unwrap(); //Checked unwrap
+        let available = (0..gateways.len())
+            .map(|i| (*active + i) % gateways.len())
+            .find(|idx| match gateways[*idx].failed_at {
+                Some(failed_at) => failed_at.elapsed() >= self.cooldown,
+                None => true,
+            });
+        *active = available.unwrap_or_else(|| {
+            (0..gateways.len())
+                .min_by_key(|idx| gateways[*idx].failed_at)
+                .unwrap() //Checked unwrap
+        });
+        gateways[*active].url.clone()
+    }
+
+    pub fn report_success(&self, url: &url::Url) {
+        let mut gateways = self.gateways.lock().unwrap(); //Checked unwrap
+        if let Some(gateway) = gateways.iter_mut().find(|gateway| &gateway.url == url) {
+            gateway.failures = 0;
+            gateway.failed_at = None;
+        }
+    }
+
+    pub fn report_failure(&self, url: &url::Url) {
+        let mut gateways = self.gateways.lock().unwrap(); //Checked unwrap
+        if let Some(idx) = gateways.iter().position(|gateway| &gateway.url == url) {
+            let gateway = &mut gateways[idx];
+            gateway.failures += 1;
+            if gateway.failures >= self.failure_threshold {
+                gateway.failures = 0;
+                gateway.failed_at = Some(Instant::now());
+                let mut active = self.active.lock().unwrap(); //Checked unwrap
+                if *active == idx {
+                    *active = (idx + 1) % gateways.len();
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(from = %url, to = %gateways[*active].url, "gateway failover");
+                    if let Some(metrics) = &self.metrics {
+                        metrics.gateway_failover(url, &gateways[*active].url);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Records the outcome of a request sent to `url`. Only transient errors count as
+    /// failures of the gateway.
+    pub fn report<T>(&self, url: &url::Url, result: &Result<T, Error>) {
+        match result {
+            Ok(_) => self.report_success(url),
+            Err(err) => self.report_error(url, err),
+        }
+    }
+
+    pub fn report_error(&self, url: &url::Url, err: &Error) {
+        if is_transient(err) {
+            self.report_failure(url);
+        }
+    }
+
+    /// Queries the `info` endpoint of every gateway, [`HEALTH_CHECK_CONCURRENCY`] at a time,
+    /// putting the ones that fail or don't answer in time in cooldown and making healthy
+    /// ones available again.
+    pub async fn health_check(&self, transport: &dyn HttpTransport) {
+        let results = stream::iter(self.urls())
+            .map(|url| async move {
+                let healthy = match url.join("info") {
+                    Ok(info_url) => matches!(
+                        timeout(
+                            self.health_check_timeout,
+                            transport.send(HttpRequest::get(info_url))
+                        )
+                        .await,
+                        Some(Ok(res)) if res.status.is_success()
+                    ),
+                    Err(_) => false,
+                };
+                (url, healthy)
+            })
+            .buffer_unordered(HEALTH_CHECK_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+        for (url, healthy) in results {
+            let mut gateways = self.gateways.lock().unwrap(); //Checked unwrap
+            if let Some(gateway) = gateways.iter_mut().find(|gateway| gateway.url == url) {
+                gateway.failures = 0;
+                gateway.failed_at = if healthy { None } else { Some(Instant::now()) };
+            }
+        }
+    }
+
+    /// Runs [`GatewayPool::health_check`] every `interval`, forever. The crate does not spawn
+    /// tasks, so this is meant to be spawned on the runtime of the application.
+    pub async fn monitor(&self, transport: &dyn HttpTransport, interval: Duration) {
+        loop {
+            self.health_check(transport).await;
+            sleep(interval).await;
+        }
+    }
+}
+
+/// Transport sending the requests meant for `base_url` to the active gateway of a pool
+/// instead, reporting the outcome of each. Gives failover to clients built on a fixed url.
+pub struct GatewayTransport {
+    inner: Arc<dyn HttpTransport>,
+    base_url: url::Url,
+    gateways: Arc<GatewayPool>,
+}
+
+impl GatewayTransport {
+    pub fn new(
+        inner: Arc<dyn HttpTransport>,
+        base_url: url::Url,
+        gateways: Arc<GatewayPool>,
+    ) -> Self {
+        Self {
+            inner,
+            base_url,
+            gateways,
+        }
+    }
+
+    /// Points `request` at the active gateway, returning it, unless it is meant for another
+    /// host.
+    fn route(&self, request: &mut HttpRequest) -> Result<Option<url::Url>, Error> {
+        let path = match request.url.as_str().strip_prefix(self.base_url.as_str()) {
+            Some(path) => path.to_string(),
+            None => return Ok(None),
+        };
+        let gateway = self.gateways.current();
+        request.url = gateway.join(&path).map_err(Error::UrlParseError)?;
+        Ok(Some(gateway))
+    }
+
+    fn report(&self, gateway: &url::Url, status: Result<StatusCode, &Error>) {
+        match status {
+            Ok(status) if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS => {
+                self.gateways.report_failure(gateway)
+            }
+            Ok(_) => self.gateways.report_success(gateway),
+            Err(err) => self.gateways.report_error(gateway, err),
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl HttpTransport for GatewayTransport {
+    async fn send(&self, mut request: HttpRequest) -> Result<HttpResponse, Error> {
+        let gateway = self.route(&mut request)?;
+        let result = self.inner.send(request).await;
+        if let Some(gateway) = gateway {
+            self.report(&gateway, result.as_ref().map(|res| res.status));
+        }
+        result
+    }
+
+    async fn send_streaming(&self, mut request: HttpRequest) -> Result<StreamingResponse, Error> {
+        let gateway = self.route(&mut request)?;
+        let result = self.inner.send_streaming(request).await;
+        if let Some(gateway) = gateway {
+            self.report(&gateway, result.as_ref().map(|res| res.status));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use httpmock::{Method::GET, MockServer};
+    use tokio_test::block_on;
+
+    use std::sync::Arc;
+
+    use crate::{error::Error, transport::ReqwestTransport, wallet::WalletInfoClient};
+
+    use super::{GatewayPool, GatewayTransport};
+
+    fn urls() -> Vec<url::Url> {
+        vec![
+            url::Url::parse("http://gateway-1/").unwrap(),
+            url::Url::parse("http://gateway-2/").unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test_rotate_on_failures() {
+        let urls = urls();
+        let pool = GatewayPool::new(urls.clone())
+            .unwrap()
+            .with_failure_threshold(2);
+        assert_eq!(pool.current(), urls[0]);
+
+        pool.report_failure(&urls[0]);
+        assert_eq!(pool.current(), urls[0]);
+        pool.report_success(&urls[0]);
+        pool.report_failure(&urls[0]);
+        assert_eq!(pool.current(), urls[0]);
+
+        pool.report(
+            &urls[0],
+            &Err::<(), _>(Error::UnexpectedStatus {
+                status: 502,
+                body: String::new(),
+            }),
+        );
+        assert_eq!(pool.current(), urls[1]);
+
+        // A permanent error does not count against the gateway.
+        pool.report(
+            &urls[1],
+            &Err::<(), _>(Error::UnexpectedStatus {
+                status: 404,
+                body: String::new(),
+            }),
+        );
+        pool.report(
+            &urls[1],
+            &Err::<(), _>(Error::UnexpectedStatus {
+                status: 404,
+                body: String::new(),
+            }),
+        );
+        assert_eq!(pool.current(), urls[1]);
+    }
+
+    #[test]
+    fn test_cooldown() {
+        let urls = urls();
+        let pool = GatewayPool::new(urls.clone())
+            .unwrap()
+            .with_failure_threshold(1)
+            .with_cooldown(Duration::from_millis(50));
+
+        pool.report_failure(&urls[0]);
+        pool.report_failure(&urls[1]);
+        // Both cooling down, the first one to fail is retried first.
+        assert_eq!(pool.current(), urls[0]);
+
+        pool.report_failure(&urls[0]);
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(pool.current(), urls[1]);
+    }
+
+    #[test]
+    fn test_health_check() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200);
+        });
+        let healthy = url::Url::parse(&server.url("/")).unwrap();
+        let unreachable = url::Url::parse("http://127.0.0.1:1/").unwrap();
+        let pool = GatewayPool::new(vec![unreachable, healthy.clone()]).unwrap();
+
+        block_on(pool.health_check(&ReqwestTransport::default()));
+        assert_eq!(pool.current(), healthy);
+        assert_eq!(pool.available_urls(), vec![healthy]);
+        assert!(GatewayPool::new(vec![]).is_err());
+    }
+
+    #[test]
+    fn test_health_check_timeout() {
+        let slow = MockServer::start();
+        slow.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200).delay(Duration::from_secs(5));
+        });
+        let slow = url::Url::parse(&slow.url("/")).unwrap();
+        let pool = GatewayPool::new(vec![slow])
+            .unwrap()
+            .with_health_check_timeout(Duration::from_millis(100));
+
+        block_on(pool.health_check(&ReqwestTransport::default()));
+        assert!(pool.available_urls().is_empty());
+    }
+
+    #[test]
+    fn test_gateway_transport() {
+        let failing = MockServer::start();
+        let failing_mock = failing.mock(|when, then| {
+            when.method(GET).path("/wallet/address/balance");
+            then.status(503);
+        });
+        let healthy = MockServer::start();
+        let healthy_mock = healthy.mock(|when, then| {
+            when.method(GET).path("/wallet/address/balance");
+            then.status(200).body("10");
+        });
+        let base_url = url::Url::parse(&failing.url("/")).unwrap();
+        let pool = Arc::new(
+            GatewayPool::new(vec![
+                base_url.clone(),
+                url::Url::parse(&healthy.url("/")).unwrap(),
+            ])
+            .unwrap()
+            .with_failure_threshold(1),
+        );
+        let transport = GatewayTransport::new(
+            Arc::new(ReqwestTransport::default()),
+            base_url.clone(),
+            pool,
+        );
+        let client = WalletInfoClient::with_transport(base_url, Arc::new(transport));
+
+        assert!(block_on(client.balance("address")).is_err());
+        assert_eq!(block_on(client.balance("address")).unwrap(), "10");
+        failing_mock.assert_hits(1);
+        healthy_mock.assert_hits(1);
+    }
+}
@@ -0,0 +1,584 @@
+//! Typed client for the [Arweave GraphQL](https://gql-guide.arweave.net) endpoint.
+
+use std::{str::FromStr, sync::Arc};
+
+use async_stream::try_stream;
+use futures::Stream;
+use reqwest::header::{HeaderValue, ACCEPT};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    address::Address,
+    block::{deserialize_previous, BlockHash, BlockHeight},
+    consts::ARWEAVE_BASE_URL,
+    error::Error,
+    transaction::tags::Tag,
+    transport::{HttpRequest, HttpTransport, ReqwestTransport},
+    tx_id::TxId,
+};
+
+const TRANSACTIONS_QUERY: &str = r#"query($ids: [ID!], $owners: [String!], $recipients: [String!], $tags: [TagFilter!], $bundledIn: [ID!], $block: BlockFilter, $first: Int, $after: String, $sort: SortOrder) {
+  transactions(ids: $ids, owners: $owners, recipients: $recipients, tags: $tags, bundledIn: $bundledIn, block: $block, first: $first, after: $after, sort: $sort) {
+    pageInfo { hasNextPage }
+    edges {
+      cursor
+      node {
+        id anchor signature recipient
+        owner { address key }
+        fee { winston ar }
+        quantity { winston ar }
+        data { size type }
+        tags { name value }
+        block { id timestamp height previous }
+        bundledIn { id }
+      }
+    }
+  }
+}"#;
+
+const BLOCKS_QUERY: &str = r#"query($ids: [ID!], $height: BlockFilter, $first: Int, $after: String, $sort: SortOrder) {
+  blocks(ids: $ids, height: $height, first: $first, after: $after, sort: $sort) {
+    pageInfo { hasNextPage }
+    edges {
+      cursor
+      node { id timestamp height previous }
+    }
+  }
+}"#;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SortOrder {
+    HeightAsc,
+    HeightDesc,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TagFilter {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+/// Inclusive range of block heights.
+#[derive(Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BlockFilter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<BlockHeight>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<BlockHeight>,
+}
+
+/// Arguments of a `transactions(...)` query.
+#[derive(Serialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionsQuery {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ids: Vec<TxId>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<Address>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<Address>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<TagFilter>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bundled_in: Vec<TxId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block: Option<BlockFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<SortOrder>,
+}
+
+impl TransactionsQuery {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn ids(mut self, ids: Vec<TxId>) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    pub fn owners(mut self, owners: Vec<Address>) -> Self {
+        self.owners = owners;
+        self
+    }
+
+    pub fn recipients(mut self, recipients: Vec<Address>) -> Self {
+        self.recipients = recipients;
+        self
+    }
+
+    pub fn tag(mut self, name: &str, values: Vec<String>) -> Self {
+        self.tags.push(TagFilter {
+            name: name.to_string(),
+            values,
+        });
+        self
+    }
+
+    pub fn bundled_in(mut self, bundle_ids: Vec<TxId>) -> Self {
+        self.bundled_in = bundle_ids;
+        self
+    }
+
+    pub fn block(mut self, min: Option<BlockHeight>, max: Option<BlockHeight>) -> Self {
+        self.block = Some(BlockFilter { min, max });
+        self
+    }
+
+    pub fn first(mut self, first: u32) -> Self {
+        self.first = Some(first);
+        self
+    }
+
+    pub fn after(mut self, cursor: String) -> Self {
+        self.after = Some(cursor);
+        self
+    }
+
+    pub fn sort(mut self, sort: SortOrder) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+}
+
+/// Arguments of a `blocks(...)` query.
+#[derive(Serialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct BlocksQuery {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ids: Vec<BlockHash>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<BlockFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<SortOrder>,
+}
+
+impl BlocksQuery {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn ids(mut self, ids: Vec<BlockHash>) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    pub fn height(mut self, min: Option<BlockHeight>, max: Option<BlockHeight>) -> Self {
+        self.height = Some(BlockFilter { min, max });
+        self
+    }
+
+    pub fn first(mut self, first: u32) -> Self {
+        self.first = Some(first);
+        self
+    }
+
+    pub fn after(mut self, cursor: String) -> Self {
+        self.after = Some(cursor);
+        self
+    }
+
+    pub fn sort(mut self, sort: SortOrder) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    pub has_next_page: bool,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Edge<T> {
+    pub cursor: String,
+    pub node: T,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Connection<T> {
+    pub page_info: PageInfo,
+    pub edges: Vec<Edge<T>>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Owner {
+    pub address: Address,
+    pub key: String,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Amount {
+    pub winston: String,
+    pub ar: String,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MetaData {
+    pub size: String,
+    #[serde(rename = "type")]
+    pub content_type: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BundleRef {
+    pub id: TxId,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlockNode {
+    pub id: BlockHash,
+    pub timestamp: u64,
+    pub height: BlockHeight,
+    /// `None` for the genesis block.
+    #[serde(deserialize_with = "deserialize_previous")]
+    pub previous: Option<BlockHash>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionNode {
+    pub id: TxId,
+    pub anchor: String,
+    pub signature: String,
+    pub recipient: String,
+    pub owner: Owner,
+    pub fee: Amount,
+    pub quantity: Amount,
+    pub data: MetaData,
+    pub tags: Vec<Tag<String>>,
+    /// `None` while the transaction is pending.
+    pub block: Option<BlockNode>,
+    pub bundled_in: Option<BundleRef>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TransactionsData {
+    transactions: Connection<TransactionNode>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BlocksData {
+    blocks: Connection<BlockNode>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GraphqlErrorMessage {
+    message: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct GraphqlResponse<T> {
+    data: Option<T>,
+    errors: Option<Vec<GraphqlErrorMessage>>,
+}
+
+pub struct GraphqlClient {
+    transport: Arc<dyn HttpTransport>,
+    url: url::Url,
+}
+
+impl Default for GraphqlClient {
+    fn default() -> Self {
+        let base_url = url::Url::from_str(ARWEAVE_BASE_URL).unwrap(); //Checked unwrap
+        Self::new(reqwest::Client::new(), base_url).unwrap() //Checked unwrap
+    }
+}
+
+impl GraphqlClient {
+    pub fn new(client: reqwest::Client, base_url: url::Url) -> Result<Self, Error> {
+        let url = base_url.join("graphql").map_err(Error::UrlParseError)?;
+        Ok(Self {
+            transport: Arc::new(ReqwestTransport(client)),
+            url,
+        })
+    }
+
+    /// Sends queries through `transport` instead of the client given to [`GraphqlClient::new`].
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    pub async fn transactions(
+        &self,
+        query: &TransactionsQuery,
+    ) -> Result<Connection<TransactionNode>, Error> {
+        let data: TransactionsData = self.query(TRANSACTIONS_QUERY, query).await?;
+        Ok(data.transactions)
+    }
+
+    pub async fn blocks(&self, query: &BlocksQuery) -> Result<Connection<BlockNode>, Error> {
+        let data: BlocksData = self.query(BLOCKS_QUERY, query).await?;
+        Ok(data.blocks)
+    }
+
+    /// Streams every transaction matching `query`, following `pageInfo.hasNextPage` and the
+    /// edge cursors. `query.first` sets the page size.
+    pub fn transactions_stream(
+        &self,
+        mut query: TransactionsQuery,
+    ) -> impl Stream<Item = Result<TransactionNode, Error>> + '_ {
+        try_stream! {
+            loop {
+                let connection = self.transactions(&query).await?;
+                let cursor = connection.edges.last().map(|edge| edge.cursor.clone());
+                for edge in connection.edges {
+                    yield edge.node;
+                }
+                match (connection.page_info.has_next_page, cursor) {
+                    (true, Some(cursor)) => query.after = Some(cursor),
+                    _ => break,
+                }
+            }
+        }
+    }
+
+    /// Streams every block matching `query`, following `pageInfo.hasNextPage` and the edge
+    /// cursors.
+    pub fn blocks_stream(
+        &self,
+        mut query: BlocksQuery,
+    ) -> impl Stream<Item = Result<BlockNode, Error>> + '_ {
+        try_stream! {
+            loop {
+                let connection = self.blocks(&query).await?;
+                let cursor = connection.edges.last().map(|edge| edge.cursor.clone());
+                for edge in connection.edges {
+                    yield edge.node;
+                }
+                match (connection.page_info.has_next_page, cursor) {
+                    (true, Some(cursor)) => query.after = Some(cursor),
+                    _ => break,
+                }
+            }
+        }
+    }
+
+    /// Runs an arbitrary query. Errors reported by the endpoint are returned as
+    /// [`Error::GraphqlError`], transport failures as returned by the transport.
+    pub async fn query<T: DeserializeOwned, V: Serialize>(
+        &self,
+        query: &str,
+        variables: &V,
+    ) -> Result<T, Error> {
+        let request = HttpRequest::post(self.url.clone())
+            .json(&json!({ "query": query, "variables": variables }))?
+            .header(ACCEPT, HeaderValue::from_static("application/json"));
+        let res = self.transport.send(request).await?;
+
+        let status = res.status;
+        let response: GraphqlResponse<T> = match serde_json::from_slice(&res.body) {
+            Ok(response) => response,
+            Err(_) if !status.is_success() => return Err(Error::StatusCodeNotOk),
+            Err(err) => return Err(Error::SerdeJsonError(err)),
+        };
+
+        match (response.data, response.errors) {
+            (_, Some(errors)) if !errors.is_empty() => Err(Error::GraphqlError(
+                errors
+                    .into_iter()
+                    .map(|e| e.message)
+                    .collect::<Vec<String>>()
+                    .join("; "),
+            )),
+            (Some(data), _) => Ok(data),
+            (None, _) => Err(Error::GraphqlError("response has no data".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{StreamExt, TryStreamExt};
+    use httpmock::{Method::POST, MockServer};
+    use serde_json::json;
+    use tokio_test::block_on;
+
+    use crate::{
+        address::Address,
+        block::{BlockHash, BlockHeight},
+        error::Error,
+        tx_id::TxId,
+    };
+
+    use super::{BlocksQuery, GraphqlClient, TransactionsQuery};
+
+    fn client(server: &MockServer) -> GraphqlClient {
+        let url = url::Url::parse(&server.url("")).unwrap();
+        GraphqlClient::new(reqwest::Client::new(), url).unwrap()
+    }
+
+    #[test]
+    fn test_transactions_query() {
+        let owner = Address::new([6; 32]);
+        let (id, bundle) = (TxId::new([1; 32]), TxId::new([2; 32]));
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST).path("/graphql").json_body_partial(
+                json!({
+                    "variables": {
+                        "owners": [owner],
+                        "tags": [{ "name": "App-Name", "values": ["arweave-rs"] }],
+                        "first": 1
+                    }
+                })
+                .to_string(),
+            );
+            then.status(200).json_body(json!({
+                "data": { "transactions": {
+                    "pageInfo": { "hasNextPage": true },
+                    "edges": [{
+                        "cursor": "cursor1",
+                        "node": {
+                            "id": id, "anchor": "", "signature": "sig", "recipient": "",
+                            "owner": { "address": owner, "key": "key" },
+                            "fee": { "winston": "10", "ar": "0.000000000010" },
+                            "quantity": { "winston": "0", "ar": "0.000000000000" },
+                            "data": { "size": "5", "type": "text/plain" },
+                            "tags": [{ "name": "App-Name", "value": "arweave-rs" }],
+                            "block": null,
+                            "bundledIn": { "id": bundle }
+                        }
+                    }]
+                }}
+            }));
+        });
+
+        let query = TransactionsQuery::new()
+            .owners(vec![owner])
+            .tag("App-Name", vec!["arweave-rs".to_string()])
+            .first(1);
+        let connection = block_on(client(&server).transactions(&query)).unwrap();
+
+        mock.assert();
+        assert!(connection.page_info.has_next_page);
+        let node = &connection.edges[0].node;
+        assert_eq!(node.owner.address, owner);
+        assert_eq!(connection.edges[0].cursor, "cursor1");
+        assert_eq!(node.data.content_type, Some("text/plain".to_string()));
+        assert_eq!(node.tags[0].value, "arweave-rs");
+        assert!(node.block.is_none());
+        assert_eq!(node.id, id);
+        assert_eq!(node.bundled_in.as_ref().unwrap().id, bundle);
+    }
+
+    #[test]
+    fn test_blocks_query() {
+        let (id, previous) = (BlockHash::new([1; 48]), BlockHash::new([2; 48]));
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST).path("/graphql");
+            then.status(200).json_body(json!({
+                "data": { "blocks": {
+                    "pageInfo": { "hasNextPage": false },
+                    "edges": [{
+                        "cursor": "c",
+                        "node": { "id": id, "timestamp": 1, "height": 100, "previous": previous }
+                    }]
+                }}
+            }));
+        });
+
+        let height = BlockHeight::new(100);
+        let query = BlocksQuery::new().height(Some(height), Some(height));
+        let connection = block_on(client(&server).blocks(&query)).unwrap();
+
+        mock.assert();
+        assert!(!connection.page_info.has_next_page);
+        let node = &connection.edges[0].node;
+        assert_eq!(node.id, id);
+        assert_eq!(node.height, height);
+        assert_eq!(node.previous, Some(previous));
+    }
+
+    #[test]
+    fn test_graphql_error() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST).path("/graphql");
+            then.status(400).json_body(json!({
+                "errors": [{ "message": "Syntax Error" }]
+            }));
+        });
+
+        let res = block_on(client(&server).blocks(&BlocksQuery::new()));
+        assert!(matches!(res, Err(Error::GraphqlError(msg)) if msg == "Syntax Error"));
+    }
+
+    fn block_page(ids: &[u64], has_next_page: bool) -> serde_json::Value {
+        let edges: Vec<serde_json::Value> = ids
+            .iter()
+            .map(|height| {
+                json!({
+                    "cursor": format!("c{}", height),
+                    "node": { "id": BlockHash::new([*height as u8; 48]), "timestamp": 1, "height": height, "previous": "" }
+                })
+            })
+            .collect();
+        json!({ "data": { "blocks": {
+            "pageInfo": { "hasNextPage": has_next_page },
+            "edges": edges
+        }}})
+    }
+
+    #[test]
+    fn test_blocks_stream() {
+        let server = MockServer::start();
+        let first_page = server.mock(|when, then| {
+            when.method(POST).path("/graphql").matches(|req| {
+                !String::from_utf8_lossy(req.body.as_ref().unwrap()).contains(r#""after":"#)
+            });
+            then.status(200).json_body(block_page(&[1, 2], true));
+        });
+        let second_page = server.mock(|when, then| {
+            when.method(POST)
+                .path("/graphql")
+                .body_contains(r#""after":"c2""#);
+            then.status(200).json_body(block_page(&[3], false));
+        });
+
+        let client = client(&server);
+        let heights: Vec<u64> = block_on(
+            client
+                .blocks_stream(BlocksQuery::new().first(2))
+                .map_ok(|block| block.height.get())
+                .try_collect(),
+        )
+        .unwrap();
+
+        first_page.assert();
+        second_page.assert();
+        assert_eq!(heights, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_transactions_stream_error() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST).path("/graphql");
+            then.status(200)
+                .json_body(json!({ "errors": [{ "message": "timeout" }] }));
+        });
+
+        let client = client(&server);
+        let results: Vec<_> = block_on(
+            client
+                .transactions_stream(TransactionsQuery::new())
+                .collect(),
+        );
+        assert_eq!(results.len(), 1);
+        assert!(matches!(&results[0], Err(Error::GraphqlError(_))));
+    }
+}
@@ -0,0 +1,402 @@
+//! Client for Irys (formerly Bundlr) nodes, which bundle the data items posted to them into
+//! layer-1 transactions and sign a receipt promising inclusion before a deadline height.
+
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+use async_trait::async_trait;
+use reqwest::{
+    header::{HeaderValue, ACCEPT, CONTENT_TYPE},
+    StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::OnceCell;
+
+use crate::{
+    bundle::DataItem,
+    crypto::{
+        base64::Base64,
+        hash::{deep_hash, DeepHashItem},
+    },
+    currency::Winston,
+    dispatch::Bundler,
+    error::Error,
+    transport::{default_transport, HttpRequest, HttpTransport},
+    tx_id::TxId,
+    verify::verify,
+    Arweave,
+};
+
+pub const IRYS_NODE_URL: &str = "https://node1.irys.xyz/";
+
+/// Currency the upload is paid with, as named in the node endpoints.
+pub const DEFAULT_CURRENCY: &str = "arweave";
+
+/// Signed promise of a node to include a data item in a bundle before `deadline_height`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Receipt {
+    pub id: String,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    pub version: String,
+    /// Public key of the node.
+    pub public: Base64,
+    pub signature: Base64,
+    pub deadline_height: u64,
+}
+
+impl Receipt {
+    /// Data the node signs, the deep hash of the receipt fields.
+    pub fn signature_data(&self) -> [u8; 48] {
+        let fields = [
+            b"Bundlr".to_vec(),
+            self.version.as_bytes().to_vec(),
+            self.id.as_bytes().to_vec(),
+            self.deadline_height.to_string().into_bytes(),
+            self.timestamp.to_string().into_bytes(),
+        ];
+        deep_hash(DeepHashItem::from_children(
+            fields
+                .into_iter()
+                .map(|f| DeepHashItem::Blob(f.into()))
+                .collect(),
+        ))
+    }
+
+    /// Checks the receipt is signed by the node with public key `public`. The `public` field
+    /// of the receipt alone proves nothing, anyone can sign a receipt with their own key.
+    pub fn verify(&self, public: &Base64) -> Result<(), Error> {
+        if &self.public != public {
+            return Err(Error::InvalidReceipt(format!(
+                "{} is not signed by the node",
+                self.id
+            )));
+        }
+        verify(&public.0, &self.signature_data(), &self.signature.0)
+            .map_err(|_| Error::InvalidReceipt(format!("invalid signature for {}", self.id)))
+    }
+}
+
+#[derive(Deserialize)]
+struct Info {
+    addresses: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct Balance {
+    balance: String,
+}
+
+pub struct IrysClient {
+    transport: Arc<dyn HttpTransport>,
+    url: url::Url,
+    currency: String,
+    public_key: OnceCell<Base64>,
+}
+
+impl Default for IrysClient {
+    fn default() -> Self {
+        Self::new(url::Url::parse(IRYS_NODE_URL).unwrap()) //Checked unwrap
+    }
+}
+
+impl IrysClient {
+    pub fn new(url: url::Url) -> Self {
+        Self {
+            transport: default_transport(),
+            url,
+            currency: DEFAULT_CURRENCY.to_string(),
+            public_key: OnceCell::new(),
+        }
+    }
+
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Checks receipts against `public_key` instead of the key served by the node.
+    pub fn with_public_key(mut self, public_key: Base64) -> Self {
+        self.public_key = OnceCell::new_with(Some(public_key));
+        self
+    }
+
+    /// Pays uploads with `currency` instead of [`DEFAULT_CURRENCY`].
+    pub fn with_currency(mut self, currency: &str) -> Self {
+        self.currency = currency.to_string();
+        self
+    }
+
+    /// Price in atomic units of the currency to upload `data_size` bytes.
+    pub async fn get_price(&self, data_size: u64) -> Result<u128, Error> {
+        let url = self
+            .url
+            .join(&format!("price/{}/{}", self.currency, data_size))
+            .map_err(Error::UrlParseError)?;
+        let res = self.transport.send(HttpRequest::get(url)).await?;
+        if res.status != StatusCode::OK {
+            return Err(Error::from_response(&res));
+        }
+        res.text()?
+            .trim()
+            .parse()
+            .map_err(|_| Error::InvalidAmount(String::from_utf8_lossy(&res.body).into_owned()))
+    }
+
+    /// Public key the node signs receipts with, as pinned with [`IrysClient::with_public_key`]
+    /// or served by the node's `public` endpoint on first use.
+    pub async fn get_public_key(&self) -> Result<&Base64, Error> {
+        self.public_key
+            .get_or_try_init(|| async {
+                let url = self.url.join("public").map_err(Error::UrlParseError)?;
+                let res = self.transport.send(HttpRequest::get(url)).await?;
+                if res.status != StatusCode::OK {
+                    return Err(Error::from_response(&res));
+                }
+                Base64::from_str(res.text()?.trim()).map_err(Error::Base64DecodeError)
+            })
+            .await
+    }
+
+    /// Address of the node wallet for the client currency, to which funding transfers are
+    /// sent.
+    pub async fn get_address(&self) -> Result<String, Error> {
+        let url = self.url.join("info").map_err(Error::UrlParseError)?;
+        let res = self.transport.send(HttpRequest::get(url)).await?;
+        if res.status != StatusCode::OK {
+            return Err(Error::from_response(&res));
+        }
+        let mut info: Info = res.json()?;
+        info.addresses.remove(&self.currency).ok_or_else(|| {
+            Error::TransactionInfoError(format!("node does not accept {}", self.currency))
+        })
+    }
+
+    /// Credit of `address` on the node, in atomic units of the currency.
+    pub async fn get_bundler_balance(&self, address: &str) -> Result<u128, Error> {
+        let mut url = self
+            .url
+            .join(&format!("account/balance/{}", self.currency))
+            .map_err(Error::UrlParseError)?;
+        url.query_pairs_mut().append_pair("address", address);
+        let res = self.transport.send(HttpRequest::get(url)).await?;
+        if res.status != StatusCode::OK {
+            return Err(Error::from_response(&res));
+        }
+        let balance: Balance = res.json()?;
+        u128::from_str(&balance.balance).map_err(|_| Error::InvalidAmount(balance.balance))
+    }
+
+    /// Transfers `amount` from the wallet of `arweave` to the node and submits the transfer,
+    /// crediting the wallet once the transfer is confirmed. Only `arweave` balances can be
+    /// funded.
+    pub async fn fund(&self, arweave: &Arweave, amount: Winston) -> Result<(TxId, u64), Error> {
+        if self.currency != DEFAULT_CURRENCY {
+            return Err(Error::InvalidAmount(format!(
+                "cannot fund a {} balance with AR",
+                self.currency
+            )));
+        }
+        let target = self.get_address().await?.parse()?;
+        let (id, reward) = arweave.transfer(target, amount).await?;
+
+        let url = self
+            .url
+            .join(&format!("account/balance/{}", self.currency))
+            .map_err(Error::UrlParseError)?;
+        let request = HttpRequest::post(url).json(&serde_json::json!({ "tx_id": id }))?;
+        let res = self.transport.send(request).await?;
+        if !res.status.is_success() {
+            return Err(Error::from_response(&res));
+        }
+        Ok((id, reward))
+    }
+
+    /// Posts a signed data item, returning the receipt of the node once checked against the
+    /// item and the node key, see [`IrysClient::get_public_key`].
+    pub async fn post_data_item(&self, data_item: &DataItem) -> Result<Receipt, Error> {
+        if data_item.signature.is_empty() {
+            return Err(Error::UnsignedTransaction);
+        }
+        let url = self
+            .url
+            .join(&format!("tx/{}", self.currency))
+            .map_err(Error::UrlParseError)?;
+        let request = HttpRequest::post(url)
+            .header(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/octet-stream"),
+            )
+            .header(ACCEPT, HeaderValue::from_static("application/json"))
+            .body(data_item.to_bytes()?);
+        let res = self.transport.send(request).await?;
+        if res.status != StatusCode::OK {
+            return Err(Error::from_response(&res));
+        }
+
+        let receipt: Receipt = res.json()?;
+        if receipt.id != data_item.id.to_string() {
+            return Err(Error::InvalidReceipt(format!(
+                "receipt for {} instead of {}",
+                receipt.id, data_item.id
+            )));
+        }
+        receipt.verify(self.get_public_key().await?)?;
+        Ok(receipt)
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Bundler for IrysClient {
+    async fn post(&self, data_item: &DataItem) -> Result<String, Error> {
+        Ok(self.post_data_item(data_item).await?.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use tokio_test::block_on;
+
+    use crate::{
+        bundle::DataItem,
+        crypto::{base64::Base64, Provider},
+        currency::Winston,
+        error::Error,
+        tx_id::TxId,
+        Arweave, ArweaveSigner,
+    };
+
+    use super::{IrysClient, Receipt};
+
+    fn signed_receipt(provider: &Provider, id: &str) -> Receipt {
+        let mut receipt = Receipt {
+            id: id.to_string(),
+            timestamp: 1_700_000_000_000,
+            version: "1.0.0".to_string(),
+            public: provider.public_key(),
+            signature: Base64::empty(),
+            deadline_height: 1_300_000,
+        };
+        receipt.signature = provider.sign(&receipt.signature_data()).unwrap();
+        receipt
+    }
+
+    #[test]
+    fn test_post_data_item() -> Result<(), Error> {
+        let signer = ArweaveSigner::from_keypair_path(PathBuf::from("res/test_wallet_4096.json"))?;
+        let data_item = signer.sign_data_item(DataItem::new(
+            Base64::empty(),
+            Base64::empty(),
+            vec![],
+            b"hello".to_vec(),
+        )?)?;
+        let node = Provider::from_keypair_path(PathBuf::from("res/test_wallet.json"))?;
+        let receipt = signed_receipt(&node, &data_item.id.to_string());
+        let server = MockServer::start();
+        let public = server.mock(|when, then| {
+            when.method(GET).path("/public");
+            then.status(200).body(node.public_key().to_string());
+        });
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx/arweave")
+                .header("content-type", "application/octet-stream")
+                .body(String::from_utf8_lossy(&data_item.to_bytes().unwrap()));
+            then.status(200)
+                .json_body(serde_json::to_value(&receipt).unwrap());
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/price/arweave/1024");
+            then.status(200).body("2000000");
+        });
+        let client = IrysClient::new(url::Url::parse(&server.url("/")).unwrap());
+
+        assert_eq!(block_on(client.post_data_item(&data_item))?, receipt);
+        assert_eq!(block_on(client.post_data_item(&data_item))?, receipt);
+        mock.assert_hits(2);
+        public.assert_hits(1);
+        assert_eq!(block_on(client.get_price(1024))?, 2_000_000);
+
+        let mut forged = receipt.clone();
+        forged.deadline_height += 1;
+        assert!(matches!(
+            forged.verify(&node.public_key()),
+            Err(Error::InvalidReceipt(_))
+        ));
+        // Receipts signed by another key, even a valid signature, are rejected.
+        let self_signed = signed_receipt(signer.get_provider(), &data_item.id.to_string());
+        assert!(matches!(
+            self_signed.verify(&node.public_key()),
+            Err(Error::InvalidReceipt(_))
+        ));
+        let pinned = IrysClient::new(url::Url::parse(&server.url("/")).unwrap())
+            .with_public_key(signer.get_public_key());
+        assert!(matches!(
+            block_on(pinned.post_data_item(&data_item)),
+            Err(Error::InvalidReceipt(_))
+        ));
+        public.assert_hits(1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fund() -> Result<(), Error> {
+        let node_address = Base64(vec![9; 32]);
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200).json_body(serde_json::json!({
+                "addresses": { "arweave": node_address.to_string() }
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(Base64(vec![1; 48]).to_string());
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/price/0/{}", node_address));
+            then.status(200).body("250000");
+        });
+        let transfer = server.mock(|when, then| {
+            when.method(POST).path("/tx").json_body_partial(format!(
+                r#"{{"target":"{}","quantity":"1000"}}"#,
+                node_address
+            ));
+            then.status(200);
+        });
+        let submit = server.mock(|when, then| {
+            when.method(POST)
+                .path("/account/balance/arweave")
+                .body_contains("tx_id");
+            then.status(200);
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/account/balance/arweave")
+                .query_param("address", "owner");
+            then.status(200).body(r#"{"balance":"1000"}"#);
+        });
+        let url = url::Url::parse(&server.url("/")).unwrap();
+        let arweave =
+            Arweave::from_keypair_path(PathBuf::from("res/test_wallet.json"), url.clone())?;
+        let client = IrysClient::new(url);
+
+        let (id, _) = block_on(client.fund(&arweave, Winston(1000)))?;
+        transfer.assert();
+        submit.assert();
+        assert_ne!(id, TxId::default());
+        assert_eq!(block_on(client.get_bundler_balance("owner"))?, 1000);
+        assert!(matches!(
+            block_on(client.with_currency("solana").fund(&arweave, Winston(1000))),
+            Err(Error::InvalidAmount(_))
+        ));
+        Ok(())
+    }
+}
@@ -1,33 +1,93 @@
 use jsonwebkey as jwk;
-use std::{fs, path::PathBuf, str::FromStr};
+use std::{
+    fs, mem,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
 
-use consts::MAX_TX_DATA;
-use crypto::base64::Base64;
+use address::Address;
+use arns::ArnsClient;
+use block::{BlockHash, BlockHeight};
+use bundle::{Bundle, DataItem};
+use cache::CachingTransport;
+use content_type::ContentTypes;
+use crypto::{base64::Base64, sign::SignatureType};
+use currency::{Currency, Winston};
+use dispatch::{Bundler, DispatchKind, DispatchResult, DEFAULT_DISPATCH_THRESHOLD};
 use error::Error;
-use futures::{stream, Stream, StreamExt};
+use futures::Stream;
+use gateway::{GatewayPool, GatewayTransport};
+use graphql::{
+    BlockNode, BlocksQuery, Connection, GraphqlClient, TransactionNode, TransactionsQuery,
+};
+use manifest::{collect_files, DirectoryReport, FileReport, Manifest, UploadMode};
+use metrics::MetricsSink;
+use network::{Network, NetworkInfoClient};
 use pretend::StatusCode;
-use reqwest::Client;
+use rate_limit::{RateLimit, RateLimitedTransport};
+use retry::RetryPolicy;
 use serde::{Deserialize, Serialize};
-use transaction::{
-    client::TxClient,
-    tags::{FromUtf8Strs, Tag},
-    Tx,
-};
-use types::TxStatus;
-use upload::Uploader;
+use tokio::io::AsyncWrite;
+use transaction::{client::TxClient, tags::Tag, TransactionBuilder, Tx};
+use transport::{HttpTransport, ProxyConfig, ReqwestTransport, Timeouts};
+use tx_id::TxId;
+use types::{Block, NetworkInfo, TxStatus};
+use upload::{ProgressCallback, UploadEvent, UploadState, Uploader};
 use verify::{verify, verify_transaction};
+use wallet::WalletInfoClient;
 
+pub mod address;
+#[cfg(feature = "ao")]
+pub mod ao;
+#[cfg(feature = "arfs")]
+pub mod arfs;
+pub mod ario;
+pub mod arns;
+pub mod block;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod bundle;
+pub mod cache;
 pub mod client;
 pub mod consts;
+pub mod content_type;
 pub mod crypto;
 pub mod currency;
+pub mod dispatch;
+#[cfg(feature = "encrypt")]
+pub mod encrypt;
 pub mod error;
+pub mod gateway;
+pub mod graphql;
+#[cfg(feature = "irys")]
+pub mod irys;
+pub mod manifest;
+pub mod metrics;
+#[cfg(feature = "mmap")]
+pub mod mmap;
 pub mod network;
+#[cfg(feature = "oracle")]
+pub mod oracle;
+pub mod rate_limit;
+pub mod retry;
+mod runtime;
 pub mod signer;
+#[cfg(feature = "smartweave")]
+pub mod smartweave;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod transaction;
+pub mod transport;
+#[cfg(feature = "turbo")]
+pub mod turbo;
+pub mod tx_id;
 pub mod types;
+pub mod udl;
 pub mod upload;
-mod verify;
+pub mod verify;
 pub mod wallet;
 
 pub use signer::ArweaveSigner;
@@ -47,13 +107,44 @@ pub struct Arweave {
     pub signer: Option<ArweaveSigner>,
     tx_client: TxClient,
     uploader: Uploader,
+    graphql_client: GraphqlClient,
+    wallet_client: WalletInfoClient,
+    network_client: NetworkInfoClient,
+    arns_client: ArnsClient,
+    fee_multiplier: f64,
+    operation_timeout: Option<Duration>,
+    bundler: Option<Arc<dyn Bundler>>,
+    dispatch_threshold: usize,
+    content_types: ContentTypes,
+    network: Network,
+    transport: Arc<dyn HttpTransport>,
+    gateways: Option<Arc<GatewayPool>>,
+    metrics: Option<Arc<dyn MetricsSink>>,
 }
 
 #[derive(Default)]
 pub struct ArweaveBuilder {
+    network: Option<Network>,
     base_url: Option<url::Url>,
     keypair_path: Option<PathBuf>,
     jwk: Option<jwk::JsonWebKey>,
+    chunks_concurrency: Option<usize>,
+    retry_policy: Option<RetryPolicy>,
+    progress: Option<ProgressCallback>,
+    gateways: Vec<url::Url>,
+    fee_multiplier: Option<f64>,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    transport: Option<Arc<dyn HttpTransport>>,
+    proxy: Option<ProxyConfig>,
+    rate_limit: Option<RateLimit>,
+    timeouts: Timeouts,
+    operation_timeout: Option<Duration>,
+    cache_capacity: Option<usize>,
+    bundler: Option<Arc<dyn Bundler>>,
+    dispatch_threshold: Option<usize>,
+    content_types: ContentTypes,
+    #[cfg(feature = "smartweave")]
+    arns_registry: Option<url::Url>,
 }
 
 impl ArweaveBuilder {
@@ -61,6 +152,14 @@ impl ArweaveBuilder {
         Default::default()
     }
 
+    /// Network whose gateway, price source and protocol parameters are used, defaults to
+    /// [`Network::Mainnet`]. A base url or gateways set on the builder are used instead of
+    /// the gateway of the network.
+    pub fn network(mut self, network: Network) -> ArweaveBuilder {
+        self.network = Some(network);
+        self
+    }
+
     pub fn base_url(mut self, url: url::Url) -> ArweaveBuilder {
         self.base_url = Some(url);
         self
@@ -76,59 +175,251 @@ impl ArweaveBuilder {
         self
     }
 
+    /// Maximum number of chunks posted simultaneously, defaults to [`consts::CHUNKS_CONCURRENCY`].
+    pub fn chunks_concurrency(mut self, chunks_concurrency: usize) -> ArweaveBuilder {
+        self.chunks_concurrency = Some(chunks_concurrency);
+        self
+    }
+
+    /// Policy applied to chunk posting, transaction submission and reads.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> ArweaveBuilder {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Gateways to fail over between. The first one is used as base url unless one is set.
+    pub fn gateways(mut self, gateways: Vec<url::Url>) -> ArweaveBuilder {
+        self.gateways = gateways;
+        self
+    }
+
+    /// Scales the prices quoted by the node before signing, e.g. `1.2` to bid 20% above the
+    /// minimum reward during congestion. Applies to estimated rewards only: explicit fees
+    /// are used as given. Building fails unless it is a finite number of at least `1.0`.
+    pub fn fee_multiplier(mut self, fee_multiplier: f64) -> ArweaveBuilder {
+        self.fee_multiplier = Some(fee_multiplier);
+        self
+    }
+
+    /// Reports bytes uploaded, retries, request latencies and gateway failovers to `metrics`.
+    pub fn metrics(mut self, metrics: Arc<dyn MetricsSink>) -> ArweaveBuilder {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Sends every request through `transport` instead of the default reqwest client.
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> ArweaveBuilder {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Sends requests through `proxy` rather than the one set in the environment. Ignored
+    /// if a transport is set.
+    pub fn proxy(mut self, proxy: ProxyConfig) -> ArweaveBuilder {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Time allowed to connect to a gateway. Ignored if a transport is set.
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> ArweaveBuilder {
+        self.timeouts.connect = Some(connect_timeout);
+        self
+    }
+
+    /// Time allowed for each request, including reading the response. Ignored if a
+    /// transport is set.
+    pub fn request_timeout(mut self, request_timeout: Duration) -> ArweaveBuilder {
+        self.timeouts.request = Some(request_timeout);
+        self
+    }
+
+    /// Deadline of every upload, retries included: posting transactions, the `upload_*`
+    /// functions and resumable posts fail with [`Error::OperationTimeout`] once it elapses.
+    pub fn operation_timeout(mut self, operation_timeout: Duration) -> ArweaveBuilder {
+        self.operation_timeout = Some(operation_timeout);
+        self
+    }
+
+    /// Caches up to `capacity` GET responses, revalidated with their `ETag`.
+    pub fn cache(mut self, capacity: usize) -> ArweaveBuilder {
+        self.cache_capacity = Some(capacity);
+        self
+    }
+
+    /// Limits the requests sent to each gateway, including transaction and chunk posts and
+    /// GraphQL queries.
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> ArweaveBuilder {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Bundler that [`Arweave::dispatch`] posts small uploads to.
+    pub fn bundler(mut self, bundler: Arc<dyn Bundler>) -> ArweaveBuilder {
+        self.bundler = Some(bundler);
+        self
+    }
+
+    /// Largest upload dispatched to the bundler, defaults to
+    /// [`dispatch::DEFAULT_DISPATCH_THRESHOLD`].
+    pub fn dispatch_threshold(mut self, dispatch_threshold: usize) -> ArweaveBuilder {
+        self.dispatch_threshold = Some(dispatch_threshold);
+        self
+    }
+
+    /// Content types the files of [`Arweave::upload_file_from_path`] and
+    /// [`Arweave::upload_directory`] are tagged with.
+    pub fn content_types(mut self, content_types: ContentTypes) -> ArweaveBuilder {
+        self.content_types = content_types;
+        self
+    }
+
+    /// Resolves ArNS names the gateway cannot resolve from the registry contract state
+    /// evaluated by the Warp gateway `warp_url`.
+    #[cfg(feature = "smartweave")]
+    pub fn arns_registry(mut self, warp_url: url::Url) -> ArweaveBuilder {
+        self.arns_registry = Some(warp_url);
+        self
+    }
+
+    /// Reports the progress of chunked uploads to `progress`.
+    pub fn on_progress(mut self, progress: ProgressCallback) -> ArweaveBuilder {
+        self.progress = Some(progress);
+        self
+    }
+
     pub fn build(self) -> Result<Arweave, Error> {
+        let network = self.network.unwrap_or_default();
         let base_url = self
             .base_url
-            .unwrap_or_else(|| url::Url::from_str(consts::ARWEAVE_BASE_URL).unwrap()); //Checked unwrap
+            .or_else(|| self.gateways.first().cloned())
+            .unwrap_or_else(|| network.url());
 
         let signer = match self.keypair_path {
             Some(p) => Some(ArweaveSigner::from_keypair_path(p)?),
-            None => match self.jwk {
-                Some(jwk) => Some(ArweaveSigner::from_jwk(jwk)),
-                None => todo!(),
-            },
+            None => self.jwk.map(ArweaveSigner::from_jwk).transpose()?,
+        };
+
+        if let Some(fee_multiplier) = self.fee_multiplier {
+            transaction::builder::check_fee_multiplier(fee_multiplier)?;
+        }
+        let retry_policy = self.retry_policy.unwrap_or_default();
+        let mut transport: Arc<dyn HttpTransport> = match self.transport {
+            Some(transport) => transport,
+            None if self.proxy.is_some() || self.timeouts != Timeouts::default() => Arc::new(
+                ReqwestTransport::configured(self.proxy.as_ref(), self.timeouts)?,
+            ),
+            None => transport::default_transport(),
         };
+        if let Some(rate_limit) = self.rate_limit {
+            transport = Arc::new(RateLimitedTransport::new(transport, rate_limit));
+        }
+        if let Some(capacity) = self.cache_capacity {
+            transport = Arc::new(CachingTransport::new(transport, capacity));
+        }
+        let gateways = match self.gateways.is_empty() {
+            true => None,
+            false => {
+                let mut gateways = GatewayPool::new(self.gateways)?;
+                if let Some(metrics) = &self.metrics {
+                    gateways = gateways.with_metrics(metrics.clone());
+                }
+                Some(Arc::new(gateways))
+            }
+        };
+        // Clients without failover of their own reach the pool through the transport.
+        let routed: Arc<dyn HttpTransport> = match &gateways {
+            Some(gateways) => Arc::new(GatewayTransport::new(
+                transport.clone(),
+                base_url.clone(),
+                gateways.clone(),
+            )),
+            None => transport.clone(),
+        };
+        let mut uploader = Uploader::new(base_url.clone())
+            .with_transport(transport.clone())
+            .with_concurrency(
+                self.chunks_concurrency
+                    .unwrap_or(consts::CHUNKS_CONCURRENCY),
+            )
+            .with_retry_policy(retry_policy);
+        if let Some(progress) = self.progress {
+            uploader = uploader.with_progress(progress);
+        }
+        let arns_client = ArnsClient::new(base_url.clone()).with_transport(routed.clone());
+        #[cfg(feature = "smartweave")]
+        let arns_client = match self.arns_registry {
+            Some(warp_url) => {
+                let registry =
+                    Base64::from_str(arns::ARNS_REGISTRY).map_err(Error::Base64DecodeError)?;
+                let warp = smartweave::WarpClient::new(warp_url).with_transport(routed.clone());
+                arns_client.with_registry(warp, registry)
+            }
+            None => arns_client,
+        };
+        let mut tx_client = TxClient::new(reqwest::Client::new(), base_url.clone())?
+            .with_transport(transport.clone())
+            .with_retry_policy(retry_policy);
+        if let Some(metrics) = &self.metrics {
+            uploader = uploader.with_metrics(metrics.clone());
+            tx_client = tx_client.with_metrics(metrics.clone());
+        }
+        if let Some(gateways) = &gateways {
+            tx_client = tx_client.with_gateways(gateways.clone());
+            uploader = uploader.with_gateways(gateways.clone());
+        }
 
         Ok(Arweave {
             signer,
+            tx_client,
+            uploader,
+            graphql_client: GraphqlClient::new(reqwest::Client::new(), base_url.clone())?
+                .with_transport(routed.clone()),
+            wallet_client: WalletInfoClient::with_transport(base_url.clone(), routed.clone()),
+            network_client: NetworkInfoClient::with_transport(base_url.clone(), routed),
+            arns_client,
+            fee_multiplier: self.fee_multiplier.unwrap_or(1.0),
+            operation_timeout: self.operation_timeout,
+            bundler: self.bundler,
+            dispatch_threshold: self
+                .dispatch_threshold
+                .unwrap_or(DEFAULT_DISPATCH_THRESHOLD),
+            content_types: self.content_types,
+            network,
             base_url,
-            tx_client: Default::default(),
-            uploader: Default::default(),
+            transport,
+            gateways,
+            metrics: self.metrics,
         })
     }
 }
 
 impl Arweave {
+    /// Shorthand for an [`ArweaveBuilder`] with only the keypair and base url set.
     pub fn from_keypair_path(keypair_path: PathBuf, base_url: url::Url) -> Result<Arweave, Error> {
-        let signer = Some(ArweaveSigner::from_keypair_path(keypair_path)?);
-        let tx_client = TxClient::new(reqwest::Client::new(), base_url.clone())?;
-        let uploader = Uploader::new(base_url.clone());
-        let arweave = Arweave {
-            base_url,
-            signer,
-            tx_client,
-            uploader,
-        };
-        Ok(arweave)
+        ArweaveBuilder::new()
+            .keypair_path(keypair_path)
+            .base_url(base_url)
+            .build()
     }
 
+    /// Creates a transaction of the signer, sending `quantity` to `target` if any.
     pub async fn create_transaction(
         &self,
-        target: Base64,
+        target: Option<Address>,
         other_tags: Vec<Tag<Base64>>,
         data: Vec<u8>,
         quantity: u128,
         fee: u64,
         auto_content_tag: bool,
     ) -> Result<Tx, Error> {
-        let last_tx = self.get_last_tx().await?;
+        let last_tx = self.get_tx_anchor().await?;
         let signer = match &self.signer {
             Some(s) => s,
-            None => return Err(Error::NoneError("signer".to_owned())),
+            None => return Err(Error::MissingSigner),
         };
         Tx::new(
             signer.get_provider(),
-            target,
+            target.map_or_else(Base64::empty, |target| target.to_base64()),
             data,
             quantity,
             fee,
@@ -138,18 +429,40 @@ impl Arweave {
         )
     }
 
+    /// Signs `transaction` once checked with [`Tx::validate_for`] the protocol parameters of
+    /// the network.
     pub fn sign_transaction(&self, transaction: Tx) -> Result<Tx, Error> {
         let signer = match &self.signer {
             Some(s) => s,
-            None => return Err(Error::NoneError("signer".to_owned())),
+            None => return Err(Error::MissingSigner),
+        };
+        transaction.sign_for(signer.get_provider(), &self.network.params())
+    }
+
+    /// Creates a data item with the signer as owner and signs it.
+    pub fn create_data_item(
+        &self,
+        target: Base64,
+        anchor: Base64,
+        tags: Vec<Tag<Base64>>,
+        data: Vec<u8>,
+    ) -> Result<DataItem, Error> {
+        let data_item = DataItem::new(target, anchor, tags, data)?;
+        self.sign_data_item(data_item)
+    }
+
+    pub fn sign_data_item(&self, data_item: DataItem) -> Result<DataItem, Error> {
+        let signer = match &self.signer {
+            Some(s) => s,
+            None => return Err(Error::MissingSigner),
         };
-        signer.sign_transaction(transaction)
+        signer.sign_data_item(data_item)
     }
 
     pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Error> {
         let signer = match &self.signer {
             Some(s) => s,
-            None => return Err(Error::NoneError("signer".to_owned())),
+            None => return Err(Error::MissingSigner),
         };
         Ok(signer.sign(message)?.0)
     }
@@ -162,47 +475,318 @@ impl Arweave {
         verify(pub_key, message, signature)
     }
 
-    pub async fn post_transaction(&self, signed_transaction: Tx) -> Result<(String, u64), Error> {
-        if signed_transaction.data.0.len() > MAX_TX_DATA as usize {
-            self.post_transaction_chunks(signed_transaction, 100).await
-        } else {
-            self.tx_client
-                .post_transaction(&signed_transaction)
+    pub async fn post_transaction(&self, signed_transaction: Tx) -> Result<(TxId, u64), Error> {
+        self.with_deadline(async {
+            if signed_transaction.data.0.len() > self.network.params().max_tx_data as usize {
+                self.post_transaction_chunks(signed_transaction).await
+            } else {
+                self.tx_client.post_transaction(&signed_transaction).await
+            }
+        })
+        .await
+    }
+
+    /// Runs `operation` within the operation timeout, if any.
+    async fn with_deadline<T>(
+        &self,
+        operation: impl std::future::Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        match self.operation_timeout {
+            Some(deadline) => runtime::timeout(deadline, operation)
                 .await
-                .map(|(id, reward)| (id.to_string(), reward))
+                .unwrap_or(Err(Error::OperationTimeout(deadline))),
+            None => operation.await,
         }
     }
 
-    async fn get_last_tx(&self) -> Result<Base64, Error> {
-        self.tx_client.get_last_tx().await
+    /// Fetches a recent anchor to be used as the `last_tx` of a new transaction.
+    pub async fn get_tx_anchor(&self) -> Result<Base64, Error> {
+        self.tx_client.get_tx_anchor().await
     }
 
-    pub async fn get_fee(&self, target: Base64, data: Vec<u8>) -> Result<u64, Error> {
+    pub async fn get_fee(&self, target: Option<Address>, data: Vec<u8>) -> Result<u64, Error> {
         self.tx_client.get_fee(target, data).await
     }
 
-    pub async fn get_tx(&self, id: Base64) -> Result<(StatusCode, Option<Tx>), Error> {
+    pub async fn get_price(&self, data_size: u64, target: Option<&Address>) -> Result<u64, Error> {
+        self.tx_client.get_price(data_size, target).await
+    }
+
+    /// Populates the builder's reward from the configured node, scaled by the fee multiplier
+    /// unless the builder sets its own.
+    pub async fn estimate_reward(
+        &self,
+        builder: TransactionBuilder,
+    ) -> Result<TransactionBuilder, Error> {
+        builder
+            .default_fee_multiplier(self.fee_multiplier)
+            .estimate_reward(&self.tx_client)
+            .await
+    }
+
+    /// Price quoted by the node scaled by the fee multiplier, used as reward of the
+    /// transactions this client creates on its own.
+    async fn get_reward(&self, data_size: u64, target: Option<&Address>) -> Result<u64, Error> {
+        let price = self.get_price(data_size, target).await?;
+        transaction::builder::apply_fee_multiplier(price, self.fee_multiplier)
+    }
+
+    /// Balance of `address` in winstons.
+    pub async fn get_balance(&self, address: &Address) -> Result<Currency, Error> {
+        self.wallet_client.get_balance(address).await
+    }
+
+    /// Id of the last transaction sent from `address`, empty if it has none.
+    pub async fn get_last_tx(&self, address: &Address) -> Result<Base64, Error> {
+        self.wallet_client.get_last_tx(address).await
+    }
+
+    /// Health checks the configured gateways every `interval`, see [`GatewayPool::monitor`].
+    /// `None` without gateways. The future runs forever and is meant to be spawned.
+    pub fn gateway_monitor(
+        &self,
+        interval: Duration,
+    ) -> Option<impl std::future::Future<Output = ()>> {
+        let gateways = self.gateways.clone()?;
+        let transport = self.transport.clone();
+        Some(async move { gateways.monitor(transport.as_ref(), interval).await })
+    }
+
+    /// Network the client is configured for.
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    /// Oracle reading the AR price from the price source of the network, `None` if its
+    /// tokens have no price.
+    #[cfg(feature = "oracle")]
+    pub fn price_oracle(&self) -> Option<oracle::PriceOracle> {
+        self.network.price_source().map(|source| {
+            oracle::PriceOracle::new(source).with_transport(self.tx_client.transport())
+        })
+    }
+
+    /// Client of the ArLocal endpoints of the configured node.
+    #[cfg(feature = "testing")]
+    fn arlocal(&self) -> testing::ArLocal {
+        testing::ArLocal::new(self.base_url.clone()).with_transport(self.tx_client.transport())
+    }
+
+    /// Mines `blocks` blocks on ArLocal or a [`testing::MockGateway`], confirming the pending
+    /// transactions.
+    #[cfg(feature = "testing")]
+    pub async fn mine(&self, blocks: u64) -> Result<(), Error> {
+        self.arlocal().mine(blocks).await
+    }
+
+    /// Credits `amount` to `address` on ArLocal or a [`testing::MockGateway`].
+    #[cfg(feature = "testing")]
+    pub async fn mint(&self, address: &Address, amount: Winston) -> Result<(), Error> {
+        self.arlocal().mint(address, amount).await
+    }
+
+    /// Information about the configured node and the state of the network.
+    pub async fn network_info(&self) -> Result<NetworkInfo, Error> {
+        self.network_client.network_info().await
+    }
+
+    /// Peers known to the configured node.
+    pub async fn get_peers(&self) -> Result<Vec<SocketAddr>, Error> {
+        self.network_client.peers().await
+    }
+
+    pub async fn get_block_by_height(&self, height: BlockHeight) -> Result<Block, Error> {
+        self.network_client.get_block_by_height(height).await
+    }
+
+    pub async fn get_block_by_hash(&self, indep_hash: &BlockHash) -> Result<Block, Error> {
+        self.network_client.get_block_by_hash(indep_hash).await
+    }
+
+    /// Directs transaction reads and submission and chunk seeding at up to `limit` peers of
+    /// the configured node, reached with `scheme` (nodes serve `http`), failing over to the
+    /// configured gateways or node. GraphQL, wallet and block queries are left to the
+    /// gateways, which peers do not replace. Returns the number of peers used.
+    pub async fn route_to_peers(&mut self, limit: usize, scheme: &str) -> Result<usize, Error> {
+        let mut urls: Vec<url::Url> = self
+            .get_peers()
+            .await?
+            .into_iter()
+            .take(limit)
+            .map(|peer| url::Url::parse(&format!("{}://{}/", scheme, peer)))
+            .collect::<Result<_, _>>()
+            .map_err(Error::UrlParseError)?;
+        let peers = urls.len();
+        match &self.gateways {
+            Some(gateways) => urls.extend(gateways.urls()),
+            None => urls.push(self.base_url.clone()),
+        }
+
+        let mut gateways = GatewayPool::new(urls)?;
+        if let Some(metrics) = &self.metrics {
+            gateways = gateways.with_metrics(metrics.clone());
+        }
+        let gateways = Arc::new(gateways);
+        self.tx_client = mem::take(&mut self.tx_client).with_gateways(gateways.clone());
+        self.uploader = mem::take(&mut self.uploader).with_gateways(gateways.clone());
+        self.gateways = Some(gateways);
+        Ok(peers)
+    }
+
+    pub async fn get_tx(&self, id: &TxId) -> Result<(StatusCode, Option<Tx>), Error> {
         self.tx_client.get_tx(id).await
     }
 
-    pub async fn get_tx_status(&self, id: Base64) -> Result<(StatusCode, Option<TxStatus>), Error> {
+    /// Downloads the data of transaction `id`, checking it against the data root of the
+    /// transaction header so that tampered data served by a gateway is rejected.
+    pub async fn get_verified_data(&self, id: &TxId) -> Result<Vec<u8>, Error> {
+        let tx = match self.tx_client.get_tx(id).await? {
+            (_, Some(tx)) => tx,
+            (_, None) => return Err(Error::TransactionInfoError("pending".to_string())),
+        };
+        verify_transaction(&tx)?;
+        if tx.format == 1 || tx.data_size == 0 {
+            tx.verify_data(&tx.data.0)?;
+            return Ok(tx.data.0);
+        }
+
+        let data = match self.tx_client.get_data(id).await {
+            Ok(data) if data.len() as u64 == tx.data_size => data,
+            _ => self.tx_client.get_data_from_chunks(id).await?,
+        };
+        tx.verify_data(&data)?;
+        Ok(data)
+    }
+
+    /// Downloads the data of data item `id` from the `raw` endpoint, checking it against the
+    /// owner, tags and signature of the item as indexed by the GraphQL endpoint.
+    pub async fn get_verified_data_item(&self, id: &TxId) -> Result<Vec<u8>, Error> {
+        let query = TransactionsQuery::new().ids(vec![*id]).first(1);
+        let node = self
+            .graphql_client
+            .transactions(&query)
+            .await?
+            .edges
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::InvalidDataItem(format!("{} is not indexed", id)))?
+            .node;
+        if node.bundled_in.is_none() {
+            return Err(Error::InvalidDataItem(format!("{} is not a data item", id)));
+        }
+        let decode = |s: &str| Base64::from_str(s).map_err(Error::Base64DecodeError);
+        let mut item = DataItem {
+            signature_type: SignatureType::Arweave,
+            id: id.to_base64(),
+            signature: decode(&node.signature)?,
+            owner: decode(&node.owner.key)?,
+            target: decode(&node.recipient)?,
+            anchor: decode(&node.anchor)?,
+            tags: node
+                .tags
+                .iter()
+                .map(|tag| Tag::new(&tag.name, &tag.value))
+                .collect(),
+            data: Base64(self.get_raw_data(id).await?),
+        };
+        // Ed25519 and Solana keys have the same length, so each matching type is tried.
+        let mut result = Err(Error::InvalidDataItem(format!("unknown owner of {}", id)));
+        for signature_type in [
+            SignatureType::Arweave,
+            SignatureType::Ed25519,
+            SignatureType::Ethereum,
+            SignatureType::Solana,
+        ] {
+            if signature_type.pub_length() == item.owner.0.len() {
+                item.signature_type = signature_type;
+                result = item.verify();
+                if result.is_ok() {
+                    break;
+                }
+            }
+        }
+        result.map(|_| item.data.0)
+    }
+
+    /// Data of transaction or data item `id` as served by the gateway, unverified.
+    pub async fn get_raw_data(&self, id: &TxId) -> Result<Vec<u8>, Error> {
+        self.tx_client.get_raw_data(id).await
+    }
+
+    /// Reads bytes `start..end` of the data of transaction `id` without downloading the rest.
+    pub async fn get_data_range(&self, id: &TxId, start: u64, end: u64) -> Result<Vec<u8>, Error> {
+        self.tx_client.get_data_range(id, start, end).await
+    }
+
+    /// Streams the data of transaction `id` into `writer`, returning the number of bytes
+    /// written.
+    pub async fn download_to<W>(&self, id: &TxId, writer: &mut W) -> Result<u64, Error>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        self.tx_client.download_to(id, writer).await
+    }
+
+    #[cfg(all(feature = "rt-tokio", not(target_arch = "wasm32")))]
+    pub async fn download_to_file(&self, id: &TxId, path: &Path) -> Result<u64, Error> {
+        self.tx_client.download_to_file(id, path).await
+    }
+
+    /// Id of the transaction the ArNS name `name` points to, resolved by the gateway.
+    pub async fn resolve_arns(&self, name: &str) -> Result<TxId, Error> {
+        self.arns_client.resolve(name).await
+    }
+
+    pub async fn get_tx_status(&self, id: &TxId) -> Result<(StatusCode, Option<TxStatus>), Error> {
         self.tx_client.get_tx_status(id).await
     }
 
+    /// Polls the status of `id` until it has at least `min_confirmations`.
+    pub async fn wait_for_confirmation(
+        &self,
+        id: &TxId,
+        min_confirmations: u64,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<TxStatus, Error> {
+        self.tx_client
+            .wait_for_confirmation(id, min_confirmations, poll_interval, timeout)
+            .await
+    }
+
+    pub async fn query_transactions(
+        &self,
+        query: &TransactionsQuery,
+    ) -> Result<Connection<TransactionNode>, Error> {
+        self.graphql_client.transactions(query).await
+    }
+
+    pub async fn query_blocks(&self, query: &BlocksQuery) -> Result<Connection<BlockNode>, Error> {
+        self.graphql_client.blocks(query).await
+    }
+
+    /// Streams all transactions matching `query`, transparently following pagination.
+    pub fn query_transactions_stream(
+        &self,
+        query: TransactionsQuery,
+    ) -> impl Stream<Item = Result<TransactionNode, Error>> + '_ {
+        self.graphql_client.transactions_stream(query)
+    }
+
     pub fn get_pub_key(&self) -> Result<String, Error> {
         let signer = match &self.signer {
             Some(s) => s,
-            None => return Err(Error::NoneError("signer".to_owned())),
+            None => return Err(Error::MissingSigner),
         };
         Ok(signer.keypair_modulus().to_string())
     }
 
-    pub fn get_wallet_address(&self) -> Result<String, Error> {
+    pub fn get_wallet_address(&self) -> Result<Address, Error> {
         let signer = match &self.signer {
             Some(s) => s,
-            None => return Err(Error::NoneError("signer".to_owned())),
+            None => return Err(Error::MissingSigner),
         };
-        Ok(signer.wallet_address().to_string())
+        Ok(signer.wallet_address())
     }
 
     pub async fn upload_file_from_path(
@@ -210,20 +794,13 @@ impl Arweave {
         file_path: PathBuf,
         additional_tags: Vec<Tag<Base64>>,
         fee: u64,
-    ) -> Result<(String, u64), Error> {
-        let mut auto_content_tag = true;
+    ) -> Result<(TxId, u64), Error> {
         let mut additional_tags = additional_tags;
+        let data = fs::read(&file_path)?;
+        self.content_types
+            .tag(&file_path, &data, &mut additional_tags);
 
-        if let Some(content_type) = mime_guess::from_path(file_path.clone()).first() {
-            auto_content_tag = false;
-            let content_tag: Tag<Base64> =
-                Tag::from_utf8_strs("Content-Type", content_type.as_ref())?;
-            additional_tags.push(content_tag);
-        }
-        let data = fs::read(file_path)?;
-
-        self.post_bytes(data, auto_content_tag, additional_tags, fee)
-            .await
+        self.post_bytes(data, false, additional_tags, fee).await
     }
 
     pub async fn upload_bytes<T: AsRef<[u8]>>(
@@ -232,28 +809,292 @@ impl Arweave {
         mime_type: Option<&str>,
         additional_tags: Vec<Tag<Base64>>,
         fee: u64,
-    ) -> Result<(String, u64), Error> {
+    ) -> Result<(TxId, u64), Error> {
         let mut auto_content_tag = true;
         let mut additional_tags = additional_tags;
         if let Some(mime_type) = mime_type {
-            let content_tag: Tag<Base64> = Tag::from_utf8_strs("Content-Type", mime_type)?;
-            additional_tags.push(content_tag);
+            additional_tags.push(Tag::content_type(mime_type));
             auto_content_tag = false;
         };
         self.post_bytes(buffer, auto_content_tag, additional_tags, fee)
             .await
     }
 
+    /// Sends `quantity` to the wallet `target` in a transaction without data. The reward is
+    /// quoted by the node for the target, which includes the fee for wallets that do not
+    /// exist yet, and scaled by the fee multiplier.
+    pub async fn transfer(&self, target: Address, quantity: Winston) -> Result<(TxId, u64), Error> {
+        let fee = self.get_reward(0, Some(&target)).await?;
+        let transaction = self
+            .create_transaction(Some(target), vec![], vec![], quantity.as_u128(), fee, false)
+            .await?;
+        let signed_transaction = self.sign_transaction(transaction)?;
+        self.post_transaction(signed_transaction).await
+    }
+
+    /// Uploads `data` as a data item to the configured bundler if it is at most the dispatch
+    /// threshold, and as a transaction paying the estimated reward otherwise.
+    pub async fn dispatch(
+        &self,
+        data: Vec<u8>,
+        tags: Vec<Tag<Base64>>,
+    ) -> Result<DispatchResult, Error> {
+        match &self.bundler {
+            Some(bundler) if data.len() <= self.dispatch_threshold => {
+                let data_item =
+                    self.create_data_item(Base64::empty(), Base64::empty(), tags, data)?;
+                let id = self.with_deadline(bundler.post(&data_item)).await?;
+                Ok(DispatchResult {
+                    id,
+                    kind: DispatchKind::Bundled,
+                })
+            }
+            _ => {
+                let fee = self.get_reward(data.len() as u64, None).await?;
+                let auto_content_tag = !tags
+                    .iter()
+                    .any(|tag| tag.has_name(transaction::tags::CONTENT_TYPE));
+                let (id, reward) = self.post_bytes(data, auto_content_tag, tags, fee).await?;
+                Ok(DispatchResult {
+                    id: id.to_string(),
+                    kind: DispatchKind::Transaction { reward },
+                })
+            }
+        }
+    }
+
+    /// Posts `interaction` as a layer-1 transaction without data, paying the estimated
+    /// reward.
+    #[cfg(feature = "smartweave")]
+    pub async fn write_interaction(
+        &self,
+        interaction: &smartweave::Interaction,
+    ) -> Result<(TxId, u64), Error> {
+        let fee = self.get_reward(0, None).await?;
+        self.post_bytes(vec![], false, interaction.to_tags()?, fee)
+            .await
+    }
+
+    /// Mints `asset`, posting it as a data item to the configured bundler or as a layer-1
+    /// transaction paying the estimated reward without one. Returns the asset id.
+    #[cfg(feature = "smartweave")]
+    pub async fn mint_atomic_asset(
+        &self,
+        asset: &smartweave::AtomicAsset,
+    ) -> Result<String, Error> {
+        match &self.bundler {
+            Some(bundler) => {
+                let data_item = self.sign_data_item(asset.to_data_item()?)?;
+                self.with_deadline(bundler.post(&data_item)).await
+            }
+            None => {
+                let fee = self.get_reward(asset.data.len() as u64, None).await?;
+                let (id, _) = self
+                    .post_bytes(&asset.data, false, asset.to_tags()?, fee)
+                    .await?;
+                Ok(id.to_string())
+            }
+        }
+    }
+
+    /// Posts a bundle as the data of a layer-1 transaction tagged as an ANS-104 bundle.
+    pub async fn upload_bundle(
+        &self,
+        bundle: &Bundle,
+        additional_tags: Vec<Tag<Base64>>,
+        fee: u64,
+    ) -> Result<(TxId, u64), Error> {
+        let mut tags = Bundle::tags();
+        tags.extend(additional_tags);
+        self.post_bytes(bundle.to_bytes()?, false, tags, fee).await
+    }
+
+    /// Uploads every file under `dir` and publishes a path manifest for them. A root
+    /// `index.html` is used as the manifest index. Fees are estimated from the node.
+    pub async fn upload_directory(
+        &self,
+        dir: &Path,
+        mode: UploadMode,
+    ) -> Result<DirectoryReport, Error> {
+        self.with_deadline(self.upload_directory_files(dir, mode))
+            .await
+    }
+
+    async fn upload_directory_files(
+        &self,
+        dir: &Path,
+        mode: UploadMode,
+    ) -> Result<DirectoryReport, Error> {
+        let mut manifest = Manifest::default();
+        let mut files = vec![];
+        let mut items = vec![];
+
+        for (path, file_path) in collect_files(dir)? {
+            let data = fs::read(&file_path)?;
+            let mut tags = vec![];
+            self.content_types.tag(&file_path, &data, &mut tags);
+            let size = data.len() as u64;
+            let (id, reward) = match mode {
+                UploadMode::Transactions => {
+                    let fee = self.get_reward(size, None).await?;
+                    let (id, reward) = self.post_bytes(data, false, tags, fee).await?;
+                    (id.to_base64(), reward)
+                }
+                UploadMode::DataItems => {
+                    let item =
+                        self.create_data_item(Base64::empty(), Base64::empty(), tags, data)?;
+                    let id = item.id.clone();
+                    items.push(item);
+                    (id, 0)
+                }
+            };
+            manifest.add_path(&path, id.clone());
+            files.push(FileReport {
+                path,
+                id,
+                size,
+                reward,
+            });
+        }
+        if manifest.get("index.html").is_some() {
+            manifest.set_index("index.html");
+        }
+
+        let (manifest_id, bundle_id) = match mode {
+            UploadMode::Transactions => {
+                let (id, _) = self.publish_manifest(&manifest).await?;
+                (id.to_base64(), None)
+            }
+            UploadMode::DataItems => {
+                let item = self.create_data_item(
+                    Base64::empty(),
+                    Base64::empty(),
+                    Manifest::tags(),
+                    manifest.to_json()?,
+                )?;
+                let manifest_id = item.id.clone();
+                items.push(item);
+                let bundle = Bundle::from_items(items)?;
+                let data = bundle.to_bytes()?;
+                let fee = self.get_reward(data.len() as u64, None).await?;
+                let (id, _) = self.post_bytes(data, false, Bundle::tags(), fee).await?;
+                (manifest_id, Some(id.to_base64()))
+            }
+        };
+
+        Ok(DirectoryReport {
+            manifest_id,
+            manifest,
+            bundle_id,
+            files,
+        })
+    }
+
+    /// Downloads the files of the manifest `id` under `dest_dir`, recreating its paths. Files
+    /// are fetched concurrently and verified, see [`Arweave::get_verified_data`], or
+    /// [`Arweave::get_verified_data_item`] for the files not found as transactions.
+    #[cfg(all(feature = "rt-tokio", not(target_arch = "wasm32")))]
+    pub async fn download_manifest(
+        &self,
+        id: &TxId,
+        dest_dir: &Path,
+    ) -> Result<Vec<FileReport>, Error> {
+        use futures::StreamExt;
+
+        let manifest = Manifest::from_tx(self, id).await?;
+        let files = manifest
+            .paths
+            .iter()
+            .map(|(path, entry)| {
+                let local = crate::manifest::local_path(dest_dir, path)?;
+                Ok((path.clone(), entry.id.clone(), local))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut downloads =
+            futures::stream::iter(files.into_iter().map(|(path, id, local)| async move {
+                let tx_id = TxId::try_from(&id)?;
+                let data = match self.get_verified_data(&tx_id).await {
+                    Err(Error::UnexpectedStatus { status: 404, .. }) => {
+                        self.get_verified_data_item(&tx_id).await?
+                    }
+                    result => result?,
+                };
+                if let Some(parent) = local.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::write(&local, &data).await?;
+                Ok::<_, Error>(FileReport {
+                    path,
+                    id,
+                    size: data.len() as u64,
+                    reward: 0,
+                })
+            }))
+            .buffer_unordered(consts::DOWNLOAD_CONCURRENCY);
+
+        let mut reports = vec![];
+        while let Some(report) = downloads.next().await {
+            reports.push(report?);
+        }
+        reports.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(reports)
+    }
+
+    /// Posts `manifest` as a transaction paying the estimated reward, returning its id and
+    /// reward. Updated manifests are published as new transactions.
+    pub async fn publish_manifest(&self, manifest: &Manifest) -> Result<(TxId, u64), Error> {
+        let data = manifest.to_json()?;
+        let fee = self.get_reward(data.len() as u64, None).await?;
+        self.post_bytes(data, false, Manifest::tags(), fee).await
+    }
+
+    /// Uploads a file in chunks read from a memory mapping of it, without copying the
+    /// whole file into memory.
+    #[cfg(feature = "mmap")]
+    pub async fn upload_file_mmap(
+        &self,
+        file_path: &Path,
+        additional_tags: Vec<Tag<Base64>>,
+        fee: u64,
+    ) -> Result<(TxId, u64), Error> {
+        let signer = match &self.signer {
+            Some(s) => s,
+            None => return Err(Error::MissingSigner),
+        };
+        let file = mmap::MmapFile::open(file_path)?;
+        let mut additional_tags = additional_tags;
+        self.content_types
+            .tag(file_path, &file, &mut additional_tags);
+
+        let transaction = Tx::new_with_detached_data(
+            signer.get_provider(),
+            Base64::empty(),
+            &file,
+            0,
+            fee,
+            self.get_tx_anchor().await?,
+            additional_tags,
+            false,
+        )?;
+        let signed_transaction = self.sign_transaction(transaction)?;
+        let mut state = UploadState::new(&signed_transaction);
+        self.with_deadline(
+            self.upload_with_state(&signed_transaction, &file, &mut state, |_| Ok(())),
+        )
+        .await
+    }
+
     async fn post_bytes<T: AsRef<[u8]>>(
         &self,
         buffer: T,
         auto_content_tag: bool,
         additional_tags: Vec<Tag<Base64>>,
         fee: u64,
-    ) -> Result<(String, u64), Error> {
+    ) -> Result<(TxId, u64), Error> {
         let transaction = self
             .create_transaction(
-                Base64(b"".to_vec()),
+                None,
                 additional_tags,
                 buffer.as_ref().into(),
                 0,
@@ -265,54 +1106,153 @@ impl Arweave {
         self.post_transaction(signed_transaction).await
     }
 
-    async fn post_transaction_chunks(
+    async fn post_transaction_chunks(&self, signed_transaction: Tx) -> Result<(TxId, u64), Error> {
+        let mut state = UploadState::new(&signed_transaction);
+        self.upload_with_state(
+            &signed_transaction,
+            &signed_transaction.data.0,
+            &mut state,
+            |_| Ok(()),
+        )
+        .await
+    }
+
+    /// Posts a transaction and its chunks, recording progress in the file at `state_path`.
+    /// If the file already holds the state of a previous attempt for the same transaction,
+    /// only the chunks that were not accepted yet are posted.
+    pub async fn post_transaction_resumable(
         &self,
         signed_transaction: Tx,
-        chunks_buffer: usize,
-    ) -> Result<(String, u64), Error> {
+        state_path: &Path,
+    ) -> Result<(TxId, u64), Error> {
+        let mut state = if state_path.exists() {
+            UploadState::from_path(state_path)?
+        } else {
+            UploadState::new(&signed_transaction)
+        };
+        if !state.matches(&signed_transaction) {
+            return Err(Error::UploadStateMismatch(state.id.to_string()));
+        }
+
+        let result = self
+            .with_deadline(self.upload_with_state(
+                &signed_transaction,
+                &signed_transaction.data.0,
+                &mut state,
+                |s| s.save(state_path),
+            ))
+            .await;
+        state.save(state_path)?;
+        result
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(id = %signed_transaction.id)))]
+    async fn upload_with_state<F>(
+        &self,
+        signed_transaction: &Tx,
+        data: &[u8],
+        state: &mut UploadState,
+        checkpoint: F,
+    ) -> Result<(TxId, u64), Error>
+    where
+        F: FnMut(&UploadState) -> Result<(), Error>,
+    {
         if signed_transaction.id.0.is_empty() {
             return Err(error::Error::UnsignedTransaction);
         }
 
-        let transaction_with_no_data = signed_transaction.clone_with_no_data()?;
-        let (id, reward) = self
-            .tx_client
-            .post_transaction(&transaction_with_no_data)
-            .await
-            .map(|(id, reward)| (id.to_string(), reward))?;
-
-        let results: Vec<Result<usize, Error>> =
-            Self::upload_transaction_chunks_stream(self, signed_transaction, chunks_buffer)
-                .collect()
-                .await;
+        if !state.header_posted {
+            let transaction_with_no_data = signed_transaction.clone_with_no_data()?;
+            self.tx_client
+                .post_transaction(&transaction_with_no_data)
+                .await?;
+            state.header_posted = true;
+            #[cfg(feature = "tracing")]
+            tracing::info!("posted header");
+            self.uploader.emit(UploadEvent::HeaderPosted {
+                id: signed_transaction.id.clone(),
+            });
+        }
 
-        results.into_iter().collect::<Result<Vec<usize>, Error>>()?;
+        self.uploader
+            .upload_chunks_from(signed_transaction, data, state, checkpoint)
+            .await?;
 
-        Ok((id, reward))
+        Ok((
+            TxId::try_from(&signed_transaction.id)?,
+            signed_transaction.reward,
+        ))
     }
+}
 
-    fn upload_transaction_chunks_stream(
-        arweave: &Arweave,
-        signed_transaction: Tx,
-        buffer: usize,
-    ) -> impl Stream<Item = Result<usize, Error>> + '_ {
-        let client = Client::new();
-        stream::iter(0..signed_transaction.chunks.len())
-            .map(move |i| {
-                let chunk = signed_transaction.get_chunk(i).unwrap(); //TODO: remove this unwrap
-                arweave
-                    .uploader
-                    .post_chunk_with_retries(chunk, client.clone())
-            })
-            .buffer_unordered(buffer)
-    }
+/// Path in the temporary directory unique to the process and to the call, so that tests
+/// running in parallel do not share files.
+#[cfg(test)]
+pub(crate) fn temp_path(name: &str) -> PathBuf {
+    static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    let count = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    std::env::temp_dir().join(format!("{}-{}-{}", name, std::process::id(), count))
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{fs::File, io::Read, str::FromStr};
+    use std::{
+        fs::{self, File},
+        io::Read,
+        path::PathBuf,
+        str::FromStr,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use tokio_test::block_on;
+
+    use crate::{
+        address::Address,
+        block::{BlockHash, BlockHeight},
+        bundle::DataItem,
+        consts,
+        crypto::base64::Base64,
+        currency::Winston,
+        dispatch::{Bundler, DispatchKind},
+        error::Error,
+        manifest::{Manifest, UploadMode},
+        metrics::MetricsSink,
+        network::{Network, PriceSource, ProtocolParams},
+        rate_limit::RateLimit,
+        retry::RetryPolicy,
+        runtime,
+        transaction::{client::TxClient, tags::Tag, TransactionBuilder, Tx},
+        tx_id::TxId,
+        verify::verify_transaction,
+        Arweave, ArweaveBuilder, ArweaveSigner,
+    };
+
+    fn mock_node(server: &MockServer) {
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(Base64(vec![1; 48]).to_string());
+        });
+        server.mock(|when, then| {
+            when.method(GET).path_contains("/price/");
+            then.status(200).body("1000");
+        });
+    }
 
-    use crate::{error::Error, transaction::Tx, verify::verify_transaction};
+    fn test_directory(name: &str) -> PathBuf {
+        let dir = crate::temp_path(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("css")).unwrap();
+        fs::write(dir.join("index.html"), b"<html></html>").unwrap();
+        fs::write(dir.join("css/style.css"), b"body {}").unwrap();
+        dir
+    }
 
     #[test]
     pub fn should_parse_and_verify_valid_tx() -> Result<(), Error> {
@@ -326,4 +1266,902 @@ mod tests {
             Err(_) => Err(Error::InvalidSignature),
         }
     }
+
+    #[test]
+    pub fn should_build_read_only_client_for_base_url() {
+        let mut data = String::new();
+        File::open("res/sample_tx.json")
+            .unwrap()
+            .read_to_string(&mut data)
+            .unwrap();
+        let tx = Tx::from_str(&data).unwrap();
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", tx.id));
+            then.status(200).body(&data);
+        });
+
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("")).unwrap())
+            .build()
+            .unwrap();
+        let (_, fetched) = block_on(arweave.get_tx(&TxId::try_from(&tx.id).unwrap())).unwrap();
+
+        mock.assert();
+        assert!(arweave.signer.is_none());
+        assert_eq!(fetched, Some(tx));
+        assert!(arweave.gateway_monitor(Duration::from_secs(60)).is_none());
+    }
+
+    #[test]
+    pub fn should_route_clients_through_gateways() {
+        let failing = MockServer::start();
+        let failing_mock = failing.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(502);
+        });
+        let healthy = MockServer::start();
+        let healthy_mock = healthy.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200);
+        });
+        let arweave = ArweaveBuilder::new()
+            .gateways(vec![
+                url::Url::parse(&failing.url("/")).unwrap(),
+                url::Url::parse(&healthy.url("/")).unwrap(),
+            ])
+            .build()
+            .unwrap();
+
+        let monitor = arweave.gateway_monitor(Duration::from_secs(60)).unwrap();
+        block_on(runtime::timeout(Duration::from_millis(500), monitor));
+        failing_mock.assert_hits(1);
+        healthy_mock.assert_hits(1);
+        // Network info goes to the healthy gateway, through the pool.
+        assert!(block_on(arweave.network_info()).is_err());
+        healthy_mock.assert_hits(2);
+    }
+
+    #[test]
+    fn should_configure_network() -> Result<(), Error> {
+        let arweave = ArweaveBuilder::new().build()?;
+        assert_eq!(arweave.base_url.as_str(), consts::ARWEAVE_BASE_URL);
+        assert_eq!(
+            arweave.network().price_source(),
+            Some(PriceSource::CoinGecko)
+        );
+        let arweave = ArweaveBuilder::new().network(Network::Testnet).build()?;
+        assert_eq!(arweave.base_url.as_str(), consts::TESTNET_BASE_URL);
+        assert_eq!(arweave.network().price_source(), None);
+        assert_eq!(arweave.network().params(), ProtocolParams::TESTNET);
+        let url = url::Url::parse("http://localhost:1985/").unwrap();
+        let arweave = ArweaveBuilder::new()
+            .network(Network::custom(url.clone()))
+            .build()?;
+        assert_eq!(arweave.base_url, url);
+        assert_eq!(arweave.network().price_source(), None);
+
+        // Data above the limit of the network is posted chunk by chunk.
+        let server = MockServer::start();
+        mock_node(&server);
+        let post = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx")
+                .json_body_partial(r#"{"data":""}"#);
+            then.status(200);
+        });
+        let chunk = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200);
+        });
+        let arweave = ArweaveBuilder::new()
+            .network(Network::Custom {
+                url: url::Url::parse(&server.url("")).unwrap(),
+                price_source: None,
+                params: ProtocolParams { max_tx_data: 1024 },
+            })
+            .keypair_path(PathBuf::from("res/test_wallet.json"))
+            .build()?;
+        let tx = arweave.sign_transaction(block_on(arweave.create_transaction(
+            None,
+            vec![],
+            vec![7; 2048],
+            0,
+            1000,
+            false,
+        ))?)?;
+        block_on(arweave.post_transaction(tx))?;
+        post.assert();
+        chunk.assert();
+        Ok(())
+    }
+
+    #[test]
+    fn test_upload_directory() -> Result<(), Error> {
+        let dir = test_directory("arweave-rs-upload-directory");
+        let server = MockServer::start();
+        mock_node(&server);
+        let post = server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200);
+        });
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("res/test_wallet.json"),
+            url::Url::parse(&server.url("")).unwrap(),
+        )?;
+
+        let report = block_on(arweave.upload_directory(&dir, UploadMode::Transactions))?;
+
+        post.assert_hits(3);
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.files[0].path, "css/style.css");
+        assert_eq!(report.files[0].reward, 1000);
+        assert_eq!(report.manifest.get("index.html"), Some(&report.files[1].id));
+        assert_eq!(report.manifest.index.unwrap().path, "index.html");
+        assert!(report.bundle_id.is_none());
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_update_published_manifest() -> Result<(), Error> {
+        let mut published = Manifest::default();
+        published
+            .add_path("index.html", Base64(vec![1; 32]))
+            .add_path("old.html", Base64(vec![2; 32]))
+            .set_index("old.html");
+        let manifest_id = TxId::new([9; 32]);
+        let server = MockServer::start();
+        mock_node(&server);
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/raw/{}", manifest_id));
+            then.status(200).body(published.to_json().unwrap());
+        });
+        let post = server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200);
+        });
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("res/test_wallet.json"),
+            url::Url::parse(&server.url("")).unwrap(),
+        )?;
+
+        let mut manifest = block_on(Manifest::from_tx(&arweave, &manifest_id))?;
+        assert_eq!(manifest, published);
+        assert_eq!(manifest.remove_path("/old.html"), Some(Base64(vec![2; 32])));
+        assert!(manifest.index.is_none());
+        manifest
+            .add_path("new.html", Base64(vec![3; 32]))
+            .set_index("index.html");
+        let (id, reward) = block_on(arweave.publish_manifest(&manifest))?;
+        post.assert();
+        assert_eq!(reward, 1000);
+        assert_ne!(id, manifest_id);
+        Ok(())
+    }
+
+    #[test]
+    fn should_scale_estimated_rewards_by_fee_multiplier() -> Result<(), Error> {
+        let dir = test_directory("arweave-rs-fee-multiplier");
+        let server = MockServer::start();
+        mock_node(&server);
+        server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200);
+        });
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("")).unwrap())
+            .keypair_path(PathBuf::from("res/test_wallet.json"))
+            .fee_multiplier(1.2)
+            .build()?;
+
+        let report = block_on(arweave.upload_directory(&dir, UploadMode::Transactions))?;
+        assert_eq!(report.files[0].reward, 1200);
+
+        let builder = TransactionBuilder::new().last_tx(Base64(vec![0; 32]));
+        let estimated = block_on(arweave.estimate_reward(builder.clone()))?;
+        assert_eq!(estimated.build_for_owner(Base64::empty())?.reward, 1200);
+        let estimated = block_on(arweave.estimate_reward(builder.fee_multiplier(2.0)))?;
+        assert_eq!(estimated.build_for_owner(Base64::empty())?.reward, 2000);
+        for fee_multiplier in [0.9, f64::NAN] {
+            assert!(matches!(
+                ArweaveBuilder::new().fee_multiplier(fee_multiplier).build(),
+                Err(Error::InvalidAmount(_))
+            ));
+        }
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_rate_limit_requests() -> Result<(), Error> {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/price/0");
+            then.status(200).body("1000");
+        });
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("")).unwrap())
+            .rate_limit(RateLimit::new(1000.0, 1)?)
+            .build()?;
+
+        // Waits are covered by the limiter's tests, requests still reach the gateway.
+        for _ in 0..4 {
+            block_on(arweave.get_price(0, None))?;
+        }
+        mock.assert_hits(4);
+        Ok(())
+    }
+
+    #[test]
+    fn should_fail_uploads_past_operation_timeout() -> Result<(), Error> {
+        let (tx, _) = signed_rebar3_tx();
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200).delay(Duration::from_millis(500));
+        });
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("")).unwrap())
+            .operation_timeout(Duration::from_millis(50))
+            .build()?;
+
+        let res = block_on(arweave.post_transaction(tx));
+        assert!(matches!(res, Err(Error::OperationTimeout(_))));
+        Ok(())
+    }
+
+    #[derive(Default)]
+    struct RecordingBundler(Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl Bundler for RecordingBundler {
+        async fn post(&self, data_item: &DataItem) -> Result<String, Error> {
+            data_item.verify()?;
+            let id = data_item.id.to_string();
+            self.0.lock().unwrap().push(id.clone());
+            Ok(id)
+        }
+    }
+
+    #[test]
+    fn should_dispatch_small_uploads_to_bundler() -> Result<(), Error> {
+        let server = MockServer::start();
+        mock_node(&server);
+        let post = server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200);
+        });
+        let bundler = Arc::new(RecordingBundler::default());
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("")).unwrap())
+            .keypair_path(PathBuf::from("res/test_wallet_4096.json"))
+            .bundler(bundler.clone())
+            .dispatch_threshold(16)
+            .build()?;
+
+        let bundled = block_on(arweave.dispatch(b"hello".to_vec(), vec![]))?;
+        assert_eq!(bundled.kind, DispatchKind::Bundled);
+        assert_eq!(*bundler.0.lock().unwrap(), vec![bundled.id]);
+
+        let posted = block_on(arweave.dispatch(vec![1; 17], vec![]))?;
+        post.assert();
+        assert_eq!(posted.kind, DispatchKind::Transaction { reward: 1000 });
+        assert_eq!(bundler.0.lock().unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn should_transfer() -> Result<(), Error> {
+        let server = MockServer::start();
+        let target = Address::new([9; 32]);
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(Base64(vec![1; 48]).to_string());
+        });
+        let price = server.mock(|when, then| {
+            when.method(GET).path(format!("/price/0/{}", target));
+            then.status(200).body("250000");
+        });
+        let post = server.mock(|when, then| {
+            when.method(POST).path("/tx").json_body_partial(format!(
+                r#"{{"target":"{}","quantity":"1500000000000"}}"#,
+                target
+            ));
+            then.status(200);
+        });
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("res/test_wallet.json"),
+            url::Url::parse(&server.url("")).unwrap(),
+        )?;
+
+        let (id, reward) = block_on(arweave.transfer(target, Winston::from_ar("1.5")?))?;
+        price.assert();
+        post.assert();
+        assert_eq!(reward, 250000);
+        assert_ne!(id, TxId::default());
+        Ok(())
+    }
+
+    #[test]
+    fn test_upload_directory_as_data_items() -> Result<(), Error> {
+        let dir = test_directory("arweave-rs-upload-directory-items");
+        let server = MockServer::start();
+        mock_node(&server);
+        let post = server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200);
+        });
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("res/test_wallet_4096.json"),
+            url::Url::parse(&server.url("")).unwrap(),
+        )?;
+
+        let report = block_on(arweave.upload_directory(&dir, UploadMode::DataItems))?;
+
+        post.assert_hits(1);
+        assert!(report.bundle_id.is_some());
+        assert_eq!(report.files[1].reward, 0);
+        assert_eq!(
+            report.manifest.get("css/style.css"),
+            Some(&report.files[0].id)
+        );
+        assert_ne!(report.manifest_id, report.files[0].id);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_route_to_peers() -> Result<(), Error> {
+        let peer = MockServer::start();
+        let peer_mock = peer.mock(|when, then| {
+            when.method(GET).path("/price/10");
+            then.status(200).body("7");
+        });
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/peers");
+            then.status(200)
+                .json_body(serde_json::json!([peer.address().to_string(), "invalid"]));
+        });
+
+        let mut arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("")).unwrap())
+            .build()?;
+        assert_eq!(block_on(arweave.get_peers())?, vec![*peer.address()]);
+        assert_eq!(block_on(arweave.route_to_peers(10, "http"))?, 1);
+        assert_eq!(block_on(arweave.get_price(10, None))?, 7);
+        peer_mock.assert();
+        Ok(())
+    }
+
+    #[test]
+    fn should_keep_gateways_and_metrics_when_routing_to_peers() -> Result<(), Error> {
+        let peer = MockServer::start();
+        let peer_mock = peer.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(503);
+        });
+        let gateway = MockServer::start();
+        gateway.mock(|when, then| {
+            when.method(GET).path("/peers");
+            then.status(200)
+                .json_body(serde_json::json!([peer.address().to_string()]));
+        });
+        let gateway_mock = gateway.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200);
+        });
+        #[derive(Default)]
+        struct Failovers(Mutex<Vec<url::Url>>);
+        impl MetricsSink for Failovers {
+            fn gateway_failover(&self, from: &url::Url, _to: &url::Url) {
+                self.0.lock().unwrap().push(from.clone());
+            }
+        }
+        let metrics = Arc::new(Failovers::default());
+        let mut arweave = ArweaveBuilder::new()
+            .gateways(vec![url::Url::parse(&gateway.url("/")).unwrap()])
+            .metrics(metrics.clone())
+            .retry_policy(
+                RetryPolicy::default()
+                    .max_attempts(4)
+                    .base_delay(Duration::from_millis(1)),
+            )
+            .build()?;
+        assert_eq!(block_on(arweave.route_to_peers(1, "http"))?, 1);
+
+        let tx = Tx::from_str(&fs::read_to_string("res/sample_tx.json")?)?;
+        block_on(arweave.post_transaction(tx))?;
+        peer_mock.assert_hits(3);
+        gateway_mock.assert_hits(1);
+        assert_eq!(metrics.0.lock().unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn should_get_block_by_height_and_hash() -> Result<(), Error> {
+        let indep_hash = BlockHash::new([1; 48]);
+        let block = serde_json::json!({
+            "indep_hash": indep_hash,
+            "hash": Base64(vec![2; 32]).to_string(),
+            "height": 1000,
+            "previous_block": Base64(vec![3; 48]).to_string(),
+            "nonce": Base64(vec![4; 32]).to_string(),
+            "timestamp": 1700000000,
+            "last_retarget": 1699999000,
+            "diff": "1000",
+            "txs": [],
+            "wallet_list": Base64(vec![7; 48]).to_string(),
+            "reward_addr": Base64(vec![8; 32]).to_string(),
+            "reward_pool": "100",
+            "weave_size": "200",
+            "block_size": "0",
+        });
+        let server = MockServer::start();
+        let height_mock = server.mock(|when, then| {
+            when.method(GET).path("/block/height/1000");
+            then.status(200).json_body(block.clone());
+        });
+        let hash_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/block/hash/{}", indep_hash));
+            then.status(200).json_body(block.clone());
+        });
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("")).unwrap())
+            .build()?;
+
+        let by_height = block_on(arweave.get_block_by_height(BlockHeight::new(1000)))?;
+        let by_hash = block_on(arweave.get_block_by_hash(&indep_hash))?;
+        height_mock.assert();
+        hash_mock.assert();
+        assert_eq!(by_height, by_hash);
+        assert_eq!(by_height.indep_hash, indep_hash);
+        assert_eq!(by_height.previous_block, Some(BlockHash::new([3; 48])));
+        assert_eq!(by_height.weave_size, 200);
+        Ok(())
+    }
+
+    #[test]
+    fn should_get_network_info() -> Result<(), Error> {
+        let current = BlockHash::new([1; 48]);
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200).json_body(serde_json::json!({
+                "network": "arweave.N.1",
+                "version": 5,
+                "release": 69,
+                "height": 1300000,
+                "current": current,
+                "blocks": 1300001,
+                "peers": 120,
+                "queue_length": 0,
+                "node_state_latency": 1,
+            }));
+        });
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("")).unwrap())
+            .build()?;
+
+        let info = block_on(arweave.network_info())?;
+        mock.assert();
+        assert_eq!(info.network, "arweave.N.1");
+        assert_eq!(info.height, BlockHeight::new(1300000));
+        assert_eq!(info.current, current);
+        assert_eq!(info.peers, 120);
+        Ok(())
+    }
+
+    #[test]
+    fn should_map_network_info_errors() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200).body("not json");
+        });
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("")).unwrap())
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            block_on(arweave.network_info()),
+            Err(Error::NetworkInfoError(_))
+        ));
+    }
+
+    fn signed_rebar3_tx() -> (Tx, Vec<u8>) {
+        let data = fs::read("res/rebar3").unwrap();
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("res/test_wallet.json"),
+            url::Url::parse("http://localhost/").unwrap(),
+        )
+        .unwrap();
+        let tx = Tx::new(
+            arweave.signer.as_ref().unwrap().get_provider(),
+            Base64::empty(),
+            data.clone(),
+            0,
+            100,
+            Base64(vec![1; 48]),
+            vec![],
+            false,
+        )
+        .unwrap();
+        (arweave.sign_transaction(tx).unwrap(), data)
+    }
+
+    #[test]
+    fn should_get_verified_data() -> Result<(), Error> {
+        let (tx, data) = signed_rebar3_tx();
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", tx.id));
+            then.status(200)
+                .json_body(serde_json::to_value(tx.clone_with_no_data().unwrap()).unwrap());
+        });
+        let mut data_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/data", tx.id));
+            then.status(200).body(Base64(data.clone()).to_string());
+        });
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("")).unwrap())
+            .build()?;
+
+        assert_eq!(
+            block_on(arweave.get_verified_data(&TxId::try_from(&tx.id)?))?,
+            data
+        );
+
+        data_mock.delete();
+        let mut tampered = data.clone();
+        tampered[10] ^= 1;
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/data", tx.id));
+            then.status(200).body(Base64(tampered).to_string());
+        });
+        assert!(matches!(
+            block_on(arweave.get_verified_data(&TxId::try_from(&tx.id)?)),
+            Err(Error::InvalidData(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn should_download_manifest() -> Result<(), Error> {
+        let (tx, data) = signed_rebar3_tx();
+        let mut manifest = Manifest::default();
+        manifest
+            .add_path("bin/rebar3", tx.id.clone())
+            .add_path("rebar3", tx.id.clone());
+        let manifest_id = TxId::new([9; 32]);
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/raw/{}", manifest_id));
+            then.status(200).body(manifest.to_json().unwrap());
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", tx.id));
+            then.status(200)
+                .json_body(serde_json::to_value(tx.clone_with_no_data().unwrap()).unwrap());
+        });
+        let data_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/data", tx.id));
+            then.status(200).body(Base64(data.clone()).to_string());
+        });
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("")).unwrap())
+            .build()?;
+        let dir = crate::temp_path("arweave-rs-download-manifest");
+        let _ = fs::remove_dir_all(&dir);
+
+        let files = block_on(arweave.download_manifest(&manifest_id, &dir))?;
+        data_mock.assert_hits(2);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "bin/rebar3");
+        assert_eq!(files[0].size, data.len() as u64);
+        assert_eq!(fs::read(dir.join("bin/rebar3"))?, data);
+        assert_eq!(fs::read(dir.join("rebar3"))?, data);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_download_manifest_of_data_items() -> Result<(), Error> {
+        let signer = ArweaveSigner::from_keypair_path(PathBuf::from("res/test_wallet_4096.json"))?;
+        let item = signer.sign_data_item(DataItem::new(
+            Base64::empty(),
+            Base64(vec![7; 32]),
+            vec![Tag::new("Content-Type", "text/html")],
+            b"<html></html>".to_vec(),
+        )?)?;
+        let mut manifest = Manifest::default();
+        manifest.add_path("index.html", item.id.clone());
+        let manifest_id = TxId::new([9; 32]);
+        let node = serde_json::json!({
+            "id": item.id.to_string(), "anchor": item.anchor.to_string(),
+            "signature": item.signature.to_string(), "recipient": "",
+            "owner": { "address": signer.wallet_address().to_string(), "key": item.owner.to_string() },
+            "fee": { "winston": "0", "ar": "0.000000000000" },
+            "quantity": { "winston": "0", "ar": "0.000000000000" },
+            "data": { "size": "13", "type": "text/html" },
+            "tags": [{ "name": "Content-Type", "value": "text/html" }],
+            "block": null,
+            "bundledIn": { "id": Base64(vec![8; 32]).to_string() }
+        });
+        let gateway = |data: &'static [u8]| {
+            let server = MockServer::start();
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/raw/{}", manifest_id));
+                then.status(200).body(manifest.to_json().unwrap());
+            });
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/tx/{}", item.id));
+                then.status(404);
+            });
+            server.mock(|when, then| {
+                when.method(POST).path("/graphql");
+                then.status(200).json_body(serde_json::json!({
+                    "data": { "transactions": {
+                        "pageInfo": { "hasNextPage": false },
+                        "edges": [{ "cursor": "cursor", "node": node }]
+                    }}
+                }));
+            });
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/raw/{}", item.id));
+                then.status(200).body(data);
+            });
+            server
+        };
+        let dir = crate::temp_path("arweave-rs-download-manifest-items");
+
+        let server = gateway(b"<html></html>");
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("")).unwrap())
+            .build()?;
+        let files = block_on(arweave.download_manifest(&manifest_id, &dir))?;
+        assert_eq!(files[0].size, 13);
+        assert_eq!(fs::read(dir.join("index.html"))?, b"<html></html>");
+        fs::remove_dir_all(&dir)?;
+
+        let server = gateway(b"<html>tampered</html>");
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("")).unwrap())
+            .build()?;
+        assert!(block_on(arweave.download_manifest(&manifest_id, &dir)).is_err());
+        assert!(!dir.join("index.html").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn should_get_verified_data_from_chunks() -> Result<(), Error> {
+        let (tx, data) = signed_rebar3_tx();
+        // Absolute offset of the last byte of the data in the weave.
+        let end = 1_000_000 + data.len() as u64 - 1;
+        let start = end + 1 - data.len() as u64;
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", tx.id));
+            then.status(200)
+                .json_body(serde_json::to_value(tx.clone_with_no_data().unwrap()).unwrap());
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/data", tx.id));
+            then.status(400);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", tx.id));
+            then.status(200).json_body(serde_json::json!({
+                "size": data.len().to_string(),
+                "offset": end.to_string(),
+            }));
+        });
+        for (chunk, proof) in tx.chunks.iter().zip(tx.proofs.iter()) {
+            server.mock(|when, then| {
+                when.method(GET)
+                    .path(format!("/chunk/{}", start + chunk.min_byte_range as u64));
+                then.status(200).json_body(serde_json::json!({
+                    "chunk": Base64(data[chunk.min_byte_range..chunk.max_byte_range].to_vec()).to_string(),
+                    "data_path": Base64(proof.proof.clone()).to_string(),
+                }));
+            });
+        }
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("")).unwrap())
+            .build()?;
+
+        assert_eq!(
+            block_on(arweave.get_verified_data(&TxId::try_from(&tx.id)?))?,
+            data
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn should_get_data_range() -> Result<(), Error> {
+        let (tx, data) = signed_rebar3_tx();
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/{}", tx.id))
+                .header("Range", "bytes=10-19");
+            then.status(206)
+                .header("Content-Range", format!("bytes 10-19/{}", data.len()))
+                .body(&data[10..20]);
+        });
+        // Ranges past the end of the data are served up to its end.
+        let len = data.len();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/{}", tx.id))
+                .header("Range", format!("bytes={}-{}", len - 5, len + 4));
+            then.status(206)
+                .header(
+                    "Content-Range",
+                    format!("bytes {}-{}/{}", len - 5, len - 1, len),
+                )
+                .body(&data[len - 5..]);
+        });
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("")).unwrap())
+            .build()?;
+
+        let id = TxId::try_from(&tx.id)?;
+        assert_eq!(
+            block_on(arweave.get_data_range(&id, 10, 20))?,
+            &data[10..20]
+        );
+        assert_eq!(
+            block_on(arweave.get_data_range(&id, len as u64 - 5, len as u64 + 5))?,
+            &data[len - 5..]
+        );
+        assert!(block_on(arweave.get_data_range(&TxId::try_from(&tx.id)?, 20, 10)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn should_get_data_range_from_chunks() -> Result<(), Error> {
+        let (tx, data) = signed_rebar3_tx();
+        assert!(tx.chunks.len() > 1);
+        let end = 1_000_000 + data.len() as u64 - 1;
+        let start = end + 1 - data.len() as u64;
+        let first = &tx.chunks[0];
+        let range_end = first.max_byte_range as u64 + 50;
+        let range = &data[100..range_end as usize];
+        let content_range = format!("bytes 100-{}/{}", range_end - 1, data.len());
+        // Nodes without range support, gateways ignoring the range and gateways serving
+        // another range or a truncated body.
+        let responses: [(u16, Option<String>, &[u8]); 5] = [
+            (404, None, b""),
+            (200, None, &data),
+            (206, None, range),
+            (
+                206,
+                Some(format!("bytes 0-{}/{}", range.len() - 1, data.len())),
+                &data[..range.len()],
+            ),
+            (206, Some(content_range), &range[1..]),
+        ];
+        for (status, content_range, body) in responses {
+            let server = MockServer::start();
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/{}", tx.id));
+                let then = then.status(status).body(body);
+                if let Some(content_range) = &content_range {
+                    then.header("Content-Range", content_range);
+                }
+            });
+            let offset = server.mock(|when, then| {
+                when.method(GET).path(format!("/tx/{}/offset", tx.id));
+                then.status(200).json_body(serde_json::json!({
+                    "size": data.len().to_string(),
+                    "offset": end.to_string(),
+                }));
+            });
+            // Nodes answer with the chunk containing any offset within it.
+            for (chunk, proof) in tx.chunks.iter().zip(tx.proofs.iter()) {
+                server.mock(|when, then| {
+                    let offset = if chunk.min_byte_range == 0 {
+                        start + 100
+                    } else {
+                        start + chunk.min_byte_range as u64
+                    };
+                    when.method(GET).path(format!("/chunk/{}", offset));
+                    then.status(200).json_body(serde_json::json!({
+                        "chunk": Base64(data[chunk.min_byte_range..chunk.max_byte_range].to_vec()).to_string(),
+                        "data_path": Base64(proof.proof.clone()).to_string(),
+                    }));
+                });
+            }
+            let arweave = ArweaveBuilder::new()
+                .base_url(url::Url::parse(&server.url("")).unwrap())
+                .build()?;
+
+            assert_eq!(
+                block_on(arweave.get_data_range(&TxId::try_from(&tx.id)?, 100, range_end))?,
+                range
+            );
+            offset.assert();
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn should_download_to_writer() -> Result<(), Error> {
+        let (tx, data) = signed_rebar3_tx();
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/{}", tx.id));
+            then.status(200).body(&data);
+        });
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("")).unwrap())
+            .build()?;
+
+        let mut written = vec![];
+        assert_eq!(
+            block_on(arweave.download_to(&TxId::try_from(&tx.id)?, &mut written))?,
+            data.len() as u64
+        );
+        assert_eq!(written, data);
+
+        #[cfg(feature = "rt-tokio")]
+        {
+            let path = crate::temp_path("arweave-rs-download-to-file");
+            block_on(arweave.download_to_file(&TxId::try_from(&tx.id)?, &path))?;
+            assert_eq!(fs::read(&path)?, data);
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn should_download_to_writer_from_chunks() -> Result<(), Error> {
+        let (tx, data) = signed_rebar3_tx();
+        let end = 1_000_000 + data.len() as u64 - 1;
+        let start = end + 1 - data.len() as u64;
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/{}", tx.id));
+            then.status(404);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", tx.id));
+            then.status(200).json_body(serde_json::json!({
+                "size": data.len().to_string(),
+                "offset": end.to_string(),
+            }));
+        });
+        for (chunk, proof) in tx.chunks.iter().zip(tx.proofs.iter()) {
+            server.mock(|when, then| {
+                when.method(GET)
+                    .path(format!("/chunk/{}", start + chunk.min_byte_range as u64));
+                then.status(200).json_body(serde_json::json!({
+                    "chunk": Base64(data[chunk.min_byte_range..chunk.max_byte_range].to_vec()).to_string(),
+                    "data_path": Base64(proof.proof.clone()).to_string(),
+                }));
+            });
+        }
+        let client = TxClient::new(
+            reqwest::Client::new(),
+            url::Url::parse(&server.url("")).unwrap(),
+        )?;
+
+        let mut written = vec![];
+        let mut progress = vec![];
+        block_on(client.download_to_with_progress(
+            &TxId::try_from(&tx.id)?,
+            &mut written,
+            |done, total| progress.push((done, total)),
+        ))?;
+        assert_eq!(written, data);
+        assert_eq!(progress.len(), tx.chunks.len());
+        assert_eq!(
+            progress.last(),
+            Some(&(data.len() as u64, Some(data.len() as u64)))
+        );
+        Ok(())
+    }
 }
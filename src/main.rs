@@ -1,11 +1,10 @@
 use std::fs;
 use std::{path::PathBuf, str::FromStr};
 
-use arweave_rs::crypto::base64::Base64;
 use arweave_rs::Arweave;
 use url::Url;
 
-#[tokio::main]
+#[tokio::main(flavor = "current_thread")]
 async fn main() {
     /* let target = Base64::from_str("PAgdonEn9f5xd-UbYdCX40Sj28eltQVnxz6bbUijeVY").unwrap();
     let path = PathBuf::from_str(".wallet.json").unwrap();
@@ -43,13 +42,12 @@ async fn main() {
 
     let path = PathBuf::from_str(".wallet.json").unwrap();
     let file_path = PathBuf::from_str("data").unwrap();
-    let target = Base64::empty();
 
     let arweave =
         Arweave::from_keypair_path(path, Url::from_str("https://arweave.net").unwrap()).unwrap();
     let data = fs::read(file_path.clone()).expect("Could not read file");
 
-    let fee = arweave.get_fee(target, data).await.unwrap();
+    let fee = arweave.get_fee(None, data).await.unwrap();
 
     let res = arweave.upload_file_from_path(file_path, vec![], fee).await;
 
@@ -0,0 +1,304 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{crypto::base64::Base64, error::Error, transaction::tags::Tag, tx_id::TxId, Arweave};
+
+pub const MANIFEST_TYPE: &str = "arweave/paths";
+pub const MANIFEST_CONTENT_TYPE: &str = "application/x.arweave-manifest+json";
+
+/// Version of the `arweave/paths` spec. Fallbacks require 0.2.0.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestVersion {
+    #[serde(rename = "0.1.0")]
+    V0_1_0,
+    #[default]
+    #[serde(rename = "0.2.0")]
+    V0_2_0,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ManifestIndex {
+    pub path: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ManifestPath {
+    pub id: Base64,
+}
+
+/// How the files of a directory are published.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UploadMode {
+    /// One layer-1 transaction per file and one for the manifest.
+    #[default]
+    Transactions,
+    /// Files and manifest as data items of a single ANS-104 bundle.
+    DataItems,
+}
+
+/// Outcome of uploading a single file of a directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileReport {
+    pub path: String,
+    pub id: Base64,
+    pub size: u64,
+    /// Fee paid in winstons, zero for data items.
+    pub reward: u64,
+}
+
+/// Outcome of a directory upload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryReport {
+    pub manifest_id: Base64,
+    pub manifest: Manifest,
+    /// Id of the bundle transaction when uploading data items.
+    pub bundle_id: Option<Base64>,
+    pub files: Vec<FileReport>,
+}
+
+/// Lists the files under `dir` recursively, sorted by their `/` separated relative path.
+pub fn collect_files(dir: &Path) -> Result<Vec<(String, PathBuf)>, Error> {
+    fn walk(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) -> Result<(), Error> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(root, &path, files)?;
+            } else {
+                let relative = path
+                    .strip_prefix(root)
+                    .unwrap() //Checked unwrap
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.push((relative, path));
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = vec![];
+    walk(dir, dir, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// Location of the manifest path `path` under `dir`, rejecting paths that would escape it.
+pub fn local_path(dir: &Path, path: &str) -> Result<PathBuf, Error> {
+    let mut local = dir.to_path_buf();
+    for component in path.trim_start_matches('/').split('/') {
+        if component.is_empty() || component == "." || component == ".." || component.contains('\\')
+        {
+            return Err(Error::InvalidManifest(format!("unsafe path {}", path)));
+        }
+        local.push(component);
+    }
+    Ok(local)
+}
+
+/// Path manifest mapping paths to transaction ids.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    pub manifest: String,
+    pub version: ManifestVersion,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<ManifestIndex>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<ManifestPath>,
+    pub paths: BTreeMap<String, ManifestPath>,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self::new(ManifestVersion::default())
+    }
+}
+
+impl Manifest {
+    pub fn new(version: ManifestVersion) -> Self {
+        Self {
+            manifest: MANIFEST_TYPE.to_string(),
+            version,
+            index: None,
+            fallback: None,
+            paths: BTreeMap::new(),
+        }
+    }
+
+    /// Maps `path` to `id`, replacing any previous mapping.
+    pub fn add_path(&mut self, path: &str, id: Base64) -> &mut Self {
+        self.paths.insert(
+            path.trim_start_matches('/').to_string(),
+            ManifestPath { id },
+        );
+        self
+    }
+
+    /// Removes the mapping of `path`, and the index if it was `path`.
+    pub fn remove_path(&mut self, path: &str) -> Option<Base64> {
+        let path = path.trim_start_matches('/');
+        if self.index.as_ref().is_some_and(|index| index.path == path) {
+            self.index = None;
+        }
+        self.paths.remove(path).map(|path| path.id)
+    }
+
+    /// Sets the path served at the root of the manifest. It must be one of the mapped paths.
+    pub fn set_index(&mut self, path: &str) -> &mut Self {
+        self.index = Some(ManifestIndex {
+            path: path.trim_start_matches('/').to_string(),
+        });
+        self
+    }
+
+    /// Sets the transaction served for paths that are not mapped.
+    pub fn set_fallback(&mut self, id: Base64) -> &mut Self {
+        self.fallback = Some(ManifestPath { id });
+        self
+    }
+
+    pub fn get(&self, path: &str) -> Option<&Base64> {
+        self.paths
+            .get(path.trim_start_matches('/'))
+            .map(|path| &path.id)
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.manifest != MANIFEST_TYPE {
+            return Err(Error::InvalidManifest(format!(
+                "unknown manifest type {}",
+                self.manifest
+            )));
+        }
+        if let Some(index) = &self.index {
+            if !self.paths.contains_key(&index.path) {
+                return Err(Error::InvalidManifest(format!(
+                    "index {} is not a manifest path",
+                    index.path
+                )));
+            }
+        }
+        if self.fallback.is_some() && self.version == ManifestVersion::V0_1_0 {
+            return Err(Error::InvalidManifest(
+                "fallback requires version 0.2.0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Result<Vec<u8>, Error> {
+        self.validate()?;
+        serde_json::to_vec(self).map_err(Error::SerdeJsonError)
+    }
+
+    pub fn from_json(bytes: &[u8]) -> Result<Self, Error> {
+        let manifest: Manifest = serde_json::from_slice(bytes).map_err(Error::SerdeJsonError)?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Loads the manifest uploaded as transaction or data item `id`, to be updated and
+    /// published again with [`Arweave::publish_manifest`].
+    pub async fn from_tx(arweave: &Arweave, id: &TxId) -> Result<Self, Error> {
+        Self::from_json(&arweave.get_raw_data(id).await?)
+    }
+
+    /// Tags required for gateways to resolve the uploaded manifest.
+    pub fn tags() -> Vec<Tag<Base64>> {
+        vec![Tag::content_type(MANIFEST_CONTENT_TYPE)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{crypto::base64::Base64, error::Error};
+
+    use super::{collect_files, local_path, Manifest, ManifestVersion};
+
+    #[test]
+    fn test_manifest_to_json() -> Result<(), Error> {
+        let index_id = Base64(vec![1; 32]);
+        let image_id = Base64(vec![2; 32]);
+        let fallback_id = Base64(vec![3; 32]);
+        let mut manifest = Manifest::default();
+        manifest
+            .add_path("index.html", index_id.clone())
+            .add_path("/images/logo.png", image_id.clone())
+            .set_index("index.html")
+            .set_fallback(fallback_id.clone());
+
+        let json: serde_json::Value = serde_json::from_slice(&manifest.to_json()?).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "manifest": "arweave/paths",
+                "version": "0.2.0",
+                "index": { "path": "index.html" },
+                "fallback": { "id": fallback_id.to_string() },
+                "paths": {
+                    "images/logo.png": { "id": image_id.to_string() },
+                    "index.html": { "id": index_id.to_string() },
+                },
+            })
+        );
+        assert_eq!(Manifest::from_json(&manifest.to_json()?)?, manifest);
+        assert_eq!(manifest.get("/images/logo.png"), Some(&image_id));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_manifest() {
+        let mut manifest = Manifest::new(ManifestVersion::V0_1_0);
+        manifest.add_path("index.html", Base64(vec![1; 32]));
+
+        manifest.set_fallback(Base64(vec![3; 32]));
+        assert!(matches!(manifest.to_json(), Err(Error::InvalidManifest(_))));
+
+        manifest.fallback = None;
+        manifest.set_index("missing.html");
+        assert!(
+            matches!(manifest.to_json(), Err(Error::InvalidManifest(msg)) if msg == "index missing.html is not a manifest path")
+        );
+    }
+
+    #[test]
+    fn test_local_path() {
+        let dir = std::path::Path::new("site");
+        assert_eq!(
+            local_path(dir, "/css/style.css").unwrap(),
+            dir.join("css").join("style.css")
+        );
+        for path in [
+            "../etc/passwd",
+            "css//style.css",
+            "css/./style.css",
+            "css\\..\\x",
+            "",
+        ] {
+            assert!(local_path(dir, path).is_err(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_collect_files() -> Result<(), Error> {
+        let dir = crate::temp_path("arweave-rs-collect-files");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("css"))?;
+        fs::write(dir.join("index.html"), b"<html></html>")?;
+        fs::write(dir.join("css/style.css"), b"body {}")?;
+
+        let files = collect_files(&dir)?;
+        let paths: Vec<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, vec!["css/style.css", "index.html"]);
+        assert_eq!(files[1].1, dir.join("index.html"));
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
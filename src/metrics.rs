@@ -0,0 +1,155 @@
+//! Hooks reporting upload and network activity to a metrics backend.
+
+use std::time::Duration;
+
+/// Receives measurements of the client, uploader and gateway pool. All methods do nothing
+/// by default, so implementations only override what they record. Methods are called from
+/// the tasks doing the work and should not block.
+pub trait MetricsSink: Send + Sync {
+    /// A chunk of `bytes` bytes was accepted by the node.
+    fn bytes_uploaded(&self, _bytes: u64) {}
+
+    /// Posting the chunk at `offset` failed and is being retried.
+    fn chunk_retry(&self, _offset: usize) {}
+
+    /// A request to `endpoint`, the first segment of its path such as `tx` or `chunk`,
+    /// completed in `latency`.
+    fn request_latency(&self, _endpoint: &str, _latency: Duration, _success: bool) {}
+
+    /// Requests moved from gateway `from` to `to` after repeated failures.
+    fn gateway_failover(&self, _from: &url::Url, _to: &url::Url) {}
+}
+
+/// Endpoint reported for a request path, without ids or parameters.
+pub(crate) fn endpoint(path: &str) -> &str {
+    path.trim_start_matches('/')
+        .split(['/', '?'])
+        .next()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use httpmock::{Method::POST, MockServer};
+    use tokio_test::block_on;
+
+    use crate::{
+        crypto::{base64::Base64, Provider},
+        error::Error,
+        gateway::GatewayPool,
+        retry::RetryPolicy,
+        transaction::Tx,
+        upload::{UploadState, Uploader},
+    };
+
+    use super::{endpoint, MetricsSink};
+
+    #[derive(Default)]
+    struct Recorder {
+        bytes: Mutex<u64>,
+        retries: Mutex<Vec<usize>>,
+        requests: Mutex<Vec<(String, bool)>>,
+        failovers: Mutex<Vec<(url::Url, url::Url)>>,
+    }
+
+    impl MetricsSink for Recorder {
+        fn bytes_uploaded(&self, bytes: u64) {
+            *self.bytes.lock().unwrap() += bytes;
+        }
+
+        fn chunk_retry(&self, offset: usize) {
+            self.retries.lock().unwrap().push(offset);
+        }
+
+        fn request_latency(&self, endpoint: &str, _latency: Duration, success: bool) {
+            self.requests
+                .lock()
+                .unwrap()
+                .push((endpoint.to_string(), success));
+        }
+
+        fn gateway_failover(&self, from: &url::Url, to: &url::Url) {
+            self.failovers
+                .lock()
+                .unwrap()
+                .push((from.clone(), to.clone()));
+        }
+    }
+
+    #[test]
+    fn test_endpoint() {
+        assert_eq!(endpoint("tx/abc/data"), "tx");
+        assert_eq!(endpoint("/price/100"), "price");
+        assert_eq!(endpoint("tx_anchor"), "tx_anchor");
+        assert_eq!(endpoint("graphql?query"), "graphql");
+    }
+
+    #[test]
+    fn test_upload_metrics() -> Result<(), Error> {
+        let data = fs::read("res/rebar3")?;
+        let size = data.len() as u64;
+        let mut tx = Tx::new(
+            &Provider::default(),
+            Base64::empty(),
+            data,
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )?;
+        tx.id = Base64(vec![1; 32]);
+
+        let failing = MockServer::start();
+        failing.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(503);
+        });
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200);
+        });
+        let urls = vec![
+            url::Url::parse(&failing.url("/")).unwrap(),
+            url::Url::parse(&server.url("/")).unwrap(),
+        ];
+
+        let recorder = Arc::new(Recorder::default());
+        let gateways = Arc::new(
+            GatewayPool::new(urls.clone())?
+                .with_failure_threshold(1)
+                .with_metrics(recorder.clone()),
+        );
+        let uploader = Uploader::new(urls[0].clone())
+            .with_concurrency(1)
+            .with_gateways(gateways)
+            .with_retry_policy(
+                RetryPolicy::default()
+                    .max_attempts(2)
+                    .base_delay(Duration::from_millis(1)),
+            )
+            .with_metrics(recorder.clone());
+
+        let mut state = UploadState::new(&tx);
+        block_on(uploader.upload_chunks(&tx, &mut state, |_| Ok(())))?;
+
+        assert_eq!(*recorder.bytes.lock().unwrap(), size);
+        assert_eq!(*recorder.retries.lock().unwrap(), vec![tx.proofs[0].offset]);
+        let requests = recorder.requests.lock().unwrap();
+        assert_eq!(requests.len(), tx.chunks.len() + 1);
+        assert_eq!(requests[0], ("chunk".to_string(), false));
+        assert!(requests[1..].iter().all(|(_, success)| *success));
+        assert_eq!(
+            *recorder.failovers.lock().unwrap(),
+            vec![(urls[0].clone(), urls[1].clone())]
+        );
+        Ok(())
+    }
+}
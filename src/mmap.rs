@@ -0,0 +1,74 @@
+//! Memory mapped files, so that large files are chunked and uploaded from the page cache
+//! instead of being copied into memory.
+
+use std::{fs::File, ops::Deref, path::Path};
+
+use memmap2::Mmap;
+
+use crate::{
+    crypto::merkle::{generate_data_root, generate_leaves_from_slice, Node},
+    error::Error,
+};
+
+#[derive(Debug)]
+pub struct MmapFile(Mmap);
+
+impl MmapFile {
+    /// Maps the file at `path` read only. The file must not be modified while it is mapped.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        // Safety: the mapping is read only and documented to require the file to stay unchanged.
+        let mmap = unsafe { Mmap::map(&file)? };
+        Ok(Self(mmap))
+    }
+
+    pub fn leaves(&self) -> Result<Vec<Node>, Error> {
+        generate_leaves_from_slice(self)
+    }
+
+    pub fn data_root(&self) -> Result<Node, Error> {
+        generate_data_root(self.leaves()?)
+    }
+}
+
+impl Deref for MmapFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for MmapFile {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use crate::{
+        crypto::{base64::Base64, merkle::generate_leaves},
+        error::Error,
+    };
+
+    use super::MmapFile;
+
+    #[test]
+    fn test_mmap_leaves() -> Result<(), Error> {
+        let path = PathBuf::from("res/rebar3");
+        let file = MmapFile::open(&path)?;
+        assert_eq!(&file[..], fs::read(&path)?.as_slice());
+        assert_eq!(file.leaves()?, generate_leaves(fs::read(&path)?)?);
+        assert_eq!(
+            file.data_root()?.id.to_vec(),
+            "t-GCOnjPWxdox950JsrFMu3nzOE4RktXpMcIlkqSUTw"
+                .parse::<Base64>()
+                .unwrap()
+                .0
+        );
+        Ok(())
+    }
+}
@@ -1,29 +1,22 @@
+use std::{net::SocketAddr, str::FromStr, sync::Arc};
+
 use crate::{
+    block::{BlockHash, BlockHeight},
     client::Client,
-    types::{BlockInfo, NetworkInfo},
+    consts::{ARWEAVE_BASE_URL, MAX_TX_DATA, TESTNET_BASE_URL},
+    error::Error,
+    transport::HttpTransport,
+    types::{Block, BlockInfo, NetworkInfo},
 };
 use pretend::{
     interceptor::NoopRequestInterceptor, pretend, resolver::UrlResolver, JsonResult, Pretend, Url,
 };
-use serde::{Deserialize, Serialize};
-use thiserror::Error;
-
-#[derive(Serialize, Deserialize, Debug)]
-struct HeightInfo {
-    height: u64,
-}
-
-#[derive(Debug, Error, Deserialize)]
 
-pub enum ResponseError {
-    #[error("Internal error")]
-    InternalError(String),
+/// Body of error responses, kept as is for [`Error::NetworkInfoError`].
+type ResponseError = serde_json::Value;
 
-    #[error("Unknown error")]
-    UnknownError(String),
-}
-
-#[pretend]
+#[cfg_attr(not(target_arch = "wasm32"), pretend)]
+#[cfg_attr(target_arch = "wasm32", pretend(?Send))]
 trait NetworkInfoFetch {
     #[request(method = "GET", path = "/info")]
     async fn network_info(&self) -> pretend::Result<JsonResult<NetworkInfo, ResponseError>>;
@@ -42,6 +35,15 @@ trait NetworkInfoFetch {
         &self,
         height: u64,
     ) -> pretend::Result<JsonResult<BlockInfo, ResponseError>>;
+
+    #[request(method = "GET", path = "/block/hash/{id}")]
+    async fn block(&self, id: &str) -> pretend::Result<JsonResult<Block, ResponseError>>;
+
+    #[request(method = "GET", path = "/block/height/{height}")]
+    async fn block_at_height(
+        &self,
+        height: u64,
+    ) -> pretend::Result<JsonResult<Block, ResponseError>>;
 }
 
 pub struct NetworkInfoClient(Pretend<Client, UrlResolver, NoopRequestInterceptor>);
@@ -53,51 +55,174 @@ impl NetworkInfoClient {
         Self(pretend)
     }
 
-    pub async fn network_info(&self) -> Result<NetworkInfo, ResponseError> {
+    /// Client sending requests through `transport`.
+    pub fn with_transport(url: Url, transport: Arc<dyn HttpTransport>) -> Self {
+        let pretend = Pretend::for_client(Client::new(transport)).with_url(url);
+        Self(pretend)
+    }
+
+    pub async fn network_info(&self) -> Result<NetworkInfo, Error> {
         let response = self
             .0
             .network_info()
             .await
-            .map_err(|err| ResponseError::InternalError(err.to_string()))?;
+            .map_err(|err| Error::NetworkInfoError(err.to_string()))?;
         match response {
             JsonResult::Ok(n) => Ok(n),
-            JsonResult::Err(err) => Err(err),
+            JsonResult::Err(err) => Err(Error::NetworkInfoError(err.to_string())),
         }
     }
 
-    pub async fn peer_info(&self) -> Result<Vec<String>, ResponseError> {
+    pub async fn peer_info(&self) -> Result<Vec<String>, Error> {
         let response = self
             .0
             .peer_info()
             .await
-            .map_err(|err| ResponseError::InternalError(err.to_string()))?;
+            .map_err(|err| Error::NetworkInfoError(err.to_string()))?;
         match response {
             JsonResult::Ok(n) => Ok(n),
-            JsonResult::Err(err) => Err(err),
+            JsonResult::Err(err) => Err(Error::NetworkInfoError(err.to_string())),
         }
     }
 
-    pub async fn block_by_hash(&self, id: &str) -> Result<BlockInfo, ResponseError> {
+    /// Peers as socket addresses. Entries that do not parse as `ip:port` are skipped.
+    pub async fn peers(&self) -> Result<Vec<SocketAddr>, Error> {
+        Ok(self
+            .peer_info()
+            .await?
+            .iter()
+            .filter_map(|peer| SocketAddr::from_str(peer).ok())
+            .collect())
+    }
+
+    pub async fn block_by_hash(&self, id: &str) -> Result<BlockInfo, Error> {
         let response = self
             .0
             .block_by_hash(id)
             .await
-            .map_err(|err| ResponseError::InternalError(err.to_string()))?;
+            .map_err(|err| Error::NetworkInfoError(err.to_string()))?;
         match response {
             JsonResult::Ok(n) => Ok(n),
-            JsonResult::Err(err) => Err(err),
+            JsonResult::Err(err) => Err(Error::NetworkInfoError(err.to_string())),
         }
     }
 
-    pub async fn block_by_height(&self, id: &str) -> Result<BlockInfo, ResponseError> {
+    pub async fn block_by_height(&self, height: u64) -> Result<BlockInfo, Error> {
         let response = self
             .0
-            .block_by_hash(id)
+            .block_by_height(height)
+            .await
+            .map_err(|err| Error::NetworkInfoError(err.to_string()))?;
+        match response {
+            JsonResult::Ok(n) => Ok(n),
+            JsonResult::Err(err) => Err(Error::NetworkInfoError(err.to_string())),
+        }
+    }
+
+    pub async fn get_block_by_hash(&self, indep_hash: &BlockHash) -> Result<Block, Error> {
+        let response = self
+            .0
+            .block(&indep_hash.to_string())
+            .await
+            .map_err(|err| Error::NetworkInfoError(err.to_string()))?;
+        match response {
+            JsonResult::Ok(n) => Ok(n),
+            JsonResult::Err(err) => Err(Error::NetworkInfoError(err.to_string())),
+        }
+    }
+
+    pub async fn get_block_by_height(&self, height: BlockHeight) -> Result<Block, Error> {
+        let response = self
+            .0
+            .block_at_height(height.get())
             .await
-            .map_err(|err| ResponseError::InternalError(err.to_string()))?;
+            .map_err(|err| Error::NetworkInfoError(err.to_string()))?;
         match response {
             JsonResult::Ok(n) => Ok(n),
-            JsonResult::Err(err) => Err(err),
+            JsonResult::Err(err) => Err(Error::NetworkInfoError(err.to_string())),
+        }
+    }
+}
+
+/// Feed the AR price is read from. RedStone only quotes USD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceSource {
+    CoinGecko,
+    RedStone,
+}
+
+/// Protocol parameters the client depends on, which test networks may set differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolParams {
+    /// Largest data posted with the transaction header, larger data is posted chunk by chunk.
+    pub max_tx_data: u64,
+}
+
+impl ProtocolParams {
+    pub const MAINNET: ProtocolParams = ProtocolParams {
+        max_tx_data: MAX_TX_DATA,
+    };
+
+    /// ArLocal takes data with the transaction header up to the mainnet limit.
+    pub const TESTNET: ProtocolParams = ProtocolParams {
+        max_tx_data: MAX_TX_DATA,
+    };
+}
+
+impl Default for ProtocolParams {
+    fn default() -> Self {
+        Self::MAINNET
+    }
+}
+
+/// Environment a client is configured for, with its gateway, price source and protocol
+/// parameters.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Network {
+    #[default]
+    Mainnet,
+    /// ArLocal test network at [`TESTNET_BASE_URL`]. Its tokens have no price.
+    Testnet,
+    /// Any other network, e.g. ArLocal or a private deployment.
+    Custom {
+        url: Url,
+        /// Feed of the AR price, `None` if the tokens of the network have no price.
+        price_source: Option<PriceSource>,
+        params: ProtocolParams,
+    },
+}
+
+impl Network {
+    /// Network at `url` with the protocol parameters of mainnet and no price source.
+    pub fn custom(url: Url) -> Self {
+        Network::Custom {
+            url,
+            price_source: None,
+            params: ProtocolParams::default(),
+        }
+    }
+
+    pub fn url(&self) -> Url {
+        match self {
+            Network::Mainnet => Url::parse(ARWEAVE_BASE_URL).unwrap(), //Checked unwrap
+            Network::Testnet => Url::parse(TESTNET_BASE_URL).unwrap(), //Checked unwrap
+            Network::Custom { url, .. } => url.clone(),
+        }
+    }
+
+    pub fn price_source(&self) -> Option<PriceSource> {
+        match self {
+            Network::Mainnet => Some(PriceSource::CoinGecko),
+            Network::Testnet => None,
+            Network::Custom { price_source, .. } => *price_source,
+        }
+    }
+
+    pub fn params(&self) -> ProtocolParams {
+        match self {
+            Network::Mainnet => ProtocolParams::MAINNET,
+            Network::Testnet => ProtocolParams::TESTNET,
+            Network::Custom { params, .. } => *params,
         }
     }
 }
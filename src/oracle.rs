@@ -0,0 +1,180 @@
+//! Conversion of winston amounts to fiat currencies from a public price feed.
+
+use std::{collections::HashMap, sync::Arc};
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+pub use crate::network::PriceSource;
+use crate::{
+    currency::{Winston, WINSTONS_PER_AR},
+    error::Error,
+    transport::{default_transport, HttpRequest, HttpTransport, ReqwestTransport},
+};
+
+pub const COINGECKO_URL: &str = "https://api.coingecko.com/";
+pub const REDSTONE_URL: &str = "https://api.redstone.finance/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fiat {
+    Usd,
+    Eur,
+}
+
+impl Fiat {
+    /// Lowercase ISO 4217 code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Fiat::Usd => "usd",
+            Fiat::Eur => "eur",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RedStonePrice {
+    value: f64,
+}
+
+pub struct PriceOracle {
+    transport: Arc<dyn HttpTransport>,
+    source: PriceSource,
+    base_url: url::Url,
+}
+
+impl PriceOracle {
+    pub fn new(source: PriceSource) -> Self {
+        let base_url = match source {
+            PriceSource::CoinGecko => COINGECKO_URL,
+            PriceSource::RedStone => REDSTONE_URL,
+        };
+        Self {
+            transport: default_transport(),
+            source,
+            base_url: url::Url::parse(base_url).unwrap(), //Checked unwrap
+        }
+    }
+
+    /// Sends requests to `base_url` instead of the public API of the source, e.g. a proxy or
+    /// a paid plan endpoint.
+    pub fn with_base_url(mut self, base_url: url::Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.transport = Arc::new(ReqwestTransport(client));
+        self
+    }
+
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    async fn get<T: DeserializeOwned>(&self, url: url::Url) -> Result<T, Error> {
+        let error = |e: Error| Error::OracleGetPriceError(e.to_string());
+        let res = self
+            .transport
+            .send(HttpRequest::get(url))
+            .await
+            .map_err(error)?;
+        if !res.status.is_success() {
+            return Err(error(Error::from_response(&res)));
+        }
+        res.json().map_err(error)
+    }
+
+    /// Price of one AR in `fiat`.
+    pub async fn price(&self, fiat: Fiat) -> Result<f64, Error> {
+        match self.source {
+            PriceSource::CoinGecko => {
+                let url = self
+                    .base_url
+                    .join(&format!(
+                        "api/v3/simple/price?ids=arweave&vs_currencies={}",
+                        fiat.code()
+                    ))
+                    .map_err(Error::UrlParseError)?;
+                let prices: HashMap<String, HashMap<String, f64>> = self.get(url).await?;
+                prices
+                    .get("arweave")
+                    .and_then(|prices| prices.get(fiat.code()))
+                    .copied()
+                    .ok_or_else(|| Error::OracleGetPriceError(format!("no {} price", fiat.code())))
+            }
+            PriceSource::RedStone => {
+                if fiat != Fiat::Usd {
+                    return Err(Error::OracleGetPriceError(format!(
+                        "no {} price",
+                        fiat.code()
+                    )));
+                }
+                let url = self
+                    .base_url
+                    .join("prices?symbol=AR&provider=redstone&limit=1")
+                    .map_err(Error::UrlParseError)?;
+                let prices: Vec<RedStonePrice> = self.get(url).await?;
+                prices
+                    .first()
+                    .map(|price| price.value)
+                    .ok_or_else(|| Error::OracleGetPriceError("no usd price".to_string()))
+            }
+        }
+    }
+
+    /// Value of `amount` in `fiat`, as an estimate for display.
+    pub async fn to_fiat(&self, amount: Winston, fiat: Fiat) -> Result<f64, Error> {
+        let price = self.price(fiat).await?;
+        Ok(amount.as_u128() as f64 / WINSTONS_PER_AR as f64 * price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::{Method::GET, MockServer};
+    use tokio_test::block_on;
+
+    use crate::{currency::Winston, error::Error};
+
+    use super::{Fiat, PriceOracle, PriceSource};
+
+    #[test]
+    fn test_coingecko_price() -> Result<(), Error> {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v3/simple/price")
+                .query_param("ids", "arweave")
+                .query_param("vs_currencies", "eur");
+            then.status(200)
+                .json_body(serde_json::json!({ "arweave": { "eur": 8.0 } }));
+        });
+        let oracle = PriceOracle::new(PriceSource::CoinGecko)
+            .with_base_url(url::Url::parse(&server.url("/")).unwrap());
+
+        assert_eq!(block_on(oracle.price(Fiat::Eur))?, 8.0);
+        let value = block_on(oracle.to_fiat(Winston::from_ar("1.5")?, Fiat::Eur))?;
+        assert!((value - 12.0).abs() < 1e-9);
+        assert!(block_on(oracle.price(Fiat::Usd)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_redstone_price() -> Result<(), Error> {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/prices").query_param("symbol", "AR");
+            then.status(200)
+                .json_body(serde_json::json!([{ "symbol": "AR", "value": 10.5 }]));
+        });
+        let oracle = PriceOracle::new(PriceSource::RedStone)
+            .with_base_url(url::Url::parse(&server.url("/")).unwrap());
+
+        assert_eq!(block_on(oracle.price(Fiat::Usd))?, 10.5);
+        assert!(matches!(
+            block_on(oracle.price(Fiat::Eur)),
+            Err(Error::OracleGetPriceError(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+//! Client-side rate limiting of the requests sent to each gateway, so that bulk uploads stay
+//! below the limits public gateways ban clients for.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+
+use crate::{
+    error::Error,
+    runtime::{sleep, Instant},
+    transport::{HttpRequest, HttpResponse, HttpTransport, StreamingResponse},
+};
+
+/// Sustained rate and burst size allowed per gateway.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    requests_per_second: f64,
+    burst: u32,
+}
+
+impl RateLimit {
+    /// Fails unless `requests_per_second` is a positive, finite number. `burst` is at least
+    /// one.
+    pub fn new(requests_per_second: f64, burst: u32) -> Result<Self, Error> {
+        if !(requests_per_second.is_finite() && requests_per_second > 0.0) {
+            return Err(Error::InvalidRateLimit(format!(
+                "{} requests per second",
+                requests_per_second
+            )));
+        }
+        Ok(Self {
+            requests_per_second,
+            burst: burst.max(1),
+        })
+    }
+
+    pub fn requests_per_second(&self) -> f64 {
+        self.requests_per_second
+    }
+
+    /// Requests that can be sent at once after a pause.
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket per gateway, identified by host and port.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a token for the gateway of `url`, returning how long the request must wait for
+    /// it. Requests reserve tokens in order, so waiting requests are sent in turn.
+    pub fn reserve(&self, url: &url::Url) -> Duration {
+        self.reserve_at(url, Instant::now())
+    }
+
+    fn reserve_at(&self, url: &url::Url, now: Instant) -> Duration {
+        let key = format!(
+            "{}:{}",
+            url.host_str().unwrap_or_default(),
+            url.port_or_known_default().unwrap_or_default()
+        );
+        let burst = self.limit.burst as f64;
+        let mut buckets = self.buckets.lock().unwrap(); //Checked unwrap
+        let bucket = buckets.entry(key).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.limit.requests_per_second).min(burst) - 1.0;
+        bucket.updated = now;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::try_from_secs_f64(-bucket.tokens / self.limit.requests_per_second)
+                .unwrap_or(Duration::MAX)
+        }
+    }
+
+    pub async fn acquire(&self, url: &url::Url) {
+        let delay = self.reserve(url);
+        if !delay.is_zero() {
+            sleep(delay).await;
+        }
+    }
+}
+
+/// Transport waiting for the [`RateLimiter`] before every request.
+pub struct RateLimitedTransport {
+    inner: Arc<dyn HttpTransport>,
+    limiter: RateLimiter,
+}
+
+impl RateLimitedTransport {
+    pub fn new(inner: Arc<dyn HttpTransport>, limit: RateLimit) -> Self {
+        Self {
+            inner,
+            limiter: RateLimiter::new(limit),
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl HttpTransport for RateLimitedTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        self.limiter.acquire(&request.url).await;
+        self.inner.send(request).await
+    }
+
+    async fn send_streaming(&self, request: HttpRequest) -> Result<StreamingResponse, Error> {
+        self.limiter.acquire(&request.url).await;
+        self.inner.send_streaming(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use reqwest::{header::HeaderMap, StatusCode};
+    use tokio_test::block_on;
+
+    use crate::runtime::Instant;
+
+    use crate::{
+        error::Error,
+        transport::{HttpRequest, HttpResponse, HttpTransport},
+    };
+
+    use super::{RateLimit, RateLimitedTransport, RateLimiter};
+
+    /// Answers every request, recording their urls.
+    #[derive(Default)]
+    struct RecordingTransport(Mutex<Vec<url::Url>>);
+
+    #[async_trait]
+    impl HttpTransport for RecordingTransport {
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+            self.0.lock().unwrap().push(request.url);
+            Ok(HttpResponse {
+                status: StatusCode::OK,
+                headers: HeaderMap::new(),
+                body: Bytes::new(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_new() {
+        for requests_per_second in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                RateLimit::new(requests_per_second, 1),
+                Err(Error::InvalidRateLimit(_))
+            ));
+        }
+        assert_eq!(RateLimit::new(0.5, 0).unwrap().burst(), 1);
+
+        // Waits too long to represent are capped instead of panicking.
+        let limiter = RateLimiter::new(RateLimit::new(f64::MIN_POSITIVE, 1).unwrap());
+        let gateway = url::Url::parse("https://arweave.net/tx").unwrap();
+        let now = Instant::now();
+        assert_eq!(limiter.reserve_at(&gateway, now), Duration::ZERO);
+        assert_eq!(limiter.reserve_at(&gateway, now), Duration::MAX);
+    }
+
+    #[test]
+    fn test_reserve() {
+        let limiter = RateLimiter::new(RateLimit::new(10.0, 2).unwrap());
+        let gateway = url::Url::parse("https://arweave.net/tx").unwrap();
+        let other = url::Url::parse("https://ar-io.net/tx").unwrap();
+        let now = Instant::now();
+
+        assert_eq!(limiter.reserve_at(&gateway, now), Duration::ZERO);
+        assert_eq!(limiter.reserve_at(&gateway, now), Duration::ZERO);
+        assert_eq!(
+            limiter.reserve_at(&gateway, now),
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            limiter.reserve_at(&gateway, now),
+            Duration::from_millis(200)
+        );
+        // The bucket refills at the sustained rate.
+        let later = now + Duration::from_millis(500);
+        assert_eq!(limiter.reserve_at(&gateway, later), Duration::ZERO);
+        // Gateways are limited separately.
+        assert_eq!(limiter.reserve_at(&other, now), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_rate_limited_transport() -> Result<(), Error> {
+        let inner = Arc::new(RecordingTransport::default());
+        let transport = RateLimitedTransport::new(inner.clone(), RateLimit::new(0.01, 2)?);
+        let gateway = url::Url::parse("https://arweave.net/price/0").unwrap();
+        let other = url::Url::parse("https://ar-io.net/price/0").unwrap();
+
+        // The burst is sent without waiting and takes the tokens of the gateway.
+        for _ in 0..2 {
+            block_on(transport.send(HttpRequest::get(gateway.clone())))?;
+        }
+        assert_eq!(inner.0.lock().unwrap().len(), 2);
+        let now = Instant::now();
+        let wait = transport.limiter.reserve_at(&gateway, now);
+        assert!(wait > Duration::from_secs(90) && wait <= Duration::from_secs(100));
+        assert_eq!(transport.limiter.reserve_at(&other, now), Duration::ZERO);
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+//! Retrying of transient failures with exponential backoff.
+
+use std::{future::Future, time::Duration};
+
+use rand::Rng;
+
+use crate::{
+    consts::{CHUNKS_RETRIES, CHUNKS_RETRY_SLEEP},
+    error::Error,
+    runtime::sleep,
+};
+
+/// Classifies the errors worth retrying, see [`Error::is_retryable`].
+pub fn is_transient(err: &Error) -> bool {
+    err.is_retryable()
+}
+
+/// How failed requests are retried. The delay before retry `n` is `base_delay * 2^n`, capped
+/// at `max_delay` and reduced by a random fraction of up to `jitter`.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Between 0 (fixed delays) and 1.
+    pub jitter: f64,
+    pub retry_on: fn(&Error) -> bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: CHUNKS_RETRIES as u32,
+            base_delay: Duration::from_secs(CHUNKS_RETRY_SLEEP),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.5,
+            retry_on: is_transient,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy making a single attempt.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn retry_on(mut self, retry_on: fn(&Error) -> bool) -> Self {
+        self.retry_on = retry_on;
+        self
+    }
+
+    /// Delay before the retry following attempt number `attempt`, starting at 0.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        if self.jitter > 0.0 {
+            delay.mul_f64(1.0 - rand::thread_rng().gen_range(0.0..=self.jitter))
+        } else {
+            delay
+        }
+    }
+
+    /// Runs `operation` until it succeeds, fails with an error that should not be retried,
+    /// or runs out of attempts. The last error is returned.
+    pub async fn retry<T, F, Fut>(&self, operation: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        self.retry_with(operation, |_, _| {}).await
+    }
+
+    /// Same as [`RetryPolicy::retry`], calling `on_retry` with the number of the failed
+    /// attempt and its error before every retry.
+    pub async fn retry_with<T, F, Fut, R>(
+        &self,
+        mut operation: F,
+        mut on_retry: R,
+    ) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+        R: FnMut(u32, &Error),
+    {
+        let mut attempt = 0;
+        loop {
+            match operation().await {
+                Ok(value) => return Ok(value),
+                Err(err) if attempt + 1 < self.max_attempts && (self.retry_on)(&err) => {
+                    on_retry(attempt, &err);
+                    let delay = self.delay(attempt);
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(attempt, error = %err, ?delay, "retrying");
+                    sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Same as [`RetryPolicy::retry`] for blocking operations, sleeping the current thread
+    /// between attempts.
+    #[cfg(feature = "blocking")]
+    pub fn retry_blocking<T, F>(&self, mut operation: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Result<T, Error>,
+    {
+        let mut attempt = 0;
+        loop {
+            match operation() {
+                Ok(value) => return Ok(value),
+                Err(err) if attempt + 1 < self.max_attempts && (self.retry_on)(&err) => {
+                    let delay = self.delay(attempt);
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(attempt, error = %err, ?delay, "retrying");
+                    std::thread::sleep(delay);
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, time::Duration};
+
+    use tokio_test::block_on;
+
+    use crate::error::Error;
+
+    use super::RetryPolicy;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::default()
+            .max_attempts(4)
+            .base_delay(Duration::from_millis(1))
+            .jitter(0.0)
+    }
+
+    #[test]
+    fn test_delay() {
+        let policy = RetryPolicy::default()
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(500))
+            .jitter(0.0);
+        assert_eq!(policy.delay(0), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(400));
+        assert_eq!(policy.delay(3), Duration::from_millis(500));
+        assert_eq!(policy.delay(40), Duration::from_millis(500));
+
+        let policy = policy.jitter(0.5);
+        for _ in 0..10 {
+            let delay = policy.delay(1);
+            assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn test_retry_transient() {
+        let attempts = Cell::new(0);
+        let res = block_on(policy().retry(|| async {
+            attempts.set(attempts.get() + 1);
+            if attempts.get() < 3 {
+                Err(Error::UnexpectedStatus {
+                    status: 503,
+                    body: String::new(),
+                })
+            } else {
+                Ok(attempts.get())
+            }
+        }));
+        assert_eq!(res.unwrap(), 3);
+
+        attempts.set(0);
+        let res: Result<(), Error> = block_on(policy().retry(|| async {
+            attempts.set(attempts.get() + 1);
+            Err(Error::UnexpectedStatus {
+                status: 502,
+                body: String::new(),
+            })
+        }));
+        assert!(matches!(
+            res,
+            Err(Error::UnexpectedStatus { status: 502, .. })
+        ));
+        assert_eq!(attempts.get(), 4);
+
+        let mut retried = vec![];
+        let _: Result<(), Error> = block_on(policy().retry_with(
+            || async {
+                Err(Error::UnexpectedStatus {
+                    status: 500,
+                    body: String::new(),
+                })
+            },
+            |attempt, _| retried.push(attempt),
+        ));
+        assert_eq!(retried, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_no_retry_on_permanent_error() {
+        let attempts = Cell::new(0);
+        let res: Result<(), Error> = block_on(policy().retry(|| async {
+            attempts.set(attempts.get() + 1);
+            Err(Error::UnexpectedStatus {
+                status: 400,
+                body: String::new(),
+            })
+        }));
+        assert!(matches!(
+            res,
+            Err(Error::UnexpectedStatus { status: 400, .. })
+        ));
+        assert_eq!(attempts.get(), 1);
+
+        attempts.set(0);
+        let res: Result<(), Error> = block_on(policy().retry_on(|_| true).retry(|| async {
+            attempts.set(attempts.get() + 1);
+            Err(Error::UnexpectedStatus {
+                status: 400,
+                body: String::new(),
+            })
+        }));
+        assert!(res.is_err());
+        assert_eq!(attempts.get(), 4);
+    }
+}
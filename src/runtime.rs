@@ -0,0 +1,70 @@
+//! Timers and clocks of the async runtime, selected by the `rt-tokio` (default) and
+//! `rt-async-std` features. With neither of them, timers run on a background thread and
+//! work with any executor. Browsers have no system clock, so JavaScript timers and
+//! `performance.now()` are used on wasm32.
+//!
+//! The crate does not spawn tasks, concurrent requests are polled from the calling task.
+
+use std::{future::Future, time::Duration};
+
+use futures::future::{select, Either};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_time::Instant;
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep(duration: Duration) {
+    gloo_timers::future::sleep(duration).await;
+}
+
+// async-std is opt-in, so it takes precedence over the default tokio runtime.
+#[cfg(all(not(target_arch = "wasm32"), feature = "rt-async-std"))]
+pub(crate) async fn sleep(duration: Duration) {
+    async_std::task::sleep(duration).await;
+}
+
+#[cfg(all(
+    not(target_arch = "wasm32"),
+    not(feature = "rt-async-std"),
+    feature = "rt-tokio"
+))]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+#[cfg(all(
+    not(target_arch = "wasm32"),
+    not(feature = "rt-async-std"),
+    not(feature = "rt-tokio")
+))]
+pub(crate) async fn sleep(duration: Duration) {
+    futures_timer::Delay::new(duration).await;
+}
+
+/// Output of `future`, or `None` if it did not complete within `duration`.
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    futures::pin_mut!(future);
+    let delay = sleep(duration);
+    futures::pin_mut!(delay);
+    match select(future, delay).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+// tokio timers panic outside of a tokio runtime.
+#[cfg(all(test, any(feature = "rt-async-std", not(feature = "rt-tokio"))))]
+mod tests {
+    use std::time::Duration;
+
+    use super::{sleep, Instant};
+
+    #[test]
+    fn test_sleep_outside_of_runtime() {
+        let start = Instant::now();
+        futures::executor::block_on(sleep(Duration::from_millis(20)));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+}
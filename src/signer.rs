@@ -1,20 +1,19 @@
-use data_encoding::BASE64URL;
 use jsonwebkey as jwk;
-use rand::thread_rng;
-use rsa::{pkcs8::DecodePublicKey, PaddingScheme, PublicKey, RsaPublicKey};
-use sha2::Digest;
-use std::path::PathBuf;
+use std::{io::Read, path::PathBuf};
 
 use crate::{
+    address::Address,
+    bundle::DataItem,
     crypto::{
         base64::Base64,
-        hash::{self, ToItems},
+        hash::{deep_hash_reader, DeepHashItem, ToItems},
         verify, Provider,
     },
     error::Error,
     transaction::Tx,
 };
 
+#[cfg_attr(test, derive(Default))]
 pub struct ArweaveSigner {
     crypto: Box<Provider>,
 }
@@ -24,6 +23,20 @@ impl ArweaveSigner {
         verify::verify(pub_key, message, signature)
     }
 
+    /// Signer backed by any [`Signer`](crate::crypto::sign::Signer) implementation.
+    pub fn new(signer: Box<dyn crate::crypto::sign::Signer>) -> Self {
+        ArweaveSigner {
+            crypto: Box::new(Provider::new(signer)),
+        }
+    }
+
+    /// Signer of data items for the Ed25519 key of a Solana CLI keypair file.
+    #[cfg(feature = "ed25519")]
+    pub fn from_solana_keypair_file(path: &std::path::Path) -> Result<Self, Error> {
+        let signer = crate::crypto::ed25519::Ed25519Signer::from_solana_keypair_file(path)?;
+        Ok(Self::new(Box::new(signer)))
+    }
+
     pub fn from_keypair_path(keypair_path: PathBuf) -> Result<ArweaveSigner, Error> {
         let crypto = Provider::from_keypair_path(keypair_path)?;
         let signer = ArweaveSigner {
@@ -32,21 +45,96 @@ impl ArweaveSigner {
         Ok(signer)
     }
 
-    pub fn from_jwk(jwk: jwk::JsonWebKey) -> Self {
-        let crypto = Provider::from_jwk(jwk);
-        ArweaveSigner {
+    pub fn from_jwk(jwk: jwk::JsonWebKey) -> Result<Self, Error> {
+        let crypto = Provider::from_jwk(jwk)?;
+        Ok(ArweaveSigner {
             crypto: Box::new(crypto),
-        }
+        })
+    }
+
+    /// Parses the contents of a JWK wallet file.
+    pub fn from_jwk_str(jwk: &str) -> Result<Self, Error> {
+        Ok(ArweaveSigner {
+            crypto: Box::new(Provider::from_jwk_str(jwk)?),
+        })
+    }
+
+    pub fn from_jwk_value(jwk: serde_json::Value) -> Result<Self, Error> {
+        Ok(ArweaveSigner {
+            crypto: Box::new(Provider::from_jwk_value(jwk)?),
+        })
+    }
+
+    /// Reads the JWK wallet JSON held by the `ARWEAVE_WALLET_JWK` environment variable.
+    pub fn from_env() -> Result<Self, Error> {
+        Ok(ArweaveSigner {
+            crypto: Box::new(Provider::from_env()?),
+        })
+    }
+
+    /// Creates a signer for a new random RSA-4096 keypair, returned as a JWK to be saved as
+    /// a wallet file.
+    pub fn generate() -> Result<(Self, jwk::JsonWebKey), Error> {
+        let (crypto, jwk) = Provider::generate()?;
+        let signer = ArweaveSigner {
+            crypto: Box::new(crypto),
+        };
+        Ok((signer, jwk))
     }
 
-    pub fn sign_transaction(&self, mut transaction: Tx) -> Result<Tx, Error> {
-        let deep_hash_item = transaction.to_deep_hash_item()?;
+    /// Saves the private key to `path` encrypted with `passphrase`, see
+    /// [`crate::crypto::keystore`]. Fails for signers whose key can't be exported.
+    #[cfg(feature = "keystore")]
+    pub fn to_encrypted_file(&self, path: &std::path::Path, passphrase: &str) -> Result<(), Error> {
+        let jwk = self.crypto.signer.export_jwk().ok_or_else(|| {
+            Error::KeystoreError("the key of the signer can't be exported".to_string())
+        })?;
+        crate::crypto::keystore::Keystore::encrypt(&jwk, passphrase)?.write(path)
+    }
+
+    #[cfg(feature = "keystore")]
+    pub fn from_encrypted_file(path: &std::path::Path, passphrase: &str) -> Result<Self, Error> {
+        let crypto = Provider::from_encrypted_file(path, passphrase)?;
+        Ok(ArweaveSigner {
+            crypto: Box::new(crypto),
+        })
+    }
+
+    /// Signs `transaction` once checked with [`Tx::validate`].
+    pub fn sign_transaction(&self, transaction: Tx) -> Result<Tx, Error> {
+        transaction.sign(&self.crypto)
+    }
+
+    /// Sets the owner and signature type of the data item from this signer, then signs it.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(data_size = data_item.data.0.len())))]
+    pub fn sign_data_item(&self, mut data_item: DataItem) -> Result<DataItem, Error> {
+        data_item.owner = self.crypto.public_key();
+        data_item.signature_type = self.crypto.signer.signature_type();
+        let deep_hash_item = data_item.to_deep_hash_item()?;
+        let signature_data = self.crypto.deep_hash(deep_hash_item);
+        data_item.signature = self.crypto.sign(&signature_data)?;
+        data_item.id = data_item.compute_id()?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(id = %data_item.id, "signed data item");
+        Ok(data_item)
+    }
+
+    /// Same as [`ArweaveSigner::sign_data_item`] for data read from `data` to the end instead
+    /// of held by the item, which is left as is. Items signed this way are written with their
+    /// data by [`crate::bundle::BundleWriter`].
+    pub fn sign_data_item_reader<R: Read>(
+        &self,
+        mut data_item: DataItem,
+        data: R,
+    ) -> Result<DataItem, Error> {
+        data_item.owner = self.crypto.public_key();
+        data_item.signature_type = self.crypto.signer.signature_type();
+        let data_hash = DeepHashItem::Hash(deep_hash_reader(data)?);
+        let deep_hash_item = data_item.to_deep_hash_item_with_data(data_hash)?;
         let signature_data = self.crypto.deep_hash(deep_hash_item);
-        let signature = self.crypto.sign(&signature_data)?;
-        let id = self.crypto.hash_sha256(&signature.0);
-        transaction.signature = signature;
-        transaction.id = Base64(id.to_vec());
-        Ok(transaction)
+        data_item.signature = self.crypto.sign(&signature_data)?;
+        data_item.id = data_item.compute_id()?;
+        Ok(data_item)
     }
 
     pub fn sign(&self, message: &[u8]) -> Result<Base64, Error> {
@@ -54,38 +142,10 @@ impl ArweaveSigner {
     }
 
     pub fn verify_transaction(transaction: &Tx) -> Result<(), Error> {
-        if transaction.signature.is_empty() {
-            return Err(Error::UnsignedTransaction);
-        }
-
-        let deep_hash_item = transaction.to_deep_hash_item()?;
-        let message = hash::deep_hash(deep_hash_item);
-        let signature = &transaction.signature;
-
-        let jwt_str = format!(
-            "{{\"kty\":\"RSA\",\"e\":\"AQAB\",\"n\":\"{}\"}}",
-            BASE64URL.encode(&transaction.owner.0)
-        );
-        let jwk: jwk::JsonWebKey = jwt_str.parse().unwrap();
-
-        let pub_key = RsaPublicKey::from_public_key_der(jwk.key.to_der().as_slice()).unwrap();
-        let mut hasher = sha2::Sha256::new();
-        hasher.update(message);
-        let hashed = &hasher.finalize();
-
-        let rng = thread_rng();
-        let padding = PaddingScheme::PSS {
-            salt_rng: Box::new(rng),
-            digest: Box::new(sha2::Sha256::new()),
-            salt_len: None,
-        };
-        pub_key
-            .verify(padding, hashed, &signature.0)
-            .map(|_| ())
-            .map_err(|_| Error::InvalidSignature)
+        crate::verify::verify_transaction(transaction)
     }
 
-    pub fn wallet_address(&self) -> Base64 {
+    pub fn wallet_address(&self) -> Address {
         self.crypto.wallet_address()
     }
 
@@ -110,14 +170,6 @@ mod tests {
 
     use super::{jwk, ArweaveSigner, Base64};
 
-    impl Default for ArweaveSigner {
-        fn default() -> Self {
-            Self {
-                crypto: Default::default(),
-            }
-        }
-    }
-
     #[test]
     fn test_sign_verify() -> Result<(), Error> {
         let message = Base64(
@@ -137,7 +189,7 @@ mod tests {
         let result = ArweaveSigner::verify(&pubk.0, &message.0, &signature.0);
         assert!(result.is_ok());
 
-        let signer = ArweaveSigner::from_jwk(jwk);
+        let signer = ArweaveSigner::from_jwk(jwk)?;
         let signature = signer.sign(&message.0)?;
         let pubk = signer.get_public_key();
         let result = ArweaveSigner::verify(&pubk.0, &message.0, &signature.0);
@@ -0,0 +1,382 @@
+//! Interactions with SmartWeave contracts, including Warp contracts. Interactions are
+//! transactions or data items tagged with the contract id and the JSON input of the call, the
+//! state is evaluated by replaying them, which is left to the Warp gateway.
+
+use std::{collections::HashMap, sync::Arc};
+
+use reqwest::StatusCode;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    bundle::DataItem,
+    crypto::base64::Base64,
+    error::Error,
+    transaction::tags::Tag,
+    transport::{default_transport, HttpRequest, HttpTransport},
+    tx_id::TxId,
+};
+
+pub const WARP_GATEWAY_URL: &str = "https://gateway.warp.cc/";
+
+pub const SMARTWEAVE_ACTION: &str = "SmartWeaveAction";
+
+/// Version of the interaction protocol tagged on interactions.
+pub const SMARTWEAVE_VERSION: &str = "0.3.0";
+
+pub const SMARTWEAVE_CONTRACT: &str = "SmartWeaveContract";
+
+pub const CONTRACT: &str = "Contract";
+pub const INPUT: &str = "Input";
+pub const CONTRACT_SRC: &str = "Contract-Src";
+pub const INIT_STATE: &str = "Init-State";
+
+/// Call of the contract `contract` with `input`, usually a JSON object with a `function` field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interaction {
+    pub contract: Base64,
+    pub input: serde_json::Value,
+    /// Tags added after the interaction tags.
+    pub tags: Vec<Tag<Base64>>,
+}
+
+impl Interaction {
+    pub fn new<T: Serialize>(contract: Base64, input: &T) -> Result<Self, Error> {
+        Ok(Self {
+            contract,
+            input: serde_json::to_value(input).map_err(Error::SerdeJsonError)?,
+            tags: vec![],
+        })
+    }
+
+    pub fn tag(mut self, name: &str, value: &str) -> Self {
+        self.tags.push(Tag::new(name, value));
+        self
+    }
+
+    /// `App-Name`, `App-Version`, `Contract` and `Input` tags followed by the additional tags.
+    pub fn to_tags(&self) -> Result<Vec<Tag<Base64>>, Error> {
+        let input = serde_json::to_string(&self.input).map_err(Error::SerdeJsonError)?;
+        let mut tags = vec![
+            Tag::app_name(SMARTWEAVE_ACTION),
+            Tag::app_version(SMARTWEAVE_VERSION),
+            Tag::new(CONTRACT, &self.contract.to_string()),
+            Tag::new(INPUT, &input),
+        ];
+        tags.extend(self.tags.iter().cloned());
+        Ok(tags)
+    }
+
+    /// Unsigned data item of the interaction, for contracts evaluated from bundled
+    /// interactions such as Warp contracts.
+    pub fn to_data_item(&self) -> Result<DataItem, Error> {
+        DataItem::new(Base64::empty(), Base64::empty(), self.to_tags()?, vec![])
+    }
+}
+
+/// Atomic asset: data that is its own contract, deployed from the source `contract_src` with
+/// `init_state`. The id of the transaction or data item holding the data is the asset id.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtomicAsset {
+    pub data: Vec<u8>,
+    pub content_type: String,
+    pub contract_src: Base64,
+    pub init_state: serde_json::Value,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Kind of asset, such as `image` or `video`.
+    pub asset_type: Option<String>,
+    /// Tags added after the asset tags.
+    pub tags: Vec<Tag<Base64>>,
+}
+
+impl AtomicAsset {
+    pub fn new<T: Serialize>(
+        data: Vec<u8>,
+        content_type: &str,
+        contract_src: Base64,
+        init_state: &T,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            data,
+            content_type: content_type.to_string(),
+            contract_src,
+            init_state: serde_json::to_value(init_state).map_err(Error::SerdeJsonError)?,
+            title: None,
+            description: None,
+            asset_type: None,
+            tags: vec![],
+        })
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    pub fn asset_type(mut self, asset_type: &str) -> Self {
+        self.asset_type = Some(asset_type.to_string());
+        self
+    }
+
+    pub fn tag(mut self, name: &str, value: &str) -> Self {
+        self.tags.push(Tag::new(name, value));
+        self
+    }
+
+    /// Contract tags, then the `Title`, `Description` and `Type` metadata if set and the
+    /// additional tags.
+    pub fn to_tags(&self) -> Result<Vec<Tag<Base64>>, Error> {
+        let init_state = serde_json::to_string(&self.init_state).map_err(Error::SerdeJsonError)?;
+        let mut tags = vec![
+            Tag::content_type(&self.content_type),
+            Tag::app_name(SMARTWEAVE_CONTRACT),
+            Tag::app_version(SMARTWEAVE_VERSION),
+            Tag::new(CONTRACT_SRC, &self.contract_src.to_string()),
+            Tag::new(INIT_STATE, &init_state),
+        ];
+        for (name, value) in [
+            ("Title", &self.title),
+            ("Description", &self.description),
+            ("Type", &self.asset_type),
+        ] {
+            if let Some(value) = value {
+                tags.push(Tag::new(name, value));
+            }
+        }
+        tags.extend(self.tags.iter().cloned());
+        Ok(tags)
+    }
+
+    /// Unsigned data item holding the asset.
+    pub fn to_data_item(&self) -> Result<DataItem, Error> {
+        DataItem::new(
+            Base64::empty(),
+            Base64::empty(),
+            self.to_tags()?,
+            self.data.clone(),
+        )
+    }
+}
+
+/// Options of a state read. Results listed per interaction are paged.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StateQuery {
+    /// Evaluates the state up to the interaction with this sort key instead of the latest.
+    pub sort_key: Option<String>,
+    /// Includes the validity of every interaction.
+    pub validity: bool,
+    pub page: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+impl StateQuery {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn sort_key(mut self, sort_key: &str) -> Self {
+        self.sort_key = Some(sort_key.to_string());
+        self
+    }
+
+    pub fn validity(mut self, validity: bool) -> Self {
+        self.validity = validity;
+        self
+    }
+
+    pub fn page(mut self, page: u32) -> Self {
+        self.page = Some(page);
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+/// State of a contract evaluated by the gateway, as `serde_json::Value` unless a type is given.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractState<T = serde_json::Value> {
+    pub contract_tx_id: TxId,
+    pub state: T,
+    /// Sort key of the last interaction evaluated.
+    pub sort_key: Option<String>,
+    /// Whether each interaction, by id, was applied.
+    #[serde(default)]
+    pub validity: HashMap<String, bool>,
+    #[serde(default)]
+    pub error_messages: HashMap<String, String>,
+}
+
+/// Read client of the Warp gateway.
+pub struct WarpClient {
+    transport: Arc<dyn HttpTransport>,
+    url: url::Url,
+}
+
+impl Default for WarpClient {
+    fn default() -> Self {
+        Self::new(url::Url::parse(WARP_GATEWAY_URL).unwrap()) //Checked unwrap
+    }
+}
+
+impl WarpClient {
+    pub fn new(url: url::Url) -> Self {
+        Self {
+            transport: default_transport(),
+            url,
+        }
+    }
+
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// State of `contract` evaluated by the gateway.
+    pub async fn get_state<T: DeserializeOwned>(
+        &self,
+        contract: &Base64,
+        query: &StateQuery,
+    ) -> Result<ContractState<T>, Error> {
+        let mut url = self.url.join("v1/contract").map_err(Error::UrlParseError)?;
+        {
+            let mut pairs = url.query_pairs_mut();
+            pairs.append_pair("id", &contract.to_string());
+            if let Some(sort_key) = &query.sort_key {
+                pairs.append_pair("sortKey", sort_key);
+            }
+            if query.validity {
+                pairs.append_pair("validity", "true");
+                pairs.append_pair("errorMessages", "true");
+            }
+            if let Some(page) = query.page {
+                pairs.append_pair("page", &page.to_string());
+            }
+            if let Some(limit) = query.limit {
+                pairs.append_pair("limit", &limit.to_string());
+            }
+        }
+        let res = self.transport.send(HttpRequest::get(url)).await?;
+        if res.status != StatusCode::OK {
+            return Err(Error::from_response(&res));
+        }
+        res.json()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::{Method::GET, MockServer};
+    use serde::Deserialize;
+    use serde_json::json;
+    use tokio_test::block_on;
+
+    use crate::{
+        crypto::base64::Base64,
+        error::Error,
+        transaction::tags::{find_tag_value, Tag},
+    };
+
+    use super::{AtomicAsset, ContractState, Interaction, StateQuery, WarpClient};
+
+    #[test]
+    fn test_interaction_tags() -> Result<(), Error> {
+        let contract = Base64(vec![4; 32]);
+        let interaction = Interaction::new(
+            contract.clone(),
+            &json!({ "function": "transfer", "target": "address", "qty": 10 }),
+        )?
+        .tag("Unix-Time", "1700000000");
+
+        let tags = interaction.to_tags()?;
+        assert_eq!(
+            find_tag_value(&tags, "App-Name").as_deref(),
+            Some("SmartWeaveAction")
+        );
+        assert_eq!(
+            find_tag_value(&tags, "Contract"),
+            Some(contract.to_string())
+        );
+        let input: serde_json::Value =
+            serde_json::from_str(&find_tag_value(&tags, "Input").unwrap()).unwrap();
+        assert_eq!(input["function"], "transfer");
+        assert_eq!(tags.last(), Some(&Tag::new("Unix-Time", "1700000000")));
+        assert_eq!(interaction.to_data_item()?.tags, tags);
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_state() -> Result<(), Error> {
+        #[derive(Deserialize)]
+        struct Token {
+            balances: std::collections::HashMap<String, u64>,
+        }
+
+        let contract = Base64(vec![4; 32]);
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v1/contract")
+                .query_param("id", contract.to_string())
+                .query_param("sortKey", "000001301234,0000000000000,abcd")
+                .query_param("validity", "true")
+                .query_param("limit", "100");
+            then.status(200).json_body(json!({
+                "contractTxId": contract.to_string(),
+                "state": { "ticker": "TKN", "balances": { "address": 10 } },
+                "sortKey": "000001301234,0000000000000,abcd",
+                "validity": { "interaction": true },
+            }));
+        });
+        let client = WarpClient::new(url::Url::parse(&server.url("/")).unwrap());
+        let query = StateQuery::new()
+            .sort_key("000001301234,0000000000000,abcd")
+            .validity(true)
+            .limit(100);
+
+        let state: ContractState = block_on(client.get_state(&contract, &query))?;
+        assert_eq!(state.state["ticker"], "TKN");
+        assert_eq!(state.validity.get("interaction"), Some(&true));
+        let token: ContractState<Token> = block_on(client.get_state(&contract, &query))?;
+        assert_eq!(token.state.balances["address"], 10);
+        mock.assert_hits(2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_atomic_asset_tags() -> Result<(), Error> {
+        let asset = AtomicAsset::new(
+            b"<svg/>".to_vec(),
+            "image/svg+xml",
+            Base64(vec![3; 32]),
+            &json!({ "balances": { "owner": 1 }, "name": "Asset", "ticker": "ATOMIC" }),
+        )?
+        .title("Asset")
+        .asset_type("image");
+
+        let item = asset.to_data_item()?;
+        assert_eq!(item.data.0, b"<svg/>");
+        assert_eq!(
+            find_tag_value(&item.tags, "App-Name").as_deref(),
+            Some("SmartWeaveContract")
+        );
+        assert_eq!(
+            find_tag_value(&item.tags, "Contract-Src"),
+            Some(Base64(vec![3; 32]).to_string())
+        );
+        let init_state: serde_json::Value =
+            serde_json::from_str(&find_tag_value(&item.tags, "Init-State").unwrap()).unwrap();
+        assert_eq!(init_state["balances"]["owner"], 1);
+        assert_eq!(find_tag_value(&item.tags, "Type").as_deref(), Some("image"));
+        assert_eq!(find_tag_value(&item.tags, "Description"), None);
+        Ok(())
+    }
+}
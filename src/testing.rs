@@ -0,0 +1,914 @@
+//! Test doubles: deterministic signers, so that signing flows can be tested without wallet
+//! files in the repository, and gateways to run upload flows against offline, either
+//! [in process](MockGateway) or [ArLocal].
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use lazy_static::lazy_static;
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use reqwest::{
+    header::{HeaderMap, HeaderValue, CONTENT_TYPE},
+    StatusCode,
+};
+use rsa::RsaPrivateKey;
+use serde_json::{json, Value};
+
+use crate::{
+    address::Address,
+    block::{BlockHash, BlockHeight},
+    crypto::{
+        base64::{Base64, Base64Ref},
+        hash::{sha256, sha384},
+        merkle::{generate_data_root, generate_leaves_from_slice, resolve_proofs, validate_path},
+        sign::{RsaSigner, SignatureType, Signer, RSA_KEY_SIZE},
+    },
+    currency::{Winston, WINSTONS_PER_AR},
+    error::Error,
+    network::Network,
+    transaction::Tx,
+    transport::{default_transport, HttpRequest, HttpResponse, HttpTransport},
+    tx_id::TxId,
+    types::Chunk,
+    verify::verify_transaction,
+    Arweave, ArweaveBuilder, ArweaveSigner,
+};
+
+lazy_static! {
+    /// Keys generated so far by seed, as generating RSA-4096 keys takes a while.
+    static ref KEYS: Mutex<HashMap<u64, RsaPrivateKey>> = Mutex::new(HashMap::new());
+}
+
+fn private_key(seed: u64) -> RsaPrivateKey {
+    let mut keys = KEYS.lock().unwrap(); //Checked unwrap
+    keys.entry(seed)
+        .or_insert_with(|| {
+            let mut rng = ChaCha20Rng::seed_from_u64(seed);
+            RsaPrivateKey::new(&mut rng, RSA_KEY_SIZE).unwrap() //Checked unwrap
+        })
+        .clone()
+}
+
+/// RSA-4096 [`Signer`] whose key is generated from `seed`, so the same seed always gives the
+/// same wallet and address. Signatures are still randomized, as for any Arweave wallet.
+///
+/// The keys are not secret and must never hold funds.
+pub struct TestSigner {
+    seed: u64,
+    signer: RsaSigner,
+}
+
+impl TestSigner {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            signer: RsaSigner::new(private_key(seed)),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl Default for TestSigner {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Signer for TestSigner {
+    fn sign(&self, message: &[u8]) -> Result<Base64, Error> {
+        self.signer.sign(message)
+    }
+
+    fn public_key(&self) -> Base64 {
+        self.signer.public_key()
+    }
+
+    fn signature_type(&self) -> SignatureType {
+        SignatureType::Arweave
+    }
+}
+
+impl From<TestSigner> for ArweaveSigner {
+    fn from(signer: TestSigner) -> Self {
+        ArweaveSigner::new(Box::new(signer))
+    }
+}
+
+/// Base url of the clients of [`MockGateway`]. Requests to it never leave the process.
+pub const MOCK_GATEWAY_URL: &str = "http://gateway.mock/";
+
+/// Default url of [ArLocal](https://github.com/textury/arlocal), started with `npx arlocal`.
+pub const ARLOCAL_URL: &str = crate::consts::TESTNET_BASE_URL;
+
+const GENESIS_TIMESTAMP: u64 = 1_560_000_000;
+const BLOCK_TIME: u64 = 120;
+const DEFAULT_PAGE_SIZE: usize = 10;
+
+fn block_hash(height: BlockHeight) -> BlockHash {
+    BlockHash::new(sha384(&height.get().to_be_bytes()))
+}
+
+fn response(status: StatusCode, body: impl Into<Bytes>) -> HttpResponse {
+    HttpResponse {
+        status,
+        headers: HeaderMap::new(),
+        body: body.into(),
+    }
+}
+
+fn bad_request(reason: impl fmt::Display) -> HttpResponse {
+    response(StatusCode::BAD_REQUEST, reason.to_string())
+}
+
+fn status_response(status: StatusCode) -> HttpResponse {
+    response(status, status.canonical_reason().unwrap_or_default())
+}
+
+fn json_response(value: &Value) -> HttpResponse {
+    let mut response = response(StatusCode::OK, value.to_string());
+    response
+        .headers
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+fn amount(winstons: u128) -> Value {
+    let per_ar = WINSTONS_PER_AR as u128;
+    json!({
+        "winston": winstons.to_string(),
+        "ar": format!("{}.{:012}", winstons / per_ar, winstons % per_ar),
+    })
+}
+
+/// Strings of the GraphQL variable `name`, `None` if it is not set.
+fn strings(variables: &Value, name: &str) -> Option<Vec<String>> {
+    variables[name].as_array().map(|values| {
+        values
+            .iter()
+            .filter_map(|value| value.as_str().map(str::to_string))
+            .collect()
+    })
+}
+
+/// Page of `items` after the `after` cursor, the index of the last item of the previous page.
+fn page(items: Vec<Value>, variables: &Value) -> Value {
+    let after = variables["after"]
+        .as_str()
+        .and_then(|cursor| cursor.parse::<usize>().ok())
+        .map_or(0, |cursor| cursor + 1);
+    let first = variables["first"]
+        .as_u64()
+        .map_or(DEFAULT_PAGE_SIZE, |first| first as usize);
+    let has_next_page = items.len() > after + first;
+    let edges: Vec<Value> = items
+        .into_iter()
+        .enumerate()
+        .skip(after)
+        .take(first)
+        .map(|(cursor, node)| json!({ "cursor": cursor.to_string(), "node": node }))
+        .collect();
+    json!({ "pageInfo": { "hasNextPage": has_next_page }, "edges": edges })
+}
+
+struct MockBlock {
+    height: BlockHeight,
+    hash: BlockHash,
+    previous: Option<BlockHash>,
+    timestamp: u64,
+}
+
+impl MockBlock {
+    fn new(height: BlockHeight, previous: Option<BlockHash>) -> Self {
+        Self {
+            height,
+            hash: block_hash(height),
+            previous,
+            timestamp: GENESIS_TIMESTAMP + height.get() * BLOCK_TIME,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "id": self.hash,
+            "timestamp": self.timestamp,
+            "height": self.height,
+            "previous": self.previous.map(|hash| hash.to_string()).unwrap_or_default(),
+        })
+    }
+}
+
+struct MockTx {
+    /// Header as posted, without data.
+    header: Tx,
+    /// Chunks received so far with their data path, by the offset of their last byte.
+    chunks: BTreeMap<usize, (Vec<u8>, Vec<u8>)>,
+    /// Absolute weave offset of the last byte of the data.
+    weave_offset: u64,
+    block: Option<BlockHeight>,
+}
+
+impl MockTx {
+    /// Data of the transaction, `None` until all of its chunks are received.
+    fn data(&self) -> Option<Vec<u8>> {
+        let data: Vec<u8> = self
+            .chunks
+            .values()
+            .flat_map(|(chunk, _)| chunk.iter().copied())
+            .collect();
+        (data.len() as u64 == self.header.data_size).then_some(data)
+    }
+
+    fn to_json(&self, blocks: &[MockBlock]) -> Result<Value, Error> {
+        let header = &self.header;
+        let tags = header
+            .tags
+            .iter()
+            .map(|tag| tag.to_utf8_strings())
+            .collect::<Result<Vec<_>, _>>()?;
+        let content_type = tags
+            .iter()
+            .find(|tag| tag.name.eq_ignore_ascii_case(CONTENT_TYPE.as_str()))
+            .map(|tag| tag.value.clone());
+        Ok(json!({
+            "id": header.id,
+            "anchor": header.last_tx,
+            "signature": header.signature,
+            "recipient": header.target,
+            "owner": {
+                "address": Address::from_owner(&header.owner.0),
+                "key": header.owner,
+            },
+            "fee": amount(header.reward as u128),
+            "quantity": amount(Winston::from(header.quantity).as_u128()),
+            "data": { "size": header.data_size.to_string(), "type": content_type },
+            "tags": tags
+                .iter()
+                .map(|tag| json!({ "name": tag.name, "value": tag.value }))
+                .collect::<Vec<_>>(),
+            "block": self.block.map(|height| blocks[height.get() as usize].to_json()),
+            "bundledIn": null,
+        }))
+    }
+
+    fn matches(&self, variables: &Value) -> bool {
+        let header = &self.header;
+        let within = |name: &str, value: String| {
+            strings(variables, name).is_none_or(|values| values.contains(&value))
+        };
+        let block = &variables["block"];
+        let in_block = match (self.block, block.is_object()) {
+            (_, false) => true,
+            (None, true) => false,
+            (Some(height), true) => {
+                block["min"].as_u64().is_none_or(|min| height.get() >= min)
+                    && block["max"].as_u64().is_none_or(|max| height.get() <= max)
+            }
+        };
+        let tags = variables["tags"].as_array().cloned().unwrap_or_default();
+        let tagged = tags.iter().all(|filter| {
+            let values = strings(filter, "values").unwrap_or_default();
+            header.tags.iter().any(|tag| {
+                tag.to_utf8_strings().is_ok_and(|tag| {
+                    Some(tag.name.as_str()) == filter["name"].as_str()
+                        && values.contains(&tag.value)
+                })
+            })
+        });
+        within("ids", header.id.to_string())
+            && within("owners", Address::from_owner(&header.owner.0).to_string())
+            && within("recipients", header.target.to_string())
+            // Bundles are not unpacked.
+            && strings(variables, "bundledIn").is_none()
+            && in_block
+            && tagged
+    }
+}
+
+struct MockState {
+    txs: HashMap<TxId, MockTx>,
+    /// Ids in the order the transactions were posted.
+    order: Vec<TxId>,
+    /// Blocks by height, starting with the genesis block.
+    blocks: Vec<MockBlock>,
+    weave_size: u64,
+    balances: HashMap<Address, u128>,
+    last_txs: HashMap<Address, TxId>,
+}
+
+impl MockState {
+    fn height(&self) -> BlockHeight {
+        self.blocks[self.blocks.len() - 1].height
+    }
+
+    /// Mines `blocks` blocks, the first one including the pending transactions.
+    fn mine(&mut self, blocks: u64) -> BlockHeight {
+        for _ in 0..blocks {
+            let height = self.height() + 1;
+            let previous = Some(block_hash(self.height()));
+            self.blocks.push(MockBlock::new(height, previous));
+            for tx in self.txs.values_mut().filter(|tx| tx.block.is_none()) {
+                tx.block = Some(height);
+            }
+        }
+        self.height()
+    }
+
+    fn tx(&self, id: &str) -> Result<&MockTx, StatusCode> {
+        let id = TxId::from_str(id).map_err(|_| StatusCode::BAD_REQUEST)?;
+        self.txs.get(&id).ok_or(StatusCode::NOT_FOUND)
+    }
+
+    fn info(&self) -> HttpResponse {
+        let pending = self.txs.values().filter(|tx| tx.block.is_none()).count();
+        json_response(&json!({
+            "network": "arweave.mock",
+            "version": 5,
+            "release": 1,
+            "height": self.height(),
+            "current": block_hash(self.height()),
+            "blocks": self.blocks.len(),
+            "peers": 0,
+            "queue_length": pending,
+            "node_state_latency": 0,
+        }))
+    }
+
+    fn post_tx(&mut self, body: &[u8]) -> Result<HttpResponse, Error> {
+        let tx = match std::str::from_utf8(body)
+            .map_err(|e| e.to_string())
+            .and_then(|body| Tx::from_str(body).map_err(|e| e.to_string()))
+        {
+            Ok(tx) => tx,
+            Err(err) => return Ok(bad_request(err)),
+        };
+        if tx.format != 2 {
+            return Ok(bad_request("only format 2 transactions are accepted"));
+        }
+        if let Err(err) = verify_transaction(&tx) {
+            return Ok(bad_request(err));
+        }
+        let id = TxId::try_from(&tx.id)?;
+        if self.txs.contains_key(&id) {
+            return Ok(response(StatusCode::OK, "OK"));
+        }
+        let owner = Address::from_owner(&tx.owner.0);
+        let quantity = Winston::from(tx.quantity).as_u128();
+        let balance = self.balances.get(&owner).copied().unwrap_or_default();
+        let balance = match balance.checked_sub(tx.reward as u128 + quantity) {
+            Some(balance) => balance,
+            None => return Ok(response(StatusCode::GONE, "You don't have enough tokens")),
+        };
+
+        let mut chunks = BTreeMap::new();
+        if !tx.data.is_empty() {
+            if let Err(err) = tx.verify_data(&tx.data.0) {
+                return Ok(bad_request(err));
+            }
+            let leaves = generate_leaves_from_slice(&tx.data.0)?;
+            let proofs = resolve_proofs(generate_data_root(leaves.clone())?, None)?;
+            for (leaf, proof) in leaves.iter().zip(proofs) {
+                if leaf.max_byte_range > leaf.min_byte_range {
+                    let chunk = tx.data.0[leaf.min_byte_range..leaf.max_byte_range].to_vec();
+                    chunks.insert(proof.offset, (chunk, proof.proof));
+                }
+            }
+        }
+        self.balances.insert(owner, balance);
+        if let Some(target) = tx.target_address()? {
+            *self.balances.entry(target).or_default() += quantity;
+        }
+        self.last_txs.insert(owner, id);
+        self.weave_size += tx.data_size;
+        self.txs.insert(
+            id,
+            MockTx {
+                header: tx.clone_with_no_data()?,
+                chunks,
+                weave_offset: self.weave_size.saturating_sub(1),
+                block: None,
+            },
+        );
+        self.order.push(id);
+        Ok(response(StatusCode::OK, "OK"))
+    }
+
+    fn post_chunk(&mut self, body: &[u8]) -> HttpResponse {
+        let chunk: Chunk = match serde_json::from_slice(body) {
+            Ok(chunk) => chunk,
+            Err(err) => return bad_request(err),
+        };
+        let data_root: [u8; 32] = match chunk.data_root.0.as_slice().try_into() {
+            Ok(data_root) => data_root,
+            Err(_) => return bad_request("invalid data root"),
+        };
+        let range = match validate_path(
+            data_root,
+            chunk.offset,
+            &chunk.data_path.0,
+            &sha256(&chunk.chunk.0),
+        ) {
+            Ok(range) if range.len() == chunk.chunk.0.len() => range,
+            _ => return bad_request("invalid proof"),
+        };
+
+        let mut accepted = false;
+        for tx in self.txs.values_mut().filter(|tx| {
+            tx.header.data_root == chunk.data_root && tx.header.data_size == chunk.data_size
+        }) {
+            tx.chunks.insert(
+                range.end - 1,
+                (chunk.chunk.0.clone(), chunk.data_path.0.clone()),
+            );
+            accepted = true;
+        }
+        match accepted {
+            true => response(StatusCode::OK, "OK"),
+            false => bad_request("data root not found"),
+        }
+    }
+
+    fn get_chunk(&self, offset: &str) -> HttpResponse {
+        let offset: u64 = match offset.parse() {
+            Ok(offset) => offset,
+            Err(err) => return bad_request(err),
+        };
+        let chunk = self.txs.values().find_map(|tx| {
+            let start = (tx.weave_offset + 1).checked_sub(tx.header.data_size)?;
+            if tx.header.data_size == 0 || offset < start || offset > tx.weave_offset {
+                return None;
+            }
+            let relative = (offset - start) as usize;
+            tx.chunks
+                .range(relative..)
+                .next()
+                .filter(|(end, (chunk, _))| *end + 1 - chunk.len() <= relative)
+                .map(|(_, chunk)| chunk)
+        });
+        match chunk {
+            Some((chunk, data_path)) => json_response(&json!({
+                "chunk": Base64Ref(chunk),
+                "data_path": Base64Ref(data_path),
+                "tx_path": "",
+            })),
+            None => status_response(StatusCode::NOT_FOUND),
+        }
+    }
+
+    fn graphql(&self, body: &[u8]) -> Result<HttpResponse, Error> {
+        let body: Value = match serde_json::from_slice(body) {
+            Ok(body) => body,
+            Err(err) => return Ok(bad_request(err)),
+        };
+        let query = body["query"].as_str().unwrap_or_default();
+        let variables = &body["variables"];
+        let ascending = variables["sort"].as_str() == Some("HEIGHT_ASC");
+
+        let data = if query.contains("transactions(") {
+            let mut nodes = vec![];
+            for id in &self.order {
+                let tx = &self.txs[id];
+                if tx.matches(variables) {
+                    nodes.push(tx.to_json(&self.blocks)?);
+                }
+            }
+            if !ascending {
+                nodes.reverse();
+            }
+            json!({ "transactions": page(nodes, variables) })
+        } else if query.contains("blocks(") {
+            let ids = strings(variables, "ids");
+            let height = &variables["height"];
+            let mut nodes: Vec<Value> = self
+                .blocks
+                .iter()
+                .filter(|block| {
+                    ids.as_ref()
+                        .is_none_or(|ids| ids.contains(&block.hash.to_string()))
+                        && height["min"]
+                            .as_u64()
+                            .is_none_or(|min| block.height.get() >= min)
+                        && height["max"]
+                            .as_u64()
+                            .is_none_or(|max| block.height.get() <= max)
+                })
+                .map(MockBlock::to_json)
+                .collect();
+            if !ascending {
+                nodes.reverse();
+            }
+            json!({ "blocks": page(nodes, variables) })
+        } else {
+            return Ok(json_response(&json!({
+                "errors": [{ "message": "only transactions and blocks queries are supported" }],
+            })));
+        };
+        Ok(json_response(&json!({ "data": data })))
+    }
+
+    fn handle(&mut self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+        let path = request.url.path().trim_matches('/').to_string();
+        let segments: Vec<&str> = path.split('/').collect();
+        let body = request.body.as_deref().unwrap_or_default();
+        let response = match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["info"]) => self.info(),
+            ("GET", ["tx_anchor"]) => {
+                response(StatusCode::OK, block_hash(self.height()).to_string())
+            }
+            ("GET", ["price", size]) | ("GET", ["price", size, _]) => match size.parse::<u64>() {
+                Ok(size) => response(StatusCode::OK, size.to_string()),
+                Err(err) => bad_request(err),
+            },
+            ("GET", ["mine"]) => {
+                self.mine(1);
+                self.info()
+            }
+            ("GET", ["mine", blocks]) => match blocks.parse() {
+                Ok(blocks) => {
+                    self.mine(blocks);
+                    self.info()
+                }
+                Err(err) => bad_request(err),
+            },
+            ("GET", ["mint", address, amount]) => {
+                match (Address::from_str(address), amount.parse::<u128>()) {
+                    (Ok(address), Ok(amount)) => {
+                        let balance = self.balances.entry(address).or_default();
+                        *balance += amount;
+                        response(StatusCode::OK, balance.to_string())
+                    }
+                    _ => status_response(StatusCode::BAD_REQUEST),
+                }
+            }
+            ("GET", ["wallet", address, "balance"]) => match Address::from_str(address) {
+                Ok(address) => {
+                    let balance = self.balances.get(&address).copied().unwrap_or_default();
+                    response(StatusCode::OK, balance.to_string())
+                }
+                Err(err) => bad_request(err),
+            },
+            ("GET", ["wallet", address, "last_tx"]) => match Address::from_str(address) {
+                Ok(address) => response(
+                    StatusCode::OK,
+                    self.last_txs
+                        .get(&address)
+                        .map(|id| id.to_string())
+                        .unwrap_or_default(),
+                ),
+                Err(err) => bad_request(err),
+            },
+            ("POST", ["tx"]) => self.post_tx(body)?,
+            ("POST", ["chunk"]) => self.post_chunk(body),
+            ("POST", ["graphql"]) => self.graphql(body)?,
+            ("GET", ["chunk", offset]) => self.get_chunk(offset),
+            ("GET", ["tx", id]) => match self.tx(id) {
+                Ok(MockTx { block: None, .. }) => response(StatusCode::ACCEPTED, "Pending"),
+                Ok(tx) => response(
+                    StatusCode::OK,
+                    serde_json::to_vec(&tx.header).map_err(Error::SerdeJsonError)?,
+                ),
+                Err(status) => status_response(status),
+            },
+            ("GET", ["tx", id, "status"]) => match self.tx(id) {
+                Ok(MockTx {
+                    block: Some(height),
+                    ..
+                }) => json_response(&json!({
+                    "block_height": height,
+                    "block_indep_hash": block_hash(*height),
+                    "number_of_confirmations": self.height().blocks_since(*height).unwrap_or(0) + 1,
+                })),
+                Ok(_) => response(StatusCode::ACCEPTED, "Pending"),
+                Err(status) => status_response(status),
+            },
+            ("GET", ["tx", id, "offset"]) => match self.tx(id) {
+                Ok(tx) => json_response(&json!({
+                    "size": tx.header.data_size.to_string(),
+                    "offset": tx.weave_offset.to_string(),
+                })),
+                Err(status) => status_response(status),
+            },
+            ("GET", ["tx", id, "data"]) => match self.tx(id).map(MockTx::data) {
+                Ok(Some(data)) => response(StatusCode::OK, Base64(data).to_string()),
+                Ok(None) => status_response(StatusCode::NOT_FOUND),
+                Err(status) => status_response(status),
+            },
+            ("GET", ["raw", id]) | ("GET", [id]) => match self.tx(id).map(MockTx::data) {
+                Ok(Some(data)) => response(StatusCode::OK, data),
+                Ok(None) => status_response(StatusCode::NOT_FOUND),
+                Err(status) => status_response(status),
+            },
+            _ => status_response(StatusCode::NOT_FOUND),
+        };
+        Ok(response)
+    }
+}
+
+/// In-process gateway implementing [`HttpTransport`], so that upload flows can be tested
+/// offline. It serves transaction submission and status, chunks, data and GraphQL queries
+/// from memory.
+///
+/// Signatures, data roots and chunk proofs are checked as nodes do. Posted transactions stay
+/// pending until mined, and prices are 1 winston per byte. As on ArLocal, wallets are funded
+/// through the `mint` endpoint and transactions are refused unless the owner can pay their
+/// reward and quantity.
+pub struct MockGateway {
+    state: Mutex<MockState>,
+}
+
+impl Default for MockGateway {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(MockState {
+                txs: HashMap::new(),
+                order: vec![],
+                blocks: vec![MockBlock::new(BlockHeight::GENESIS, None)],
+                weave_size: 0,
+                balances: HashMap::new(),
+                last_txs: HashMap::new(),
+            }),
+        }
+    }
+}
+
+impl MockGateway {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Client sending every request to this gateway, signing with `signer`.
+    pub fn client(self: &Arc<Self>, signer: ArweaveSigner) -> Result<Arweave, Error> {
+        let url = url::Url::parse(MOCK_GATEWAY_URL).unwrap(); //Checked unwrap
+        let mut arweave = ArweaveBuilder::new()
+            .network(Network::custom(url))
+            .transport(self.clone())
+            .build()?;
+        arweave.signer = Some(signer);
+        Ok(arweave)
+    }
+
+    /// Mines the pending transactions in a new block, returning its height.
+    pub fn mine(&self) -> BlockHeight {
+        self.state.lock().unwrap().mine(1) //Checked unwrap
+    }
+
+    pub fn height(&self) -> BlockHeight {
+        self.state.lock().unwrap().height() //Checked unwrap
+    }
+
+    /// Ids of the transactions posted so far, in order.
+    pub fn transactions(&self) -> Vec<TxId> {
+        self.state.lock().unwrap().order.clone() //Checked unwrap
+    }
+
+    /// Data of transaction `id`, `None` if it is unknown or some chunks are missing.
+    pub fn data(&self, id: &TxId) -> Option<Vec<u8>> {
+        let state = self.state.lock().unwrap(); //Checked unwrap
+        state.txs.get(id).and_then(MockTx::data)
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl HttpTransport for MockGateway {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        self.state.lock().unwrap().handle(&request) //Checked unwrap
+    }
+}
+
+/// Client of the endpoints ArLocal adds to the gateway API, to fund wallets and mine blocks
+/// in tests running against a local instance. [`MockGateway`] serves them too.
+pub struct ArLocal {
+    url: url::Url,
+    transport: Arc<dyn HttpTransport>,
+}
+
+impl ArLocal {
+    pub fn new(url: url::Url) -> Self {
+        Self {
+            url,
+            transport: default_transport(),
+        }
+    }
+
+    /// Sends requests through `transport` instead of the default reqwest client.
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Instance at the url in the `ARLOCAL_URL` environment variable, [`ARLOCAL_URL`] if it
+    /// is not set.
+    pub fn from_env() -> Result<Self, Error> {
+        let url = std::env::var("ARLOCAL_URL").unwrap_or_else(|_| ARLOCAL_URL.to_string());
+        Ok(Self::new(
+            url::Url::parse(&url).map_err(Error::UrlParseError)?,
+        ))
+    }
+
+    pub fn url(&self) -> &url::Url {
+        &self.url
+    }
+
+    async fn get(&self, path: &str) -> Result<HttpResponse, Error> {
+        let url = self.url.join(path).map_err(Error::UrlParseError)?;
+        let res = self.transport.send(HttpRequest::get(url)).await?;
+        if !res.status.is_success() {
+            return Err(Error::from_response(&res));
+        }
+        Ok(res)
+    }
+
+    /// Whether the instance answers, so that tests can be skipped when it is not started.
+    pub async fn is_running(&self) -> bool {
+        self.get("info").await.is_ok()
+    }
+
+    /// Mines `blocks` blocks, confirming the pending transactions.
+    pub async fn mine(&self, blocks: u64) -> Result<(), Error> {
+        self.get(&format!("mine/{}", blocks)).await.map(|_| ())
+    }
+
+    /// Credits `amount` to `address`.
+    pub async fn mint(&self, address: &Address, amount: Winston) -> Result<(), Error> {
+        self.get(&format!("mint/{}/{}", address, amount.as_u128()))
+            .await
+            .map(|_| ())
+    }
+
+    /// Client of the instance, signing with `signer`.
+    pub fn client(&self, signer: ArweaveSigner) -> Result<Arweave, Error> {
+        let mut arweave = ArweaveBuilder::new()
+            .network(Network::custom(self.url.clone()))
+            .transport(self.transport.clone())
+            .build()?;
+        arweave.signer = Some(signer);
+        Ok(arweave)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::Arc};
+
+    use httpmock::{Method::GET, MockServer};
+    use reqwest::StatusCode;
+    use tokio_test::block_on;
+
+    use crate::{
+        address::Address,
+        block::BlockHeight,
+        bundle::DataItem,
+        crypto::{base64::Base64, sign::Signer},
+        currency::Winston,
+        error::Error,
+        graphql::{BlocksQuery, TransactionsQuery},
+        transport::{HttpRequest, HttpTransport},
+        ArweaveSigner,
+    };
+
+    use super::{ArLocal, MockGateway, TestSigner, MOCK_GATEWAY_URL};
+
+    #[test]
+    fn test_signer_is_deterministic() -> Result<(), Error> {
+        let signer = TestSigner::new(7);
+        assert_eq!(signer.public_key().0.len(), 512);
+        assert_eq!(TestSigner::new(7).wallet_address(), signer.wallet_address());
+        assert_ne!(TestSigner::new(8).wallet_address(), signer.wallet_address());
+
+        let signer = ArweaveSigner::from(signer);
+        let item = signer.sign_data_item(DataItem::new(
+            Base64::empty(),
+            Base64::empty(),
+            vec![],
+            b"test".to_vec(),
+        )?)?;
+        DataItem::from_bytes(&item.to_bytes()?)?.verify()
+    }
+
+    #[test]
+    fn test_mock_gateway_upload() -> Result<(), Error> {
+        let gateway = Arc::new(MockGateway::new());
+        let arweave = gateway.client(TestSigner::new(7).into())?;
+        let address = arweave.get_wallet_address()?;
+
+        let (id, _) = block_on(arweave.upload_bytes(b"hello", Some("text/plain"), vec![], 0))?;
+        assert_eq!(gateway.data(&id), Some(b"hello".to_vec()));
+
+        let data: Vec<u8> = (0..600_000).map(|i| (i % 251) as u8).collect();
+        let tx = arweave.sign_transaction(block_on(arweave.create_transaction(
+            None,
+            vec![],
+            data.clone(),
+            0,
+            0,
+            false,
+        ))?)?;
+        let mut altered = tx.get_chunk_from(0, &data)?;
+        altered.chunk.0[0] ^= 1;
+        let path = crate::temp_path("arweave-rs-mock-gateway.json");
+        let _ = fs::remove_file(&path);
+        let (chunked, _) = block_on(arweave.post_transaction_resumable(tx, &path))?;
+        assert_eq!(gateway.data(&chunked), Some(data.clone()));
+        let url = url::Url::parse(MOCK_GATEWAY_URL)
+            .unwrap()
+            .join("chunk")
+            .unwrap();
+        let res = block_on(gateway.send(HttpRequest::post(url).json(&altered)?))?;
+        assert_eq!(res.status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = block_on(arweave.get_tx_status(&chunked))?;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let height = gateway.mine();
+        let (_, status) = block_on(arweave.get_tx_status(&chunked))?;
+        assert_eq!(status.unwrap().block_height, height);
+        assert_eq!(block_on(arweave.get_verified_data(&chunked))?, data);
+        assert_eq!(
+            block_on(arweave.tx_client.get_data_from_chunks(&chunked))?,
+            data
+        );
+
+        let owned =
+            block_on(arweave.query_transactions(&TransactionsQuery::new().owners(vec![address])))?;
+        assert_eq!(owned.edges.len(), 2);
+        assert_eq!(owned.edges[0].node.id, chunked);
+        assert!(owned.edges.iter().all(|edge| edge.node.block.is_some()));
+        let tagged = block_on(arweave.query_transactions(
+            &TransactionsQuery::new().tag("Content-Type", vec!["text/plain".to_string()]),
+        ))?;
+        assert_eq!(tagged.edges.len(), 1);
+        assert_eq!(tagged.edges[0].node.id, id);
+        let blocks = block_on(arweave.query_blocks(&BlocksQuery::new()))?;
+        assert_eq!(blocks.edges.len(), 2);
+        assert_eq!(blocks.edges[0].node.height, height);
+        Ok(())
+    }
+
+    #[test]
+    fn test_arlocal() -> Result<(), Error> {
+        let server = MockServer::start();
+        let address = Address::new([1; 32]);
+        let mint = server.mock(|when, then| {
+            when.method(GET).path(format!("/mint/{}/1000", address));
+            then.status(200).body("1000");
+        });
+        let mine = server.mock(|when, then| {
+            when.method(GET).path("/mine/2");
+            then.status(200);
+        });
+
+        let arlocal = ArLocal::new(url::Url::parse(&server.url("/")).unwrap());
+        assert!(!block_on(arlocal.is_running()));
+        block_on(arlocal.mint(&address, Winston::new(1000)))?;
+        block_on(arlocal.mine(2))?;
+        mint.assert();
+        mine.assert();
+        Ok(())
+    }
+
+    #[test]
+    fn test_mine_and_mint() -> Result<(), Error> {
+        let gateway = Arc::new(MockGateway::new());
+        let arweave = gateway.client(TestSigner::new(7).into())?;
+        let address = arweave.get_wallet_address()?;
+        let target = Address::new([2; 32]);
+
+        let transfer = arweave.transfer(target, Winston::new(100));
+        assert!(matches!(
+            block_on(transfer),
+            Err(Error::UnexpectedStatus { status: 410, .. })
+        ));
+        block_on(arweave.mint(&address, Winston::new(1000)))?;
+        assert_eq!(
+            Winston::from(block_on(arweave.get_balance(&address))?),
+            Winston::new(1000)
+        );
+
+        let (id, _) = block_on(arweave.transfer(target, Winston::new(100)))?;
+        assert_eq!(
+            Winston::from(block_on(arweave.get_balance(&address))?),
+            Winston::new(900)
+        );
+        assert_eq!(
+            Winston::from(block_on(arweave.get_balance(&target))?),
+            Winston::new(100)
+        );
+        assert_eq!(block_on(arweave.get_last_tx(&address))?, id.to_base64());
+
+        block_on(arweave.mine(2))?;
+        assert_eq!(gateway.height(), BlockHeight::new(2));
+        let (_, status) = block_on(arweave.get_tx_status(&id))?;
+        let status = status.unwrap();
+        assert_eq!(status.block_height, BlockHeight::new(1));
+        assert_eq!(status.number_of_confirmations, 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,466 @@
+use crate::{
+    address::Address,
+    crypto::{base64::Base64, merkle::ChunkingOptions, Provider},
+    currency::Winston,
+    error::Error,
+};
+
+use super::{client::TxClient, tags::Tag, Tx};
+
+/// Fluent alternative to [`Tx::new`].
+///
+/// ```no_run
+/// # use arweave_rs::{
+/// #     crypto::{base64::Base64, Provider}, currency::Winston, transaction::TransactionBuilder,
+/// # };
+/// # fn main() -> Result<(), arweave_rs::error::Error> {
+/// # let provider = Provider::from_keypair_path("wallet.json".into())?;
+/// let tx = TransactionBuilder::new()
+///     .data(b"hello".to_vec())
+///     .tag("Content-Type", "text/plain")
+///     .reward(Winston::new(1_000_000))
+///     .last_tx(Base64(vec![0; 32]))
+///     .sign(&provider)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct TransactionBuilder {
+    target: Option<Address>,
+    data: Vec<u8>,
+    tags: Vec<Tag<Base64>>,
+    quantity: Winston,
+    reward: Option<Winston>,
+    adjust_reward: Option<fn(u64) -> u64>,
+    fee_multiplier: Option<f64>,
+    last_tx: Option<Base64>,
+    auto_content_tag: bool,
+    legacy_format: bool,
+    chunking: ChunkingOptions,
+}
+
+impl TransactionBuilder {
+    pub fn new() -> TransactionBuilder {
+        Default::default()
+    }
+
+    pub fn data(mut self, data: Vec<u8>) -> TransactionBuilder {
+        self.data = data;
+        self
+    }
+
+    /// Adds a tag from utf-8 strings.
+    pub fn tag(mut self, name: &str, value: &str) -> TransactionBuilder {
+        self.tags.push(Tag::new(name, value));
+        self
+    }
+
+    pub fn tags(mut self, tags: Vec<Tag<Base64>>) -> TransactionBuilder {
+        self.tags.extend(tags);
+        self
+    }
+
+    pub fn target(mut self, target: Address) -> TransactionBuilder {
+        self.target = Some(target);
+        self
+    }
+
+    /// Amount transferred to the target.
+    pub fn quantity(mut self, quantity: Winston) -> TransactionBuilder {
+        self.quantity = quantity;
+        self
+    }
+
+    /// Fee, at most `u64::MAX` winstons. Takes precedence over
+    /// [`TransactionBuilder::estimate_reward`].
+    pub fn reward(mut self, reward: Winston) -> TransactionBuilder {
+        self.reward = Some(reward);
+        self
+    }
+
+    /// Hook applied to the price returned by the node when estimating the reward.
+    pub fn adjust_reward(mut self, adjust_reward: fn(u64) -> u64) -> TransactionBuilder {
+        self.adjust_reward = Some(adjust_reward);
+        self
+    }
+
+    /// Scales the price returned by the node when estimating the reward, e.g. `1.2` to bid 20%
+    /// above the minimum during congestion. Applied before [`TransactionBuilder::adjust_reward`].
+    /// Estimating fails unless it is a finite number of at least `1.0`.
+    pub fn fee_multiplier(mut self, fee_multiplier: f64) -> TransactionBuilder {
+        self.fee_multiplier = Some(fee_multiplier);
+        self
+    }
+
+    pub(crate) fn default_fee_multiplier(mut self, fee_multiplier: f64) -> TransactionBuilder {
+        self.fee_multiplier.get_or_insert(fee_multiplier);
+        self
+    }
+
+    /// Populates the reward from the node's `price` endpoint, unless already set.
+    pub async fn estimate_reward(mut self, client: &TxClient) -> Result<TransactionBuilder, Error> {
+        if self.reward.is_some() {
+            return Ok(self);
+        }
+        if let Some(fee_multiplier) = self.fee_multiplier {
+            check_fee_multiplier(fee_multiplier)?;
+        }
+        let mut price = client
+            .get_price(self.data.len() as u64, self.target.as_ref())
+            .await?;
+        if let Some(fee_multiplier) = self.fee_multiplier {
+            price = apply_fee_multiplier(price, fee_multiplier)?;
+        }
+        let price = self.adjust_reward.map_or(price, |adjust| adjust(price));
+        self.reward = Some(Winston::from(price));
+        Ok(self)
+    }
+
+    fn reward_u64(reward: Winston) -> Result<u64, Error> {
+        u64::try_from(reward.as_u128())
+            .map_err(|_| Error::InvalidAmount(format!("reward of {} winstons", reward)))
+    }
+
+    /// Anchor, as returned by the `tx_anchor` endpoint.
+    pub fn last_tx(mut self, last_tx: Base64) -> TransactionBuilder {
+        self.last_tx = Some(last_tx);
+        self
+    }
+
+    /// Adds a `Content-Type` tag inferred from the data.
+    pub fn auto_content_tag(mut self, auto_content_tag: bool) -> TransactionBuilder {
+        self.auto_content_tag = auto_content_tag;
+        self
+    }
+
+    /// Builds a format 1 transaction, see [`Tx::into_format_1`].
+    pub fn legacy_format(mut self, legacy_format: bool) -> TransactionBuilder {
+        self.legacy_format = legacy_format;
+        self
+    }
+
+    /// Splits the data into chunks as set by `chunking` instead of the defaults, changing the
+    /// data root.
+    pub fn chunking(mut self, chunking: ChunkingOptions) -> TransactionBuilder {
+        self.chunking = chunking;
+        self
+    }
+
+    /// Builds the unsigned transaction, computing its data root and chunks.
+    pub fn build(self, crypto: &Provider) -> Result<Tx, Error> {
+        let mut transaction = Tx::new_with_chunking(
+            crypto.keypair_modulus(),
+            self.target
+                .map_or_else(Base64::empty, |target| target.to_base64()),
+            &self.data,
+            self.quantity.as_u128(),
+            Self::reward_u64(self.reward.unwrap_or_default())?,
+            self.last_tx.unwrap_or_default(),
+            self.tags,
+            self.auto_content_tag,
+            &self.chunking,
+        )?;
+        transaction.data = Base64(self.data);
+        Ok(match self.legacy_format {
+            true => transaction.into_format_1(),
+            false => transaction,
+        })
+    }
+
+    /// Builds the unsigned transaction of the wallet with public key `owner`, to be signed
+    /// elsewhere with [`Tx::sign_detached`].
+    ///
+    /// Fails unless both the reward and the anchor are set, as they can't be changed once
+    /// signed.
+    pub fn build_for_owner(self, owner: Base64) -> Result<Tx, Error> {
+        let reward = self
+            .reward
+            .ok_or_else(|| Error::NoneError("reward".to_string()))
+            .and_then(Self::reward_u64)?;
+        let last_tx = self
+            .last_tx
+            .ok_or_else(|| Error::NoneError("last_tx".to_string()))?;
+        let mut transaction = Tx::new_with_chunking(
+            owner,
+            self.target
+                .map_or_else(Base64::empty, |target| target.to_base64()),
+            &self.data,
+            self.quantity.as_u128(),
+            reward,
+            last_tx,
+            self.tags,
+            self.auto_content_tag,
+            &self.chunking,
+        )?;
+        transaction.data = Base64(self.data);
+        Ok(match self.legacy_format {
+            true => transaction.into_format_1(),
+            false => transaction,
+        })
+    }
+
+    /// Builds and signs the transaction, once checked with [`Tx::validate`].
+    pub fn sign(self, crypto: &Provider) -> Result<Tx, Error> {
+        self.build(crypto)?.sign(crypto)
+    }
+}
+
+/// Fails unless `fee_multiplier` is finite and doesn't lower the price.
+pub(crate) fn check_fee_multiplier(fee_multiplier: f64) -> Result<f64, Error> {
+    match fee_multiplier.is_finite() && fee_multiplier >= 1.0 {
+        true => Ok(fee_multiplier),
+        false => Err(Error::InvalidAmount(format!(
+            "fee multiplier {}",
+            fee_multiplier
+        ))),
+    }
+}
+
+/// Precision of fee multipliers, which are applied in millionths.
+const FEE_MULTIPLIER_SCALE: u128 = 1_000_000;
+
+/// Scales `price` by `fee_multiplier`, rounded to millionths, rounding up to the next winston.
+pub(crate) fn apply_fee_multiplier(price: u64, fee_multiplier: f64) -> Result<u64, Error> {
+    let fee_multiplier = check_fee_multiplier(fee_multiplier)?;
+    //Checked cast: the multiplier is finite and positive, larger values saturate
+    let millionths = (fee_multiplier * FEE_MULTIPLIER_SCALE as f64).round() as u128;
+    Winston::from(price)
+        .checked_mul(millionths)
+        .map(|scaled| scaled.as_u128().div_ceil(FEE_MULTIPLIER_SCALE))
+        .and_then(|reward| u64::try_from(reward).ok())
+        .ok_or_else(|| {
+            Error::InvalidAmount(format!(
+                "price of {} winstons times fee multiplier {}",
+                price, fee_multiplier
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use httpmock::{Method::GET, MockServer};
+    use tokio_test::block_on;
+
+    use crate::{
+        address::Address,
+        crypto::{base64::Base64, hash::sha256, merkle::ChunkingOptions, Provider},
+        currency::Winston,
+        error::Error,
+        transaction::{
+            client::TxClient,
+            tags::{FromUtf8Strs, Tag},
+        },
+        verify::verify_transaction,
+    };
+
+    use super::{apply_fee_multiplier, TransactionBuilder};
+
+    #[test]
+    fn test_build_and_sign() -> Result<(), Error> {
+        let provider = Provider::default();
+        let data = fs::read("res/rebar3")?;
+        let tx = TransactionBuilder::new()
+            .data(data)
+            .tag("Content-Type", "application/octet-stream")
+            .target(Address::new([1; 32]))
+            .quantity(Winston::new(100))
+            .reward(Winston::new(42))
+            .last_tx(Base64(vec![2; 48]))
+            .sign(&provider)?;
+
+        assert!(verify_transaction(&tx).is_ok());
+        assert_eq!(tx.id.0, sha256(&tx.signature.0));
+        assert_eq!(
+            tx.data_root,
+            "t-GCOnjPWxdox950JsrFMu3nzOE4RktXpMcIlkqSUTw"
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(tx.reward, 42);
+        assert_eq!(tx.quantity.to_string(), "100");
+        assert_eq!(tx.target_address()?, Some(Address::new([1; 32])));
+        assert_eq!(tx.owner, provider.keypair_modulus());
+        assert!(tx.tags.contains(&Tag::from_utf8_strs(
+            "Content-Type",
+            "application/octet-stream"
+        )?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_with_chunking() -> Result<(), Error> {
+        let data = fs::read("res/rebar3")?;
+        let chunking = ChunkingOptions::new(16 * 1024)?.with_max_chunk_size(64 * 1024);
+        let tx = TransactionBuilder::new()
+            .data(data.clone())
+            .chunking(chunking)
+            .reward(Winston::new(42))
+            .last_tx(Base64(vec![2; 48]))
+            .build_for_owner(Base64(vec![1; 512]))?;
+
+        assert_eq!(tx.data.0, data);
+        assert_eq!(tx.chunks.len(), data.len().div_ceil(64 * 1024));
+        assert!(tx
+            .chunks
+            .iter()
+            .all(|chunk| chunk.max_byte_range - chunk.min_byte_range <= 64 * 1024));
+        assert_ne!(
+            tx.data_root,
+            "t-GCOnjPWxdox950JsrFMu3nzOE4RktXpMcIlkqSUTw"
+                .parse()
+                .unwrap()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_for_owner_requires_reward_and_anchor() {
+        let owner = Base64(vec![1; 512]);
+        assert!(matches!(
+            TransactionBuilder::new()
+                .last_tx(Base64(vec![2; 48]))
+                .build_for_owner(owner.clone()),
+            Err(Error::NoneError(field)) if field == "reward"
+        ));
+        assert!(matches!(
+            TransactionBuilder::new()
+                .reward(Winston::new(42))
+                .build_for_owner(owner.clone()),
+            Err(Error::NoneError(field)) if field == "last_tx"
+        ));
+        assert!(TransactionBuilder::new()
+            .reward(Winston::new(0))
+            .last_tx(Base64(vec![2; 48]))
+            .build_for_owner(owner)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_build_and_sign_legacy_format() -> Result<(), Error> {
+        let provider = Provider::default();
+        let tx = TransactionBuilder::new()
+            .data(b"hello".to_vec())
+            .tag("App-Name", "test")
+            .reward(Winston::new(42))
+            .last_tx(Base64(vec![2; 32]))
+            .legacy_format(true)
+            .sign(&provider)?;
+
+        assert_eq!(tx.format, 1);
+        assert_eq!(tx.data.0, b"hello");
+        assert_eq!(tx.data_size, 5);
+        assert!(tx.data_root.is_empty());
+        assert!(verify_transaction(&tx).is_ok());
+        tx.verify_data(b"hello")?;
+
+        let mut tampered = tx;
+        tampered.data = Base64(b"hellO".to_vec());
+        assert!(matches!(
+            verify_transaction(&tampered),
+            Err(Error::InvalidSignature)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_estimate_reward() -> Result<(), Error> {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/price/5");
+            then.status(200).body("1000");
+        });
+        let url = url::Url::parse(&server.url("")).unwrap();
+        let client = TxClient::new(reqwest::Client::new(), url)?;
+        let provider = Provider::default();
+
+        let tx = block_on(
+            TransactionBuilder::new()
+                .data(b"hello".to_vec())
+                .estimate_reward(&client),
+        )?
+        .build(&provider)?;
+        assert_eq!(tx.reward, 1000);
+
+        let tx = block_on(
+            TransactionBuilder::new()
+                .data(b"hello".to_vec())
+                .adjust_reward(|price| price * 2)
+                .estimate_reward(&client),
+        )?
+        .build(&provider)?;
+        assert_eq!(tx.reward, 2000);
+        mock.assert_hits(2);
+
+        let tx = block_on(
+            TransactionBuilder::new()
+                .data(b"hello".to_vec())
+                .reward(Winston::new(7))
+                .estimate_reward(&client),
+        )?
+        .build(&provider)?;
+        assert_eq!(tx.reward, 7);
+        mock.assert_hits(2);
+
+        let tx = block_on(
+            TransactionBuilder::new()
+                .data(b"hello".to_vec())
+                .fee_multiplier(1.25)
+                .adjust_reward(|price| price + 1)
+                .estimate_reward(&client),
+        )?
+        .build(&provider)?;
+        assert_eq!(tx.reward, 1251);
+        mock.assert_hits(3);
+
+        for fee_multiplier in [0.5, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                block_on(
+                    TransactionBuilder::new()
+                        .data(b"hello".to_vec())
+                        .fee_multiplier(fee_multiplier)
+                        .estimate_reward(&client),
+                ),
+                Err(Error::InvalidAmount(_))
+            ));
+        }
+        mock.assert_hits(3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_fee_multiplier() -> Result<(), Error> {
+        assert_eq!(apply_fee_multiplier(1000, 1.2)?, 1200);
+        assert_eq!(apply_fee_multiplier(3, 1.000001)?, 4);
+        // Exact past the 53 bits of precision of a float.
+        assert_eq!(
+            apply_fee_multiplier(9_007_199_254_740_993, 1.5)?,
+            13_510_798_882_111_490
+        );
+        assert_eq!(apply_fee_multiplier(u64::MAX, 1.0)?, u64::MAX);
+        assert!(matches!(
+            apply_fee_multiplier(u64::MAX, 1.5),
+            Err(Error::InvalidAmount(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_oversized_reward() -> Result<(), Error> {
+        let provider = Provider::default();
+        let oversized = TransactionBuilder::new().reward(Winston::new(u128::from(u64::MAX) + 1));
+        assert!(matches!(
+            oversized.clone().build(&provider),
+            Err(Error::InvalidAmount(_))
+        ));
+        assert!(matches!(
+            oversized
+                .last_tx(Base64(vec![0; 32]))
+                .build_for_owner(Base64::empty()),
+            Err(Error::InvalidAmount(_))
+        ));
+        Ok(())
+    }
+}
@@ -1,147 +1,901 @@
+use futures::StreamExt;
 use reqwest::{
-    header::{ACCEPT, CONTENT_TYPE},
+    header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_RANGE, RANGE},
     StatusCode,
 };
-use serde_json::json;
-use std::{str::FromStr, thread::sleep, time::Duration};
+use std::{str::FromStr, sync::Arc, time::Duration};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 
 use crate::{
-    consts::{ARWEAVE_BASE_URL, CHUNKS_RETRIES, CHUNKS_RETRY_SLEEP},
+    address::Address,
+    consts::ARWEAVE_BASE_URL,
     crypto::base64::Base64,
     error::Error,
-    types::TxStatus,
+    gateway::GatewayPool,
+    metrics::{endpoint, MetricsSink},
+    retry::RetryPolicy,
+    runtime::{sleep, Instant},
+    transport::{
+        default_transport, HttpRequest, HttpResponse, HttpTransport, ReqwestTransport,
+        StreamingResponse,
+    },
+    tx_id::TxId,
+    types::{ChunkData, TxOffset, TxStatus},
 };
 
 use super::Tx;
 
+/// Anchors are either a block hash (48 bytes) or a transaction id (32 bytes).
+const MAX_ANCHOR_LENGTH: usize = 48;
+/// Largest buffer allocated upfront for data of a size reported by the gateway.
+const MAX_PREALLOCATION: u64 = 1024 * 1024;
+
 pub struct TxClient {
-    client: reqwest::Client,
+    transport: Arc<dyn HttpTransport>,
     base_url: url::Url,
+    retry_policy: RetryPolicy,
+    gateways: Option<Arc<GatewayPool>>,
+    metrics: Option<Arc<dyn MetricsSink>>,
 }
 
 impl Default for TxClient {
     fn default() -> Self {
         Self {
-            client: reqwest::Client::new(),
+            transport: default_transport(),
             base_url: url::Url::from_str(ARWEAVE_BASE_URL).unwrap(),
+            retry_policy: RetryPolicy::default(),
+            gateways: None,
+            metrics: None,
         }
     }
 }
 
 impl TxClient {
     pub fn new(client: reqwest::Client, base_url: url::Url) -> Result<Self, Error> {
-        Ok(Self { client, base_url })
+        Ok(Self {
+            transport: Arc::new(ReqwestTransport(client)),
+            base_url,
+            retry_policy: RetryPolicy::default(),
+            gateways: None,
+            metrics: None,
+        })
     }
 
-    pub async fn post_transaction(&self, signed_transaction: &Tx) -> Result<(Base64, u64), Error> {
-        if signed_transaction.id.0.is_empty() {
-            return Err(Error::UnsignedTransaction);
+    /// Sends requests through `transport` instead of the client given to [`TxClient::new`].
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Reports request latencies to `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<dyn MetricsSink>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Sends requests to the active gateway of `gateways` instead of the base url.
+    pub fn with_gateways(mut self, gateways: Arc<GatewayPool>) -> Self {
+        self.gateways = Some(gateways);
+        self
+    }
+
+    fn base_url(&self) -> url::Url {
+        match &self.gateways {
+            Some(gateways) => gateways.current(),
+            None => self.base_url.clone(),
         }
+    }
 
-        let mut retries = 0;
-        let mut status = reqwest::StatusCode::NOT_FOUND;
-        let url = self.base_url.join("tx").map_err(Error::UrlParseError)?;
-
-        dbg!(json!(signed_transaction));
-        while (retries < CHUNKS_RETRIES) & (status != reqwest::StatusCode::OK) {
-            let res = self
-                .client
-                .post(url.clone())
-                .json(&signed_transaction)
-                .header(&ACCEPT, "application/json")
-                .header(&CONTENT_TYPE, "application/json")
-                .send()
-                .await
-                .map_err(Error::ReqwestError)?;
-            status = res.status();
-            dbg!(status);
-            if status == reqwest::StatusCode::OK {
-                return Ok((signed_transaction.id.clone(), signed_transaction.reward));
-            }
-            sleep(Duration::from_secs(CHUNKS_RETRY_SLEEP));
-            retries += 1;
+    #[cfg(any(feature = "oracle", feature = "testing"))]
+    pub(crate) fn transport(&self) -> Arc<dyn HttpTransport> {
+        self.transport.clone()
+    }
+
+    fn report<T>(&self, base_url: &url::Url, result: &Result<T, Error>) {
+        if let Some(gateways) = &self.gateways {
+            gateways.report(base_url, result);
         }
+    }
+
+    fn record<T>(&self, path: &str, start: Instant, result: &Result<T, Error>) {
+        if let Some(metrics) = &self.metrics {
+            metrics.request_latency(endpoint(path), start.elapsed(), result.is_ok());
+        }
+    }
+
+    /// Policy applied to transaction submission and reads.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
 
-        Err(Error::StatusCodeNotOk)
+    /// Sends a GET request, retrying transport failures and server errors.
+    async fn get(&self, path: &str) -> Result<HttpResponse, Error> {
+        self.get_with_headers(path, HeaderMap::new()).await
     }
 
-    pub async fn get_last_tx(&self) -> Result<Base64, Error> {
-        let resp = self
-            .client
-            .get(
-                self.base_url
-                    .join("tx_anchor")
-                    .map_err(Error::UrlParseError)?,
-            )
-            .send()
+    async fn get_with_headers(
+        &self,
+        path: &str,
+        headers: HeaderMap,
+    ) -> Result<HttpResponse, Error> {
+        self.get_streaming(path, headers)
+            .await?
+            .into_response()
             .await
-            .map_err(Error::ReqwestError)?;
-        let last_tx_str = resp.text().await.unwrap();
-        Base64::from_str(&last_tx_str).map_err(Error::Base64DecodeError)
     }
 
-    pub async fn get_fee(&self, target: Base64, data: Vec<u8>) -> Result<u64, Error> {
-        let url = self
-            .base_url
-            .join(&format!("price/{}/{}", data.len(), target))
-            .map_err(Error::UrlParseError)?;
-        let winstons_per_bytes = reqwest::get(url)
+    /// Same as [`TxClient::get`], returning as soon as the headers are received.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, headers))
+    )]
+    async fn get_streaming(
+        &self,
+        path: &str,
+        headers: HeaderMap,
+    ) -> Result<StreamingResponse, Error> {
+        self.retry_policy
+            .retry(|| async {
+                let base_url = self.base_url();
+                let url = base_url.join(path).map_err(Error::UrlParseError)?;
+                let start = Instant::now();
+                let request = HttpRequest::get(url).headers(headers.clone());
+                let result = match self.transport.send_streaming(request).await {
+                    Ok(res)
+                        if res.status.is_server_error()
+                            || res.status == StatusCode::TOO_MANY_REQUESTS =>
+                    {
+                        Err(Error::from_response(&res.into_response().await?))
+                    }
+                    Ok(res) => Ok(res),
+                    Err(err) => Err(err),
+                };
+                self.record(path, start, &result);
+                self.report(&base_url, &result);
+                result
+            })
             .await
-            .map_err(|e| Error::GetPriceError(e.to_string()))?
-            .json::<u64>()
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(id = %signed_transaction.id)))]
+    pub async fn post_transaction(&self, signed_transaction: &Tx) -> Result<(TxId, u64), Error> {
+        if signed_transaction.id.0.is_empty() {
+            return Err(Error::UnsignedTransaction);
+        }
+        let id = TxId::try_from(&signed_transaction.id)?;
+
+        let request = HttpRequest::post(self.base_url.clone())
+            .json(&signed_transaction)?
+            .header(ACCEPT, HeaderValue::from_static("application/json"));
+        self.retry_policy
+            .retry(|| async {
+                let base_url = self.base_url();
+                let mut request = request.clone();
+                request.url = base_url.join("tx").map_err(Error::UrlParseError)?;
+                let start = Instant::now();
+                let result = match self.transport.send(request).await {
+                    Ok(res) if res.status == StatusCode::OK => Ok((id, signed_transaction.reward)),
+                    Ok(res) => Err(Error::from_response(&res)),
+                    Err(err) => Err(err),
+                };
+                self.record("tx", start, &result);
+                self.report(&base_url, &result);
+                result
+            })
             .await
-            .map_err(Error::ReqwestError)?;
+    }
+
+    /// Fetches a recent anchor to be used as the `last_tx` of a new transaction.
+    pub async fn get_tx_anchor(&self) -> Result<Base64, Error> {
+        let resp = self.get("tx_anchor").await?;
+        if resp.status != StatusCode::OK {
+            return Err(Error::from_response(&resp));
+        }
+        let anchor_str = resp.text()?;
+        let anchor = Base64::from_str(anchor_str.trim()).map_err(Error::Base64DecodeError)?;
+        if anchor.is_empty() || anchor.0.len() > MAX_ANCHOR_LENGTH {
+            return Err(Error::TransactionInfoError(format!(
+                "invalid tx anchor of {} bytes",
+                anchor.0.len()
+            )));
+        }
+        Ok(anchor)
+    }
+
+    pub async fn get_fee(&self, target: Option<Address>, data: Vec<u8>) -> Result<u64, Error> {
+        self.get_price(data.len() as u64, target.as_ref()).await
+    }
+
+    /// Winstons required to store `data_size` bytes, optionally transferring to a new `target`.
+    pub async fn get_price(&self, data_size: u64, target: Option<&Address>) -> Result<u64, Error> {
+        let path = match target {
+            Some(target) => format!("price/{}/{}", data_size, target),
+            None => format!("price/{}", data_size),
+        };
+        let winstons_per_bytes = self.get(&path).await?.json::<u64>()?;
 
         Ok(winstons_per_bytes)
     }
 
-    pub async fn get_tx(&self, id: Base64) -> Result<(StatusCode, Option<Tx>), Error> {
-        let res = self
-            .client
-            .get(
-                self.base_url
-                    .join(&format!("tx/{}", id))
-                    .map_err(Error::UrlParseError)?,
-            )
-            .send()
-            .await
-            .map_err(Error::ReqwestError)?;
+    pub async fn get_tx(&self, id: &TxId) -> Result<(StatusCode, Option<Tx>), Error> {
+        let res = self.get(&format!("tx/{}", id)).await?;
 
-        if res.status() == StatusCode::OK {
-            let text = res.text().await.map_err(Error::ReqwestError)?;
-            let tx = Tx::from_str(&text)?;
+        if res.status == StatusCode::OK {
+            let tx = Tx::from_str(&res.text()?)?;
             return Ok((StatusCode::OK, Some(tx)));
-        } else if res.status() == StatusCode::ACCEPTED {
+        } else if res.status == StatusCode::ACCEPTED {
             //Tx is pending
             return Ok((StatusCode::ACCEPTED, None));
         }
 
-        Err(Error::TransactionInfoError(res.status().to_string()))
+        Err(Error::from_response(&res))
     }
 
-    pub async fn get_tx_status(&self, id: Base64) -> Result<(StatusCode, Option<TxStatus>), Error> {
-        let res = self
-            .client
-            .get(
-                self.base_url
-                    .join(&format!("tx/{}/status", id))
-                    .map_err(Error::UrlParseError)?,
-            )
-            .send()
-            .await
-            .map_err(Error::ReqwestError)?;
+    pub async fn get_tx_status(&self, id: &TxId) -> Result<(StatusCode, Option<TxStatus>), Error> {
+        let res = self.get(&format!("tx/{}/status", id)).await?;
 
-        if res.status() == StatusCode::OK {
+        if res.status == StatusCode::OK {
             let status = res
                 .json::<TxStatus>()
-                .await
                 .map_err(|err| Error::TransactionInfoError(err.to_string()))?;
 
             Ok((StatusCode::OK, Some(status)))
-        } else if res.status() == StatusCode::ACCEPTED {
+        } else if res.status == StatusCode::ACCEPTED {
             Ok((StatusCode::ACCEPTED, None))
         } else {
-            Err(Error::TransactionInfoError(res.status().to_string()))
+            Err(Error::from_response(&res))
         }
     }
+
+    /// Downloads the data of transaction `id` from the `tx/{id}/data` endpoint.
+    pub async fn get_data(&self, id: &TxId) -> Result<Vec<u8>, Error> {
+        let res = self.get(&format!("tx/{}/data", id)).await?;
+        if res.status != StatusCode::OK {
+            return Err(Error::from_response(&res));
+        }
+        Ok(Base64::from_str(res.text()?.trim())
+            .map_err(Error::Base64DecodeError)?
+            .0)
+    }
+
+    /// Downloads the data of transaction or data item `id` from the `raw/{id}` endpoint of
+    /// gateways, which serves manifests as uploaded rather than resolving them.
+    pub async fn get_raw_data(&self, id: &TxId) -> Result<Vec<u8>, Error> {
+        let res = self.get(&format!("raw/{}", id)).await?;
+        if res.status != StatusCode::OK {
+            return Err(Error::from_response(&res));
+        }
+        Ok(res.body.to_vec())
+    }
+
+    pub async fn get_tx_offset(&self, id: &TxId) -> Result<TxOffset, Error> {
+        let res = self.get(&format!("tx/{}/offset", id)).await?;
+        if res.status != StatusCode::OK {
+            return Err(Error::from_response(&res));
+        }
+        res.json()
+    }
+
+    /// Fetches the chunk containing the absolute weave `offset`.
+    pub async fn get_chunk(&self, offset: u64) -> Result<ChunkData, Error> {
+        let res = self.get(&format!("chunk/{}", offset)).await?;
+        if res.status != StatusCode::OK {
+            return Err(Error::from_response(&res));
+        }
+        res.json()
+    }
+
+    /// Downloads the data of transaction `id` chunk by chunk, for data too large to be
+    /// served by the `tx/{id}/data` endpoint.
+    pub async fn get_data_from_chunks(&self, id: &TxId) -> Result<Vec<u8>, Error> {
+        let TxOffset { size, offset } = self.get_tx_offset(id).await?;
+        let start = (offset + 1).saturating_sub(size);
+        let mut data = Vec::with_capacity(size.min(MAX_PREALLOCATION) as usize);
+        while (data.len() as u64) < size {
+            let chunk = self.get_chunk(start + data.len() as u64).await?;
+            if chunk.chunk.is_empty() {
+                return Err(Error::TransactionInfoError(format!(
+                    "empty chunk at offset {}",
+                    start + data.len() as u64
+                )));
+            }
+            data.extend(chunk.chunk.0);
+        }
+        data.truncate(size as usize);
+        Ok(data)
+    }
+
+    /// Reads bytes `start..end` of the data of transaction `id`. Gateways are asked for the
+    /// range with a `Range` header; nodes, which serve data without range support, are read
+    /// chunk by chunk from the offset of the transaction in the weave. So is the data of
+    /// gateways answering with other than the requested range, without reading the body.
+    pub async fn get_data_range(&self, id: &TxId, start: u64, end: u64) -> Result<Vec<u8>, Error> {
+        if start >= end {
+            return Err(Error::TransactionInfoError(format!(
+                "invalid range {}..{}",
+                start, end
+            )));
+        }
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            RANGE,
+            HeaderValue::from_str(&format!("bytes={}-{}", start, end - 1)).unwrap(), //Checked unwrap
+        );
+        if let Ok(res) = self.get_streaming(&id.to_string(), headers).await {
+            // A 200 carries all of the data, left unread.
+            if res.status == StatusCode::PARTIAL_CONTENT {
+                if let Some(last) = served_range_end(&res.headers, start, end) {
+                    if let Ok(res) = res.into_response().await {
+                        if u64::try_from(res.body.len()).ok() == Some(last - start + 1) {
+                            return Ok(res.body.to_vec());
+                        }
+                    }
+                }
+            }
+        }
+        self.get_data_range_from_chunks(id, start, end).await
+    }
+
+    async fn get_data_range_from_chunks(
+        &self,
+        id: &TxId,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<u8>, Error> {
+        let TxOffset { size, offset } = self.get_tx_offset(id).await?;
+        let tx_start = (offset + 1).saturating_sub(size);
+        let end = end.min(size);
+        let mut data =
+            Vec::with_capacity(end.saturating_sub(start).min(MAX_PREALLOCATION) as usize);
+        let mut position = start;
+        while position < end {
+            let (bytes, next) = self.read_chunk(tx_start, position, end).await?;
+            data.extend_from_slice(&bytes);
+            position = next;
+        }
+        Ok(data)
+    }
+
+    /// Reads the bytes from `position` up to `end`, or to the end of the chunk containing
+    /// `position`, of the data starting at weave offset `tx_start`. Returns the bytes with
+    /// the position following them.
+    async fn read_chunk(
+        &self,
+        tx_start: u64,
+        position: u64,
+        end: u64,
+    ) -> Result<(Vec<u8>, u64), Error> {
+        let invalid_chunk = |reason: &str| Error::InvalidChunk {
+            offset: tx_start + position,
+            reason: reason.to_string(),
+        };
+        let mut chunk = self.get_chunk(tx_start + position).await?;
+        // The data path ends with the offset of the end of the chunk in the data.
+        let len = chunk.data_path.0.len();
+        if len < 8 || chunk.chunk.is_empty() {
+            return Err(invalid_chunk("empty chunk or data path"));
+        }
+        let chunk_end = u64::from_be_bytes(
+            chunk.data_path.0[len - 8..].try_into().unwrap(), //Checked unwrap
+        );
+        let chunk_start = chunk_end.saturating_sub(chunk.chunk.0.len() as u64);
+        if position < chunk_start || position >= chunk_end {
+            return Err(invalid_chunk("chunk does not contain the offset"));
+        }
+        let to = end.min(chunk_end);
+        chunk.chunk.0.truncate((to - chunk_start) as usize);
+        chunk.chunk.0.drain(..(position - chunk_start) as usize);
+        Ok((chunk.chunk.0, to))
+    }
+
+    /// Streams the data of transaction `id` into `writer`, returning the number of bytes
+    /// written.
+    pub async fn download_to<W>(&self, id: &TxId, writer: &mut W) -> Result<u64, Error>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        self.download_to_with_progress(id, writer, |_, _| {}).await
+    }
+
+    /// Same as [`TxClient::download_to`], calling `on_progress` with the number of bytes
+    /// written so far and the data size, when known, after every write.
+    pub async fn download_to_with_progress<W, F>(
+        &self,
+        id: &TxId,
+        writer: &mut W,
+        mut on_progress: F,
+    ) -> Result<u64, Error>
+    where
+        W: AsyncWrite + Unpin,
+        F: FnMut(u64, Option<u64>),
+    {
+        let mut written = 0;
+        match self.get_streaming(&id.to_string(), HeaderMap::new()).await {
+            Ok(res) if res.status == StatusCode::OK => {
+                let total = res.content_length();
+                let mut stream = res.body;
+                while let Some(bytes) = stream.next().await {
+                    let bytes = bytes?;
+                    writer.write_all(&bytes).await?;
+                    written += bytes.len() as u64;
+                    on_progress(written, total);
+                }
+            }
+            _ => {
+                // Nodes do not serve raw data, read it chunk by chunk.
+                let TxOffset { size, offset } = self.get_tx_offset(id).await?;
+                let tx_start = (offset + 1).saturating_sub(size);
+                while written < size {
+                    let (bytes, next) = self.read_chunk(tx_start, written, size).await?;
+                    writer.write_all(&bytes).await?;
+                    written = next;
+                    on_progress(written, Some(size));
+                }
+            }
+        }
+        writer.flush().await?;
+        Ok(written)
+    }
+
+    /// Downloads the data of transaction `id` into the file at `path`, creating or
+    /// truncating it.
+    #[cfg(all(feature = "rt-tokio", not(target_arch = "wasm32")))]
+    pub async fn download_to_file(&self, id: &TxId, path: &std::path::Path) -> Result<u64, Error> {
+        let mut file = tokio::fs::File::create(path).await?;
+        self.download_to(id, &mut file).await
+    }
+
+    /// Polls the status of `id` every `poll_interval` until it has at least
+    /// `min_confirmations`, failing once `timeout` has elapsed. Transactions not found yet, as
+    /// right after submission, and retryable errors count as pending.
+    pub async fn wait_for_confirmation(
+        &self,
+        id: &TxId,
+        min_confirmations: u64,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<TxStatus, Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.get_tx_status(id).await {
+                Ok((_, Some(status))) if status.number_of_confirmations >= min_confirmations => {
+                    return Ok(status)
+                }
+                Ok(_) => {}
+                // Gateways answer 404 until the transaction has propagated to them.
+                Err(Error::UnexpectedStatus { status: 404, .. }) => {}
+                Err(err) if err.is_retryable() => {}
+                Err(err) => return Err(err),
+            }
+            if Instant::now() + poll_interval > deadline {
+                return Err(Error::ConfirmationTimeout(id.to_string()));
+            }
+            sleep(poll_interval).await;
+        }
+    }
+}
+
+/// Last byte of the range served for a request of `start..end`, read from the
+/// `Content-Range: bytes {first}-{last}/{size}` header. `None` unless the range starts at
+/// `start` and ends at `end`, or at the end of the data when it is shorter.
+fn served_range_end(headers: &HeaderMap, start: u64, end: u64) -> Option<u64> {
+    let range = headers.get(CONTENT_RANGE)?.to_str().ok()?;
+    let (bounds, size) = range.strip_prefix("bytes ")?.split_once('/')?;
+    let (first, last) = bounds.split_once('-')?;
+    let (first, last) = (first.parse::<u64>().ok()?, last.parse::<u64>().ok()?);
+    let size = size.parse::<u64>().ok();
+    let served = last.checked_add(1)?;
+    let complete = served == end || (served < end && size == Some(served));
+    match first == start && last >= first && complete {
+        true => Some(last),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, fs, str::FromStr, sync::Mutex, time::Duration};
+
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use reqwest::{header::HeaderMap, StatusCode};
+    use tokio_test::block_on;
+
+    use crate::{
+        address::Address,
+        block::{BlockHash, BlockHeight},
+        crypto::base64::Base64,
+        error::Error,
+        transaction::Tx,
+        tx_id::TxId,
+    };
+
+    use std::sync::Arc;
+
+    use crate::{
+        gateway::GatewayPool,
+        retry::RetryPolicy,
+        transport::{HttpRequest, HttpResponse, HttpTransport},
+    };
+
+    use super::TxClient;
+
+    const SAMPLE_TX: &str = "res/sample_tx.json";
+
+    /// Answers requests with the given responses in order, then with the last one.
+    struct SequenceTransport(Mutex<VecDeque<(StatusCode, String)>>);
+
+    #[async_trait]
+    impl HttpTransport for SequenceTransport {
+        async fn send(&self, _request: HttpRequest) -> Result<HttpResponse, Error> {
+            let mut responses = self.0.lock().unwrap();
+            let (status, body) = match responses.len() {
+                1 => responses[0].clone(),
+                _ => responses.pop_front().unwrap(),
+            };
+            Ok(HttpResponse {
+                status,
+                headers: HeaderMap::new(),
+                body: Bytes::from(body),
+            })
+        }
+    }
+
+    fn client(server: &MockServer) -> TxClient {
+        let url = url::Url::parse(&server.url("")).unwrap();
+        TxClient::new(reqwest::Client::new(), url).unwrap()
+    }
+
+    #[test]
+    fn test_post_transaction() {
+        let data = fs::read_to_string(SAMPLE_TX).unwrap();
+        let tx = Tx::from_str(&data).unwrap();
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx")
+                .json_body(serde_json::to_value(&tx).unwrap());
+            then.status(200);
+        });
+
+        let (id, reward) = block_on(client(&server).post_transaction(&tx)).unwrap();
+
+        mock.assert();
+        assert_eq!(id.to_base64(), tx.id);
+        assert_eq!(reward, tx.reward);
+    }
+
+    #[test]
+    fn test_post_transaction_retries() {
+        let data = fs::read_to_string(SAMPLE_TX).unwrap();
+        let tx = Tx::from_str(&data).unwrap();
+        let server = MockServer::start();
+        let mut mock = server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(503);
+        });
+        let client = client(&server).with_retry_policy(
+            RetryPolicy::default()
+                .max_attempts(3)
+                .base_delay(Duration::from_millis(1)),
+        );
+
+        let res = block_on(client.post_transaction(&tx));
+        assert!(matches!(
+            res,
+            Err(Error::UnexpectedStatus { status: 503, .. })
+        ));
+        mock.assert_hits(3);
+
+        mock.delete();
+        let mock = server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(400).body("Transaction verification failed.");
+        });
+        let res = block_on(client.post_transaction(&tx));
+        assert!(
+            matches!(res, Err(Error::UnexpectedStatus { status: 400, body }) if body == "Transaction verification failed.")
+        );
+        mock.assert_hits(1);
+    }
+
+    #[test]
+    fn test_gateway_failover() {
+        let failing = MockServer::start();
+        let failing_mock = failing.mock(|when, then| {
+            when.method(GET).path("/price/10");
+            then.status(502);
+        });
+        let healthy = MockServer::start();
+        let healthy_mock = healthy.mock(|when, then| {
+            when.method(GET).path("/price/10");
+            then.status(200).body("42");
+        });
+        let gateways = GatewayPool::new(vec![
+            url::Url::parse(&failing.url("/")).unwrap(),
+            url::Url::parse(&healthy.url("/")).unwrap(),
+        ])
+        .unwrap()
+        .with_failure_threshold(2);
+        let client = client(&failing)
+            .with_gateways(Arc::new(gateways))
+            .with_retry_policy(
+                RetryPolicy::default()
+                    .max_attempts(3)
+                    .base_delay(Duration::from_millis(1)),
+            );
+
+        assert_eq!(block_on(client.get_price(10, None)).unwrap(), 42);
+        failing_mock.assert_hits(2);
+        healthy_mock.assert_hits(1);
+    }
+
+    #[test]
+    fn test_post_transaction_failover() {
+        let tx = Tx::from_str(&fs::read_to_string(SAMPLE_TX).unwrap()).unwrap();
+        let failing = MockServer::start();
+        let failing_mock = failing.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(503);
+        });
+        let healthy = MockServer::start();
+        let healthy_mock = healthy.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200);
+        });
+        let gateways = GatewayPool::new(vec![
+            url::Url::parse(&failing.url("/")).unwrap(),
+            url::Url::parse(&healthy.url("/")).unwrap(),
+        ])
+        .unwrap()
+        .with_failure_threshold(1);
+        let client = client(&failing)
+            .with_gateways(Arc::new(gateways))
+            .with_retry_policy(
+                RetryPolicy::default()
+                    .max_attempts(2)
+                    .base_delay(Duration::from_millis(1)),
+            );
+
+        let (id, _) = block_on(client.post_transaction(&tx)).unwrap();
+        assert_eq!(id.to_base64(), tx.id);
+        failing_mock.assert_hits(1);
+        healthy_mock.assert_hits(1);
+    }
+
+    #[test]
+    fn test_get_data_from_chunks_of_huge_size() {
+        let id = TxId::new([1; 32]);
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", id));
+            then.status(200).json_body(
+                serde_json::json!({ "size": "1125899906842624", "offset": "1125899906842624" }),
+            );
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/chunk/1");
+            then.status(200).json_body(serde_json::json!({
+                "chunk": "",
+                "data_path": "",
+                "tx_path": "",
+            }));
+        });
+
+        // Fails on the empty chunk rather than aborting on a petabyte allocation.
+        let res = block_on(client(&server).get_data_from_chunks(&id));
+        assert!(matches!(res, Err(Error::TransactionInfoError(_))));
+    }
+
+    #[test]
+    fn test_post_unsigned_transaction() {
+        let server = MockServer::start();
+        let res = block_on(client(&server).post_transaction(&Tx::default()));
+        assert!(matches!(res, Err(Error::UnsignedTransaction)));
+    }
+
+    #[test]
+    fn test_get_tx() {
+        let data = fs::read_to_string(SAMPLE_TX).unwrap();
+        let expected = Tx::from_str(&data).unwrap();
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", expected.id));
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(&data);
+        });
+        let pending = TxId::new([1; 32]);
+        let pending_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", pending));
+            then.status(202);
+        });
+
+        let client = client(&server);
+        let (status, tx) = block_on(client.get_tx(&TxId::try_from(&expected.id).unwrap())).unwrap();
+        mock.assert();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(tx, Some(expected));
+
+        let (status, tx) = block_on(client.get_tx(&pending)).unwrap();
+        pending_mock.assert();
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(tx, None);
+    }
+
+    #[test]
+    fn test_get_tx_anchor() {
+        let anchor = Base64(vec![3; 48]);
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(anchor.to_string());
+        });
+
+        assert_eq!(block_on(client(&server).get_tx_anchor()).unwrap(), anchor);
+        mock.assert();
+    }
+
+    #[test]
+    fn test_get_invalid_tx_anchor() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(Base64(vec![3; 64]).to_string());
+        });
+
+        let res = block_on(client(&server).get_tx_anchor());
+        assert!(
+            matches!(res, Err(Error::TransactionInfoError(msg)) if msg == "invalid tx anchor of 64 bytes")
+        );
+    }
+
+    #[test]
+    fn test_get_price() {
+        let target = Address::new([1; 32]);
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/price/1024");
+            then.status(200).body("12345");
+        });
+        let target_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/price/1024/{}", target));
+            then.status(200).body("23456");
+        });
+
+        let client = client(&server);
+        assert_eq!(block_on(client.get_price(1024, None)).unwrap(), 12345);
+        assert_eq!(
+            block_on(client.get_fee(Some(target), vec![0; 1024])).unwrap(),
+            23456
+        );
+        assert_eq!(
+            block_on(client.get_fee(None, vec![0; 1024])).unwrap(),
+            12345
+        );
+        mock.assert_hits(2);
+        target_mock.assert();
+    }
+
+    #[test]
+    fn test_get_tx_status() {
+        let id = TxId::new([1; 32]);
+        let block_indep_hash = BlockHash::new([2; 48]);
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/status", id));
+            then.status(200).json_body(serde_json::json!({
+                "block_height": 1000,
+                "block_indep_hash": block_indep_hash,
+                "number_of_confirmations": 25,
+            }));
+        });
+
+        let (status, tx_status) = block_on(client(&server).get_tx_status(&id)).unwrap();
+
+        mock.assert();
+        assert_eq!(status, StatusCode::OK);
+        let tx_status = tx_status.unwrap();
+        assert_eq!(tx_status.block_height, BlockHeight::new(1000));
+        assert_eq!(tx_status.block_indep_hash, block_indep_hash);
+        assert_eq!(tx_status.number_of_confirmations, 25);
+    }
+
+    #[test]
+    fn test_wait_for_confirmation() {
+        let id = TxId::new([1; 32]);
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/status", id));
+            then.status(200).json_body(serde_json::json!({
+                "block_height": 1000,
+                "block_indep_hash": Base64(vec![2; 48]).to_string(),
+                "number_of_confirmations": 3,
+            }));
+        });
+
+        let client = client(&server);
+        let status = block_on(client.wait_for_confirmation(
+            &id,
+            2,
+            Duration::from_millis(10),
+            Duration::from_secs(1),
+        ))
+        .unwrap();
+        assert_eq!(status.number_of_confirmations, 3);
+        mock.assert_hits(1);
+
+        let res = block_on(client.wait_for_confirmation(
+            &id,
+            10,
+            Duration::from_millis(10),
+            Duration::from_millis(50),
+        ));
+        assert!(matches!(res, Err(Error::ConfirmationTimeout(tx_id)) if tx_id == id.to_string()));
+        assert!(mock.hits() > 2);
+    }
+
+    #[test]
+    fn test_wait_for_pending_confirmation() {
+        let id = TxId::new([1; 32]);
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/status", id));
+            then.status(202);
+        });
+
+        let res = block_on(client(&server).wait_for_confirmation(
+            &id,
+            1,
+            Duration::from_millis(10),
+            Duration::from_millis(30),
+        ));
+        assert!(matches!(res, Err(Error::ConfirmationTimeout(_))));
+        assert!(mock.hits() >= 2);
+    }
+
+    #[test]
+    fn test_wait_for_propagating_confirmation() {
+        let status = serde_json::json!({
+            "block_height": 1000,
+            "block_indep_hash": Base64(vec![2; 48]).to_string(),
+            "number_of_confirmations": 1,
+        });
+        let transport = SequenceTransport(Mutex::new(VecDeque::from(vec![
+            (StatusCode::NOT_FOUND, "Not Found.".to_string()),
+            (StatusCode::ACCEPTED, "Pending".to_string()),
+            (StatusCode::OK, status.to_string()),
+        ])));
+        let client = TxClient::default().with_transport(Arc::new(transport));
+
+        let status = block_on(client.wait_for_confirmation(
+            &TxId::new([1; 32]),
+            1,
+            Duration::from_millis(1),
+            Duration::from_secs(5),
+        ))
+        .unwrap();
+        assert_eq!(status.number_of_confirmations, 1);
+
+        let rejected = SequenceTransport(Mutex::new(VecDeque::from(vec![(
+            StatusCode::BAD_REQUEST,
+            "Invalid hash.".to_string(),
+        )])));
+        let client = TxClient::default().with_transport(Arc::new(rejected));
+        let res = block_on(client.wait_for_confirmation(
+            &TxId::new([1; 32]),
+            1,
+            Duration::from_millis(1),
+            Duration::from_secs(5),
+        ));
+        assert!(matches!(
+            res,
+            Err(Error::UnexpectedStatus { status: 400, .. })
+        ));
+    }
 }
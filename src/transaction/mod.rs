@@ -1,25 +1,43 @@
 use serde::Deserialize;
 
 use crate::{
+    address::Address,
     consts::VERSION,
-    crypto::{base64::Base64, Provider},
+    content_type::{self, DEFAULT_CONTENT_TYPE},
     crypto::{
-        hash::{DeepHashItem, ToItems},
-        merkle::{generate_data_root, generate_leaves, resolve_proofs, Node, Proof},
+        base64::{Base64, Base64Ref},
+        Provider,
+    },
+    crypto::{
+        hash::{deep_hash, sha256, DeepHashItem, ToItems},
+        merkle::{
+            generate_data_root, generate_leaves_with_options, resolve_proofs, ChunkingOptions,
+            Node, Proof,
+        },
+        utils::ct_eq,
     },
     currency::Currency,
     error::Error,
+    network::ProtocolParams,
     transaction::tags::Tag,
-    types::Chunk,
+    types::{Chunk, ChunkRef, Tx as JsonTx},
 };
 
-use self::tags::FromUtf8Strs;
+use self::tags::USER_AGENT;
 
+pub mod builder;
 pub mod client;
+pub mod offline;
 pub mod parser;
 pub mod tags;
+pub mod validate;
+
+pub use builder::TransactionBuilder;
 
+/// Transaction. (De)serializes to the JSON format of the `tx` endpoints, with
+/// base64url-encoded binary fields and stringified amounts.
 #[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(try_from = "JsonTx")]
 pub struct Tx {
     /* Fields required for signing */
     pub format: u8,
@@ -34,64 +52,59 @@ pub struct Tx {
     pub data_size: u64,
     pub reward: u64,
     pub signature: Base64,
-    #[serde(skip)]
     pub chunks: Vec<Node>,
-    #[serde(skip)]
     pub proofs: Vec<Proof>,
 }
 
 impl<'a> ToItems<'a, Tx> for Tx {
-    fn to_deep_hash_item(&'a self) -> Result<DeepHashItem, Error> {
+    fn to_deep_hash_item(&'a self) -> Result<DeepHashItem<'a>, Error> {
         match &self.format {
             1 => {
-                let quantity = Base64::from_utf8_str(&self.quantity.to_string()).unwrap();
-                let reward = Base64::from_utf8_str(&self.reward.to_string()).unwrap();
                 let mut children: Vec<DeepHashItem> = vec![
-                    &self.owner,
-                    &self.target,
-                    &self.data,
-                    &quantity,
-                    &reward,
-                    &self.last_tx,
-                ]
-                .into_iter()
-                .map(|op| DeepHashItem::from_item(&op.0))
-                .collect();
+                    DeepHashItem::from_item(&self.owner.0),
+                    DeepHashItem::from_item(&self.target.0),
+                    DeepHashItem::from_item(&self.data.0),
+                    DeepHashItem::Blob(self.quantity.to_string().into_bytes().into()),
+                    DeepHashItem::Blob(self.reward.to_string().into_bytes().into()),
+                    DeepHashItem::from_item(&self.last_tx.0),
+                ];
                 children.push(self.tags.to_deep_hash_item()?);
 
                 Ok(DeepHashItem::from_children(children))
             }
             2 => {
                 let mut children: Vec<DeepHashItem> = vec![
-                    self.format.to_string().as_bytes(),
-                    &self.owner.0,
-                    &self.target.0,
-                    self.quantity.to_string().as_bytes(),
-                    self.reward.to_string().as_bytes(),
-                    &self.last_tx.0,
-                ]
-                .into_iter()
-                .map(DeepHashItem::from_item)
-                .collect();
+                    DeepHashItem::Blob(self.format.to_string().into_bytes().into()),
+                    DeepHashItem::from_item(&self.owner.0),
+                    DeepHashItem::from_item(&self.target.0),
+                    DeepHashItem::Blob(self.quantity.to_string().into_bytes().into()),
+                    DeepHashItem::Blob(self.reward.to_string().into_bytes().into()),
+                    DeepHashItem::from_item(&self.last_tx.0),
+                ];
                 children.push(self.tags.to_deep_hash_item().unwrap());
-                children.push(DeepHashItem::from_item(
-                    self.data_size.to_string().as_bytes(),
+                children.push(DeepHashItem::Blob(
+                    self.data_size.to_string().into_bytes().into(),
                 ));
                 children.push(DeepHashItem::from_item(&self.data_root.0));
 
                 Ok(DeepHashItem::from_children(children))
             }
-            _ => unreachable!(),
+            format => Err(Error::TransactionInfoError(format!(
+                "unsupported format {}",
+                format
+            ))),
         }
     }
 }
 
 impl Tx {
     fn base_tag() -> Tag<Base64> {
-        Tag::<Base64>::from_utf8_strs("User-Agent", &format!("arweave-rs/{}", VERSION)).unwrap()
+        Tag::new(USER_AGENT, &format!("arweave-rs/{}", VERSION))
     }
 
-    fn generate_merkle(data: Vec<u8>) -> Result<Tx, Error> {
+    /// Computes data root, chunks and proofs of `data`, leaving the data field empty.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(data_size = data.len())))]
+    fn generate_merkle(data: &[u8], options: &ChunkingOptions) -> Result<Tx, Error> {
         if data.is_empty() {
             let empty = Base64(vec![]);
             Ok(Tx {
@@ -104,7 +117,7 @@ impl Tx {
                 ..Default::default()
             })
         } else {
-            let mut chunks = generate_leaves(data.clone()).unwrap();
+            let mut chunks = generate_leaves_with_options(data, options)?;
             let root = generate_data_root(chunks.clone()).unwrap();
             let data_root = Base64(root.id.into_iter().collect());
             let mut proofs = resolve_proofs(root, None).unwrap();
@@ -115,11 +128,12 @@ impl Tx {
                 chunks.pop();
                 proofs.pop();
             }
+            #[cfg(feature = "tracing")]
+            tracing::debug!(chunks = chunks.len(), data_root = %data_root, "chunked data");
 
             Ok(Tx {
                 format: 2,
                 data_size: data.len() as u64,
-                data: Base64(data),
                 data_root,
                 chunks,
                 proofs,
@@ -140,26 +154,99 @@ impl Tx {
         last_tx: Base64,
         other_tags: Vec<Tag<Base64>>,
         auto_content_tag: bool,
+    ) -> Result<Self, Error> {
+        let mut transaction = Tx::new_with_detached_data(
+            crypto,
+            target,
+            &data,
+            quantity,
+            fee,
+            last_tx,
+            other_tags,
+            auto_content_tag,
+        )?;
+        transaction.data = Base64(data);
+        Ok(transaction)
+    }
+
+    /// Same as [`Tx::new`] without copying `data` into the transaction. Chunks have to be
+    /// read from the original data with [`Tx::get_chunk_from`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_detached_data(
+        crypto: &Provider,
+        target: Base64,
+        data: &[u8],
+        quantity: u128,
+        fee: u64,
+        last_tx: Base64,
+        other_tags: Vec<Tag<Base64>>,
+        auto_content_tag: bool,
+    ) -> Result<Self, Error> {
+        Tx::new_with_owner(
+            crypto.keypair_modulus(),
+            target,
+            data,
+            quantity,
+            fee,
+            last_tx,
+            other_tags,
+            auto_content_tag,
+        )
+    }
+
+    /// Same as [`Tx::new_with_detached_data`] for the wallet with public key `owner`, without
+    /// access to its private key.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_owner(
+        owner: Base64,
+        target: Base64,
+        data: &[u8],
+        quantity: u128,
+        fee: u64,
+        last_tx: Base64,
+        other_tags: Vec<Tag<Base64>>,
+        auto_content_tag: bool,
+    ) -> Result<Self, Error> {
+        Tx::new_with_chunking(
+            owner,
+            target,
+            data,
+            quantity,
+            fee,
+            last_tx,
+            other_tags,
+            auto_content_tag,
+            &ChunkingOptions::default(),
+        )
+    }
+
+    /// Same as [`Tx::new_with_owner`] with the data split as set by `options`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new_with_chunking(
+        owner: Base64,
+        target: Base64,
+        data: &[u8],
+        quantity: u128,
+        fee: u64,
+        last_tx: Base64,
+        other_tags: Vec<Tag<Base64>>,
+        auto_content_tag: bool,
+        options: &ChunkingOptions,
     ) -> Result<Self, Error> {
         if quantity.lt(&0) {
             return Err(Error::InvalidValueForTx);
         }
 
-        let mut transaction = Tx::generate_merkle(data).unwrap();
-        transaction.owner = crypto.keypair_modulus();
+        let mut transaction = Tx::generate_merkle(data, options)?;
+        transaction.owner = owner;
 
         let mut tags = vec![Tx::base_tag()];
 
         // Get content type from [magic numbers](https://developer.mozilla.org/en-US/docs/Web/HTTP/Basics_of_HTTP/MIME_types)
         // and include additional tags if any.
         if auto_content_tag {
-            let content_type = if let Some(kind) = infer::get(&transaction.data.0) {
-                kind.mime_type()
-            } else {
-                "application/octet-stream"
-            };
-
-            tags.push(Tag::<Base64>::from_utf8_strs("Content-Type", content_type)?)
+            let content_type = content_type::from_data(data).unwrap_or(DEFAULT_CONTENT_TYPE);
+            tags.push(Tag::content_type(content_type))
         }
 
         // Add other tags if provided.
@@ -176,6 +263,76 @@ impl Tx {
         Ok(transaction)
     }
 
+    /// Wallet the quantity is transferred to, `None` without target. Fails if the target is
+    /// not an address.
+    pub fn target_address(&self) -> Result<Option<Address>, Error> {
+        match self.target.is_empty() {
+            true => Ok(None),
+            false => Address::try_from(&self.target).map(Some),
+        }
+    }
+
+    /// Converts the transaction to the legacy format 1, which carries its data inline and
+    /// has no data root.
+    pub fn into_format_1(mut self) -> Self {
+        self.format = 1;
+        self.data_root = Base64::empty();
+        self.chunks = vec![];
+        self.proofs = vec![];
+        self
+    }
+
+    /// Message signed by the owner: the deep hash of the fields for format 2 and the
+    /// concatenation of the fields for format 1.
+    pub fn signature_data(&self) -> Result<Vec<u8>, Error> {
+        match self.format {
+            1 => {
+                let mut data = [
+                    &self.owner.0,
+                    &self.target.0,
+                    &self.data.0,
+                    self.quantity.to_string().as_bytes(),
+                    self.reward.to_string().as_bytes(),
+                    &self.last_tx.0,
+                ]
+                .concat();
+                for tag in &self.tags {
+                    data.extend_from_slice(&tag.name.0);
+                    data.extend_from_slice(&tag.value.0);
+                }
+                Ok(data)
+            }
+            _ => Ok(deep_hash(self.to_deep_hash_item()?).to_vec()),
+        }
+    }
+
+    /// Id of the signed transaction: the SHA-256 hash of its signature. Known as soon as the
+    /// transaction is signed, before it is posted.
+    pub fn compute_id(&self) -> Result<Base64, Error> {
+        if self.signature.is_empty() {
+            return Err(Error::UnsignedTransaction);
+        }
+        Ok(Base64(sha256(&self.signature.0).to_vec()))
+    }
+
+    /// Signs the transaction with `crypto` once checked with [`Tx::validate`], setting its
+    /// signature and id.
+    pub fn sign(self, crypto: &Provider) -> Result<Self, Error> {
+        self.sign_for(crypto, &ProtocolParams::default())
+    }
+
+    /// Same as [`Tx::sign`] for a network with the protocol parameters `params`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(format = self.format, data_size = self.data_size)))]
+    pub fn sign_for(mut self, crypto: &Provider, params: &ProtocolParams) -> Result<Self, Error> {
+        self.validate_for(params)?;
+        let signature_data = self.signature_data()?;
+        self.signature = crypto.sign(&signature_data)?;
+        self.id = self.compute_id()?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(id = %self.id, "signed transaction");
+        Ok(self)
+    }
+
     pub fn clone_with_no_data(&self) -> Result<Self, Error> {
         Ok(Self {
             format: self.format,
@@ -195,16 +352,83 @@ impl Tx {
         })
     }
 
+    /// Checks `data` against the data size and data root of the transaction. Format 1
+    /// transactions carry their data, which is compared directly. The data root is computed
+    /// with the default [`ChunkingOptions`].
+    pub fn verify_data(&self, data: &[u8]) -> Result<(), Error> {
+        if self.format == 1 {
+            return match self.data.0 == data {
+                true => Ok(()),
+                false => Err(Error::InvalidData(
+                    "data differs from transaction".to_string(),
+                )),
+            };
+        }
+        if data.len() as u64 != self.data_size {
+            return Err(Error::InvalidData(format!(
+                "expected {} bytes, got {}",
+                self.data_size,
+                data.len()
+            )));
+        }
+        let data_root = Tx::generate_merkle(data, &ChunkingOptions::default())?.data_root;
+        if !ct_eq(&data_root.0, &self.data_root.0) {
+            return Err(Error::InvalidData(format!(
+                "data root {} does not match {}",
+                data_root, self.data_root
+            )));
+        }
+        Ok(())
+    }
+
     pub fn get_chunk(&self, idx: usize) -> Result<Chunk, Error> {
-        Ok(Chunk {
-            data_root: self.data_root.clone(),
+        self.get_chunk_from(idx, &self.data.0)
+    }
+
+    /// Reads chunk `idx` from `data`, which must be the data the transaction was created from.
+    pub fn get_chunk_from(&self, idx: usize, data: &[u8]) -> Result<Chunk, Error> {
+        self.get_chunk_ref_from(idx, data)
+            .map(|chunk| chunk.to_chunk())
+    }
+
+    /// Borrows chunk `idx` from `data` and the proofs of the transaction, without copying them.
+    pub fn get_chunk_ref_from<'a>(
+        &'a self,
+        idx: usize,
+        data: &'a [u8],
+    ) -> Result<ChunkRef<'a>, Error> {
+        if data.len() as u64 != self.data_size {
+            return Err(Error::TransactionInfoError(format!(
+                "expected {} bytes of data, got {}",
+                self.data_size,
+                data.len()
+            )));
+        }
+        let chunk = self.chunks.get(idx).ok_or_else(|| Error::InvalidChunk {
+            offset: self.data_size,
+            reason: format!("no chunk {} among {}", idx, self.chunks.len()),
+        })?;
+        let invalid_chunk = |reason: String| Error::InvalidChunk {
+            offset: chunk.min_byte_range as u64,
+            reason,
+        };
+        let proof = self.proofs.get(idx).ok_or_else(|| {
+            invalid_chunk(format!("no proof {} among {}", idx, self.proofs.len()))
+        })?;
+        let bytes = data
+            .get(chunk.min_byte_range..chunk.max_byte_range)
+            .ok_or_else(|| {
+                invalid_chunk(format!(
+                    "byte range {}..{} outside the data",
+                    chunk.min_byte_range, chunk.max_byte_range
+                ))
+            })?;
+        Ok(ChunkRef {
+            data_root: self.data_root.as_base64_ref(),
             data_size: self.data_size,
-            data_path: Base64(self.proofs[idx].proof.clone()),
-            offset: self.proofs[idx].offset,
-            chunk: Base64(
-                self.data.0[self.chunks[idx].min_byte_range..self.chunks[idx].max_byte_range]
-                    .to_vec(),
-            ),
+            data_path: Base64Ref(&proof.proof),
+            offset: proof.offset,
+            chunk: Base64Ref(bytes),
         })
     }
 }
@@ -0,0 +1,214 @@
+//! Air-gapped signing. The online machine exports the unsigned transaction, including its
+//! chunks metadata, as a portable JSON document; the offline machine signs it with
+//! [`Tx::sign_detached`] and the [`DetachedSignature`] is attached back before posting.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    crypto::{base64::Base64, merkle::Node, merkle::Proof, Provider},
+    error::Error,
+    types::Tx as JsonTx,
+    verify::verify_transaction,
+};
+
+use super::Tx;
+
+pub const PORTABLE_TX_VERSION: u8 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct PortableChunk {
+    id: Base64,
+    data_hash: Base64,
+    min_byte_range: usize,
+    max_byte_range: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct PortableProof {
+    offset: usize,
+    proof: Base64,
+}
+
+#[derive(Serialize)]
+struct PortableTxRef<'a> {
+    version: u8,
+    transaction: &'a Tx,
+    chunks: Vec<PortableChunk>,
+    proofs: Vec<PortableProof>,
+}
+
+#[derive(Deserialize)]
+struct PortableTx {
+    version: u8,
+    transaction: JsonTx,
+    chunks: Vec<PortableChunk>,
+    proofs: Vec<PortableProof>,
+}
+
+/// Signature produced on the offline machine.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DetachedSignature {
+    pub id: Base64,
+    pub signature: Base64,
+}
+
+impl Tx {
+    /// Exports the transaction with its chunks and proofs as a portable JSON document.
+    pub fn to_portable_json(&self) -> Result<String, Error> {
+        let portable = PortableTxRef {
+            version: PORTABLE_TX_VERSION,
+            transaction: self,
+            chunks: self
+                .chunks
+                .iter()
+                .map(|chunk| PortableChunk {
+                    id: Base64(chunk.id.to_vec()),
+                    data_hash: Base64(chunk.data_hash.unwrap_or_default().to_vec()),
+                    min_byte_range: chunk.min_byte_range,
+                    max_byte_range: chunk.max_byte_range,
+                })
+                .collect(),
+            proofs: self
+                .proofs
+                .iter()
+                .map(|proof| PortableProof {
+                    offset: proof.offset,
+                    proof: Base64(proof.proof.clone()),
+                })
+                .collect(),
+        };
+        serde_json::to_string(&portable).map_err(Error::SerdeJsonError)
+    }
+
+    pub fn from_portable_json(json: &str) -> Result<Self, Error> {
+        let portable: PortableTx = serde_json::from_str(json).map_err(Error::SerdeJsonError)?;
+        if portable.version != PORTABLE_TX_VERSION {
+            return Err(Error::TransactionInfoError(format!(
+                "unsupported portable transaction version {}",
+                portable.version
+            )));
+        }
+        if portable.chunks.len() != portable.proofs.len() {
+            return Err(Error::TransactionInfoError(
+                "chunks and proofs do not match".to_string(),
+            ));
+        }
+        let mut transaction = Tx::try_from(portable.transaction)?;
+        transaction.chunks = portable
+            .chunks
+            .into_iter()
+            .map(|chunk| {
+                Ok(Node {
+                    id: hash_from(&chunk.id)?,
+                    data_hash: Some(hash_from(&chunk.data_hash)?),
+                    min_byte_range: chunk.min_byte_range,
+                    max_byte_range: chunk.max_byte_range,
+                    left_child: None,
+                    right_child: None,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        transaction.proofs = portable
+            .proofs
+            .into_iter()
+            .map(|proof| Proof {
+                offset: proof.offset,
+                proof: proof.proof.0,
+            })
+            .collect();
+        Ok(transaction)
+    }
+
+    /// Signs the transaction without modifying it, once checked with [`Tx::validate`].
+    /// `crypto` must hold the key of the owner the transaction was built for.
+    pub fn sign_detached(&self, crypto: &Provider) -> Result<DetachedSignature, Error> {
+        if self.owner != crypto.public_key() {
+            return Err(Error::SigningError(
+                "transaction was built for another owner".to_string(),
+            ));
+        }
+        self.validate()?;
+        let signature_data = self.signature_data()?;
+        let signature = crypto.sign(&signature_data)?;
+        let id = Base64(crypto.hash_sha256(&signature.0).to_vec());
+        Ok(DetachedSignature { id, signature })
+    }
+
+    /// Sets a signature produced by [`Tx::sign_detached`], checking it is valid.
+    pub fn attach_signature(mut self, signature: DetachedSignature) -> Result<Self, Error> {
+        self.id = signature.id;
+        self.signature = signature.signature;
+        verify_transaction(&self)?;
+        Ok(self)
+    }
+}
+
+fn hash_from(bytes: &Base64) -> Result<[u8; 32], Error> {
+    bytes
+        .0
+        .as_slice()
+        .try_into()
+        .map_err(|_| Error::TransactionInfoError("invalid chunk hash".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use crate::{
+        crypto::{base64::Base64, Provider},
+        currency::Winston,
+        error::Error,
+        transaction::{TransactionBuilder, Tx},
+    };
+
+    #[test]
+    fn test_air_gapped_signing() -> Result<(), Error> {
+        let provider = Provider::from_keypair_path(PathBuf::from("res/test_wallet.json"))?;
+        let data = fs::read("res/rebar3")?;
+
+        // Online machine, holding only the public key.
+        let unsigned = TransactionBuilder::new()
+            .data(data.clone())
+            .reward(Winston::new(1000))
+            .last_tx(Base64(vec![1; 48]))
+            .build_for_owner(provider.public_key())?;
+        // Data stays online, only the header and chunks metadata are needed to sign.
+        let mut exported = unsigned.clone_with_no_data()?;
+        exported.chunks = unsigned.chunks.clone();
+        exported.proofs = unsigned.proofs.clone();
+        let json = exported.to_portable_json()?;
+
+        // Offline machine.
+        let imported = Tx::from_portable_json(&json)?;
+        assert_eq!(imported, exported);
+        let signature = imported.sign_detached(&provider)?;
+        let signature_json = serde_json::to_string(&signature).unwrap();
+
+        // Back online.
+        let signed = unsigned.attach_signature(serde_json::from_str(&signature_json).unwrap())?;
+        assert_eq!(signed.get_chunk(0)?.data_root, signed.data_root);
+
+        let mut tampered = signature;
+        tampered.signature.0[0] ^= 1;
+        assert!(Tx::from_portable_json(&json)?
+            .attach_signature(tampered)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_detached_for_other_owner() -> Result<(), Error> {
+        let provider = Provider::from_keypair_path(PathBuf::from("res/test_wallet.json"))?;
+        let unsigned = TransactionBuilder::new()
+            .data(b"hello".to_vec())
+            .reward(Winston::new(0))
+            .last_tx(Base64(vec![0; 32]))
+            .build_for_owner(Base64(vec![1; 512]))?;
+        assert!(matches!(
+            unsigned.sign_detached(&provider),
+            Err(Error::SigningError(_))
+        ));
+        Ok(())
+    }
+}
@@ -7,11 +7,17 @@ use crate::{currency::Currency, error::Error};
 use super::{tags::Tag, Tx};
 use crate::types::Tx as JsonTx;
 
-impl From<JsonTx> for Tx {
-    fn from(json_tx: JsonTx) -> Self {
+impl TryFrom<JsonTx> for Tx {
+    type Error = Error;
+
+    fn try_from(json_tx: JsonTx) -> Result<Self, Self::Error> {
         let tags = json_tx.tags.iter().map(Tag::from).collect();
-        Tx {
-            quantity: Currency::from_str(&json_tx.quantity).unwrap(),
+        let data_size = match json_tx.data_size.as_str() {
+            "" => json_tx.data.0.len() as u64,
+            data_size => u64::from_str(data_size).map_err(Error::ParseIntError)?,
+        };
+        Ok(Tx {
+            quantity: Currency::from_str(&json_tx.quantity)?,
             format: json_tx.format,
             id: json_tx.id,
             last_tx: json_tx.last_tx,
@@ -20,12 +26,12 @@ impl From<JsonTx> for Tx {
             target: json_tx.target,
             data_root: json_tx.data_root,
             data: json_tx.data,
-            data_size: u64::from_str(&json_tx.data_size).unwrap(),
-            reward: u64::from_str(&json_tx.reward).unwrap(),
+            data_size,
+            reward: u64::from_str(&json_tx.reward).map_err(Error::ParseIntError)?,
             signature: json_tx.signature,
             chunks: vec![],
             proofs: vec![],
-        }
+        })
     }
 }
 
@@ -34,7 +40,7 @@ impl FromStr for Tx {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let json_tx: JsonTx = serde_json::from_str(s).map_err(Error::SerdeJsonError)?;
-        Ok(Tx::from(json_tx))
+        Tx::try_from(json_tx)
     }
 }
 
@@ -99,4 +105,33 @@ mod tests {
 
         assert_eq!(actual_tx, expected_tx);
     }
+    #[test]
+    pub fn should_round_trip_json() {
+        let data = std::fs::read_to_string("res/sample_tx.json").unwrap();
+        let tx = Tx::from_str(&data).unwrap();
+
+        let json = serde_json::to_value(&tx).unwrap();
+        assert_eq!(json["quantity"], "100000");
+        assert_eq!(json["reward"], "600912");
+        assert_eq!(json["data_size"], "0");
+        assert_eq!(json["tags"][0]["name"], "dGVzdA");
+        assert_eq!(serde_json::from_value::<Tx>(json).unwrap(), tx);
+    }
+
+    #[test]
+    pub fn should_reject_malformed_amounts() {
+        let data = std::fs::read_to_string("res/sample_tx.json").unwrap();
+        let json = serde_json::to_value(Tx::from_str(&data).unwrap()).unwrap();
+        for (field, value) in [
+            ("quantity", "1e9"),
+            ("reward", "-1"),
+            ("reward", "a lot"),
+            ("data_size", "18446744073709551616"),
+        ] {
+            let mut malformed = json.clone();
+            malformed[field] = value.into();
+            assert!(serde_json::from_value::<Tx>(malformed.clone()).is_err());
+            assert!(Tx::from_str(&malformed.to_string()).is_err());
+        }
+    }
 }
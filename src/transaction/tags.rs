@@ -1,4 +1,4 @@
-use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     crypto::{base64::Base64, hash::DeepHashItem},
@@ -8,8 +8,15 @@ use crate::{
 
 use super::ToItems;
 
+/// Well-known tag names.
+pub const CONTENT_TYPE: &str = "Content-Type";
+pub const APP_NAME: &str = "App-Name";
+pub const APP_VERSION: &str = "App-Version";
+pub const UNIX_TIME: &str = "Unix-Time";
+pub const USER_AGENT: &str = "User-Agent";
+
 /// Transaction tag.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct Tag<T> {
     pub name: T,
     pub value: T,
@@ -32,6 +39,53 @@ impl FromUtf8Strs<Tag<Base64>> for Tag<Base64> {
     }
 }
 
+impl Tag<Base64> {
+    /// Creates a tag from utf-8 strings, encoding them internally.
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: Base64(name.as_bytes().to_vec()),
+            value: Base64(value.as_bytes().to_vec()),
+        }
+    }
+
+    pub fn content_type(mime_type: &str) -> Self {
+        Self::new(CONTENT_TYPE, mime_type)
+    }
+
+    pub fn app_name(name: &str) -> Self {
+        Self::new(APP_NAME, name)
+    }
+
+    pub fn app_version(version: &str) -> Self {
+        Self::new(APP_VERSION, version)
+    }
+
+    /// `Unix-Time` tag, in seconds since the epoch.
+    pub fn unix_time(secs: u64) -> Self {
+        Self::new(UNIX_TIME, &secs.to_string())
+    }
+
+    /// Checks the tag name, comparing the decoded bytes.
+    pub fn has_name(&self, name: &str) -> bool {
+        self.name.0 == name.as_bytes()
+    }
+
+    /// Decodes name and value back to utf-8 strings.
+    pub fn to_utf8_strings(&self) -> Result<Tag<String>, Error> {
+        Ok(Tag {
+            name: self.name.to_utf8_string()?,
+            value: self.value.to_utf8_string()?,
+        })
+    }
+}
+
+/// Finds the utf-8 value of the first tag named `name`.
+pub fn find_tag_value(tags: &[Tag<Base64>], name: &str) -> Option<String> {
+    tags.iter()
+        .find(|tag| tag.has_name(name))
+        .and_then(|tag| tag.value.to_utf8_string().ok())
+}
+
 impl FromUtf8Strs<Tag<String>> for Tag<String> {
     fn from_utf8_strs(name: &str, value: &str) -> Result<Self, Error> {
         let name = String::from(name);
@@ -42,7 +96,7 @@ impl FromUtf8Strs<Tag<String>> for Tag<String> {
 }
 
 impl<'a> ToItems<'a, Vec<Tag<Base64>>> for Vec<Tag<Base64>> {
-    fn to_deep_hash_item(&'a self) -> Result<DeepHashItem, Error> {
+    fn to_deep_hash_item(&'a self) -> Result<DeepHashItem<'a>, Error> {
         Ok(DeepHashItem::List(
             self.iter()
                 .map(|t| t.to_deep_hash_item().unwrap())
@@ -52,27 +106,14 @@ impl<'a> ToItems<'a, Vec<Tag<Base64>>> for Vec<Tag<Base64>> {
 }
 
 impl<'a> ToItems<'a, Tag<Base64>> for Tag<Base64> {
-    fn to_deep_hash_item(&'a self) -> Result<DeepHashItem, Error> {
+    fn to_deep_hash_item(&'a self) -> Result<DeepHashItem<'a>, Error> {
         Ok(DeepHashItem::List(vec![
-            DeepHashItem::Blob(self.name.0.to_vec()),
-            DeepHashItem::Blob(self.value.0.to_vec()),
+            DeepHashItem::from_item(&self.name.0),
+            DeepHashItem::from_item(&self.value.0),
         ]))
     }
 }
 
-impl Serialize for Tag<Base64> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut s = serializer.serialize_struct("Tag", 2)?;
-        s.serialize_field("name", &self.name.to_string())?;
-        s.serialize_field("value", &self.value.to_string())?;
-
-        s.end()
-    }
-}
-
 impl From<&BaseTag> for Tag<Base64> {
     fn from(base_tag: &BaseTag) -> Self {
         Tag {
@@ -81,3 +122,45 @@ impl From<&BaseTag> for Tag<Base64> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::crypto::base64::Base64;
+
+    use super::{find_tag_value, Tag, CONTENT_TYPE};
+
+    #[test]
+    fn test_well_known_tags() {
+        let tag = Tag::content_type("text/html");
+        assert_eq!(tag.name, Base64(b"Content-Type".to_vec()));
+        assert_eq!(tag.to_utf8_strings().unwrap().value, "text/html");
+        assert_eq!(
+            Tag::unix_time(1700000000).to_utf8_strings().unwrap().value,
+            "1700000000"
+        );
+        assert_eq!(
+            Tag::app_name("app").to_utf8_strings().unwrap().name,
+            "App-Name"
+        );
+        assert_eq!(
+            Tag::app_version("1.0").to_utf8_strings().unwrap().name,
+            "App-Version"
+        );
+    }
+
+    #[test]
+    fn test_decode_tags() {
+        let tags = vec![Tag::app_name("app"), Tag::content_type("image/png")];
+        assert_eq!(
+            find_tag_value(&tags, CONTENT_TYPE),
+            Some("image/png".to_string())
+        );
+        assert_eq!(find_tag_value(&tags, "Missing"), None);
+
+        let invalid = Tag {
+            name: Base64(vec![0xff]),
+            value: Base64(vec![]),
+        };
+        assert!(invalid.to_utf8_strings().is_err());
+    }
+}
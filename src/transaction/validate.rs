@@ -0,0 +1,347 @@
+//! Checks of the limits nodes apply to transactions, run before signing so that every
+//! violation is reported at once instead of the first rejection of the node.
+
+use std::fmt;
+
+use crate::{
+    address::Address, crypto::base64::Base64, currency::Winston, error::Error,
+    network::ProtocolParams,
+};
+
+use super::Tx;
+
+/// Maximum total size of the names and values of the tags of a transaction.
+pub const MAX_TAGS_SIZE: usize = 2048;
+/// Maximum size of the JSON header of a signed transaction, without its data.
+pub const MAX_HEADER_SIZE: usize = 10 * 1024;
+
+const MAX_ANCHOR_SIZE: usize = 48;
+const MAX_OWNER_SIZE: usize = 512;
+const MAX_SIGNATURE_SIZE: usize = 512;
+const HASH_SIZE: usize = 32;
+/// Digits of the largest quantity and reward nodes accept.
+const MAX_AMOUNT_DIGITS: usize = 21;
+
+/// Limit of the protocol broken by a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    UnsupportedFormat(u8),
+    /// Names and values of the `count` tags add up to more than [`MAX_TAGS_SIZE`] bytes.
+    TagsTooLarge {
+        count: usize,
+        size: usize,
+    },
+    /// JSON header of `size` bytes once signed, above [`MAX_HEADER_SIZE`].
+    HeaderTooLarge(usize),
+    /// Header field above its size limit, or of a size other than the 32 bytes of a hash.
+    InvalidFieldSize {
+        field: &'static str,
+        size: usize,
+    },
+    /// Format 1 data above the `max_tx_data` limit of the network, which only format 2
+    /// transactions can carry.
+    DataTooLarge {
+        size: u64,
+        limit: u64,
+    },
+    /// Format 2 data whose length differs from the `data_size` of the header.
+    DataSizeMismatch {
+        data_size: u64,
+        data: u64,
+    },
+    /// Format 2 transaction with data but no data root.
+    MissingDataRoot,
+    /// Quantity sent without a target to receive it.
+    QuantityWithoutTarget,
+    /// Target is the wallet of the owner.
+    TargetIsOwner,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::UnsupportedFormat(format) => write!(f, "unsupported format {}", format),
+            Violation::TagsTooLarge { count, size } => write!(
+                f,
+                "{} tags of {} bytes, above {}",
+                count, size, MAX_TAGS_SIZE
+            ),
+            Violation::HeaderTooLarge(size) => {
+                write!(f, "header of {} bytes, above {}", size, MAX_HEADER_SIZE)
+            }
+            Violation::InvalidFieldSize { field, size } => {
+                write!(f, "{} of invalid size {}", field, size)
+            }
+            Violation::DataTooLarge { size, limit } => {
+                write!(f, "{} bytes of format 1 data, above {}", size, limit)
+            }
+            Violation::DataSizeMismatch { data_size, data } => {
+                write!(f, "data_size {} for {} bytes of data", data_size, data)
+            }
+            Violation::MissingDataRoot => write!(f, "data without data root"),
+            Violation::QuantityWithoutTarget => write!(f, "quantity without target"),
+            Violation::TargetIsOwner => write!(f, "target is the owner"),
+        }
+    }
+}
+
+impl Tx {
+    /// Limits of the protocol broken by the transaction, empty if mainnet nodes can accept it.
+    pub fn violations(&self) -> Vec<Violation> {
+        self.violations_for(&ProtocolParams::default())
+    }
+
+    /// Same as [`Tx::violations`] for a network with the protocol parameters `params`.
+    pub fn violations_for(&self, params: &ProtocolParams) -> Vec<Violation> {
+        let mut violations = vec![];
+        if self.format != 1 && self.format != 2 {
+            violations.push(Violation::UnsupportedFormat(self.format));
+        }
+
+        let tags_size: usize = self
+            .tags
+            .iter()
+            .map(|tag| tag.name.0.len() + tag.value.0.len())
+            .sum();
+        if tags_size > MAX_TAGS_SIZE {
+            violations.push(Violation::TagsTooLarge {
+                count: self.tags.len(),
+                size: tags_size,
+            });
+        }
+
+        let header_size = self.header_size();
+        if header_size > MAX_HEADER_SIZE {
+            violations.push(Violation::HeaderTooLarge(header_size));
+        }
+
+        let quantity = Winston::from(self.quantity);
+        let quantity_digits = quantity.as_u128().to_string().len();
+        let reward_digits = self.reward.to_string().len();
+        let fields = [
+            (
+                "last_tx",
+                self.last_tx.0.len(),
+                self.last_tx.0.len() <= MAX_ANCHOR_SIZE,
+            ),
+            (
+                "owner",
+                self.owner.0.len(),
+                self.owner.0.len() <= MAX_OWNER_SIZE,
+            ),
+            (
+                "signature",
+                self.signature.0.len(),
+                self.signature.0.len() <= MAX_SIGNATURE_SIZE,
+            ),
+            (
+                "target",
+                self.target.0.len(),
+                self.target.is_empty() || self.target.0.len() == HASH_SIZE,
+            ),
+            (
+                "data_root",
+                self.data_root.0.len(),
+                self.data_root.is_empty() || self.data_root.0.len() == HASH_SIZE,
+            ),
+            (
+                "quantity",
+                quantity_digits,
+                quantity_digits <= MAX_AMOUNT_DIGITS,
+            ),
+            ("reward", reward_digits, reward_digits <= MAX_AMOUNT_DIGITS),
+        ];
+        for (field, size, valid) in fields {
+            if !valid {
+                violations.push(Violation::InvalidFieldSize { field, size });
+            }
+        }
+
+        let data = self.data.0.len() as u64;
+        match self.format {
+            1 if data > params.max_tx_data => violations.push(Violation::DataTooLarge {
+                size: data,
+                limit: params.max_tx_data,
+            }),
+            2 => {
+                if data > 0 && data != self.data_size {
+                    violations.push(Violation::DataSizeMismatch {
+                        data_size: self.data_size,
+                        data,
+                    });
+                }
+                if self.data_size > 0 && self.data_root.is_empty() {
+                    violations.push(Violation::MissingDataRoot);
+                }
+            }
+            _ => {}
+        }
+
+        if quantity != Winston::ZERO && self.target.is_empty() {
+            violations.push(Violation::QuantityWithoutTarget);
+        }
+        if !self.target.is_empty()
+            && !self.owner.is_empty()
+            && Address::from_owner(&self.owner.0).as_bytes()[..] == self.target.0[..]
+        {
+            violations.push(Violation::TargetIsOwner);
+        }
+        violations
+    }
+
+    /// Size of the JSON header without data, counting the signature and id of an unsigned
+    /// transaction at the size the key of its owner signs with.
+    fn header_size(&self) -> usize {
+        let mut header = Tx {
+            format: self.format,
+            id: self.id.clone(),
+            last_tx: self.last_tx.clone(),
+            owner: self.owner.clone(),
+            tags: self.tags.clone(),
+            target: self.target.clone(),
+            quantity: self.quantity,
+            data_root: self.data_root.clone(),
+            data_size: self.data_size,
+            reward: self.reward,
+            signature: self.signature.clone(),
+            ..Default::default()
+        };
+        if header.signature.is_empty() {
+            header.signature = Base64(vec![0; self.owner.0.len()]);
+            header.id = Base64(vec![0; HASH_SIZE]);
+        }
+        serde_json::to_vec(&header).map_or(usize::MAX, |json| json.len())
+    }
+
+    /// Checks the transaction against the limits of the protocol, returning all of the
+    /// violations as [`Error::InvalidTransaction`].
+    pub fn validate(&self) -> Result<(), Error> {
+        self.validate_for(&ProtocolParams::default())
+    }
+
+    /// Same as [`Tx::validate`] for a network with the protocol parameters `params`.
+    pub fn validate_for(&self, params: &ProtocolParams) -> Result<(), Error> {
+        let violations = self.violations_for(params);
+        match violations.is_empty() {
+            true => Ok(()),
+            false => Err(Error::InvalidTransaction(violations)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        address::Address,
+        crypto::base64::Base64,
+        currency::Winston,
+        error::Error,
+        network::ProtocolParams,
+        transaction::{tags::Tag, TransactionBuilder},
+        ArweaveSigner,
+    };
+
+    use super::{Violation, MAX_HEADER_SIZE};
+
+    #[test]
+    fn test_validate() -> Result<(), Error> {
+        let signer = ArweaveSigner::from_keypair_path(PathBuf::from("res/test_wallet.json"))?;
+        let owner = signer.get_public_key();
+        let tx = TransactionBuilder::new()
+            .target(Address::new([1; 32]))
+            .quantity(Winston::new(100))
+            .data(b"data".to_vec())
+            .reward(Winston::new(0))
+            .last_tx(Base64(vec![0; 32]))
+            .build_for_owner(owner.clone())?;
+        tx.validate()?;
+        signer.sign_transaction(tx)?.validate()?;
+
+        let mut tx = TransactionBuilder::new()
+            .tags(vec![Tag::new("Name", &"v".repeat(2048))])
+            .data(b"data".to_vec())
+            .reward(Winston::new(0))
+            .last_tx(Base64(vec![0; 32]))
+            .build_for_owner(owner.clone())?;
+        tx.quantity = 100u128.into();
+        tx.last_tx = Base64(vec![1; 64]);
+        tx.data_size += 1;
+        let tags_size = tx
+            .tags
+            .iter()
+            .map(|tag| tag.name.0.len() + tag.value.0.len())
+            .sum();
+        assert_eq!(
+            tx.violations(),
+            vec![
+                Violation::TagsTooLarge {
+                    count: tx.tags.len(),
+                    size: tags_size,
+                },
+                Violation::InvalidFieldSize {
+                    field: "last_tx",
+                    size: 64
+                },
+                Violation::DataSizeMismatch {
+                    data_size: 5,
+                    data: 4
+                },
+                Violation::QuantityWithoutTarget,
+            ]
+        );
+        let err = tx.validate().unwrap_err();
+        assert!(err.to_string().contains("quantity without target"));
+        assert!(matches!(err, Error::InvalidTransaction(v) if v.len() == 4));
+        assert!(matches!(
+            signer.sign_transaction(tx),
+            Err(Error::InvalidTransaction(v)) if v.len() == 4
+        ));
+
+        let mut format_1 = TransactionBuilder::new()
+            .data(vec![7; 2048])
+            .reward(Winston::new(0))
+            .last_tx(Base64(vec![0; 32]))
+            .build_for_owner(owner.clone())?;
+        format_1.format = 1;
+        assert_eq!(format_1.violations(), vec![]);
+        assert_eq!(
+            format_1.violations_for(&ProtocolParams { max_tx_data: 1024 }),
+            vec![Violation::DataTooLarge {
+                size: 2048,
+                limit: 1024
+            }]
+        );
+
+        let to_self = TransactionBuilder::new()
+            .target(signer.wallet_address())
+            .quantity(Winston::new(1))
+            .reward(Winston::new(0))
+            .last_tx(Base64(vec![0; 32]))
+            .build_for_owner(owner)?;
+        assert_eq!(to_self.violations(), vec![Violation::TargetIsOwner]);
+
+        // Tags within MAX_TAGS_SIZE but too many to fit the header.
+        let tags = (0..1000).map(|_| Tag::new("a", "b")).collect();
+        let many_tags = TransactionBuilder::new()
+            .tags(tags)
+            .reward(Winston::new(0))
+            .last_tx(Base64(vec![0; 32]))
+            .build_for_owner(signer.get_public_key())?;
+        let violations = many_tags.violations();
+        assert!(matches!(
+            violations[..],
+            [Violation::HeaderTooLarge(size)] if size > MAX_HEADER_SIZE
+        ));
+        assert!(matches!(
+            many_tags.sign_detached(signer.get_provider()),
+            Err(Error::InvalidTransaction(_))
+        ));
+        assert!(matches!(
+            signer.sign_transaction(many_tags),
+            Err(Error::InvalidTransaction(v)) if v == violations
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,395 @@
+//! HTTP transport used for every request of the crate. [`ReqwestTransport`] is used by
+//! default; other clients, middleware stacks or test doubles can be plugged in by
+//! implementing [`HttpTransport`].
+
+use std::{pin::Pin, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE},
+    Method, StatusCode,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::error::Error;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub type ByteStream = Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send>>;
+#[cfg(target_arch = "wasm32")]
+pub type ByteStream = Pin<Box<dyn Stream<Item = Result<Bytes, Error>>>>;
+
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: url::Url,
+    pub headers: HeaderMap,
+    pub body: Option<Bytes>,
+}
+
+impl HttpRequest {
+    pub fn new(method: Method, url: url::Url) -> Self {
+        Self {
+            method,
+            url,
+            headers: HeaderMap::new(),
+            body: None,
+        }
+    }
+
+    pub fn get(url: url::Url) -> Self {
+        Self::new(Method::GET, url)
+    }
+
+    pub fn post(url: url::Url) -> Self {
+        Self::new(Method::POST, url)
+    }
+
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    pub fn headers(mut self, headers: HeaderMap) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Sets `value` serialized as JSON as body, with the matching content type.
+    pub fn json<T: Serialize + ?Sized>(self, value: &T) -> Result<Self, Error> {
+        let body = serde_json::to_vec(value).map_err(Error::SerdeJsonError)?;
+        Ok(self
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+            .body(body))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl HttpResponse {
+    pub fn text(&self) -> Result<String, Error> {
+        String::from_utf8(self.body.to_vec()).map_err(Error::FromUtf8Error)
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        serde_json::from_slice(&self.body).map_err(Error::SerdeJsonError)
+    }
+}
+
+/// Response whose body is read as it arrives.
+pub struct StreamingResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: ByteStream,
+}
+
+impl StreamingResponse {
+    pub fn content_length(&self) -> Option<u64> {
+        self.headers
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+    }
+
+    /// Reads the whole body.
+    pub async fn into_response(mut self) -> Result<HttpResponse, Error> {
+        let mut body = Vec::new();
+        while let Some(bytes) = self.body.next().await {
+            body.extend_from_slice(&bytes?);
+        }
+        Ok(HttpResponse {
+            status: self.status,
+            headers: self.headers,
+            body: body.into(),
+        })
+    }
+}
+
+impl From<HttpResponse> for StreamingResponse {
+    fn from(response: HttpResponse) -> Self {
+        Self {
+            status: response.status,
+            headers: response.headers,
+            body: Box::pin(futures::stream::once(async move { Ok(response.body) })),
+        }
+    }
+}
+
+/// Sends requests and returns the responses, whatever their status. Errors are reserved to
+/// requests that got no response; transports other than reqwest should report them as
+/// [`Error::TransportError`] so they are retried.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait HttpTransport: Send + Sync {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error>;
+
+    /// Same as [`HttpTransport::send`], streaming the body. Defaults to buffering it.
+    async fn send_streaming(&self, request: HttpRequest) -> Result<StreamingResponse, Error> {
+        Ok(self.send(request).await?.into())
+    }
+}
+
+/// Proxy every request is sent through. `http://` and `https://` proxies are always
+/// supported, `socks5://` and `socks5h://` ones with the `socks` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyConfig {
+    pub url: url::Url,
+    /// Username and password.
+    pub auth: Option<(String, String)>,
+}
+
+impl ProxyConfig {
+    pub fn new(url: url::Url) -> Self {
+        Self { url, auth: None }
+    }
+
+    pub fn auth(mut self, username: &str, password: &str) -> Self {
+        self.auth = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn to_proxy(&self) -> Result<reqwest::Proxy, Error> {
+        let error = |e: reqwest::Error| Error::InvalidProxy(e.to_string());
+        let mut url = self.url.clone();
+        let is_socks = url.scheme().starts_with("socks");
+        // SOCKS credentials are only read from the url.
+        if let (true, Some((username, password))) = (is_socks, &self.auth) {
+            url.set_username(username)
+                .and_then(|_| url.set_password(Some(password)))
+                .map_err(|_| Error::InvalidProxy(self.url.to_string()))?;
+        }
+        let mut proxy = reqwest::Proxy::all(url).map_err(error)?;
+        if let (false, Some((username, password))) = (is_socks, &self.auth) {
+            proxy = proxy.basic_auth(username, password);
+        }
+        Ok(proxy.no_proxy(reqwest::NoProxy::from_env()))
+    }
+}
+
+/// Timeouts of the requests sent by [`ReqwestTransport`]. Browsers apply their own, so
+/// they are ignored on wasm32.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timeouts {
+    /// Time allowed to establish connections.
+    pub connect: Option<Duration>,
+    /// Time allowed for a whole request, from sending it to reading the end of the body.
+    pub request: Option<Duration>,
+}
+
+fn request_error(err: reqwest::Error) -> Error {
+    if err.is_timeout() {
+        Error::RequestTimeout(err.url().map(|url| url.to_string()).unwrap_or_default())
+    } else {
+        Error::ReqwestError(err)
+    }
+}
+
+/// Transport sending requests with a [`reqwest::Client`]. The default client honors the
+/// `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY` environment variables.
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport(pub reqwest::Client);
+
+impl ReqwestTransport {
+    /// Transport sending requests through `proxy`, for hosts not excluded by `NO_PROXY`.
+    /// Proxy environment variables are ignored otherwise. Browsers apply their own proxy
+    /// settings, so this fails on wasm32.
+    pub fn with_proxy(proxy: &ProxyConfig) -> Result<Self, Error> {
+        Self::configured(Some(proxy), Timeouts::default())
+    }
+
+    /// Transport sending requests through `proxy`, if any, with `timeouts`.
+    pub fn configured(proxy: Option<&ProxyConfig>, timeouts: Timeouts) -> Result<Self, Error> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let mut builder = reqwest::Client::builder();
+            if let Some(proxy) = proxy {
+                builder = builder.proxy(proxy.to_proxy()?);
+            }
+            if let Some(connect) = timeouts.connect {
+                builder = builder.connect_timeout(connect);
+            }
+            if let Some(request) = timeouts.request {
+                builder = builder.timeout(request);
+            }
+            builder
+                .build()
+                .map(Self)
+                .map_err(|e| Error::InvalidProxy(e.to_string()))
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = timeouts;
+        #[cfg(target_arch = "wasm32")]
+        match proxy {
+            Some(proxy) => Err(Error::InvalidProxy(proxy.url.to_string())),
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn request(&self, request: HttpRequest) -> reqwest::RequestBuilder {
+        let mut builder = self
+            .0
+            .request(request.method, request.url)
+            .headers(request.headers);
+        if let Some(body) = request.body {
+            builder = builder.body(body);
+        }
+        builder
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl HttpTransport for ReqwestTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        let response = self.request(request).send().await.map_err(request_error)?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await.map_err(request_error)?;
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+
+    async fn send_streaming(&self, request: HttpRequest) -> Result<StreamingResponse, Error> {
+        let response = self.request(request).send().await.map_err(request_error)?;
+        Ok(StreamingResponse {
+            status: response.status(),
+            headers: response.headers().clone(),
+            body: Box::pin(
+                response
+                    .bytes_stream()
+                    .map(|bytes| bytes.map_err(request_error)),
+            ),
+        })
+    }
+}
+
+impl From<reqwest::Client> for ReqwestTransport {
+    fn from(client: reqwest::Client) -> Self {
+        Self(client)
+    }
+}
+
+pub(crate) fn default_transport() -> Arc<dyn HttpTransport> {
+    Arc::new(ReqwestTransport::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use httpmock::{Method::GET, MockServer};
+    use reqwest::{header::HeaderMap, Method, StatusCode};
+    use tokio_test::block_on;
+
+    use crate::{
+        crypto::base64::Base64, error::Error, network::NetworkInfoClient,
+        transaction::client::TxClient,
+    };
+
+    use super::{
+        HttpRequest, HttpResponse, HttpTransport, ProxyConfig, ReqwestTransport, Timeouts,
+    };
+
+    /// Answers every request with the same body, recording the requests.
+    struct StaticTransport {
+        body: String,
+        requests: Mutex<Vec<HttpRequest>>,
+    }
+
+    #[async_trait]
+    impl HttpTransport for StaticTransport {
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+            self.requests.lock().unwrap().push(request);
+            Ok(HttpResponse {
+                status: StatusCode::OK,
+                headers: HeaderMap::new(),
+                body: Bytes::from(self.body.clone()),
+            })
+        }
+    }
+
+    #[test]
+    fn test_custom_transport() -> Result<(), Error> {
+        let anchor = Base64(vec![1; 48]).to_string();
+        let transport = Arc::new(StaticTransport {
+            body: anchor.clone(),
+            requests: Mutex::new(vec![]),
+        });
+        let url = url::Url::parse("http://transport.invalid/").unwrap();
+
+        let client =
+            TxClient::new(reqwest::Client::new(), url.clone())?.with_transport(transport.clone());
+        assert_eq!(block_on(client.get_tx_anchor())?.to_string(), anchor);
+        // Clients built on pretend go through the transport too.
+        let network = NetworkInfoClient::with_transport(url, transport.clone());
+        assert!(block_on(network.peer_info()).is_err());
+
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, Method::GET);
+        assert_eq!(requests[0].url.path(), "/tx_anchor");
+        assert_eq!(requests[1].url.path(), "/peers");
+        Ok(())
+    }
+
+    #[test]
+    fn test_proxy() -> Result<(), Error> {
+        let proxy = MockServer::start();
+        let mock = proxy.mock(|when, then| {
+            when.method(GET)
+                .path("/info")
+                .header("proxy-authorization", "Basic dXNlcjpwYXNz");
+            then.status(200);
+        });
+        let transport = ReqwestTransport::with_proxy(
+            &ProxyConfig::new(url::Url::parse(&proxy.url("/")).unwrap()).auth("user", "pass"),
+        )?;
+
+        let url = url::Url::parse("http://gateway.invalid/info").unwrap();
+        let res = block_on(transport.send(HttpRequest::get(url)))?;
+        assert_eq!(res.status, StatusCode::OK);
+        mock.assert();
+        Ok(())
+    }
+
+    #[test]
+    fn test_request_timeout() -> Result<(), Error> {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200).delay(Duration::from_millis(500));
+        });
+        let transport = ReqwestTransport::configured(
+            None,
+            Timeouts {
+                connect: None,
+                request: Some(Duration::from_millis(50)),
+            },
+        )?;
+
+        let url = url::Url::parse(&server.url("/info")).unwrap();
+        let res = block_on(transport.send(HttpRequest::get(url)));
+        assert!(matches!(res, Err(Error::RequestTimeout(url)) if url.ends_with("/info")));
+        Ok(())
+    }
+}
@@ -0,0 +1,324 @@
+//! Client for the ArDrive Turbo upload service, which bundles data items like an Irys node and
+//! subsidizes the fees of small uploads.
+
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use reqwest::{
+    header::{HeaderValue, ACCEPT, CONTENT_TYPE},
+    StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::OnceCell;
+
+use crate::{
+    address::Address,
+    bundle::DataItem,
+    currency::Winston,
+    dispatch::Bundler,
+    error::Error,
+    irys::Receipt,
+    transport::{default_transport, HttpRequest, HttpTransport},
+    tx_id::TxId,
+    Arweave,
+};
+
+pub const TURBO_UPLOAD_URL: &str = "https://upload.ardrive.io/";
+
+/// Payment service holding the credit balances of Turbo users.
+pub const TURBO_PAYMENT_URL: &str = "https://payment.ardrive.io/";
+
+/// Receipt of the upload service, signed like an Irys [`Receipt`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TurboReceipt {
+    #[serde(flatten)]
+    pub receipt: Receipt,
+    /// Address of the data item owner.
+    pub owner: Address,
+    /// Winston credits charged for the upload, zero for subsidized uploads.
+    pub winc: String,
+    /// Gateways the data item is cached on.
+    #[serde(default)]
+    pub data_caches: Vec<String>,
+    #[serde(default)]
+    pub fast_finality_indexes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Info {
+    addresses: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct Balance {
+    winc: Winston,
+}
+
+pub struct TurboClient {
+    transport: Arc<dyn HttpTransport>,
+    url: url::Url,
+    payment_url: url::Url,
+    bundler_address: OnceCell<Address>,
+}
+
+impl Default for TurboClient {
+    fn default() -> Self {
+        Self::new(url::Url::parse(TURBO_UPLOAD_URL).unwrap()) //Checked unwrap
+    }
+}
+
+impl TurboClient {
+    pub fn new(url: url::Url) -> Self {
+        Self {
+            transport: default_transport(),
+            url,
+            payment_url: url::Url::parse(TURBO_PAYMENT_URL).unwrap(), //Checked unwrap
+            bundler_address: OnceCell::new(),
+        }
+    }
+
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    pub fn with_payment_url(mut self, payment_url: url::Url) -> Self {
+        self.payment_url = payment_url;
+        self
+    }
+
+    /// Checks receipts against the wallet `bundler_address` instead of the one served by the
+    /// upload service.
+    pub fn with_bundler_address(mut self, bundler_address: Address) -> Self {
+        self.bundler_address = OnceCell::new_with(Some(bundler_address));
+        self
+    }
+
+    /// Wallet of the payment service, to which funding transfers are sent.
+    pub async fn get_address(&self) -> Result<Address, Error> {
+        self.get_info_address(&self.payment_url, "payment service")
+            .await
+    }
+
+    /// Wallet the upload service signs receipts with, as pinned with
+    /// [`TurboClient::with_bundler_address`] or served by its `info` endpoint on first use.
+    pub async fn get_bundler_address(&self) -> Result<&Address, Error> {
+        self.bundler_address
+            .get_or_try_init(|| self.get_info_address(&self.url, "upload service"))
+            .await
+    }
+
+    async fn get_info_address(&self, base_url: &url::Url, service: &str) -> Result<Address, Error> {
+        let url = base_url.join("v1/info").map_err(Error::UrlParseError)?;
+        let res = self.transport.send(HttpRequest::get(url)).await?;
+        if res.status != StatusCode::OK {
+            return Err(Error::from_response(&res));
+        }
+        let mut info: Info = res.json()?;
+        info.addresses
+            .remove("arweave")
+            .ok_or_else(|| Error::TransactionInfoError(format!("{} does not accept AR", service)))?
+            .parse()
+    }
+
+    /// Winston credits of `address` left for uploads.
+    pub async fn get_bundler_balance(&self, address: &Address) -> Result<Winston, Error> {
+        let mut url = self
+            .payment_url
+            .join("v1/account/balance/arweave")
+            .map_err(Error::UrlParseError)?;
+        url.query_pairs_mut()
+            .append_pair("address", &address.to_string());
+        let res = self.transport.send(HttpRequest::get(url)).await?;
+        if res.status != StatusCode::OK {
+            return Err(Error::from_response(&res));
+        }
+        Ok(res.json::<Balance>()?.winc)
+    }
+
+    /// Transfers `amount` from the wallet of `arweave` to the payment service and submits the
+    /// transfer, which is converted to credits once confirmed.
+    pub async fn fund(&self, arweave: &Arweave, amount: Winston) -> Result<(TxId, u64), Error> {
+        let (id, reward) = arweave.transfer(self.get_address().await?, amount).await?;
+
+        let url = self
+            .payment_url
+            .join("v1/account/balance/arweave")
+            .map_err(Error::UrlParseError)?;
+        let request = HttpRequest::post(url).json(&serde_json::json!({ "tx_id": id }))?;
+        let res = self.transport.send(request).await?;
+        if !res.status.is_success() {
+            return Err(Error::from_response(&res));
+        }
+        Ok((id, reward))
+    }
+
+    /// Uploads a signed data item, returning the receipt of the service once checked against
+    /// the item and the service wallet, see [`TurboClient::get_bundler_address`].
+    pub async fn post_data_item(&self, data_item: &DataItem) -> Result<TurboReceipt, Error> {
+        if data_item.signature.is_empty() {
+            return Err(Error::UnsignedTransaction);
+        }
+        let url = self.url.join("v1/tx").map_err(Error::UrlParseError)?;
+        let request = HttpRequest::post(url)
+            .header(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/octet-stream"),
+            )
+            .header(ACCEPT, HeaderValue::from_static("application/json"))
+            .body(data_item.to_bytes()?);
+        let res = self.transport.send(request).await?;
+        if res.status != StatusCode::OK {
+            return Err(Error::from_response(&res));
+        }
+
+        let receipt: TurboReceipt = res.json()?;
+        if receipt.receipt.id != data_item.id.to_string() {
+            return Err(Error::InvalidReceipt(format!(
+                "receipt for {} instead of {}",
+                receipt.receipt.id, data_item.id
+            )));
+        }
+        let public = &receipt.receipt.public;
+        if &Address::from_owner(&public.0) != self.get_bundler_address().await? {
+            return Err(Error::InvalidReceipt(format!(
+                "{} is not signed by the upload service",
+                receipt.receipt.id
+            )));
+        }
+        receipt.receipt.verify(public)?;
+        Ok(receipt)
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Bundler for TurboClient {
+    async fn post(&self, data_item: &DataItem) -> Result<String, Error> {
+        Ok(self.post_data_item(data_item).await?.receipt.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use serde_json::json;
+    use tokio_test::block_on;
+
+    use crate::{
+        address::Address, bundle::DataItem, crypto::base64::Base64, currency::Winston,
+        error::Error, irys::Receipt, ArweaveSigner,
+    };
+
+    use super::TurboClient;
+
+    #[test]
+    fn test_post_data_item() -> Result<(), Error> {
+        let signer = ArweaveSigner::from_keypair_path(PathBuf::from("res/test_wallet_4096.json"))?;
+        let data_item = signer.sign_data_item(DataItem::new(
+            Base64::empty(),
+            Base64::empty(),
+            vec![],
+            b"hello".to_vec(),
+        )?)?;
+        let bundler = ArweaveSigner::from_keypair_path(PathBuf::from("res/test_wallet.json"))?;
+        let receipt_body = |receipt_signer: &ArweaveSigner| -> Result<_, Error> {
+            let signature_data = Receipt {
+                id: data_item.id.to_string(),
+                timestamp: 1_700_000_000_000,
+                version: "0.2.0".to_string(),
+                public: receipt_signer.get_public_key(),
+                signature: Base64::empty(),
+                deadline_height: 1_300_000,
+            }
+            .signature_data();
+            Ok(json!({
+                "id": data_item.id.to_string(),
+                "owner": signer.wallet_address().to_string(),
+                "dataCaches": ["arweave.net"],
+                "fastFinalityIndexes": ["arweave.net"],
+                "deadlineHeight": 1_300_000,
+                "timestamp": 1_700_000_000_000u64,
+                "version": "0.2.0",
+                "winc": "0",
+                "public": receipt_signer.get_public_key().to_string(),
+                "signature": receipt_signer.sign(&signature_data)?.to_string(),
+            }))
+        };
+        let body = receipt_body(&bundler)?;
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v1/tx")
+                .header("content-type", "application/octet-stream");
+            then.status(200).json_body(body.clone());
+        });
+        let info = server.mock(|when, then| {
+            when.method(GET).path("/v1/info");
+            then.status(200).json_body(json!({
+                "addresses": { "arweave": bundler.wallet_address().to_string() }
+            }));
+        });
+        let client = TurboClient::new(url::Url::parse(&server.url("/")).unwrap());
+
+        let receipt = block_on(client.post_data_item(&data_item))?;
+        mock.assert();
+        info.assert();
+        assert_eq!(receipt.winc, "0");
+        assert_eq!(receipt.data_caches, vec!["arweave.net".to_string()]);
+        assert_eq!(receipt.receipt.deadline_height, 1_300_000);
+
+        let other = signer.sign_data_item(DataItem::new(
+            Base64::empty(),
+            Base64::empty(),
+            vec![],
+            b"other".to_vec(),
+        )?)?;
+        assert!(matches!(
+            block_on(client.post_data_item(&other)),
+            Err(Error::InvalidReceipt(_))
+        ));
+
+        // A receipt signed by a key other than the service wallet.
+        let self_signed = MockServer::start();
+        let body = receipt_body(&signer)?;
+        self_signed.mock(|when, then| {
+            when.method(POST).path("/v1/tx");
+            then.status(200).json_body(body);
+        });
+        let client = TurboClient::new(url::Url::parse(&self_signed.url("/")).unwrap())
+            .with_bundler_address(bundler.wallet_address());
+        assert!(matches!(
+            block_on(client.post_data_item(&data_item)),
+            Err(Error::InvalidReceipt(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_balance() -> Result<(), Error> {
+        let owner = Address::new([4; 32]);
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/v1/account/balance/arweave")
+                .query_param("address", owner.to_string());
+            then.status(200)
+                .body(r#"{"winc":"1500000000000","controlledWinc":"1500000000000"}"#);
+        });
+        let client =
+            TurboClient::default().with_payment_url(url::Url::parse(&server.url("/")).unwrap());
+
+        assert_eq!(
+            block_on(client.get_bundler_balance(&owner))?,
+            Winston::from_ar("1.5")?
+        );
+        Ok(())
+    }
+}
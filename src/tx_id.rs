@@ -0,0 +1,62 @@
+//! Ids of transactions and data items, the SHA256 hash of their signature, written as 43
+//! characters of base64url.
+
+use crate::crypto::{base64::fixed_base64, hash::sha256};
+
+/// Length of an id in base64url.
+pub const TX_ID_LENGTH: usize = 43;
+
+fixed_base64!(TxId, 32, TX_ID_LENGTH, InvalidTxId);
+
+impl TxId {
+    /// Id of the transaction or data item signed with `signature`.
+    pub fn from_signature(signature: &[u8]) -> Self {
+        Self(sha256(signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        crypto::base64::Base64, currency::Winston, error::Error, transaction::TransactionBuilder,
+        ArweaveSigner,
+    };
+
+    use super::TxId;
+
+    #[test]
+    fn test_tx_id() -> Result<(), Error> {
+        let signer = ArweaveSigner::from_keypair_path(PathBuf::from("res/test_wallet.json"))?;
+        let tx = signer.sign_transaction(
+            TransactionBuilder::new()
+                .reward(Winston::new(0))
+                .last_tx(Base64(vec![0; 32]))
+                .build_for_owner(signer.get_public_key())?,
+        )?;
+        let id = TxId::from_signature(&tx.signature.0);
+        assert_eq!(id.to_base64(), tx.id);
+        assert_eq!(TxId::try_from(&tx.id)?, id);
+
+        let string = id.to_string();
+        assert_eq!(string.parse::<TxId>()?, id);
+        assert_eq!(
+            serde_json::from_str::<TxId>(&serde_json::to_string(&id).unwrap()).unwrap(),
+            id
+        );
+
+        for invalid in [
+            "",
+            "id1",
+            &format!("{}+", "a".repeat(42)),
+            &Base64(vec![1; 48]).to_string(),
+        ] {
+            assert!(matches!(
+                invalid.parse::<TxId>(),
+                Err(Error::InvalidTxId(_))
+            ));
+        }
+        Ok(())
+    }
+}
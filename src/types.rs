@@ -1,18 +1,25 @@
 use serde::{Deserialize, Serialize};
 use serde_aux::prelude::*;
 
-use crate::crypto::base64::Base64;
+use crate::{
+    block::{deserialize_previous, serialize_previous, BlockHash, BlockHeight},
+    crypto::base64::{Base64, Base64Ref},
+};
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Response of the `info` endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct NetworkInfo {
     pub network: String,
     pub version: usize,
     pub release: usize,
-    pub height: u128,
-    pub current: Base64,
+    pub height: BlockHeight,
+    /// Indep hash of the current block.
+    pub current: BlockHash,
     pub blocks: usize,
     pub peers: usize,
+    #[serde(default)]
     pub queue_length: usize,
+    #[serde(default)]
     pub node_state_latency: usize,
 }
 
@@ -53,8 +60,109 @@ pub struct BlockInfo {
     pub tx_tree: Vec<Base64>,
     pub poa: ProofOfAccess,
 }
+/// Deserializes integers nodes send either as JSON numbers or as strings.
+fn u128_from_string_or_number<'de, D>(deserializer: D) -> Result<u128, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => s.parse().map_err(serde::de::Error::custom),
+        serde_json::Value::Number(n) => n
+            .as_u64()
+            .map(u128::from)
+            .ok_or_else(|| serde::de::Error::custom("expected an unsigned integer")),
+        _ => Err(serde::de::Error::custom("expected a string or number")),
+    }
+}
+
+/// Serializes integers as strings, as nodes send the amounts too large for JSON numbers.
+fn u128_to_string<S: serde::Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+fn option_u128_to_string<S: serde::Serializer>(
+    value: &Option<u128>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => serializer.collect_str(value),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Block as returned by the `block/hash` and `block/height` endpoints, covering the fields
+/// shared by all block versions. Fields introduced by later versions default when absent.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub indep_hash: BlockHash,
+    pub hash: Base64,
+    pub height: BlockHeight,
+    /// `None` for the genesis block.
+    #[serde(
+        serialize_with = "serialize_previous",
+        deserialize_with = "deserialize_previous"
+    )]
+    pub previous_block: Option<BlockHash>,
+    pub nonce: Base64,
+    pub timestamp: u64,
+    pub last_retarget: u64,
+    #[serde(deserialize_with = "deserialize_string_from_number")]
+    pub diff: String,
+    #[serde(default)]
+    pub cumulative_diff: Option<String>,
+    pub txs: Vec<Base64>,
+    #[serde(default)]
+    pub tx_root: Base64,
+    pub wallet_list: Base64,
+    /// Address of the miner, or `unclaimed`.
+    pub reward_addr: String,
+    /// Miner reward in winstons, present since 2.6.
+    #[serde(
+        default,
+        serialize_with = "option_u128_to_string",
+        deserialize_with = "option_u128_from_string_or_number"
+    )]
+    pub reward: Option<u128>,
+    #[serde(
+        serialize_with = "u128_to_string",
+        deserialize_with = "u128_from_string_or_number"
+    )]
+    pub reward_pool: u128,
+    #[serde(
+        serialize_with = "u128_to_string",
+        deserialize_with = "u128_from_string_or_number"
+    )]
+    pub weave_size: u128,
+    #[serde(
+        serialize_with = "u128_to_string",
+        deserialize_with = "u128_from_string_or_number"
+    )]
+    pub block_size: u128,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+}
+
+fn option_u128_from_string_or_number<'de, D>(deserializer: D) -> Result<Option<u128>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Null => Ok(None),
+        value => u128_from_string_or_number(value)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+fn legacy_format() -> u8 {
+    1
+}
+
+/// Transaction as returned by nodes. Headers of early transactions have no `format`, data
+/// size or data root and are read as format 1.
 #[derive(Deserialize, Debug, Default, Eq, PartialEq)]
 pub struct Tx {
+    #[serde(default = "legacy_format")]
     pub format: u8,
     pub id: Base64,
     pub last_tx: Base64,
@@ -62,8 +170,10 @@ pub struct Tx {
     pub tags: Vec<Tag>,
     pub target: Base64,
     pub quantity: String,
+    #[serde(default)]
     pub data_root: Base64,
     pub data: Base64,
+    #[serde(default)]
     pub data_size: String,
     pub reward: String,
     pub signature: Base64,
@@ -77,16 +187,173 @@ pub struct Tag {
 
 #[derive(Serialize, Deserialize)]
 pub struct TxStatus {
-    pub block_height: u128,
-    pub block_indep_hash: Base64,
+    pub block_height: BlockHeight,
+    pub block_indep_hash: BlockHash,
     pub number_of_confirmations: u64,
 }
 
+/// Serializes numbers as strings, as nodes expect them in chunk JSON.
+fn serialize_to_string<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: std::fmt::Display,
+    S: serde::Serializer,
+{
+    serializer.collect_str(value)
+}
+
+/// Chunk as posted to the `chunk` endpoint.
 #[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
 pub struct Chunk {
     pub data_root: Base64,
+    #[serde(
+        serialize_with = "serialize_to_string",
+        deserialize_with = "deserialize_number_from_string"
+    )]
     pub data_size: u64,
     pub data_path: Base64,
+    #[serde(
+        serialize_with = "serialize_to_string",
+        deserialize_with = "deserialize_number_from_string"
+    )]
+    pub offset: usize,
+    pub chunk: Base64,
+}
+
+impl Chunk {
+    pub fn as_chunk_ref(&self) -> ChunkRef<'_> {
+        ChunkRef {
+            data_root: self.data_root.as_base64_ref(),
+            data_size: self.data_size,
+            data_path: self.data_path.as_base64_ref(),
+            offset: self.offset,
+            chunk: self.chunk.as_base64_ref(),
+        }
+    }
+}
+
+/// [`Chunk`] borrowing its proof and data from the transaction, serialized the same way.
+#[derive(Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ChunkRef<'a> {
+    pub data_root: Base64Ref<'a>,
+    #[serde(serialize_with = "serialize_to_string")]
+    pub data_size: u64,
+    pub data_path: Base64Ref<'a>,
+    #[serde(serialize_with = "serialize_to_string")]
     pub offset: usize,
+    pub chunk: Base64Ref<'a>,
+}
+
+impl ChunkRef<'_> {
+    pub fn to_chunk(&self) -> Chunk {
+        Chunk {
+            data_root: self.data_root.to_base64(),
+            data_size: self.data_size,
+            data_path: self.data_path.to_base64(),
+            offset: self.offset,
+            chunk: self.chunk.to_base64(),
+        }
+    }
+}
+
+/// Response of the `tx/{id}/offset` endpoint. `offset` is the absolute offset of the last
+/// byte of the transaction data in the weave.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxOffset {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub size: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub offset: u64,
+}
+
+/// Response of the `chunk/{offset}` endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChunkData {
     pub chunk: Base64,
+    pub data_path: Base64,
+    #[serde(default)]
+    pub tx_path: Base64,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        block::{BlockHash, BlockHeight},
+        crypto::base64::Base64,
+    };
+
+    use super::{Block, Chunk};
+
+    #[test]
+    fn test_deserialize_block() {
+        let mut json = serde_json::json!({
+            "indep_hash": Base64(vec![1; 48]).to_string(),
+            "hash": Base64(vec![2; 32]).to_string(),
+            "height": 1300000,
+            "previous_block": Base64(vec![3; 48]).to_string(),
+            "nonce": Base64(vec![4; 32]).to_string(),
+            "timestamp": 1700000000,
+            "last_retarget": 1699999000,
+            "diff": "115792089039110416828370453307525909750303103106918543621669358429592435458048",
+            "cumulative_diff": "123456789",
+            "txs": [Base64(vec![5; 32]).to_string()],
+            "tx_root": Base64(vec![6; 32]).to_string(),
+            "wallet_list": Base64(vec![7; 48]).to_string(),
+            "reward_addr": Base64(vec![8; 32]).to_string(),
+            "reward": "1234567890123",
+            "reward_pool": "98765432109876543210",
+            "weave_size": "150000000000000000",
+            "block_size": 1024,
+            "tags": [],
+        });
+        let block: Block = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(block.height, BlockHeight::new(1300000));
+        assert_eq!(block.previous_block, Some(BlockHash::new([3; 48])));
+        assert_eq!(block.txs, vec![Base64(vec![5; 32])]);
+        assert_eq!(block.reward, Some(1234567890123));
+        assert_eq!(block.reward_pool, 98765432109876543210);
+        assert_eq!(block.block_size, 1024);
+
+        // Version 1 blocks.
+        let object = json.as_object_mut().unwrap();
+        object.remove("reward");
+        object.remove("tx_root");
+        object.remove("cumulative_diff");
+        object.insert("diff".to_string(), serde_json::json!(31));
+        object.insert("reward_addr".to_string(), serde_json::json!("unclaimed"));
+        let block: Block = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(block.diff, "31");
+        assert_eq!(block.reward, None);
+        assert_eq!(block.tx_root, Base64::default());
+        assert_eq!(block.reward_addr, "unclaimed");
+
+        // The genesis block has no previous block.
+        let object = json.as_object_mut().unwrap();
+        object.insert("height".to_string(), serde_json::json!(0));
+        object.insert("previous_block".to_string(), serde_json::json!(""));
+        let block: Block = serde_json::from_value(json).unwrap();
+        assert_eq!(block.height, BlockHeight::GENESIS);
+        assert_eq!(block.previous_block, None);
+        let json = serde_json::to_string(&block).unwrap();
+        assert!(json.contains(r#""previous_block":"""#));
+        assert_eq!(serde_json::from_str::<Block>(&json).unwrap(), block);
+        let json = json.replace(r#""previous_block":"""#, r#""previous_block":null"#);
+        assert_eq!(serde_json::from_str::<Block>(&json).unwrap(), block);
+    }
+
+    #[test]
+    fn test_serialize_chunk() {
+        let chunk = Chunk {
+            data_root: Base64(vec![1; 32]),
+            data_size: 300000,
+            data_path: Base64(vec![2; 64]),
+            offset: 262143,
+            chunk: Base64(vec![3; 8]),
+        };
+        let json = serde_json::to_value(&chunk).unwrap();
+        assert_eq!(json["data_size"], "300000");
+        assert_eq!(json["offset"], "262143");
+        assert_eq!(json["chunk"], Base64(vec![3; 8]).to_string());
+        assert_eq!(serde_json::to_value(chunk.as_chunk_ref()).unwrap(), json);
+        assert_eq!(serde_json::from_value::<Chunk>(json).unwrap(), chunk);
+    }
 }
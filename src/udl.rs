@@ -0,0 +1,293 @@
+//! Tags of the Universal Data License, licensing the data of a transaction or data item. The
+//! values of the terms are restricted by the license, so they are typed here and validated when
+//! read back from tags.
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+    address::Address,
+    crypto::base64::Base64,
+    error::Error,
+    transaction::tags::{find_tag_value, Tag},
+};
+
+/// Transaction holding the text of the license.
+pub const UDL_LICENSE_ID: &str = "yRj4a5KMctX_uOmKWCFJIjmY8DeJcusVk6-HzLiM_t8";
+
+/// Currency of fees when none is tagged.
+pub const DEFAULT_LICENSE_CURRENCY: &str = "U";
+
+pub const LICENSE: &str = "License";
+pub const COMMERCIAL_USE: &str = "Commercial-Use";
+pub const DERIVATION: &str = "Derivation";
+pub const LICENSE_FEE: &str = "License-Fee";
+pub const CURRENCY: &str = "Currency";
+pub const PAYMENT_ADDRESS: &str = "Payment-Address";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommercialUse {
+    Allowed,
+    AllowedWithCredit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Derivation {
+    AllowedWithCredit,
+    AllowedWithIndication,
+    AllowedWithLicensePassthrough,
+    /// Share of the revenue of derived works owed to the licensor, in percent.
+    AllowedWithRevenueShare(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LicenseFee {
+    OneTime(f64),
+    Monthly(f64),
+}
+
+fn invalid(name: &str, value: &str) -> Error {
+    Error::InvalidLicense(format!("{}: {}", name, value))
+}
+
+fn parse_amount(name: &str, value: &str, amount: &str) -> Result<f64, Error> {
+    match amount.parse::<f64>() {
+        Ok(amount) if amount.is_finite() && amount >= 0.0 => Ok(amount),
+        _ => Err(invalid(name, value)),
+    }
+}
+
+impl fmt::Display for CommercialUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommercialUse::Allowed => write!(f, "Allowed"),
+            CommercialUse::AllowedWithCredit => write!(f, "Allowed-With-Credit"),
+        }
+    }
+}
+
+impl FromStr for CommercialUse {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "Allowed" => Ok(CommercialUse::Allowed),
+            "Allowed-With-Credit" => Ok(CommercialUse::AllowedWithCredit),
+            _ => Err(invalid(COMMERCIAL_USE, s)),
+        }
+    }
+}
+
+impl fmt::Display for Derivation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Derivation::AllowedWithCredit => write!(f, "Allowed-With-Credit"),
+            Derivation::AllowedWithIndication => write!(f, "Allowed-With-Indication"),
+            Derivation::AllowedWithLicensePassthrough => {
+                write!(f, "Allowed-With-License-Passthrough")
+            }
+            Derivation::AllowedWithRevenueShare(share) => {
+                write!(f, "Allowed-With-RevenueShare-{}%", share)
+            }
+        }
+    }
+}
+
+impl FromStr for Derivation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "Allowed-With-Credit" => Ok(Derivation::AllowedWithCredit),
+            "Allowed-With-Indication" => Ok(Derivation::AllowedWithIndication),
+            "Allowed-With-License-Passthrough" => Ok(Derivation::AllowedWithLicensePassthrough),
+            _ => {
+                let share = s
+                    .strip_prefix("Allowed-With-RevenueShare-")
+                    .and_then(|share| share.strip_suffix('%'))
+                    .ok_or_else(|| invalid(DERIVATION, s))?;
+                let share = parse_amount(DERIVATION, s, share)?;
+                if share > 100.0 {
+                    return Err(invalid(DERIVATION, s));
+                }
+                Ok(Derivation::AllowedWithRevenueShare(share))
+            }
+        }
+    }
+}
+
+impl fmt::Display for LicenseFee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LicenseFee::OneTime(amount) => write!(f, "One-Time-{}", amount),
+            LicenseFee::Monthly(amount) => write!(f, "Monthly-{}", amount),
+        }
+    }
+}
+
+impl FromStr for LicenseFee {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        if let Some(amount) = s.strip_prefix("One-Time-") {
+            Ok(LicenseFee::OneTime(parse_amount(LICENSE_FEE, s, amount)?))
+        } else if let Some(amount) = s.strip_prefix("Monthly-") {
+            Ok(LicenseFee::Monthly(parse_amount(LICENSE_FEE, s, amount)?))
+        } else {
+            Err(invalid(LICENSE_FEE, s))
+        }
+    }
+}
+
+/// Terms of the license. Terms left unset are not granted.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Udl {
+    pub commercial_use: Option<CommercialUse>,
+    pub derivation: Option<Derivation>,
+    pub license_fee: Option<LicenseFee>,
+    /// Currency of the fee, [`DEFAULT_LICENSE_CURRENCY`] if unset.
+    pub currency: Option<String>,
+    /// Wallet fees are paid to, the owner of the data if unset.
+    pub payment_address: Option<Address>,
+}
+
+impl Udl {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn commercial_use(mut self, commercial_use: CommercialUse) -> Self {
+        self.commercial_use = Some(commercial_use);
+        self
+    }
+
+    pub fn derivation(mut self, derivation: Derivation) -> Self {
+        self.derivation = Some(derivation);
+        self
+    }
+
+    pub fn license_fee(mut self, license_fee: LicenseFee) -> Self {
+        self.license_fee = Some(license_fee);
+        self
+    }
+
+    pub fn currency(mut self, currency: &str) -> Self {
+        self.currency = Some(currency.to_string());
+        self
+    }
+
+    pub fn payment_address(mut self, payment_address: Address) -> Self {
+        self.payment_address = Some(payment_address);
+        self
+    }
+
+    /// `License` tag followed by the tags of the terms set.
+    pub fn to_tags(&self) -> Result<Vec<Tag<Base64>>, Error> {
+        if let Some(Derivation::AllowedWithRevenueShare(share)) = self.derivation {
+            if !(0.0..=100.0).contains(&share) {
+                return Err(invalid(DERIVATION, &share.to_string()));
+            }
+        }
+        if let Some(LicenseFee::OneTime(amount) | LicenseFee::Monthly(amount)) = self.license_fee {
+            if !amount.is_finite() || amount < 0.0 {
+                return Err(invalid(LICENSE_FEE, &amount.to_string()));
+            }
+        }
+        if let Some(currency) = &self.currency {
+            if currency.is_empty() || !currency.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(invalid(CURRENCY, currency));
+            }
+        }
+
+        let mut tags = vec![Tag::new(LICENSE, UDL_LICENSE_ID)];
+        if let Some(commercial_use) = self.commercial_use {
+            tags.push(Tag::new(COMMERCIAL_USE, &commercial_use.to_string()));
+        }
+        if let Some(derivation) = self.derivation {
+            tags.push(Tag::new(DERIVATION, &derivation.to_string()));
+        }
+        if let Some(license_fee) = self.license_fee {
+            tags.push(Tag::new(LICENSE_FEE, &license_fee.to_string()));
+        }
+        if let Some(currency) = &self.currency {
+            tags.push(Tag::new(CURRENCY, currency));
+        }
+        if let Some(address) = &self.payment_address {
+            tags.push(Tag::new(PAYMENT_ADDRESS, &address.to_string()));
+        }
+        Ok(tags)
+    }
+
+    /// Terms tagged in `tags`, `None` if the data is not licensed under the UDL.
+    pub fn from_tags(tags: &[Tag<Base64>]) -> Result<Option<Self>, Error> {
+        if find_tag_value(tags, LICENSE).as_deref() != Some(UDL_LICENSE_ID) {
+            return Ok(None);
+        }
+        let udl = Udl {
+            commercial_use: find_tag_value(tags, COMMERCIAL_USE)
+                .map(|value| value.parse())
+                .transpose()?,
+            derivation: find_tag_value(tags, DERIVATION)
+                .map(|value| value.parse())
+                .transpose()?,
+            license_fee: find_tag_value(tags, LICENSE_FEE)
+                .map(|value| value.parse())
+                .transpose()?,
+            currency: find_tag_value(tags, CURRENCY),
+            payment_address: find_tag_value(tags, PAYMENT_ADDRESS)
+                .map(|value| {
+                    Address::from_str(&value).map_err(|_| invalid(PAYMENT_ADDRESS, &value))
+                })
+                .transpose()?,
+        };
+        udl.to_tags()?;
+        Ok(Some(udl))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        address::Address,
+        error::Error,
+        transaction::tags::{find_tag_value, Tag},
+    };
+
+    use super::{CommercialUse, Derivation, LicenseFee, Udl};
+
+    #[test]
+    fn test_udl_tags() -> Result<(), Error> {
+        let udl = Udl::new()
+            .commercial_use(CommercialUse::AllowedWithCredit)
+            .derivation(Derivation::AllowedWithRevenueShare(12.5))
+            .license_fee(LicenseFee::OneTime(0.5))
+            .payment_address(Address::new([8; 32]));
+
+        let tags = udl.to_tags()?;
+        assert_eq!(
+            find_tag_value(&tags, "Derivation").as_deref(),
+            Some("Allowed-With-RevenueShare-12.5%")
+        );
+        assert_eq!(
+            find_tag_value(&tags, "License-Fee").as_deref(),
+            Some("One-Time-0.5")
+        );
+        assert_eq!(Udl::from_tags(&tags)?, Some(udl));
+        assert_eq!(Udl::from_tags(&[Tag::content_type("text/plain")])?, None);
+        let mut tags = tags;
+        *tags.last_mut().unwrap() = Tag::new("Payment-Address", "wallet");
+        assert!(matches!(
+            Udl::from_tags(&tags),
+            Err(Error::InvalidLicense(_))
+        ));
+
+        assert!(Derivation::from_str("Allowed-With-RevenueShare-120%").is_err());
+        assert!(CommercialUse::from_str("Forbidden").is_err());
+        assert!(matches!(
+            Udl::new().license_fee(LicenseFee::Monthly(-1.0)).to_tags(),
+            Err(Error::InvalidLicense(_))
+        ));
+        Ok(())
+    }
+}
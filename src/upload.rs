@@ -1,70 +1,589 @@
-use std::{str::FromStr, thread::sleep, time::Duration};
+use std::{collections::BTreeSet, fs, path::Path, str::FromStr};
 
-use reqwest::{
-    header::{ACCEPT, CONTENT_TYPE},
-    Client,
+use futures::{stream::FuturesUnordered, StreamExt};
+use reqwest::header::{HeaderValue, ACCEPT};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::{
+    mpsc::{unbounded_channel, UnboundedReceiver},
+    Semaphore,
 };
 
 use crate::{
-    consts::{ARWEAVE_BASE_URL, CHUNKS_RETRIES, CHUNKS_RETRY_SLEEP},
+    consts::{ARWEAVE_BASE_URL, CHUNKS_CONCURRENCY},
+    crypto::base64::Base64,
     error::Error,
-    types::Chunk,
+    gateway::GatewayPool,
+    metrics::MetricsSink,
+    retry::RetryPolicy,
+    runtime::Instant,
+    transaction::Tx,
+    transport::{default_transport, HttpRequest, HttpTransport},
+    types::{Chunk, ChunkRef},
 };
 
+/// Progress of a chunked upload. Can be persisted to disk so that an upload interrupted by a
+/// crash or network failure resumes from the chunks that were not yet accepted.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct UploadState {
+    pub id: Base64,
+    pub data_root: Base64,
+    pub header_posted: bool,
+    pub uploaded_offsets: BTreeSet<usize>,
+}
+
+impl UploadState {
+    pub fn new(transaction: &Tx) -> Self {
+        Self {
+            id: transaction.id.clone(),
+            data_root: transaction.data_root.clone(),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Result<Self, Error> {
+        let data = fs::read_to_string(path)?;
+        serde_json::from_str(&data).map_err(Error::SerdeJsonError)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let data = serde_json::to_string(self).map_err(Error::SerdeJsonError)?;
+        fs::write(path, data)?;
+        Ok(())
+    }
+
+    /// Checks that the state was recorded for the given transaction.
+    pub fn matches(&self, transaction: &Tx) -> bool {
+        self.id == transaction.id && self.data_root == transaction.data_root
+    }
+
+    /// Indexes of the transaction chunks that have not been accepted yet.
+    pub fn pending_chunks(&self, transaction: &Tx) -> Vec<usize> {
+        transaction
+            .proofs
+            .iter()
+            .enumerate()
+            .filter(|(_, proof)| !self.uploaded_offsets.contains(&proof.offset))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    pub fn is_complete(&self, transaction: &Tx) -> bool {
+        self.header_posted && self.pending_chunks(transaction).is_empty()
+    }
+}
+
+/// Progress of an upload, reported to the callback set with [`Uploader::with_progress`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadEvent {
+    /// The transaction header was accepted by the node.
+    HeaderPosted { id: Base64 },
+    /// A chunk was accepted. `confirmed` out of `total` chunks are now uploaded.
+    ChunkPosted {
+        offset: usize,
+        confirmed: usize,
+        total: usize,
+    },
+    /// Posting the chunk at `offset` failed and is about to be retried.
+    ChunkRetry {
+        offset: usize,
+        attempt: u32,
+        error: String,
+    },
+}
+
+pub type ProgressCallback = Arc<dyn Fn(&UploadEvent) + Send + Sync>;
+
+/// Progress callback forwarding events to a channel, for consumers expecting a stream.
+pub fn progress_channel() -> (ProgressCallback, UnboundedReceiver<UploadEvent>) {
+    let (sender, receiver) = unbounded_channel();
+    let callback: ProgressCallback = Arc::new(move |event: &UploadEvent| {
+        let _ = sender.send(event.clone());
+    });
+    (callback, receiver)
+}
+
 pub struct Uploader {
     url: url::Url,
+    transport: Arc<dyn HttpTransport>,
+    concurrency: usize,
+    retry_policy: RetryPolicy,
+    progress: Option<ProgressCallback>,
+    gateways: Option<Arc<GatewayPool>>,
+    metrics: Option<Arc<dyn MetricsSink>>,
 }
 
 impl Default for Uploader {
     fn default() -> Self {
         let url = url::Url::from_str(ARWEAVE_BASE_URL).unwrap();
-        Self { url }
+        Self::new(url)
     }
 }
 
 impl Uploader {
     pub fn new(url: url::Url) -> Self {
-        Uploader { url }
+        Uploader {
+            url,
+            transport: default_transport(),
+            concurrency: CHUNKS_CONCURRENCY,
+            retry_policy: RetryPolicy::default(),
+            progress: None,
+            gateways: None,
+            metrics: None,
+        }
+    }
+
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Posts chunks to the active gateway of `gateways` instead of the url.
+    pub fn with_gateways(mut self, gateways: Arc<GatewayPool>) -> Self {
+        self.gateways = Some(gateways);
+        self
+    }
+
+    /// Reports upload progress to `progress`.
+    pub fn with_progress(mut self, progress: ProgressCallback) -> Self {
+        self.progress = Some(progress);
+        self
     }
 
-    pub async fn post_chunk_with_retries(
+    /// Reports uploaded bytes, retries and chunk request latencies to `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<dyn MetricsSink>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub(crate) fn emit(&self, event: UploadEvent) {
+        if let Some(progress) = &self.progress {
+            progress(&event);
+        }
+    }
+
+    /// Policy applied to every chunk.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Maximum number of chunks posted simultaneously, at least one.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    pub async fn post_chunk_with_retries(&self, chunk: Chunk) -> Result<usize, Error> {
+        self.post_chunk_ref_with_retries(chunk.as_chunk_ref()).await
+    }
+
+    /// Like [`Uploader::post_chunk_with_retries`], for a chunk borrowed from its data.
+    pub async fn post_chunk_ref_with_retries(&self, chunk: ChunkRef<'_>) -> Result<usize, Error> {
+        self.retry_policy
+            .retry_with(
+                || self.post_chunk_ref(chunk),
+                |attempt, err| {
+                    if let Some(metrics) = &self.metrics {
+                        metrics.chunk_retry(chunk.offset);
+                    }
+                    self.emit(UploadEvent::ChunkRetry {
+                        offset: chunk.offset,
+                        attempt,
+                        error: err.to_string(),
+                    })
+                },
+            )
+            .await
+    }
+
+    /// Posts the chunks of `signed_transaction` that `state` does not list as accepted, with
+    /// at most [`Uploader::concurrency`] requests in flight. `checkpoint` is called with the
+    /// updated state after every accepted chunk, so it can be persisted. All pending chunks are
+    /// attempted even if some of them fail, in which case the first error is returned.
+    pub async fn upload_chunks<F>(
         &self,
-        chunk: Chunk,
-        client: Client,
-    ) -> Result<usize, Error> {
-        let mut retries = 0;
-        let mut resp = self.post_chunk(&chunk, &client).await;
-
-        while retries < CHUNKS_RETRIES {
-            match resp {
-                Ok(offset) => return Ok(offset),
-                Err(e) => {
-                    dbg!("post_chunk_with_retries: {:?}", e);
-                    sleep(Duration::from_secs(CHUNKS_RETRY_SLEEP));
-                    retries += 1;
-                    resp = self.post_chunk(&chunk, &client).await;
+        signed_transaction: &Tx,
+        state: &mut UploadState,
+        checkpoint: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&UploadState) -> Result<(), Error>,
+    {
+        self.upload_chunks_from(
+            signed_transaction,
+            &signed_transaction.data.0,
+            state,
+            checkpoint,
+        )
+        .await
+    }
+
+    /// Same as [`Uploader::upload_chunks`], reading the chunks from `data` instead of the
+    /// transaction, e.g. for transactions created with [`Tx::new_with_detached_data`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(id = %signed_transaction.id, chunks = signed_transaction.chunks.len())))]
+    pub async fn upload_chunks_from<F>(
+        &self,
+        signed_transaction: &Tx,
+        data: &[u8],
+        state: &mut UploadState,
+        mut checkpoint: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&UploadState) -> Result<(), Error>,
+    {
+        let total = signed_transaction.chunks.len();
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let mut results = state
+            .pending_chunks(signed_transaction)
+            .into_iter()
+            .map(|idx| {
+                let semaphore = semaphore.clone();
+                async move {
+                    let _permit = semaphore
+                        .acquire_owned()
+                        .await
+                        .map_err(|e| Error::PostChunkError(e.to_string()))?;
+                    let chunk = signed_transaction.get_chunk_ref_from(idx, data)?;
+                    self.post_chunk_ref_with_retries(chunk).await
+                }
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        let mut error = None;
+        while let Some(result) = results.next().await {
+            match result {
+                Ok(offset) => {
+                    state.uploaded_offsets.insert(offset);
+                    self.emit(UploadEvent::ChunkPosted {
+                        offset,
+                        confirmed: state.uploaded_offsets.len(),
+                        total,
+                    });
+                    checkpoint(state)?;
+                }
+                Err(err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(error = %err, "chunk failed");
+                    error.get_or_insert(err);
                 }
             }
         }
-        resp
+
+        match error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
     }
 
-    pub async fn post_chunk(&self, chunk: &Chunk, client: &Client) -> Result<usize, Error> {
-        let url = self.url.join("chunk").map_err(Error::UrlParseError)?;
-        // let client = reqwest::Client::new();
+    pub async fn post_chunk(&self, chunk: &Chunk) -> Result<usize, Error> {
+        self.post_chunk_ref(chunk.as_chunk_ref()).await
+    }
 
-        let resp = client
-            .post(url)
-            .json(&chunk)
-            .header(&ACCEPT, "application/json")
-            .header(&CONTENT_TYPE, "application/json")
-            .send()
-            .await
-            .map_err(|e| Error::PostChunkError(e.to_string()))?;
+    /// Posts a chunk borrowed from its data, see [`Tx::get_chunk_ref_from`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(offset = chunk.offset)))]
+    pub async fn post_chunk_ref(&self, chunk: ChunkRef<'_>) -> Result<usize, Error> {
+        let base_url = match &self.gateways {
+            Some(gateways) => gateways.current(),
+            None => self.url.clone(),
+        };
+        let url = base_url.join("chunk").map_err(Error::UrlParseError)?;
+
+        let request = HttpRequest::post(url)
+            .json(&chunk)?
+            .header(ACCEPT, HeaderValue::from_static("application/json"));
+
+        let start = Instant::now();
+        let result = self.transport.send(request).await.and_then(|resp| {
+            match resp.status == reqwest::StatusCode::OK {
+                true => Ok(chunk.offset),
+                false => Err(Error::from_response(&resp)),
+            }
+        });
+        if let Some(metrics) = &self.metrics {
+            metrics.request_latency("chunk", start.elapsed(), result.is_ok());
+            if result.is_ok() {
+                metrics.bytes_uploaded(chunk.chunk.0.len() as u64);
+            }
+        }
+        if let Some(gateways) = &self.gateways {
+            gateways.report(&base_url, &result);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use httpmock::{Method::POST, MockServer};
+    use reqwest::{header::HeaderMap, StatusCode};
+    use tokio_test::block_on;
+
+    use crate::{
+        crypto::{base64::Base64, Provider},
+        error::Error,
+        runtime::sleep,
+        transaction::Tx,
+        transport::{HttpRequest, HttpResponse, HttpTransport},
+    };
+
+    use crate::retry::RetryPolicy;
+
+    use super::{progress_channel, UploadEvent, UploadState, Uploader};
+
+    #[test]
+    fn test_resume_upload() -> Result<(), Error> {
+        let data = fs::read("res/1mb.bin")?;
+        let provider = Provider::default();
+        let mut tx = Tx::new(
+            &provider,
+            Base64::empty(),
+            data,
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )?;
+        tx.id = Base64(vec![1; 32]);
+
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200);
+        });
+        let uploader = Uploader::new(url::Url::parse(&server.url("")).unwrap());
+
+        // First two chunks were accepted before the upload got interrupted.
+        let mut state = UploadState::new(&tx);
+        state.header_posted = true;
+        state.uploaded_offsets.insert(tx.proofs[0].offset);
+        state.uploaded_offsets.insert(tx.proofs[1].offset);
+        assert!(!state.is_complete(&tx));
+
+        let mut checkpoints = 0;
+        block_on(uploader.upload_chunks(&tx, &mut state, |_| {
+            checkpoints += 1;
+            Ok(())
+        }))?;
+
+        mock.assert_hits(tx.chunks.len() - 2);
+        assert_eq!(checkpoints, tx.chunks.len() - 2);
+        assert!(state.is_complete(&tx));
+        assert!(state.matches(&tx));
+        Ok(())
+    }
+
+    /// Accepts every request after a pause, recording the most requests in flight at once.
+    #[derive(Default)]
+    struct InFlightTransport {
+        current: AtomicUsize,
+        max: AtomicUsize,
+    }
 
-        match resp.status() {
-            reqwest::StatusCode::OK => Ok(chunk.offset),
-            _ => Err(Error::StatusCodeNotOk),
+    #[async_trait]
+    impl HttpTransport for InFlightTransport {
+        async fn send(&self, _request: HttpRequest) -> Result<HttpResponse, Error> {
+            let current = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max.fetch_max(current, Ordering::SeqCst);
+            sleep(Duration::from_millis(20)).await;
+            self.current.fetch_sub(1, Ordering::SeqCst);
+            Ok(HttpResponse {
+                status: StatusCode::OK,
+                headers: HeaderMap::new(),
+                body: Default::default(),
+            })
         }
     }
+
+    struct TimeoutTransport;
+
+    #[async_trait]
+    impl HttpTransport for TimeoutTransport {
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+            Err(Error::RequestTimeout(request.url.to_string()))
+        }
+    }
+
+    #[test]
+    fn test_post_chunk_keeps_transport_errors() -> Result<(), Error> {
+        let provider = Provider::default();
+        let tx = Tx::new(
+            &provider,
+            Base64::empty(),
+            b"data".to_vec(),
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )?;
+        let uploader = Uploader::new(url::Url::parse("http://gateway.invalid/").unwrap())
+            .with_transport(Arc::new(TimeoutTransport));
+        let err = block_on(uploader.post_chunk(&tx.get_chunk(0)?)).unwrap_err();
+        assert!(matches!(&err, Error::RequestTimeout(url) if url.ends_with("/chunk")));
+        assert!(err.is_retryable());
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_chunks() -> Result<(), Error> {
+        let data = fs::read("res/1mb.bin")?;
+        let provider = Provider::default();
+        let mut tx = Tx::new(
+            &provider,
+            Base64::empty(),
+            data,
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )?;
+        let last = tx.chunks.len() - 1;
+        assert!(matches!(
+            tx.get_chunk(last + 1),
+            Err(Error::InvalidChunk { .. })
+        ));
+
+        tx.chunks[last].max_byte_range += 1;
+        assert!(matches!(
+            tx.get_chunk(last),
+            Err(Error::InvalidChunk { .. })
+        ));
+        tx.chunks[last].max_byte_range -= 1;
+
+        tx.proofs.pop();
+        assert!(matches!(
+            tx.get_chunk(last),
+            Err(Error::InvalidChunk { offset, .. })
+                if offset == tx.chunks[last].min_byte_range as u64
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_upload_concurrency() -> Result<(), Error> {
+        let data = fs::read("res/1mb.bin")?;
+        let provider = Provider::default();
+        let mut tx = Tx::new(
+            &provider,
+            Base64::empty(),
+            data,
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )?;
+        tx.id = Base64(vec![1; 32]);
+        let url = url::Url::parse("http://gateway.invalid/").unwrap();
+
+        for concurrency in [1, tx.chunks.len()] {
+            let transport = Arc::new(InFlightTransport::default());
+            let uploader = Uploader::new(url.clone())
+                .with_transport(transport.clone())
+                .with_concurrency(concurrency);
+            let mut state = UploadState::new(&tx);
+            block_on(uploader.upload_chunks(&tx, &mut state, |_| Ok(())))?;
+            assert_eq!(transport.max.load(Ordering::SeqCst), concurrency);
+            assert_eq!(state.uploaded_offsets.len(), tx.chunks.len());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_upload_progress() -> Result<(), Error> {
+        let provider = Provider::default();
+        let mut tx = Tx::new(
+            &provider,
+            Base64::empty(),
+            fs::read("res/rebar3")?,
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )?;
+        tx.id = Base64(vec![1; 32]);
+
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(500);
+        });
+        let (progress, mut events) = progress_channel();
+        let uploader = Uploader::new(url::Url::parse(&server.url("")).unwrap())
+            .with_concurrency(1)
+            .with_retry_policy(
+                RetryPolicy::default()
+                    .max_attempts(2)
+                    .base_delay(Duration::from_millis(1)),
+            )
+            .with_progress(progress);
+
+        let mut state = UploadState::new(&tx);
+        state.uploaded_offsets.insert(tx.proofs[0].offset);
+        assert!(block_on(uploader.upload_chunks(&tx, &mut state, |_| Ok(()))).is_err());
+        mock.assert_hits(2 * (tx.chunks.len() - 1));
+        let event = events.try_recv().unwrap();
+        assert!(
+            matches!(event, UploadEvent::ChunkRetry { attempt: 0, offset, .. } if offset == tx.proofs[1].offset)
+        );
+
+        let mut mock = mock;
+        mock.delete();
+        server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200);
+        });
+        let (progress, mut events) = progress_channel();
+        let uploader =
+            Uploader::new(url::Url::parse(&server.url("")).unwrap()).with_progress(progress);
+        block_on(uploader.upload_chunks(&tx, &mut state, |_| Ok(())))?;
+
+        let mut confirmed = vec![];
+        while let Ok(event) = events.try_recv() {
+            if let UploadEvent::ChunkPosted {
+                confirmed: c,
+                total,
+                ..
+            } = event
+            {
+                assert_eq!(total, tx.chunks.len());
+                confirmed.push(c);
+            }
+        }
+        assert_eq!(confirmed, (2..=tx.chunks.len()).collect::<Vec<_>>());
+        Ok(())
+    }
+
+    #[test]
+    fn test_upload_state_roundtrip() -> Result<(), Error> {
+        let mut state = UploadState {
+            id: Base64(vec![1; 32]),
+            data_root: Base64(vec![2; 32]),
+            ..Default::default()
+        };
+        state.uploaded_offsets.insert(262143);
+        let path = crate::temp_path("arweave-rs-upload-state.json");
+        state.save(&path)?;
+        assert_eq!(UploadState::from_path(&path)?, state);
+        fs::remove_file(path)?;
+        Ok(())
+    }
 }
@@ -1,66 +1,213 @@
+//! Verification of transactions and data items.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
 use crate::{
-    crypto::hash::{deep_hash, ToItems},
+    bundle::DataItem,
+    crypto::{
+        self,
+        hash::{deep_hash, ToItems},
+        utils::ct_eq,
+        verify::verify_signature,
+    },
     error::Error,
     transaction::Tx,
 };
-use data_encoding::BASE64URL;
-use jsonwebkey::JsonWebKey;
-use rand::thread_rng;
-use rsa::{pkcs8::DecodePublicKey, PaddingScheme, PublicKey, RsaPublicKey};
-use sha2::Digest;
 
 pub fn verify(pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), Error> {
-    let jwt_str = format!(
-        "{{\"kty\":\"RSA\",\"e\":\"AQAB\",\"n\":\"{}\"}}",
-        BASE64URL.encode(pub_key)
-    );
-    let jwk: JsonWebKey = jwt_str.parse().unwrap();
-
-    let pub_key = RsaPublicKey::from_public_key_der(jwk.key.to_der().as_slice()).unwrap();
-    let mut hasher = sha2::Sha256::new();
-    hasher.update(message);
-    let hashed = &hasher.finalize();
-
-    let rng = thread_rng();
-    let padding = PaddingScheme::PSS {
-        salt_rng: Box::new(rng),
-        digest: Box::new(sha2::Sha256::new()),
-        salt_len: None,
-    };
-    pub_key
-        .verify(padding, hashed, signature)
-        .map(|_| ())
-        .map_err(|_| Error::InvalidSignature)
+    crypto::verify::verify(pub_key, message, signature)
 }
 
+/// Fully verifies a transaction header of any format: the signature over its
+/// [signature data](Tx::signature_data) must be valid
+/// for `owner` and the id must be the SHA-256 hash of the signature, so that a gateway
+/// cannot serve forged headers.
 pub fn verify_transaction(transaction: &Tx) -> Result<(), Error> {
-    if transaction.signature.is_empty() {
-        return Err(Error::UnsignedTransaction);
+    if !ct_eq(&transaction.id.0, &transaction.compute_id()?.0) {
+        return Err(Error::TransactionInfoError(
+            "id does not match signature".to_string(),
+        ));
     }
 
-    let deep_hash_item = transaction.to_deep_hash_item()?;
-    let message = deep_hash(deep_hash_item);
-    let signature = &transaction.signature;
-
-    let jwt_str = format!(
-        "{{\"kty\":\"RSA\",\"e\":\"AQAB\",\"n\":\"{}\"}}",
-        BASE64URL.encode(&transaction.owner.0)
-    );
-    let jwk: JsonWebKey = jwt_str.parse().unwrap();
-
-    let pub_key = RsaPublicKey::from_public_key_der(jwk.key.to_der().as_slice()).unwrap();
-    let mut hasher = sha2::Sha256::new();
-    hasher.update(message);
-    let hashed = &hasher.finalize();
-
-    let rng = thread_rng();
-    let padding = PaddingScheme::PSS {
-        salt_rng: Box::new(rng),
-        digest: Box::new(sha2::Sha256::new()),
-        salt_len: None,
+    let message = transaction.signature_data()?;
+    verify(&transaction.owner.0, &message, &transaction.signature.0)
+}
+
+/// Verifies the id and the signature of a data item, according to its signature type.
+pub fn verify_data_item(item: &DataItem) -> Result<(), Error> {
+    if !ct_eq(&item.id.0, &item.compute_id()?.0) {
+        return Err(Error::InvalidDataItem(
+            "id does not match signature".to_string(),
+        ));
+    }
+    let message = deep_hash(item.to_deep_hash_item()?);
+    verify_signature(
+        item.signature_type,
+        &item.owner.0,
+        &message,
+        &item.signature.0,
+    )
+}
+
+/// Verifies `(public key, message, signature)` triples of wallet signatures, across the
+/// available cores with the `parallel` feature, returning the result of each triple in order.
+pub fn verify_batch(items: &[(&[u8], &[u8], &[u8])]) -> Vec<Result<(), Error>> {
+    par_map(items, |(pub_key, message, signature)| {
+        verify(pub_key, message, signature)
+    })
+}
+
+/// Same as [`verify_batch`] for transaction headers, see [`verify_transaction`].
+pub fn verify_transactions(transactions: &[Tx]) -> Vec<Result<(), Error>> {
+    par_map(transactions, verify_transaction)
+}
+
+/// Maps `items` with `f` on the rayon thread pool with the `parallel` feature, sequentially
+/// otherwise.
+pub(crate) fn par_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Send + Sync,
+{
+    #[cfg(feature = "parallel")]
+    let results = items.par_iter().map(f).collect();
+    #[cfg(not(feature = "parallel"))]
+    let results = items.iter().map(f).collect();
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, str::FromStr};
+
+    use crate::{
+        crypto::{base64::Base64, Provider},
+        error::Error,
+        transaction::{
+            tags::{FromUtf8Strs, Tag},
+            Tx,
+        },
     };
-    pub_key
-        .verify(padding, hashed, &signature.0)
-        .map(|_| ())
-        .map_err(|_| Error::InvalidSignature)
+
+    use super::{par_map, verify_batch, verify_transaction, verify_transactions};
+
+    fn sample_tx() -> Tx {
+        Tx::from_str(&fs::read_to_string("res/sample_tx.json").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_verify_transaction() -> Result<(), Error> {
+        verify_transaction(&sample_tx())?;
+        assert_eq!(sample_tx().compute_id()?, sample_tx().id);
+
+        let mut forged = sample_tx();
+        forged.id = Base64(vec![1; 32]);
+        assert!(matches!(
+            verify_transaction(&forged),
+            Err(Error::TransactionInfoError(_))
+        ));
+
+        let mut tampered = sample_tx();
+        tampered.reward += 1;
+        assert!(matches!(
+            verify_transaction(&tampered),
+            Err(Error::InvalidSignature)
+        ));
+
+        let mut unsigned = sample_tx();
+        unsigned.signature = Base64::empty();
+        assert!(matches!(
+            verify_transaction(&unsigned),
+            Err(Error::UnsignedTransaction)
+        ));
+
+        let mut bad_owner = sample_tx();
+        bad_owner.owner = Base64(vec![]);
+        assert!(verify_transaction(&bad_owner).is_err());
+
+        let mut unknown_format = sample_tx();
+        unknown_format.format = 3;
+        assert!(matches!(
+            verify_transaction(&unknown_format),
+            Err(Error::TransactionInfoError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_batch() -> Result<(), Error> {
+        let provider = Provider::default();
+        let messages: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 100]).collect();
+        let signatures = messages
+            .iter()
+            .map(|message| provider.sign(message))
+            .collect::<Result<Vec<_>, _>>()?;
+        let owner = provider.keypair_modulus();
+        let mut items: Vec<(&[u8], &[u8], &[u8])> = messages
+            .iter()
+            .zip(&signatures)
+            .map(|(message, signature)| (&owner.0[..], &message[..], &signature.0[..]))
+            .collect();
+        items[5].1 = &messages[4];
+
+        let results = verify_batch(&items);
+        assert_eq!(results.len(), 8);
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(result.is_ok(), i != 5, "item {}", i);
+        }
+
+        let mut tampered = sample_tx();
+        tampered.reward += 1;
+        let results = verify_transactions(&[sample_tx(), tampered, sample_tx()]);
+        assert!(results[0].is_ok() && results[1].is_err() && results[2].is_ok());
+        assert!(verify_batch(&[]).is_empty());
+
+        let squares = par_map(&[1, 2, 3, 4, 5, 6, 7], |i| i * i);
+        assert_eq!(squares, vec![1, 4, 9, 16, 25, 36, 49]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_legacy_transaction() -> Result<(), Error> {
+        let provider = Provider::default();
+        let tx = Tx {
+            format: 1,
+            owner: provider.keypair_modulus(),
+            data: Base64(b"legacy".to_vec()),
+            data_size: 6,
+            reward: 100,
+            last_tx: Base64(vec![1; 32]),
+            tags: vec![Tag::from_utf8_strs("App-Name", "test")?],
+            ..Default::default()
+        };
+        assert_eq!(
+            tx.signature_data()?,
+            [
+                &tx.owner.0[..],
+                b"legacy",
+                b"0",
+                b"100",
+                &[1; 32],
+                b"App-Name",
+                b"test",
+            ]
+            .concat()
+        );
+        let tx = tx.sign(&provider)?;
+        verify_transaction(&tx)?;
+
+        // Early headers have no format, data size or data root.
+        let mut json = serde_json::to_value(&tx).unwrap();
+        let object = json.as_object_mut().unwrap();
+        object.remove("format");
+        object.remove("data_size");
+        object.remove("data_root");
+        let parsed = Tx::from_str(&json.to_string())?;
+        assert_eq!(parsed.format, 1);
+        assert_eq!(parsed.data_size, 6);
+        verify_transaction(&parsed)?;
+        Ok(())
+    }
 }
@@ -1,8 +1,14 @@
 use pretend::{interceptor::NoopRequestInterceptor, pretend, resolver::UrlResolver, Pretend, Url};
 
-use crate::{client::Client, error::Error};
+use std::{str::FromStr, sync::Arc};
 
-#[pretend]
+use crate::{
+    address::Address, client::Client, crypto::base64::Base64, currency::Currency, error::Error,
+    transport::HttpTransport,
+};
+
+#[cfg_attr(not(target_arch = "wasm32"), pretend)]
+#[cfg_attr(target_arch = "wasm32", pretend(?Send))]
 trait TransactionInfoFetch {
     #[request(method = "GET", path = "/wallet/{address}/balance")]
     async fn wallet_balance(&self, address: &str) -> pretend::Result<String>;
@@ -20,6 +26,12 @@ impl WalletInfoClient {
         Self(pretend)
     }
 
+    /// Client sending requests through `transport`.
+    pub fn with_transport(url: Url, transport: Arc<dyn HttpTransport>) -> Self {
+        let pretend = Pretend::for_client(Client::new(transport)).with_url(url);
+        Self(pretend)
+    }
+
     pub async fn balance(&self, address: &str) -> Result<String, Error> {
         self.0
             .wallet_balance(address)
@@ -33,6 +45,21 @@ impl WalletInfoClient {
             .await
             .map_err(|op| Error::WalletError(op.to_string()))
     }
+
+    /// Balance of `address` in winstons.
+    pub async fn get_balance(&self, address: &Address) -> Result<Currency, Error> {
+        let balance = self.balance(&address.to_string()).await?;
+        let winstons = u128::from_str(balance.trim()).map_err(|_| {
+            Error::WalletError(format!("invalid balance for {}: {}", address, balance))
+        })?;
+        Ok(Currency::from(winstons))
+    }
+
+    /// Id of the last transaction sent from `address`, empty if it has none.
+    pub async fn get_last_tx(&self, address: &Address) -> Result<Base64, Error> {
+        let last_tx = self.last_tx_id(&address.to_string()).await?;
+        Base64::from_str(last_tx.trim()).map_err(Error::Base64DecodeError)
+    }
 }
 
 #[cfg(test)]
@@ -41,7 +68,7 @@ mod tests {
     use pretend::Url;
     use tokio_test::block_on;
 
-    use crate::wallet::WalletInfoClient;
+    use crate::{address::Address, crypto::base64::Base64, error::Error, wallet::WalletInfoClient};
 
     #[test]
     fn test_balance() {
@@ -84,4 +111,51 @@ mod tests {
         mock.assert();
         assert_eq!(tx_info, "last_tx".to_string());
     }
+
+    #[test]
+    fn test_get_balance() {
+        let address = Address::new([1; 32]);
+        let invalid = Address::new([2; 32]);
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/wallet/{}/balance", address));
+            then.status(200).body("5000123123123123");
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/wallet/{}/balance", invalid));
+            then.status(200).body("-1");
+        });
+
+        let client = WalletInfoClient::new(Url::parse(&server.url("")).unwrap());
+        let balance = block_on(client.get_balance(&address)).unwrap();
+
+        mock.assert();
+        assert_eq!(balance.to_string(), "5000123123123123");
+        assert!(matches!(
+            block_on(client.get_balance(&invalid)),
+            Err(Error::WalletError(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_last_tx() {
+        let last_tx = Base64(vec![4; 32]);
+        let (address, new) = (Address::new([1; 32]), Address::new([2; 32]));
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/wallet/{}/last_tx", address));
+            then.status(200).body(last_tx.to_string());
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/wallet/{}/last_tx", new));
+            then.status(200).body("");
+        });
+
+        let client = WalletInfoClient::new(Url::parse(&server.url("")).unwrap());
+        assert_eq!(block_on(client.get_last_tx(&address)).unwrap(), last_tx);
+        assert!(block_on(client.get_last_tx(&new)).unwrap().is_empty());
+    }
 }